@@ -14,7 +14,14 @@
 ///! forks of logterm customise the files in src/custom
 #[path = "../custom/mod.rs"]
 pub mod custom;
-use self::custom::app::{set_main_view, App, DashViewMain};
+use self::custom::app::App;
+use self::custom::app_error::AppError;
+use self::custom::config_check;
+use self::custom::daemon::run_daemon;
+use self::custom::keymap::{Action, InputContext, InputKey, MouseInput};
+use self::custom::opt::Opt;
+use self::custom::self_log;
+use self::custom::terminal;
 use self::custom::ui::draw_dashboard;
 
 #[macro_use]
@@ -26,7 +33,7 @@ extern crate env_logger;
 pub mod shared;
 
 use crossterm::{
-	event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode},
+	event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, MouseButton, MouseEvent},
 	execute,
 	terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -50,27 +57,69 @@ use futures::{
 
 enum Event<I> {
 	Input(I),
+	Mouse(MouseInput),
 	Tick,
+	///! Reading the terminal failed, see initialise_events()
+	Error(AppError),
 }
 
-use tokio::stream::StreamExt;
 use tokio::sync::mpsc;
 
 // RUSTFLAGS="-A unused" cargo run --bin logtail-crossterm --features="crossterm" /var/log/auth.log /var/log/dmesg
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn Error>> {
-	env_logger::init();
+	if let Err(e) = self_log::init(Opt::from_args_and_env().self_log.as_deref()) {
+		println!("{}", e);
+		return Ok(());
+	}
 	info!("Started");
 
+	let opt = Opt::from_args_and_env();
+	if let Some(command) = &opt.command {
+		if let Err(e) = command.run(&opt) {
+			println!("{}", e);
+		}
+		return Ok(());
+	}
+	if opt.check_config {
+		if let Err(e) = config_check::run(&opt) {
+			println!("{}", e);
+		}
+		return Ok(());
+	}
+
 	let mut app = match App::new().await {
 		Ok(app) => app,
 		Err(_e) => return Ok(()),
 	};
 
-	// Terminal initialization
-	enable_raw_mode()?;
+	if let Some(socket) = app.opt.daemon.clone() {
+		return Ok(run_daemon(app, socket).await?);
+	}
+
+	// Terminal initialization, undone by restore however this returns
+	match enable_raw_mode() {
+		Ok(()) => {}
+		Err(crossterm::ErrorKind::IoError(e)) => {
+			println!("{}", terminal::setup_error(e));
+			return Ok(());
+		}
+		Err(e) => {
+			println!("{}", terminal::setup_error(e));
+			return Ok(());
+		}
+	}
+	let mut restore = RestoreTerminal { alternate_screen: false };
 	let mut stdout = stdout();
-	execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+	if terminal::alternate_screen(&app.opt) {
+		match execute!(stdout, EnterAlternateScreen) {
+			Ok(()) => restore.alternate_screen = true,
+			Err(e) => warn!("drawing inline, as the terminal has no alternate screen: {}", e),
+		}
+	}
+	if let Err(e) = execute!(stdout, EnableMouseCapture) {
+		warn!("failed to enable mouse capture: {}", e);
+	}
 	let backend = CrosstermBackend::new(stdout);
 	let mut terminal = Terminal::new(backend)?;
 	let mut rx = initialise_events(app.opt.tick_rate);
@@ -88,10 +137,10 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 			.duration_since(UNIX_EPOCH)
 			.expect("Time went backwards") {
 			terminal.draw(|f| draw_dashboard(f, &mut app))?;
-			next_update += Duration::from_secs(1);
+			next_update += app.redraw_interval();
 		}
 
-		let logfiles_future = app.logfiles.next().fuse();
+		let logfiles_future = app.next_line().fuse();
 		let events_future = rx.recv().fuse();
 		pin_mut!(logfiles_future, events_future);
 
@@ -100,79 +149,89 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 			match e {
 				Some(Event::Input(event)) => {
 					match event.code {
-						// For debugging, ~ sends a line to the debug_window
-						KeyCode::Char('~') => app.dash_state._debug_window(format!("Event::Input({:#?})", event).as_str()),
-
-						KeyCode::Char('q')|
-						KeyCode::Char('Q') => {
-							disable_raw_mode()?;
-							execute!(
-								terminal.backend_mut(),
-								LeaveAlternateScreen,
-								DisableMouseCapture
-							)?;
-							terminal.show_cursor()?;
-							break Ok(());
+						// For debugging, ~ sends a line to the debug_window (other contexts take it as a key)
+						KeyCode::Char('~') if app.keymap.context() == InputContext::Normal => app.dash_state._debug_window(format!("Event::Input({:#?})", event).as_str()),
+
+						_ => match app.keymap.handle(input_key(event.code)) {
+							Some((Action::Quit, _)) => {
+								app.shutdown();
+								terminal.show_cursor()?;
+								break Ok(());
+							},
+							Some((action, count)) => app.handle_action(action, count),
+							None => {},
 						},
-						// KeyCode::Char('s')|
-						// KeyCode::Char('S') => app.set_main_view(DashViewMain::DashSummary),
-						KeyCode::Char('v')|
-						KeyCode::Char('V') => set_main_view(DashViewMain::DashNode, &mut app),
-
-						KeyCode::Char('+')|
-						KeyCode::Char('i')|
-						KeyCode::Char('I') => app.scale_timeline_up(),
-						KeyCode::Char('-')|
-						KeyCode::Char('o')|
-						KeyCode::Char('O') => app.scale_timeline_down(),
-
-						KeyCode::Down => app.handle_arrow_down(),
-						KeyCode::Up => app.handle_arrow_up(),
-						KeyCode::Right|
-						KeyCode::Tab => app.change_focus_next(),
-						KeyCode::Left => app.change_focus_previous(),
-
-						KeyCode::Char('g') => set_main_view(DashViewMain::DashDebug, &mut app),
-						_ => {}
 					};
-					terminal.draw(|f| draw_dashboard(f, &mut app));
+					terminal.draw(|f| draw_dashboard(f, &mut app))?;
+				}
+
+				Some(Event::Mouse(input)) => {
+					app.handle_mouse(input);
+					terminal.draw(|f| draw_dashboard(f, &mut app))?;
 				}
 
 				Some(Event::Tick) => {
+					if app.shutdown.requested() {
+						app.shutdown();
+						terminal.show_cursor()?;
+						break Ok(());
+					}
 					app.update_timelines(Some(Utc::now()));
 					app.update_chunk_store_stats();
+					app.update_health();
+					app.update_availability();
+					let reloaded = app.reload_replaced_logfiles().await;
+					app.report("reloading replaced logfile", reloaded);
+					app.check_watches();
+					let followed = app.follow_latest_logfile().await;
+					app.report("following latest logfile", followed);
+					let opened = app.open_self_log().await;
+					app.report("opening vdash's log", opened);
+					app.update_snapshot_summary();
+					app.flush_merged();
+					app.update_line_rates();
+					app.watchdog.tick();
+					app.collect_control_outputs();
+					app.run_hooks();
+					if app.needs_redraw() {
+						terminal.draw(|f| draw_dashboard(f, &mut app))?;
+					}
 				// draw_dashboard(&mut f, &dash_state, &mut monitors).unwrap();
 				// draw_dashboard(f, &dash_state, &mut monitors)?;
 				}
 
+				Some(Event::Error(error)) => app.report_error(error),
+
 				None => {},
 			}
 			},
 
 			(line) = logfiles_future => {
 			match line {
-				Some(Ok(line)) => {
+				Some(Ok((source, line, length))) => {
 					trace!("logfiles_future line");
-					let source_str = line.source().to_str().unwrap();
-					let source = String::from(source_str);
-					// app.dash_state._debug_window(format!("{}: {}", source, line.line()).as_str());
+					// app.dash_state._debug_window(format!("{}: {}", source, line).as_str());
+					#[cfg(feature = "remote")]
+					app.add_syslog_monitor(&source);
+					let journaled = app.journal_line(&source, &line, length);
+					app.report("writing journal", journaled);
 
 					match app.get_monitor_for_file_path(&source) {
 						Some(monitor) => {
-							monitor.append_to_content(line.line())?;
+							let appended = monitor.append_line(&line, length);
 							if monitor.is_debug_dashboard_log {
-								app.dash_state._debug_window(line.line());
+								app.dash_state._debug_window(&line);
 							}
+							app.report(&format!("adding line from {}", source), appended);
 						},
 						None => {
 							app.dash_state._debug_window(format!("NO MONITOR FOR: {}", source).as_str());
 						},
 					}
+					app.merge_line(&source, &line);
+					app.run_hooks();
 				},
-				Some(Err(e)) => {
-					app.dash_state._debug_window(format!("logfile error: {:#?}", e).as_str());
-					panic!("{}", e)
-				}
+				Some(Err(e)) => app.report_error(AppError::new("reading logfile", e)),
 				None => {
 					app.dash_state._debug_window(format!("logfile error: None").as_str());
 					()
@@ -182,6 +241,47 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 		}
 	}
 }
+///! Returns the terminal to normal when dropped
+struct RestoreTerminal {
+	alternate_screen: bool,
+}
+
+impl Drop for RestoreTerminal {
+	fn drop(&mut self) {
+		let mut stdout = stdout();
+		let _ = execute!(stdout, DisableMouseCapture);
+		if self.alternate_screen {
+			let _ = execute!(stdout, LeaveAlternateScreen);
+		}
+		let _ = disable_raw_mode();
+	}
+}
+
+fn input_key(code: KeyCode) -> InputKey {
+	match code {
+		KeyCode::Char(c) => InputKey::Char(c),
+		KeyCode::Up => InputKey::Up,
+		KeyCode::Down => InputKey::Down,
+		KeyCode::Left => InputKey::Left,
+		KeyCode::Right => InputKey::Right,
+		KeyCode::Tab => InputKey::Tab,
+		KeyCode::BackTab => InputKey::BackTab,
+		KeyCode::Esc => InputKey::Esc,
+		KeyCode::Enter => InputKey::Enter,
+		KeyCode::Backspace => InputKey::Backspace,
+		_ => InputKey::Other,
+	}
+}
+
+fn mouse_input(event: MouseEvent) -> Option<MouseInput> {
+	match event {
+		MouseEvent::Down(MouseButton::Left, column, row, _) => Some(MouseInput::Press(column, row)),
+		MouseEvent::Drag(MouseButton::Left, column, row, _) => Some(MouseInput::Drag(column, row)),
+		MouseEvent::Up(_, _, _, _) => Some(MouseInput::Release),
+		_ => None,
+	}
+}
+
 type Rx = tokio::sync::mpsc::UnboundedReceiver<Event<crossterm::event::KeyEvent>>;
 
 fn initialise_events(tick_rate: u64) -> Rx {
@@ -192,21 +292,32 @@ fn initialise_events(tick_rate: u64) -> Rx {
 		let mut last_tick = Instant::now();
 		loop {
 			// poll for tick rate duration, if no events, sent tick event.
-			if event::poll(tick_rate - last_tick.elapsed()).unwrap() {
-				if let CEvent::Key(key) = event::read().unwrap() {
-					tx.send(Event::Input(key));
+			let event = match event::poll(tick_rate.checked_sub(last_tick.elapsed()).unwrap_or_default()) {
+				Ok(true) => match event::read() {
+					Ok(CEvent::Key(key)) => Some(Event::Input(key)),
+					Ok(CEvent::Mouse(mouse)) => mouse_input(mouse).map(Event::Mouse),
+					Ok(_) => None,
+					Err(e) => Some(Event::Error(AppError::new("reading terminal", e))),
+				},
+				Ok(false) => None,
+				Err(e) => Some(Event::Error(AppError::new("polling terminal", e))),
+			};
+			if let Some(event) = event {
+				let failed = matches!(event, Event::Error(_));
+				if tx.send(event).is_err() {
+					break; // The dashboard has exited
+				}
+				if failed {
+					// Rather than spinning on a terminal which keeps failing
+					thread::sleep(tick_rate);
 				}
-			}
-			if last_tick.elapsed() >= tick_rate {
-				tx.send(Event::Tick);
-				last_tick = Instant::now();
 			}
 
 			if last_tick.elapsed() >= tick_rate {
-				match tx.send(Event::Tick) {
-					Ok(()) => last_tick = Instant::now(),
-					Err(e) => println!("send error: {}", e),
+				if tx.send(Event::Tick).is_err() {
+					break;
 				}
+				last_tick = Instant::now();
 			}
 		}
 	});
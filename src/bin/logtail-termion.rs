@@ -11,12 +11,19 @@
 
 #![recursion_limit = "1024"] // Prevent select! macro blowing up
 
-use std::io;
+use std::io::{self, Write};
 
 ///! forks of logterm customise the files in src/custom
 #[path = "../custom/mod.rs"]
 pub mod custom;
-use self::custom::app::{set_main_view, App, DashViewMain};
+use self::custom::app::App;
+use self::custom::app_error::AppError;
+use self::custom::config_check;
+use self::custom::daemon::run_daemon;
+use self::custom::keymap::{Action, InputContext, InputKey, MouseInput};
+use self::custom::opt::Opt;
+use self::custom::self_log;
+use self::custom::terminal;
 use self::custom::ui::draw_dashboard;
 
 #[macro_use]
@@ -28,7 +35,12 @@ extern crate env_logger;
 pub mod shared;
 use shared::event::{Event, Events};
 
-use termion::{event::Key, input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
+use termion::{
+	event::{Key, MouseButton, MouseEvent},
+	input::MouseTerminal,
+	raw::IntoRawMode,
+	screen::AlternateScreen,
+};
 use tui::{
 	backend::TermionBackend,
 	Terminal,
@@ -46,11 +58,13 @@ use futures::{
 	select,
 };
 
-use tokio::stream::StreamExt;
 
 #[tokio::main]
 pub async fn main() -> std::io::Result<()> {
-	env_logger::init();
+	if let Err(e) = self_log::init(Opt::from_args_and_env().self_log.as_deref()) {
+		println!("{}", e);
+		return Ok(());
+	}
 	info!("Started");
 
 	match terminal_main().await {
@@ -61,6 +75,14 @@ pub async fn main() -> std::io::Result<()> {
 }
 
 async fn terminal_main() -> std::io::Result<()> {
+	let opt = Opt::from_args_and_env();
+	if let Some(command) = &opt.command {
+		return command.run(&opt);
+	}
+	if opt.check_config {
+		return config_check::run(&opt);
+	}
+
 	let mut app = match App::new().await {
 		Ok(app) => app,
 		Err(e) => {
@@ -68,14 +90,21 @@ async fn terminal_main() -> std::io::Result<()> {
 		}
 	};
 
-	let mut events = Events::new();
+	if let Some(socket) = app.opt.daemon.clone() {
+		return run_daemon(app, socket).await;
+	}
 
-	// Terminal initialization
+	// Terminal initialization, before reading keys so that a failure leaves the terminal as it was
 	info!("Intialising terminal (termion backend)");
-	let stdout = io::stdout().into_raw_mode()?;
+	let stdout = io::stdout().into_raw_mode().map_err(terminal::setup_error)?;
 	let stdout = MouseTerminal::from(stdout);
-	let stdout = AlternateScreen::from(stdout);
+	let stdout: Box<dyn Write> = match terminal::alternate_screen(&app.opt) {
+		true => Box::new(AlternateScreen::from(stdout)),
+		false => Box::new(stdout),
+	};
 	let backend = TermionBackend::new(stdout);
+	let mut events = Events::new();
+	events.disable_exit_key(); // 'q' only quits in the keymap's normal context
 	let mut terminal = Terminal::new(backend)?;
 
 	// Use futures of async functions to handle events
@@ -91,11 +120,11 @@ async fn terminal_main() -> std::io::Result<()> {
 			.duration_since(UNIX_EPOCH)
 			.expect("Time went backwards") {
 			terminal.draw(|f| draw_dashboard(f, &mut app))?;
-			next_update += Duration::from_secs(1);
+			next_update += app.redraw_interval();
 		}
 
 		let events_future = events.rx.recv().fuse();
-		let logfiles_future = app.logfiles.next().fuse();
+		let logfiles_future = app.next_line().fuse();
 		pin_mut!(events_future, logfiles_future);
 
 		select! {
@@ -103,31 +132,17 @@ async fn terminal_main() -> std::io::Result<()> {
 				match e {
 					Some(Event::Input(input)) => {
 						match input {
-							// For debugging, ~ sends a line to the debug_window
-							Key::Char('~') => app.dash_state._debug_window(format!("Event::Input({:#?})", input).as_str()),
-
-							Key::Char('q')|
-							Key::Char('Q') => return Ok(()),
-						// Key::Char('s')|
-						// Key::Char('S') => app.set_main_view(DashViewMain::DashSummary),
-							Key::Char('v')|
-							Key::Char('V') => set_main_view(DashViewMain::DashNode, &mut app),
-
-							Key::Char('+')|
-							Key::Char('i')|
-							Key::Char('I') => app.scale_timeline_up(),
-							Key::Char('-')|
-							Key::Char('o')|
-							Key::Char('O') => app.scale_timeline_down(),
-	
-							Key::Down => app.handle_arrow_down(),
-							Key::Up => app.handle_arrow_up(),
-							Key::Right|
-							Key::Char('\t') => app.change_focus_next(),
-							Key::Left => app.change_focus_previous(),
-
-							Key::Char('g') => set_main_view(DashViewMain::DashDebug, &mut app),
-								_ => {},
+							// For debugging, ~ sends a line to the debug_window (other contexts take it as a key)
+							Key::Char('~') if app.keymap.context() == InputContext::Normal => app.dash_state._debug_window(format!("Event::Input({:#?})", input).as_str()),
+
+							_ => match app.keymap.handle(input_key(input)) {
+								Some((Action::Quit, _)) => {
+									app.shutdown();
+									return Ok(());
+								},
+								Some((action, count)) => app.handle_action(action, count),
+								None => {},
+							},
 						};
 						match terminal.draw(|f| draw_dashboard(f, &mut app)) {
 							Ok(_) => {},
@@ -138,17 +153,45 @@ async fn terminal_main() -> std::io::Result<()> {
 						};
 					}
 
+					Some(Event::Mouse(mouse)) => {
+						if let Some(input) = mouse_input(mouse) {
+							app.handle_mouse(input);
+							if let Err(e) = terminal.draw(|f| draw_dashboard(f, &mut app)) {
+								error!("terminal.draw() '{:#?}'", e);
+								return Err(e);
+							}
+						}
+					}
+
 					Some(Event::Tick) => {
 						trace!("Event::Tick");
+						if app.shutdown.requested() {
+							app.shutdown();
+							return Ok(());
+						}
 						app.update_timelines(Some(Utc::now()));
 						app.update_chunk_store_stats();
-						match terminal.draw(|f| draw_dashboard(f, &mut app)) {
-							Ok(_) => {},
-							Err(e) => {
+						app.update_health();
+						app.update_availability();
+						let reloaded = app.reload_replaced_logfiles().await;
+						app.report("reloading replaced logfile", reloaded);
+						app.check_watches();
+						let followed = app.follow_latest_logfile().await;
+						app.report("following latest logfile", followed);
+						let opened = app.open_self_log().await;
+						app.report("opening vdash's log", opened);
+						app.update_snapshot_summary();
+						app.flush_merged();
+						app.update_line_rates();
+						app.watchdog.tick();
+						app.collect_control_outputs();
+						app.run_hooks();
+						if app.needs_redraw() {
+							if let Err(e) = terminal.draw(|f| draw_dashboard(f, &mut app)) {
 								error!("terminal.draw() '{:#?}'", e);
 								return Err(e);
 							}
-						};
+						}
 						trace!("Event::Tick DONE");
 					}
 
@@ -158,27 +201,28 @@ async fn terminal_main() -> std::io::Result<()> {
 			(line) = logfiles_future => {
 				trace!("logfiles_future line");
 				match line {
-					Some(Ok(line)) => {
-						let source_str = line.source().to_str().unwrap();
-						let source = String::from(source_str);
-						// app.dash_state._debug_window(format!("{}: {}", source, line.line()).as_str());
+					Some(Ok((source, line, length))) => {
+						// app.dash_state._debug_window(format!("{}: {}", source, line).as_str());
+						#[cfg(feature = "remote")]
+						app.add_syslog_monitor(&source);
+						let journaled = app.journal_line(&source, &line, length);
+						app.report("writing journal", journaled);
 
 						match app.get_monitor_for_file_path(&source) {
 							Some(monitor) => {
-								trace!("APPENDING: {}", line.line());
-								monitor.append_to_content(line.line())?;
+								trace!("APPENDING: {}", line);
+								let appended = monitor.append_line(&line, length);
 								if monitor.is_debug_dashboard_log {
-									app.dash_state._debug_window(line.line());
+									app.dash_state._debug_window(&line);
 								}
+								app.report(&format!("adding line from {}", source), appended);
 							},
 							None => (),
 						}
+						app.merge_line(&source, &line);
+						app.run_hooks();
 					},
-					Some(Err(e)) => {
-						app.dash_state._debug_window(format!("logfile error: {:#?}", e).as_str());
-						error!("logfiles error '{:#?}'", e);
-						return Err(e)
-					},
+					Some(Err(e)) => app.report_error(AppError::new("reading logfile", e)),
 					None => {
 						app.dash_state._debug_window(format!("logfile error: None").as_str());
 						()
@@ -188,3 +232,29 @@ async fn terminal_main() -> std::io::Result<()> {
 		}
 	}
 }
+
+fn input_key(key: Key) -> InputKey {
+	match key {
+		Key::Char('\t') => InputKey::Tab,
+		Key::BackTab => InputKey::BackTab,
+		Key::Char('\n') => InputKey::Enter,
+		Key::Char(c) => InputKey::Char(c),
+		Key::Up => InputKey::Up,
+		Key::Down => InputKey::Down,
+		Key::Left => InputKey::Left,
+		Key::Right => InputKey::Right,
+		Key::Esc => InputKey::Esc,
+		Key::Backspace => InputKey::Backspace,
+		_ => InputKey::Other,
+	}
+}
+
+///! termion counts columns and rows from one
+fn mouse_input(event: MouseEvent) -> Option<MouseInput> {
+	match event {
+		MouseEvent::Press(MouseButton::Left, column, row) => Some(MouseInput::Press(column.saturating_sub(1), row.saturating_sub(1))),
+		MouseEvent::Hold(column, row) => Some(MouseInput::Drag(column.saturating_sub(1), row.saturating_sub(1))),
+		MouseEvent::Release(_, _) => Some(MouseInput::Release),
+		_ => None,
+	}
+}
@@ -0,0 +1,277 @@
+///! Alerts: node events which need attention, until they are acknowledged
+///!
+///! Errors, changes of status other than recovery, nodes which restart as a new
+///! node, flapping (once de-bounced, see FlapDetector) and clusters of lines
+///! matching an alert rule (see Escalation) each raise an alert. The most recent
+///! alert not yet acknowledged is shown in a bar along the bottom of every view,
+///! with a count of those pending, so that one raised by a node out of view
+///! isn't missed. '!' opens the list of alerts, where enter acknowledges the
+///! selected alert and 'a' every one. Alerts, and whether they have been
+///! acknowledged, are kept in the session (see Session), so they survive a
+///! restart.
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::hooks::{
+	NodeEvent, EVENT_ERROR, EVENT_ESCALATION, EVENT_FLAPPING, EVENT_IDENTITY, EVENT_STATUS, IDENTITY_NEW,
+};
+use super::keymap::InputKey;
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+	Frame,
+};
+
+///! Most recent alerts kept
+const ALERTS_MAX: usize = 200;
+
+///! The status of a node which has recovered, which isn't an alert
+static RECOVERED_STATUS: &str = "OK";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+	pub time: DateTime<Utc>,
+	///! The node's number, as shown
+	pub node: usize,
+	pub logfile: String,
+	pub event: String,
+	pub detail: String,
+	pub acknowledged: bool,
+}
+
+impl Alert {
+	///! One line describing the alert, e.g. '14:02:11 node 3 status: ERROR'
+	pub fn describe(&self) -> String {
+		format!(
+			"{} node {} {}: {}",
+			self.time.with_timezone(&Local).format("%H:%M:%S"),
+			self.node,
+			self.event,
+			self.detail
+		)
+	}
+}
+
+///! Alerts, oldest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Alerts {
+	items: VecDeque<Alert>,
+}
+
+impl Alerts {
+	///! Raise an alert for the event of a node, if it needs attention
+	pub fn raise(&mut self, node: usize, logfile: &str, event: &NodeEvent, time: DateTime<Utc>) {
+		if !is_alert(event) {
+			return;
+		}
+		self.items.push_back(Alert {
+			time,
+			node,
+			logfile: logfile.to_string(),
+			event: event.event.to_string(),
+			detail: event.detail.clone(),
+			acknowledged: false,
+		});
+		while self.items.len() > ALERTS_MAX {
+			self.items.pop_front();
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	///! Alerts from the most recent
+	pub fn iter(&self) -> impl Iterator<Item = &Alert> {
+		self.items.iter().rev()
+	}
+
+	pub fn pending(&self) -> usize {
+		self.items.iter().filter(|alert| !alert.acknowledged).count()
+	}
+
+	pub fn latest_pending(&self) -> Option<&Alert> {
+		self.iter().find(|alert| !alert.acknowledged)
+	}
+
+	///! Acknowledge the alert at index, counting from the most recent
+	pub fn acknowledge(&mut self, index: usize) {
+		if let Some(alert) = self.items.iter_mut().rev().nth(index) {
+			alert.acknowledged = true;
+		}
+	}
+
+	pub fn acknowledge_all(&mut self) {
+		for alert in self.items.iter_mut() {
+			alert.acknowledged = true;
+		}
+	}
+}
+
+///! Whether a node event raises an alert
+fn is_alert(event: &NodeEvent) -> bool {
+	event.event == EVENT_ERROR
+		|| event.event == EVENT_FLAPPING
+		|| event.event == EVENT_ESCALATION
+		|| (event.event == EVENT_STATUS && event.detail != RECOVERED_STATUS)
+		|| (event.event == EVENT_IDENTITY && event.detail.starts_with(IDENTITY_NEW))
+}
+
+///! The list of alerts opened with '!', and the alert selected in it
+pub struct AlertList {
+	pub selected: usize,
+}
+
+///! What a key does in the alert list
+#[derive(Debug, PartialEq)]
+pub enum AlertListCommand {
+	Acknowledge(usize),
+	AcknowledgeAll,
+	Close,
+}
+
+impl AlertList {
+	pub fn new() -> AlertList {
+		AlertList { selected: 0 }
+	}
+
+	///! Move the selection within count alerts, returning any command for the key
+	pub fn handle(&mut self, key: InputKey, count: usize) -> Option<AlertListCommand> {
+		match key {
+			InputKey::Up | InputKey::Char('k') => self.selected = self.selected.saturating_sub(1),
+			InputKey::Down | InputKey::Char('j') => self.selected = (self.selected + 1).min(count.saturating_sub(1)),
+			InputKey::Enter if count > 0 => return Some(AlertListCommand::Acknowledge(self.selected)),
+			InputKey::Char('a') | InputKey::Char('A') => return Some(AlertListCommand::AcknowledgeAll),
+			InputKey::Esc | InputKey::Char('q') | InputKey::Char('!') => return Some(AlertListCommand::Close),
+			_ => {}
+		}
+		None
+	}
+}
+
+///! The most recent pending alert, over the bottom row of the screen, while there is one
+pub fn draw_alerts_bar<B: Backend>(f: &mut Frame<B>, alerts: &Alerts) {
+	let alert = match alerts.latest_pending() {
+		Some(alert) => alert,
+		None => return,
+	};
+	let size = f.size();
+	if size.height == 0 {
+		return;
+	}
+	let area = Rect::new(size.x, size.y + size.height - 1, size.width, 1);
+	let pending = match alerts.pending() {
+		1 => String::from(" 1 alert "),
+		count => format!(" {} alerts ", count),
+	};
+	let text = Spans::from(vec![
+		Span::styled(pending, Style::default().fg(Color::Black).bg(Color::Red).add_modifier(Modifier::BOLD)),
+		Span::raw(" "),
+		Span::raw(alert.describe()),
+		Span::styled("  ('!' to review)", Style::default().fg(Color::DarkGray)),
+	]);
+	f.render_widget(Clear, area);
+	f.render_widget(Paragraph::new(text).style(Style::default().fg(Color::Red)), area);
+}
+
+pub fn draw_alert_list<B: Backend>(f: &mut Frame<B>, list: &AlertList, alerts: &Alerts) {
+	let size = f.size();
+	let width = size.width.saturating_sub(8).max(size.width.min(40));
+	let height = size.height.saturating_sub(4);
+	let area = Rect::new(size.x + (size.width - width) / 2, size.y + 2, width, height);
+	f.render_widget(Clear, area);
+
+	let items: Vec<ListItem> = match alerts.is_empty() {
+		true => vec![ListItem::new(Spans::from("No alerts"))],
+		false => alerts
+			.iter()
+			.map(|alert| {
+				let (mark, style) = match alert.acknowledged {
+					true => ("  ", Style::default().fg(Color::DarkGray)),
+					false => ("! ", Style::default().fg(Color::Red)),
+				};
+				ListItem::new(Spans::from(format!("{}{}", mark, alert.describe()))).style(style)
+			})
+			.collect(),
+	};
+	let title = format!(
+		"Alerts, {} pending (enter acknowledges, 'a' all, esc closes)",
+		alerts.pending()
+	);
+	let mut state = ListState::default();
+	if !alerts.is_empty() {
+		state.select(Some(list.selected.min(alerts.len() - 1)));
+	}
+	let list_widget = List::new(items)
+		.block(Block::default().borders(Borders::ALL).title(title))
+		.highlight_style(Style::default().bg(Color::LightGreen).add_modifier(Modifier::BOLD));
+	f.render_stateful_widget(list_widget, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::escalation::{AlertRule, Escalation};
+
+	fn event(event: &'static str, detail: &str) -> NodeEvent {
+		NodeEvent {
+			event,
+			detail: detail.to_string(),
+		}
+	}
+
+	#[test]
+	fn raises_and_acknowledges_alerts() {
+		let time = Utc::now();
+		let mut alerts = Alerts::default();
+		alerts.raise(1, "node1.log", &event(EVENT_STATUS, "ERROR"), time);
+		alerts.raise(1, "node1.log", &event(EVENT_STATUS, "OK"), time);
+		alerts.raise(2, "node2.log", &event("promotion", "Elder"), time);
+		alerts.raise(2, "node2.log", &event(EVENT_IDENTITY, "same node f67c2e"), time);
+		alerts.raise(2, "node2.log", &event(EVENT_ERROR, "Dead peer"), time);
+		assert_eq!((alerts.len(), alerts.pending()), (2, 2));
+		assert_eq!(alerts.latest_pending().map(|alert| alert.node), Some(2));
+
+		alerts.acknowledge(0);
+		assert_eq!(alerts.pending(), 1);
+		assert_eq!(alerts.latest_pending().map(|alert| alert.detail.as_str()), Some("ERROR"));
+		alerts.acknowledge_all();
+		assert_eq!(alerts.latest_pending(), None);
+
+		let mut list = AlertList::new();
+		assert_eq!(list.handle(InputKey::Down, 2), None);
+		assert_eq!(list.handle(InputKey::Down, 2), None);
+		assert_eq!(list.handle(InputKey::Enter, 2), Some(AlertListCommand::Acknowledge(1)));
+		assert_eq!(list.handle(InputKey::Esc, 2), Some(AlertListCommand::Close));
+	}
+
+	#[test]
+	fn raises_alerts_for_clusters_of_matches() {
+		let rule: AlertRule = toml::from_str("name = 'warnings'\npattern = ' WARN '\ncount = 2\nseconds = 60").unwrap();
+		let mut escalation = Escalation::new(&rule);
+		let mut alerts = Alerts::default();
+		let start = Utc::now();
+		let lines = [(0, "WARN"), (30, "[sn_node] WARN Dead peer"), (40, "[sn_node] WARN Dead"), (50, "[sn_node] WARN Dead")];
+		for (seconds, line) in lines.iter() {
+			let time = start + chrono::Duration::seconds(*seconds);
+			if let Some(event) = escalation.check(line, time) {
+				alerts.raise(3, "node3.log", &event, time);
+			}
+		}
+		// One alert for the cluster, not one for each line beyond the count
+		assert_eq!((alerts.len(), alerts.pending()), (1, 1));
+		let alert = alerts.latest_pending().unwrap();
+		assert_eq!((alert.node, alert.event.as_str()), (3, EVENT_ESCALATION));
+		assert_eq!(alert.detail, "warnings: 2 lines within 60s");
+	}
+}
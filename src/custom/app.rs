@@ -1,20 +1,78 @@
 ///! Application logic
 ///!
 ///! Edit src/custom/app.rs to create a customised fork of logtail-dash
-use linemux::MuxedLines;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use regex::Regex;
 
 use chrono::{DateTime, Duration, Utc};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Error, ErrorKind, Write};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use structopt::StructOpt;
 use tempfile::NamedTempFile;
-
-use crate::custom::opt::{Opt, MIN_TIMELINE_STEPS};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+use crate::custom::alerts::{AlertList, AlertListCommand};
+use crate::custom::app_error::{AppError, ErrorToasts};
+use crate::custom::availability::{self, Availability};
+use crate::custom::backfill;
+use crate::custom::clipboard;
+use crate::custom::columns::Column;
+use crate::custom::config::{Config, NodeWidget};
+use crate::custom::self_metrics::SelfMetrics;
+use crate::custom::session::Session;
+use crate::custom::splits::Splits;
+use crate::custom::shutdown::Shutdown;
+use crate::custom::watchdog::Watchdog;
+use crate::custom::daemon;
+use crate::custom::data_dir;
+use crate::custom::escalation::{AlertRule, Escalation};
+#[cfg(feature = "exporters")]
+use crate::custom::event_log::{EventLog, EventRecord};
+use crate::custom::file_check::{self, ContentMarks, Presence};
+use crate::custom::filter::{self, FilterEditor, LineFilter};
+use crate::custom::flapping::FlapDetector;
+use crate::custom::focus::{self, Focus};
+use crate::custom::goto::{self, GotoPrompt};
+use crate::custom::groups::SummaryGroups;
+use crate::custom::hooks::{self, HookConfig, NodeEvent};
+use crate::custom::ingest::{self, LineSender, Lines, Watch, WatchCheck, WatchHandle};
+use crate::custom::journal::{self, Journal, Recovered};
+use crate::custom::keymap::{Action, InputContext, KeyMap, MouseInput, Panel, Popup};
+use crate::custom::line_detail::{LineDetail, Provenance};
+use crate::custom::node_control::{ControlMenu, MenuResult, NodeControl};
+use crate::custom::opt::{NodeLabel, Opt, MIN_TIMELINE_STEPS};
+use crate::custom::regex_playground::RegexPlayground;
+use crate::custom::search::Search;
+#[cfg(feature = "exporters")]
+use crate::custom::merged::MergedLog;
+#[cfg(feature = "exporters")]
+use crate::custom::snapshot::{self, SharedSummary, Summary};
+use crate::custom::startup::{self, Choice};
+use crate::custom::line_times::LineTimes;
+use crate::custom::state_diff::StateDiff;
+#[cfg(feature = "remote")]
+use crate::custom::syslog;
+use crate::custom::tags;
+use crate::custom::time_travel::{self, TimeTravel, Travel};
+use crate::custom::ui_top::{TOP_COLUMNS, TOP_SORT_DEFAULT};
+use crate::custom::viewport::Viewport;
+use crate::custom::watched::{ValueSeries, WatchPattern};
 use crate::shared::util::StatefulList;
 
+pub use vault_metrics::{BucketSet, LogEntry, LogFormat, NodeMetrics, NodeStatus, OUTLIER_TIMELINE, TIMELINES};
+use vault_metrics::{health_score, HealthWeights, MetricLevel, Stat, Thresholds, PARSER_PLAIN};
+
 pub static DEBUG_WINDOW_NAME: &str = "Debug Window";
+///! Messages kept by the debug window (--debug-window), oldest dropped first
+pub const DEBUG_WINDOW_LINES_MAX: usize = 1000;
+
+///! Seconds between redraws of the summary in low power mode (--low-power)
+pub const LOW_POWER_REDRAW_SECS: u64 = 5;
 
 use std::sync::Mutex;
 lazy_static::lazy_static! {
@@ -50,16 +108,50 @@ pub struct App {
 	pub opt: Opt,
 	pub dash_state: DashState,
 	pub monitors: HashMap<String, LogMonitor>,
+	///! The monitor key of each line source which isn't one, or None if none matches
+	pub source_keys: HashMap<String, Option<String>>,
 	pub logfile_with_focus: String,
-	pub logfiles: MuxedLines,
+	///! Lines from every source, see ingest
+	pub lines: Lines,
+	pub lines_tx: LineSender,
 	pub logfile_names: Vec<String>,
+	pub replaced_logfiles: Option<mpsc::UnboundedReceiver<String>>,
+	///! Length of each logfile at each check (None if missing), see ingest::WatchCheck and file_check::Presence
+	pub logfile_lengths: Option<mpsc::UnboundedReceiver<(String, Option<u64>)>>,
+	pub latest_logfiles: Option<mpsc::UnboundedReceiver<String>>,
+	///! Sizes of --data-dir directories, by monitor index
+	pub data_dir_sizes: Option<mpsc::UnboundedReceiver<(usize, Option<u64>)>>,
+	#[cfg(feature = "exporters")]
+	pub snapshot_summary: Option<SharedSummary>,
+	pub keymap: KeyMap,
+	pub hooks: Vec<HookConfig>,
+	///! Rules from the config file, each counted for every monitor (see Escalation)
+	pub alert_rules: Vec<AlertRule>,
+	pub journal: Option<Journal>,
+	///! Where node events are exported, with --events-ndjson
+	#[cfg(feature = "exporters")]
+	pub event_log: Option<EventLog>,
+	///! Where every node's new lines are merged, with --write-merged
+	#[cfg(feature = "exporters")]
+	pub merged_log: Option<MergedLog>,
+	///! Weights of the health score, from the config file
+	pub health_weights: HealthWeights,
+	///! Warn and crit levels of metrics, from the config file
+	pub thresholds: Thresholds,
+	///! Commands offered by the node control popup ('x')
+	pub node_control: NodeControl,
+	pub session: Session,
+	pub watchdog: Watchdog,
+	pub shutdown: Shutdown,
+	///! The --self-log is to be opened as a monitor ('L'), which happens on the next tick
+	pub self_log_requested: bool,
 }
 
 impl App {
 	pub async fn new() -> Result<App, std::io::Error> {
-		let mut opt = Opt::from_args();
+		let mut opt = Opt::from_args_and_env();
 
-		if opt.files.is_empty() {
+		if opt.files.is_empty() && opt.attach.is_none() && opt.latest_in.is_none() && opt.listen_syslog.is_none() {
 			println!("{}: no logfile(s) specified.", Opt::clap().get_name());
 			return exit_with_usage("missing logfiles");
 		}
@@ -72,14 +164,44 @@ impl App {
 			return exit_with_usage("invalid parameter");
 		}
 
-		let mut dash_state = DashState::new();
-		dash_state.debug_window = opt.debug_window;
-		if opt.debug_dashboard {
-			dash_state.main_view = DashViewMain::DashDebug;
+		if let (Some(since), Some(until)) = (opt.since, opt.until) {
+			if since.0 > until.0 {
+				println!("--since {} is after --until {}", since.0, until.0);
+				return exit_with_usage("invalid parameter");
+			}
+		}
+
+		let config = Config::load(&opt)?;
+		opt.tag.extend(tags::config_tags(&config.tags));
+		if let Some(socket) = opt.attach.clone() {
+			return App::new_attached(opt, config, &socket);
+		}
+
+		let mut latest_logfile = None;
+		let mut latest_logfiles = None;
+		if let Some(dir) = opt.latest_in.clone() {
+			match file_check::newest_file_in(&dir) {
+				Some(logfile) => {
+					opt.files.push(logfile.clone());
+					latest_logfiles = Some(file_check::spawn_latest_checker(dir, logfile.clone()));
+					latest_logfile = Some(logfile);
+				}
+				None => {
+					return Err(Error::new(
+						ErrorKind::NotFound,
+						format!("--latest-in: no logfile found in {}", dir),
+					))
+				}
+			}
 		}
 
+		let mut recovered = match &opt.journal {
+			Some(journal) if !opt.ignore_existing => journal::recover(journal)?,
+			_ => HashMap::new(),
+		};
+
 		let mut monitors: HashMap<String, LogMonitor> = HashMap::new();
-		let mut logfiles = MuxedLines::new()?;
+		let (lines_tx, lines) = ingest::channel();
 		let mut debug_logfile_name = String::new();
 		let mut logfile_names = Vec::<String>::new();
 
@@ -97,14 +219,20 @@ impl App {
 			None
 		};
 
-		println!("Loading {} files...", opt.files.len());
+		match startup::show(&opt, &opt.files)? {
+			Choice::Load => {}
+			Choice::SkipHistory => opt.ignore_existing = true,
+			Choice::Quit => std::process::exit(0),
+		}
 		let mut first_logfile = String::new();
+		let mut loads = Vec::new();
 		for f in &opt.files {
-			println!("file: {}", f);
 			if first_logfile.is_empty() {
 				first_logfile = f.to_string();
 			}
 			let mut monitor = LogMonitor::new(&opt, f.to_string(), opt.lines_max);
+			monitor.escalations = config.alert_rules.iter().map(Escalation::new).collect();
+			monitor.follows_latest = latest_logfile.as_ref() == Some(f);
 			if opt.debug_window && monitor.index == 0 {
 				if let Some(named_file) = debug_logfile {
 					*DEBUG_LOGFILE.lock().unwrap() = Some(named_file);
@@ -112,23 +240,36 @@ impl App {
 				}
 			}
 			if opt.ignore_existing {
-				logfile_names.push(f.to_string());
-				monitors.insert(f.to_string(), monitor);
-			} else {
-				match monitor.load_logfile(&mut dash_state) {
-					Ok(()) => {
-						logfile_names.push(f.to_string());
-						monitors.insert(f.to_string(), monitor);
-					}
-					Err(e) => {
-						println!("...failed: {}", e);
-						return Err(e);
-					}
-				}
+				monitor.logfile_position = std::fs::metadata(f).map_or(0, |metadata| metadata.len());
+				monitor.next_provenance = Provenance::at(monitor.logfile_position);
+			} else if let Some(lines) = opt.tail_lines {
+				// A logfile which doesn't exist yet is read from its start once it does
+				monitor.logfile_position = backfill::tail_offset(f, lines).unwrap_or(0);
+				monitor.next_provenance = Provenance::at(monitor.logfile_position);
 			}
+			let state = recovered.remove(f);
+			loads.push((monitor, state));
+		}
 
-			match logfiles.add_file(&f).await {
-				Ok(_) => (),
+		// Existing lines are loaded by a pool of workers, as a multi-GB history is slow to parse
+		let loaded = match opt.ignore_existing {
+			true => Ok(loads.into_iter().map(|(monitor, _)| monitor).collect()),
+			false => backfill::load_logfiles(loads, backfill::workers(opt.load_workers)),
+		};
+		let loaded = match loaded {
+			Ok(loaded) => loaded,
+			Err(e) => {
+				println!("...failed: {}", e);
+				return Err(e);
+			}
+		};
+		for mut monitor in loaded {
+			let f = monitor.logfile.clone();
+			match ingest::watch_logfile(&f, monitor.logfile_position, opt.poll_interval, lines_tx.clone()).await {
+				Ok((watch, watcher)) => {
+					monitor.watch = watch;
+					monitor.watcher = Some(watcher);
+				}
 				Err(e) => {
 					println!("ERROR: {}", e);
 					println!(
@@ -137,18 +278,58 @@ impl App {
 					return Err(e);
 				}
 			}
+			logfile_names.push(f.clone());
+			monitors.insert(f, monitor);
 		}
 
-		let activate_debug_dashboard = opt.debug_dashboard;
-		let mut app = App {
-			opt,
-			dash_state,
-			monitors,
-			logfile_with_focus: first_logfile.clone(),
-			logfiles,
-			logfile_names,
+		let mut data_dirs = Vec::new();
+		for data_dir in opt.data_dir.iter() {
+			match monitors.values().find(|monitor| data_dir.is_for(monitor.index + 1, &monitor.logfile)) {
+				Some(monitor) => data_dirs.push((monitor.index, data_dir.path.clone())),
+				None => {
+					println!("--data-dir {}: no node numbered, or with logfile or logfile directory, {}", data_dir.path.display(), data_dir.name);
+					return exit_with_usage("invalid parameter");
+				}
+			}
+		}
+		let data_dir_sizes = match data_dirs.is_empty() {
+			true => None,
+			false => Some(data_dir::spawn_sampler(data_dirs)),
 		};
-		app.update_timelines(Some(Utc::now()));
+
+		#[cfg(feature = "remote")]
+		if let Some(addr) = &opt.listen_syslog {
+			println!("Listening for syslog at {}", addr);
+			let limits = syslog::SyslogLimits {
+				sources_max: opt.syslog_sources_max,
+				connections_max: opt.syslog_connections_max,
+			};
+			syslog::listen(addr, limits, lines_tx.clone()).map_err(|e| Error::new(e.kind(), format!("--listen-syslog {}: {}", addr, e)))?;
+		}
+
+		let activate_debug_dashboard = opt.debug_dashboard;
+		let (replaced_logfiles, logfile_lengths) = file_check::spawn_checker(logfile_names.clone());
+		let mut app = App::with_monitors(opt, config, monitors, logfile_names, (lines_tx, lines));
+		app.replaced_logfiles = Some(replaced_logfiles);
+		app.logfile_lengths = Some(logfile_lengths);
+		app.latest_logfiles = latest_logfiles;
+		app.data_dir_sizes = data_dir_sizes;
+		if activate_debug_dashboard {
+			app.dash_state.main_view = DashViewMain::DashDebug;
+		}
+		app.start()?;
+		app.clear_events(); // Hooks are for live events, not those loaded
+		#[cfg(feature = "exporters")]
+		if let Some(events_ndjson) = &app.opt.events_ndjson {
+			app.event_log = Some(EventLog::open(events_ndjson)?);
+		}
+		#[cfg(feature = "exporters")]
+		if let Some(write_merged) = &app.opt.write_merged {
+			app.merged_log = Some(MergedLog::open(write_merged).map_err(|e| Error::new(e.kind(), format!("--write-merged {}: {}", write_merged, e)))?);
+		}
+		if let Some(journal) = &app.opt.journal {
+			app.journal = Some(Journal::create(journal, &app.monitors)?);
+		}
 
 		if !first_logfile.is_empty() {
 			app.dash_state.dash_node_focus = first_logfile.clone();
@@ -159,12 +340,646 @@ impl App {
 		} else {
 			app.set_logfile_with_focus(first_logfile);
 		}
+		app.focus_shown();
+		app.watchdog.ready();
 		Ok(app)
 	}
 
+	///! Create an App whose monitors are restored from, and updated by, a vdash daemon
+	fn new_attached(opt: Opt, config: Config, socket: &str) -> Result<App, std::io::Error> {
+		println!("Attaching to vdash daemon at {}...", socket);
+		let (lines_tx, lines) = ingest::channel();
+		let snapshots = daemon::attach(socket, lines_tx.clone())?;
+
+		let mut monitors: HashMap<String, LogMonitor> = HashMap::new();
+		let mut logfile_names = Vec::<String>::new();
+		for snapshot in snapshots {
+			println!("file: {}", snapshot.logfile);
+			let mut monitor = LogMonitor::new(&opt, snapshot.logfile.clone(), opt.lines_max);
+			monitor.escalations = config.alert_rules.iter().map(Escalation::new).collect();
+			monitor.content = StatefulList::with_items(snapshot.content);
+			// Where lines are in the logfile is known only to the daemon
+			monitor.provenance = vec![Provenance::default(); monitor.content.items.len()].into();
+			monitor.next_provenance = Provenance::default();
+			if monitor.content.items.len() > 0 {
+				monitor
+					.content
+					.state
+					.select(Some(monitor.content.items.len() - 1));
+			}
+			monitor.metrics = snapshot.metrics;
+			monitor.line_times.rebuild(&monitor.content.items, &monitor.metrics.assumed_offset);
+			monitor.watch = Watch::Daemon;
+			logfile_names.push(snapshot.logfile.clone());
+			monitors.insert(snapshot.logfile, monitor);
+		}
+
+		let first_logfile = match logfile_names.first() {
+			Some(logfile) => logfile.clone(),
+			None => return Err(Error::new(ErrorKind::Other, "vdash daemon has no logfiles")),
+		};
+
+		let mut app = App::with_monitors(opt, config, monitors, logfile_names, (lines_tx, lines));
+		app.hooks = Vec::new(); // Run by the daemon
+		app.dash_state.dash_node_focus = first_logfile.clone();
+		app.start()?;
+		app.set_logfile_with_focus(first_logfile);
+		app.focus_shown();
+		app.watchdog.ready();
+		Ok(app)
+	}
+
+	///! An App of monitors as set up by new or new_attached, with the settings
+	///! of opt and config, which has yet to start: it has no session, watches
+	///! no logfiles and serves nothing
+	fn with_monitors(
+		opt: Opt,
+		config: Config,
+		monitors: HashMap<String, LogMonitor>,
+		logfile_names: Vec<String>,
+		(lines_tx, lines): (LineSender, Lines),
+	) -> App {
+		let mut dash_state = DashState::new();
+		dash_state.debug_window = opt.debug_window;
+		dash_state.no_color = opt.no_color;
+		dash_state.clock_skew_secs = opt.clock_skew_secs;
+		dash_state.apdex_ms = opt.apdex_ms;
+		dash_state.node_widgets = config.node_panel.widgets;
+		if !opt.watch.is_empty() && !dash_state.node_widgets.contains(&NodeWidget::Watched) {
+			dash_state.node_widgets.push(NodeWidget::Watched);
+		}
+		dash_state.columns = config.columns;
+		dash_state.tag_filter = opt.only_tag.clone();
+		dash_state.low_power = opt.low_power;
+		let watchdog = Watchdog::new(opt.liveness_file.clone());
+		App {
+			opt,
+			dash_state,
+			monitors,
+			source_keys: HashMap::new(),
+			logfile_with_focus: String::new(),
+			lines,
+			lines_tx,
+			logfile_names,
+			replaced_logfiles: None,
+			logfile_lengths: None,
+			latest_logfiles: None,
+			data_dir_sizes: None,
+			#[cfg(feature = "exporters")]
+			snapshot_summary: None,
+			keymap: KeyMap::new(),
+			hooks: config.hooks,
+			alert_rules: config.alert_rules,
+			journal: None,
+			#[cfg(feature = "exporters")]
+			event_log: None,
+			#[cfg(feature = "exporters")]
+			merged_log: None,
+			health_weights: config.health,
+			thresholds: config.thresholds,
+			node_control: NodeControl::new(config.actions),
+			session: Session::default(),
+			watchdog,
+			shutdown: Shutdown::new(),
+			self_log_requested: false,
+		}
+	}
+
+	///! Restore the session and start serving the snapshot, once the monitors are in place
+	fn start(&mut self) -> Result<(), std::io::Error> {
+		self.session = Session::load();
+		self.restore_pins();
+		self.dash_state.splits = self.session.splits().clone();
+		self.update_timelines(Some(Utc::now()));
+		self.update_health();
+		self.start_snapshot_http()
+	}
+
+	///! An App monitoring the logfiles given by args as new would, but without
+	///! reading or watching them, whose lines and keys are given by tests (see
+	///! ui_tests)
+	#[cfg(test)]
+	pub fn for_test(args: &[&str]) -> App {
+		let opt = Opt::from_iter(args);
+		let mut monitors: HashMap<String, LogMonitor> = HashMap::new();
+		for (index, logfile) in opt.files.iter().enumerate() {
+			let mut monitor = LogMonitor::new(&opt, logfile.clone(), opt.lines_max);
+			// Numbered as in a run of its own, whatever other tests have created
+			monitor.index = index;
+			monitors.insert(logfile.clone(), monitor);
+		}
+		let logfile_names = opt.files.clone();
+		let first_logfile = logfile_names.first().cloned().unwrap_or_default();
+		let mut app = App::with_monitors(opt, Config::default(), monitors, logfile_names, ingest::channel());
+		app.dash_state.dash_node_focus = first_logfile.clone();
+		app.set_logfile_with_focus(first_logfile);
+		app
+	}
+
+	///! Wait for the next line from any source, returned as (logfile, line)
+	///!
+	///! Lines come from the monitored logfiles and syslog or, when attached,
+	///! from the daemon, each read on a task or thread of its own (see ingest).
+	pub fn next_line(&mut self) -> NextLine<'_> {
+		NextLine { lines: &mut self.lines }
+	}
+
+	///! Add a monitor for a line from a syslog host and app not seen before
+	///!
+	///! Call with the source of each line before it is journaled or ingested.
+	#[cfg(feature = "remote")]
+	pub fn add_syslog_monitor(&mut self, source: &str) {
+		if !syslog::is_syslog_source(source) || self.monitors.contains_key(source) {
+			return;
+		}
+		info!("Monitoring {}", source);
+		let mut monitor = LogMonitor::new(&self.opt, source.to_string(), self.opt.lines_max);
+		monitor.escalations = self.alert_rules.iter().map(Escalation::new).collect();
+		monitor.watch = Watch::Syslog;
+		monitor.next_provenance = Provenance::default();
+		match self.opt.syslog_parser.as_deref() {
+			Some(parser) if parser == PARSER_PLAIN => monitor.metrics.log_format = LogFormat::Plain,
+			Some(_) => monitor.metrics.log_format = LogFormat::Sniffing,
+			None => {}
+		}
+		self.logfile_names.push(source.to_string());
+		self.monitors.insert(source.to_string(), monitor);
+		if self.logfile_with_focus.is_empty() {
+			self.dash_state.dash_node_focus = source.to_string();
+			self.set_logfile_with_focus(source.to_string());
+		}
+	}
+
+	///! Time between regular redraws of the dashboard, longer in low power mode
+	pub fn redraw_interval(&self) -> std::time::Duration {
+		match self.dash_state.low_power {
+			true => std::time::Duration::from_secs(LOW_POWER_REDRAW_SECS),
+			false => std::time::Duration::from_secs(1),
+		}
+	}
+
+	///! Whether anything shown has changed since the dashboard was last drawn
+	///!
+	///! Only the focused node is shown by the node view, so other nodes can be
+	///! busy without causing a redraw. Timelines and statuses also change with
+	///! time, so the dashboard is redrawn every second regardless. In low power
+	///! mode only that regular redraw is made.
+	pub fn needs_redraw(&self) -> bool {
+		// Until the banner has been drawn and then cleared
+		if self.dash_state.celebration.is_some() {
+			return true;
+		}
+		if self.dash_state.low_power {
+			return false;
+		}
+		if self.opt.debug_window {
+			return true;
+		}
+		match self.dash_state.main_view {
+			DashViewMain::DashNode => self
+				.monitors
+				.values()
+				.any(|monitor| monitor.has_focus && (monitor.dirty || monitor.fade_due())),
+			DashViewMain::DashSummary | DashViewMain::DashTop => self.monitors.values().any(|monitor| monitor.dirty),
+			// Results are only updated when the search is run
+			DashViewMain::DashSearch => false,
+			DashViewMain::DashRegex | DashViewMain::DashTable => {
+				matches!(self.monitors.get(&self.logfile_with_focus), Some(monitor) if monitor.dirty)
+			}
+			DashViewMain::DashDebug => true,
+		}
+	}
+
+	///! Restore each monitor's pinned lines from the session
+	fn restore_pins(&mut self) {
+		for monitor in self.monitors.values_mut() {
+			monitor.pins = self.session.pins(&monitor.logfile);
+		}
+	}
+
+	///! Save each monitor's pinned lines to the session
+	fn save_pins(&mut self) {
+		for monitor in self.monitors.values() {
+			self.session.set_pins(&monitor.logfile, &monitor.pins);
+		}
+		self.save_session();
+	}
+
+	fn save_session(&mut self) {
+		if let Err(e) = self.session.save() {
+			error!("failed to save session: {}", e);
+		}
+	}
+
+	///! Notice the end of any floods which have stopped metrics only mode
+	pub fn update_line_rates(&mut self) {
+		for monitor in self.monitors.values_mut() {
+			monitor.update_line_rate();
+		}
+		let (gathered, undecoded) = self.monitors.values().fold((0, 0), |(gathered, undecoded), monitor| {
+			(gathered + monitor.metrics.lines_gathered, undecoded + monitor.metrics.lines_undecoded)
+		});
+		let (received, backlog) = (self.lines.received(), self.lines.backlog());
+		self.dash_state.self_metrics.sample(Instant::now(), received, backlog, gathered, undecoded);
+	}
+
+	///! Show an error from the event loop in a toast and carry on, returning the value if there was no error
+	pub fn report<T, E: std::fmt::Display>(&mut self, context: &str, result: Result<T, E>) -> Option<T> {
+		match result {
+			Ok(value) => Some(value),
+			Err(e) => {
+				self.report_error(AppError::new(context, e));
+				None
+			}
+		}
+	}
+
+	pub fn report_error(&mut self, error: AppError) {
+		warn!("{}", error); // Not error!(), which env_logger would write over the dashboard
+		self.dash_state._debug_window(&format!("error {}", error));
+		self.dash_state.error_toasts.push(error);
+	}
+
+	///! Score the health of each node, see vault_metrics::health
+	pub fn update_health(&mut self) {
+		let now = Utc::now();
+		for monitor in self.monitors.values_mut().filter(|monitor| monitor.gone_for(Instant::now()).is_none()) {
+			let disk_used = monitor.disk_used();
+			monitor.metric_levels = self.thresholds.check(&monitor.metrics, disk_used, now);
+			monitor.health = health_score(&monitor.metrics, &self.health_weights, disk_used, &monitor.metric_levels, now);
+			if let Some(travel) = &mut monitor.time_travel {
+				// Disk usage is only known now
+				travel.metric_levels = self.thresholds.check(&travel.metrics, None, travel.time);
+				travel.health = health_score(&travel.metrics, &self.health_weights, None, &travel.metric_levels, travel.time);
+			}
+		}
+	}
+
+	///! Sample whether each node is up, for its availability
+	pub fn update_availability(&mut self) {
+		let now = Instant::now();
+		for monitor in self.monitors.values_mut() {
+			let up = availability::is_up(monitor.metrics.status(), monitor.gone_for(now).is_some());
+			monitor.availability.sample(now, up);
+		}
+	}
+
+	///! Pass the results of node control actions which have completed to their nodes' events
+	pub fn collect_control_outputs(&mut self) {
+		for output in self.node_control.completed() {
+			if let Some(menu) = &mut self.dash_state.control_menu {
+				menu.complete(&output);
+			}
+			if let Some(monitor) = self.monitors.get_mut(&output.logfile) {
+				monitor.metrics.events.push(NodeEvent {
+					event: hooks::EVENT_CONTROL,
+					detail: output.detail(),
+				});
+				monitor.dirty = true;
+			}
+			let context = format!("control action '{}' for {}", output.command, output.logfile);
+			match output.succeeded {
+				true => info!("{}: {}", context, output.result_text()),
+				false => self.report_error(AppError::new(&context, output.result_text())),
+			}
+		}
+	}
+
+	///! Pass new node events, and any changes of node status, to the hooks, the alerts and any --events-ndjson file
+	///!
+	///! Events which flap are de-bounced, see FlapDetector.
+	pub fn run_hooks(&mut self) {
+		let now = Instant::now();
+		let time = Utc::now();
+		for monitor in self.monitors.values_mut() {
+			let status = monitor.metrics.status();
+			if matches!(monitor.last_status, Some(last_status) if last_status != status) {
+				monitor.metrics.events.push(NodeEvent {
+					event: hooks::EVENT_STATUS,
+					detail: status.label().to_string(),
+				});
+			}
+			monitor.last_status = Some(status);
+
+			let mut events: Vec<NodeEvent> = monitor.metrics.events.drain(..).collect();
+			events.extend(monitor.flapping.settle(now));
+			for event in events {
+				if let Some(event) = monitor.flapping.debounce(event, now) {
+					if self.opt.celebrate && Celebration::is_due(&event) {
+						self.dash_state.celebration = Some(Celebration::new(monitor.index + 1, &monitor.logfile, now));
+					}
+					hooks::run_hooks(&self.hooks, monitor.index + 1, &monitor.logfile, &event);
+					self.session.alerts_mut().raise(monitor.index + 1, &monitor.logfile, &event, time);
+					#[cfg(feature = "exporters")]
+					if let Some(event_log) = &self.event_log {
+						if let Err(e) = event_log.append(&EventRecord::new(monitor, &event, time)) {
+							warn!("failed to export event: {}", e);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	///! Finish writing before vdash exits, on 'q' or when a signal requests it
+	///!
+	///! Writes the final --journal checkpoint and the session, and refreshes the
+	///! --snapshot-http summary. Call before the terminal is restored, as this
+	///! exits anyway if it takes too long (see Shutdown).
+	pub fn shutdown(&mut self) {
+		info!("Shutting down");
+		self.shutdown.start_timeout();
+		self.watchdog.stopping();
+		self.update_snapshot_summary();
+		#[cfg(feature = "exporters")]
+		if let Some(merged_log) = &mut self.merged_log {
+			if let Err(e) = merged_log.flush(Utc::now(), true) {
+				error!("failed to write merged logfile: {}", e);
+			}
+		}
+		if let Some(journal) = self.journal.take() {
+			if let Err(e) = journal.close(&self.monitors) {
+				error!("failed to write final journal checkpoint: {}", e);
+			}
+		}
+		self.save_pins();
+	}
+
+	///! Hold a line just ingested for the --write-merged logfile
+	#[cfg_attr(not(feature = "exporters"), allow(unused_variables))]
+	pub fn merge_line(&mut self, source: &String, line: &str) {
+		#[cfg(feature = "exporters")]
+		{
+			if self.merged_log.is_none() {
+				return;
+			}
+			let (label, time) = match self.get_monitor_for_file_path(source) {
+				Some(monitor) if !monitor.is_debug_dashboard_log => (
+					monitor.label.clone().unwrap_or_else(|| format!("node {}", monitor.index + 1)),
+					monitor.metrics.most_recent,
+				),
+				_ => return,
+			};
+			if let Some(merged_log) = &mut self.merged_log {
+				merged_log.push(&label, time, line, Utc::now());
+			}
+		}
+	}
+
+	///! Write the lines held for the --write-merged logfile which are due
+	pub fn flush_merged(&mut self) {
+		#[cfg(feature = "exporters")]
+		if let Some(merged_log) = &mut self.merged_log {
+			let flushed = merged_log.flush(Utc::now(), false);
+			self.report("writing merged logfile", flushed);
+		}
+	}
+
+	///! Record a line in the --journal before it is ingested
+	///!
+	///! length is the bytes the line took in its logfile, as sent with it (see
+	///! ingest::Line), which may differ from its text's by a \r or bytes which
+	///! weren't UTF-8. Lines which weren't read from a logfile aren't journaled.
+	pub fn journal_line(&mut self, source: &String, line: &str, length: Option<u64>) -> Result<(), std::io::Error> {
+		let length = match length {
+			Some(length) if self.journal.is_some() => length,
+			_ => return Ok(()),
+		};
+		let (logfile, position) = match self.get_monitor_for_file_path(source) {
+			Some(monitor) => (monitor.logfile.clone(), monitor.logfile_position + length),
+			None => return Ok(()),
+		};
+		if let Some(journal) = &mut self.journal {
+			journal.append_line(&logfile, position, length, line, &self.monitors)?;
+		}
+		if let Some(monitor) = self.monitors.get_mut(&logfile) {
+			monitor.content_marks.record(monitor.logfile_position, line);
+			monitor.logfile_position = position;
+		}
+		Ok(())
+	}
+
+	///! Rewrite the --journal after monitors have been reloaded
+	fn checkpoint_journal(&mut self) -> Result<(), std::io::Error> {
+		if let Some(journal) = &mut self.journal {
+			journal.checkpoint(&self.monitors)?;
+		}
+		Ok(())
+	}
+
+	fn clear_events(&mut self) {
+		for monitor in self.monitors.values_mut() {
+			monitor.metrics.events.clear();
+			monitor.last_status = Some(monitor.metrics.status());
+		}
+	}
+
+	///! Reset and reload any monitors whose logfile has been replaced in place
+	///!
+	///! A logfile replaced by one which holds the lines already read, such as a
+	///! copy put back in its place, continues from where it was rather than being parsed again.
+	pub async fn reload_replaced_logfiles(&mut self) -> Result<(), std::io::Error> {
+		let mut reloaded = false;
+		if let Some(replaced_logfiles) = &mut self.replaced_logfiles {
+			while let Ok(logfile) = replaced_logfiles.try_recv() {
+				if let Some(monitor) = self.monitors.get_mut(&logfile) {
+					let marks = &monitor.content_marks;
+					if !marks.is_empty() && marks.are_held_by(&logfile, monitor.logfile_position) {
+						info!("Logfile {} was replaced by one holding the lines read, continuing", logfile);
+						continue;
+					}
+					info!("Reloading replaced logfile: {}", logfile);
+					monitor.reload(&self.opt, &mut self.dash_state)?;
+					// Followed afresh from the end of what was loaded, as the watch was of the old logfile
+					if monitor.watcher.is_some() {
+						let position = monitor.logfile_position;
+						monitor.watch_logfile(position, self.opt.poll_interval, self.lines_tx.clone()).await?;
+					}
+					monitor.metrics.events.clear();
+					reloaded = true;
+				}
+			}
+		}
+		if reloaded {
+			self.checkpoint_journal()?;
+		}
+		Ok(())
+	}
+
+	///! Check that each natively watched logfile's lines are arriving as it grows,
+	///! and notice any logfile which has gone (see file_check::Presence)
+	pub fn check_watches(&mut self) {
+		let now = Instant::now();
+		if let Some(logfile_lengths) = &mut self.logfile_lengths {
+			while let Ok((logfile, length)) = logfile_lengths.try_recv() {
+				if let Some(monitor) = self.monitors.get_mut(&logfile) {
+					let gone = monitor.gone_for(now).is_some();
+					monitor.presence.check(length.is_some(), now);
+					if monitor.gone_for(now).is_some() != gone {
+						match gone {
+							false => warn!("{} has been deleted and not recreated, so its metrics are frozen", logfile),
+							true => info!("{} has been recreated", logfile),
+						}
+						monitor.dirty = true;
+					}
+					let length = match length {
+						Some(length) => length,
+						None => continue,
+					};
+					let missing_changes = monitor.watch_check.is_missing_changes();
+					monitor.watch_check.check(length);
+					if monitor.watch == Watch::Native && monitor.watch_check.is_missing_changes() != missing_changes {
+						if !missing_changes {
+							warn!("{} is growing but no lines are arriving, try --poll-interval", logfile);
+						}
+						monitor.dirty = true;
+					}
+				}
+			}
+		}
+	}
+
+	///! Switch the --latest-in monitor to any newer logfile in its directory
+	///!
+	///! The monitor keeps its timelines, marking the switch on them, but its
+	///! content is replaced by that of the new logfile.
+	pub async fn follow_latest_logfile(&mut self) -> Result<(), std::io::Error> {
+		let mut newest = None;
+		if let Some(latest_logfiles) = &mut self.latest_logfiles {
+			while let Ok(logfile) = latest_logfiles.try_recv() {
+				newest = Some(logfile);
+			}
+		}
+		let newest = match newest {
+			Some(newest) => newest,
+			None => return Ok(()),
+		};
+		let previous = match self.monitors.iter().find(|(_, monitor)| monitor.follows_latest) {
+			Some((logfile, _)) => logfile.clone(),
+			None => return Ok(()),
+		};
+
+		info!("Switching from {} to newest logfile {}", previous, newest);
+		if let Some(mut monitor) = self.monitors.remove(&previous) {
+			// The previous logfile's lines would otherwise keep arriving, under its name
+			monitor.stop_watching();
+			monitor.logfile = newest.clone();
+			monitor.content = StatefulList::with_items(vec![]);
+			monitor.line_times = LineTimes::default();
+			monitor.provenance.clear();
+			monitor.live_lines = 0;
+			monitor.logfile_position = 0;
+			monitor.content_marks.clear();
+			monitor.metrics.logfile_switch(Utc::now());
+			if !self.opt.ignore_existing {
+				monitor.load_logfile(&mut self.dash_state)?;
+			}
+			let position = monitor.logfile_position;
+			monitor.watch_logfile(position, self.opt.poll_interval, self.lines_tx.clone()).await?;
+			monitor.metrics.events.clear();
+			self.monitors.insert(newest.clone(), monitor);
+		}
+
+		for logfile in self.logfile_names.iter_mut().filter(|logfile| **logfile == previous) {
+			*logfile = newest.clone();
+		}
+		if self.dash_state.dash_node_focus == previous {
+			self.dash_state.dash_node_focus = newest.clone();
+		}
+		if self.logfile_with_focus == previous {
+			self.logfile_with_focus = newest;
+		}
+		self.checkpoint_journal()
+	}
+
+	///! Open the --self-log as a monitor when requested ('L'), and focus it
+	///!
+	///! vdash's own log is then shown like any node's, so that what vdash is
+	///! doing can be watched from the dashboard.
+	pub async fn open_self_log(&mut self) -> Result<(), std::io::Error> {
+		if !std::mem::take(&mut self.self_log_requested) {
+			return Ok(());
+		}
+		let self_log = match &self.opt.self_log {
+			Some(self_log) => self_log.to_string_lossy().to_string(),
+			None => return Ok(()),
+		};
+		if !self.monitors.contains_key(&self_log) {
+			info!("Monitoring vdash's own log {}", self_log);
+			let mut monitor = LogMonitor::new(&self.opt, self_log.clone(), self.opt.lines_max);
+			monitor.escalations = self.alert_rules.iter().map(Escalation::new).collect();
+			monitor.load_logfile(&mut self.dash_state)?;
+			let position = monitor.logfile_position;
+			monitor.watch_logfile(position, self.opt.poll_interval, self.lines_tx.clone()).await?;
+			monitor.metrics.events.clear(); // Hooks are for live events, not those loaded
+			self.logfile_names.push(self_log.clone());
+			self.monitors.insert(self_log.clone(), monitor);
+		}
+		set_main_view(DashViewMain::DashNode, self);
+		self.dash_state.dash_node_focus = self_log.clone();
+		self.set_logfile_with_focus(self_log);
+		Ok(())
+	}
+
+	///! Move each monitor's timelines on to now, apart from those of logfiles which have gone
 	pub fn update_timelines(&mut self, now: Option<DateTime<Utc>>) {
+		let gone_now = Instant::now();
 		for (_monitor_file, monitor) in self.monitors.iter_mut() {
-			monitor.metrics.update_timelines(now);
+			if monitor.gone_for(gone_now).is_none() {
+				monitor.metrics.update_timelines(now);
+			}
+		}
+	}
+
+	///! Stop monitoring the focused node, if its logfile has gone (see file_check::Presence)
+	pub fn close_gone_monitor(&mut self) {
+		let logfile = self.logfile_with_focus.clone();
+		match self.monitors.get(&logfile) {
+			Some(monitor) if monitor.gone_for(Instant::now()).is_some() => {}
+			Some(_) => {
+				let context = format!("closing {}", logfile);
+				self.report_error(AppError::new(&context, "only a node whose logfile has gone can be closed"));
+				return;
+			}
+			None => return,
+		}
+
+		info!("Closing {}, whose logfile has gone", logfile);
+		self.change_focus_next();
+		if let Some(mut monitor) = self.monitors.remove(&logfile) {
+			monitor.stop_watching();
+		}
+		self.logfile_names.retain(|name| *name != logfile);
+		self.source_keys.retain(|_, key| key.as_ref() != Some(&logfile));
+		if self.dash_state.dash_node_focus == logfile {
+			self.dash_state.dash_node_focus = self.logfile_with_focus.clone();
+		}
+		if self.logfile_with_focus == logfile {
+			self.logfile_with_focus = String::new();
+		}
+		self.focus_shown();
+		let checkpointed = self.checkpoint_journal();
+		self.report("writing journal", checkpointed);
+	}
+
+	fn start_snapshot_http(&mut self) -> Result<(), std::io::Error> {
+		#[cfg(feature = "exporters")]
+		if let Some(addr) = &self.opt.snapshot_http {
+			println!("Serving summary snapshot at http://{}/", addr);
+			self.snapshot_summary = Some(snapshot::serve(addr, self.opt.snapshot_token.clone())?);
+			self.update_snapshot_summary();
+		}
+		Ok(())
+	}
+
+	///! Refresh the summary served by --snapshot-http
+	pub fn update_snapshot_summary(&mut self) {
+		#[cfg(feature = "exporters")]
+		if let Some(shared_summary) = &self.snapshot_summary {
+			let summary = Summary::new(self);
+			if let Ok(mut shared_summary) = shared_summary.lock() {
+				*shared_summary = summary;
+			}
 		}
 	}
 
@@ -173,25 +988,40 @@ impl App {
 			monitor.update_chunk_store_fsstats();
 			update_chunk_store_stats(&monitor.chunk_store_pathbuf, &mut monitor.chunk_store);
 		}
+		if let Some(data_dir_sizes) = &mut self.data_dir_sizes {
+			while let Ok((index, size)) = data_dir_sizes.try_recv() {
+				if let Some(monitor) = self.monitors.values_mut().find(|monitor| monitor.index == index) {
+					monitor.data_dir_size = size;
+				}
+			}
+		}
 	}
 
-	pub fn get_monitor_for_file_path(&mut self, logfile: &String) -> Option<&mut LogMonitor> {
-		let mut monitor_for_path = None;
-		for (monitor_file, monitor) in self.monitors.iter_mut() {
-			if monitor_file.eq(logfile) {
-				monitor_for_path = Some(monitor);
-				break;
-			}
-			use std::env::current_dir;
-			if let Ok(current_dir) = current_dir() {
-				let logfile_path = Path::new(logfile.as_str());
-				if current_dir.join(monitor_file).eq(&logfile_path) {
-					monitor_for_path = Some(monitor);
-					break;
+	///! The monitor for a line's source, which may name its logfile differently
+	///!
+	///! Sources are normally the logfile as given, but failing an exact match
+	///! the source and each logfile are compared normalised (as absolute paths
+	///! with symlinks resolved and, on Windows, ignoring case), and the match
+	///! remembered. A source which matches nothing is warned of once.
+	pub fn get_monitor_for_file_path(&mut self, source: &String) -> Option<&mut LogMonitor> {
+		if self.monitors.contains_key(source) {
+			return self.monitors.get_mut(source);
+		}
+		let key = match self.source_keys.get(source) {
+			Some(None) => None,
+			Some(Some(key)) if self.monitors.contains_key(key) => Some(key.clone()),
+			_ => {
+				let path = ingest::normalise_path(source);
+				let key = self.monitors.keys().find(|logfile| ingest::normalise_path(logfile) == path).cloned();
+				match &key {
+					Some(key) => info!("Lines from {} are for {}", source, key),
+					None => warn!("Lines from {} match no monitored logfile, so are ignored", source),
 				}
+				self.source_keys.insert(source.clone(), key.clone());
+				key
 			}
-		}
-		return monitor_for_path;
+		};
+		self.monitors.get_mut(&key?)
 	}
 
 	pub fn get_debug_dashboard_logfile(&mut self) -> Option<String> {
@@ -226,14 +1056,6 @@ impl App {
 			None => (),
 		}
 
-		if logfile_name == DEBUG_WINDOW_NAME {
-			self.dash_state.debug_window_has_focus = true;
-			self.logfile_with_focus = logfile_name.clone();
-			return;
-		} else {
-			self.dash_state.debug_window_has_focus = false;
-		}
-
 		if let Some(focus_monitor) = (&mut self.monitors).get_mut(&logfile_name) {
 			focus_monitor.has_focus = true;
 			self.logfile_with_focus = logfile_name.clone();
@@ -242,27 +1064,59 @@ impl App {
 		};
 	}
 
+	///! The logfiles of the nodes shown, in order, which is all of them unless filtered by tag
+	pub fn shown_logfile_names(&self) -> Vec<String> {
+		self.logfile_names
+			.iter()
+			.filter(|logfile| {
+				self.monitors
+					.get(*logfile)
+					.is_some_and(|monitor| tags::shows(&self.dash_state.tag_filter, &monitor.tags))
+			})
+			.cloned()
+			.collect()
+	}
+
+	///! Show only the nodes with the next tag, or every node after the last tag
+	pub fn cycle_tag_filter(&mut self) {
+		let tag_filter = tags::next_filter(&self.dash_state.tag_filter, self.monitors.values().flat_map(|monitor| monitor.tags.iter()));
+		info!("Tag filter: {}", tag_filter.as_deref().unwrap_or("(none)"));
+		self.dash_state.tag_filter = tag_filter;
+		self.focus_shown();
+	}
+
+	///! Move the focus to the first node shown if the focused node is hidden by the tag filter
+	fn focus_shown(&mut self) {
+		let shown = self.shown_logfile_names();
+		if shown.contains(&self.logfile_with_focus) {
+			return;
+		}
+		if let Some(first) = shown.first() {
+			self.dash_state.dash_node_focus = first.clone();
+			self.set_logfile_with_focus(first.clone());
+		}
+	}
+
 	pub fn change_focus_next(&mut self) {
 		if self.dash_state.main_view == DashViewMain::DashDebug {
 			return;
 		}
 
+		let logfile_names = self.shown_logfile_names();
+		if logfile_names.is_empty() {
+			return;
+		}
 		let mut next_i = 0;
-		for (i, name) in self.logfile_names.iter().enumerate() {
+		for (i, name) in logfile_names.iter().enumerate() {
 			if name == &self.logfile_with_focus {
-				if i < self.logfile_names.len() - 1 {
+				if i < logfile_names.len() - 1 {
 					next_i = i + 1;
 				}
 				break;
 			}
 		}
 
-		if next_i == 0 && self.opt.debug_window && self.logfile_with_focus != DEBUG_WINDOW_NAME {
-			self.set_logfile_with_focus(DEBUG_WINDOW_NAME.to_string());
-			return;
-		}
-
-		let logfile = self.logfile_names[next_i].to_string();
+		let logfile = logfile_names[next_i].to_string();
 		self.set_logfile_with_focus(logfile.clone());
 
 		if let Some(debug_logfile) = self.get_debug_dashboard_logfile() {
@@ -277,9 +1131,13 @@ impl App {
 			return;
 		}
 
-		let len = self.logfile_names.len();
+		let logfile_names = self.shown_logfile_names();
+		if logfile_names.is_empty() {
+			return;
+		}
+		let len = logfile_names.len();
 		let mut previous_i = len - 1;
-		for (i, name) in self.logfile_names.iter().enumerate() {
+		for (i, name) in logfile_names.iter().enumerate() {
 			if name == &self.logfile_with_focus {
 				if i > 0 {
 					previous_i = i - 1;
@@ -288,15 +1146,7 @@ impl App {
 			}
 		}
 
-		if self.opt.debug_window
-			&& previous_i == len - 1
-			&& self.logfile_with_focus != DEBUG_WINDOW_NAME
-		{
-			self.set_logfile_with_focus(DEBUG_WINDOW_NAME.to_string());
-			return;
-		}
-
-		let logfile = self.logfile_names[previous_i].to_string();
+		let logfile = logfile_names[previous_i].to_string();
 		self.set_logfile_with_focus(logfile.clone());
 
 		if let Some(debug_logfile) = self.get_debug_dashboard_logfile() {
@@ -306,22 +1156,368 @@ impl App {
 		}
 	}
 
-	pub fn handle_arrow_up(&mut self) {
-		if let Some(monitor) = self.get_monitor_with_focus() {
-			do_bracketed_next_previous(&mut monitor.content, false);
-		} else if self.opt.debug_window {
-			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, false);
+	///! Move the focus through the widgets of the view, wrapping at either end
+	pub fn change_focus_widget(&mut self, steps: isize) {
+		let order = self.dash_state.focus_order();
+		self.dash_state.focus = focus::step(&order, self.dash_state.focus, steps);
+	}
+
+	pub fn handle_arrow_up(&mut self) {
+		match self.dash_state.focus {
+			Focus::Summary => self.change_focus_previous(),
+			Focus::Timeline => self.scale_timeline_up(),
+			Focus::DebugWindow => do_bracketed_next_previous(&mut self.dash_state.debug_window_list, false),
+			Focus::Logfile | Focus::ParserView => {
+				if let Some(monitor) = self.get_monitor_with_focus() {
+					do_bracketed_next_previous(&mut monitor.content, false);
+				}
+			}
+		}
+	}
+
+	pub fn handle_arrow_down(&mut self) {
+		match self.dash_state.focus {
+			Focus::Summary => self.change_focus_next(),
+			Focus::Timeline => self.scale_timeline_down(),
+			Focus::DebugWindow => do_bracketed_next_previous(&mut self.dash_state.debug_window_list, true),
+			Focus::Logfile | Focus::ParserView => {
+				if let Some(monitor) = self.get_monitor_with_focus() {
+					do_bracketed_next_previous(&mut monitor.content, true);
+				}
+			}
+		}
+	}
+
+	pub fn handle_scroll_top(&mut self) {
+		if self.dash_state.focus == Focus::DebugWindow {
+			do_bracketed_first_last(&mut self.dash_state.debug_window_list, false);
+		} else if let Some(monitor) = self.get_monitor_with_focus() {
+			do_bracketed_first_last(&mut monitor.content, false);
+		} else if self.opt.debug_window {
+			do_bracketed_first_last(&mut self.dash_state.debug_window_list, false);
+		}
+	}
+
+	pub fn handle_scroll_bottom(&mut self) {
+		if self.dash_state.focus == Focus::DebugWindow {
+			do_bracketed_first_last(&mut self.dash_state.debug_window_list, true);
+		} else if let Some(monitor) = self.get_monitor_with_focus() {
+			do_bracketed_first_last(&mut monitor.content, true);
+		} else if self.opt.debug_window {
+			do_bracketed_first_last(&mut self.dash_state.debug_window_list, true);
+		}
+	}
+
+	///! Perform an action from the keymap count times (Action::Quit is left to the caller)
+	pub fn handle_action(&mut self, action: Action, count: usize) {
+		// Only the summary is shown in low power mode, so keys for other views would act unseen
+		if self.dash_state.low_power
+			&& !matches!(action, Action::ToggleLowPower | Action::CycleTagFilter | Action::ViewAlerts | Action::AlertsKey(_))
+		{
+			return;
+		}
+		// '<' and '>' sort the top view, and move the selected split of the node view
+		let action = match (action, &self.dash_state.main_view) {
+			(Action::TopSortNext, DashViewMain::DashNode) => Action::SplitGrow,
+			(Action::TopSortPrevious, DashViewMain::DashNode) => Action::SplitShrink,
+			_ => action,
+		};
+		self.dash_state.splits.marked = matches!(action, Action::SplitNext | Action::SplitGrow | Action::SplitShrink);
+		for _ in 0..count {
+			match action {
+				Action::Quit => {}
+				Action::ViewSummary => set_main_view(DashViewMain::DashSummary, self),
+				Action::ViewNode => set_main_view(DashViewMain::DashNode, self),
+				Action::ViewDebug => set_main_view(DashViewMain::DashDebug, self),
+				Action::ViewTop => set_main_view(DashViewMain::DashTop, self),
+				Action::ViewTable => set_main_view(DashViewMain::DashTable, self),
+				Action::ViewRegex => {
+					set_main_view(DashViewMain::DashRegex, self);
+					self.keymap.enter(InputContext::Panel(Panel::Regex));
+				}
+				Action::RegexEdit(key) => self.dash_state.regex_playground.handle(key),
+				Action::ViewSearch => {
+					set_main_view(DashViewMain::DashSearch, self);
+					self.keymap.enter(InputContext::Panel(Panel::Search));
+				}
+				Action::SearchEdit(key) => {
+					if self.dash_state.search.handle(key) {
+						let all_monitors = &self.monitors;
+						let monitors: Vec<&LogMonitor> = self
+							.shown_logfile_names()
+							.iter()
+							.filter_map(|logfile| all_monitors.get(logfile))
+							.filter(|monitor| !monitor.is_debug_dashboard_log)
+							.collect();
+						let debug_window = Some(&self.dash_state.debug_window_list.items).filter(|_| self.opt.debug_window);
+						self.dash_state.search.run(&monitors, debug_window.map(Vec::as_slice));
+					}
+				}
+				Action::CycleTagFilter => self.cycle_tag_filter(),
+				Action::EditFilter => {
+					if let Some(monitor) = self.get_monitor_with_focus() {
+						let editor = FilterEditor::new(&monitor.filter);
+						self.dash_state.filter_editor = Some(editor);
+						self.keymap.enter(InputContext::Popup(Popup::Filter));
+					}
+				}
+				Action::FilterEdit(key) => {
+					let closed = match &mut self.dash_state.filter_editor {
+						Some(editor) => editor.handle(key),
+						None => Some(None),
+					};
+					if let Some(filter) = closed {
+						self.dash_state.filter_editor = None;
+						self.keymap.leave(InputContext::Popup(Popup::Filter));
+						if let (Some(filter), Some(monitor)) = (filter, self.get_monitor_with_focus()) {
+							monitor.set_filter(filter);
+						}
+					}
+				}
+				Action::GotoTime => {
+					if self.get_monitor_with_focus().is_some() {
+						self.dash_state.goto_prompt = Some(GotoPrompt::new());
+						self.keymap.enter(InputContext::Popup(Popup::Goto));
+					}
+				}
+				Action::GotoEdit(key) => {
+					let closed = match &mut self.dash_state.goto_prompt {
+						Some(prompt) => prompt.handle(key),
+						None => Some(None),
+					};
+					if let Some(time) = closed {
+						self.dash_state.goto_prompt = None;
+						self.keymap.leave(InputContext::Popup(Popup::Goto));
+						if let (Some(time), Some(monitor)) = (time, self.get_monitor_with_focus()) {
+							monitor.goto_time(time);
+						}
+					}
+				}
+				Action::ShowLine => {
+					let detail = self.get_monitor_with_focus().and_then(|monitor| {
+						let selected = monitor.content.state.selected()?;
+						let provenance = monitor.provenance.get(selected).copied().unwrap_or_default();
+						let mut detail = LineDetail::new(monitor.content.items.get(selected)?, &monitor.logfile, provenance);
+						detail.link_source(monitor.metrics.running_version.as_deref());
+						Some(detail)
+					});
+					if let Some(detail) = detail {
+						self.dash_state.line_detail = Some(detail);
+						self.keymap.enter(InputContext::Popup(Popup::LineDetail));
+					}
+				}
+				Action::LineDetailKey(key) => {
+					let closed = match &mut self.dash_state.line_detail {
+						Some(detail) => detail.handle(key),
+						None => true,
+					};
+					if closed {
+						self.dash_state.line_detail = None;
+						self.keymap.leave(InputContext::Popup(Popup::LineDetail));
+					}
+				}
+				Action::TimeTravel => {
+					let timeline_steps = self.opt.timeline_steps;
+					let column = TIMELINES[self.dash_state.active_timeline].1;
+					if let Some(monitor) = self.get_monitor_with_focus() {
+						monitor.start_time_travel(timeline_steps, column);
+						if monitor.time_travel.is_some() {
+							self.keymap.enter(InputContext::Panel(Panel::TimeTravel));
+						}
+					}
+					self.update_health();
+				}
+				Action::TimeTravelKey(key) => match time_travel::travel_command(key) {
+					Some(Travel::Step(columns)) => {
+						let column = TIMELINES[self.dash_state.active_timeline].1;
+						if let Some(monitor) = self.get_monitor_with_focus() {
+							monitor.time_travel_by(column * columns);
+						}
+						self.update_health();
+					}
+					Some(Travel::ZoomIn) => self.scale_timeline_up(),
+					Some(Travel::ZoomOut) => self.scale_timeline_down(),
+					Some(Travel::Close) => {
+						if let Some(monitor) = self.get_monitor_with_focus() {
+							monitor.time_travel = None;
+							monitor.dirty = true;
+						}
+						self.keymap.leave(InputContext::Panel(Panel::TimeTravel));
+					}
+					None => {}
+				},
+				Action::NodeControl => {
+					let actions = &self.node_control.actions;
+					if let Some(monitor) = self.monitors.get(&self.logfile_with_focus) {
+						self.dash_state.control_menu = Some(ControlMenu::new(&monitor.logfile, monitor.index + 1, actions));
+						self.keymap.enter(InputContext::Popup(Popup::NodeControl));
+					}
+				}
+				Action::NodeControlKey(key) => {
+					let result = match (&mut self.dash_state.control_menu, self.monitors.get(&self.logfile_with_focus)) {
+						(Some(menu), Some(monitor)) => {
+							let actions = &self.node_control.actions;
+							let command = |index: usize| {
+								actions[index].command(monitor.index + 1, &monitor.logfile, &monitor.metrics.node_name).join(" ")
+							};
+							menu.handle(key, command)
+						}
+						_ => Some(MenuResult::Close),
+					};
+					match result {
+						Some(MenuResult::Run(index)) => {
+							if let Some(monitor) = self.monitors.get(&self.logfile_with_focus) {
+								info!("Running control action '{}' for {}", self.node_control.actions[index].name, monitor.logfile);
+								self.node_control.run(index, monitor.index + 1, &monitor.logfile, &monitor.metrics.node_name);
+							}
+						}
+						Some(MenuResult::Close) => {
+							self.dash_state.control_menu = None;
+							self.keymap.leave(InputContext::Popup(Popup::NodeControl));
+						}
+						None => {}
+					}
+				}
+				Action::ViewAlerts => {
+					self.dash_state.alert_list = Some(AlertList::new());
+					self.keymap.enter(InputContext::Popup(Popup::Alerts));
+				}
+				Action::AlertsKey(key) => {
+					let count = self.session.alerts().len();
+					let command = match &mut self.dash_state.alert_list {
+						Some(list) => list.handle(key, count),
+						None => Some(AlertListCommand::Close),
+					};
+					match command {
+						Some(AlertListCommand::Acknowledge(index)) => {
+							self.session.alerts_mut().acknowledge(index);
+							self.save_session();
+						}
+						Some(AlertListCommand::AcknowledgeAll) => {
+							self.session.alerts_mut().acknowledge_all();
+							self.save_session();
+						}
+						Some(AlertListCommand::Close) => {
+							self.dash_state.alert_list = None;
+							self.keymap.leave(InputContext::Popup(Popup::Alerts));
+						}
+						None => {}
+					}
+				}
+				Action::TopSortNext => self.dash_state.top_sort = (self.dash_state.top_sort + 1) % TOP_COLUMNS.len(),
+				Action::TopSortPrevious => {
+					self.dash_state.top_sort = (self.dash_state.top_sort + TOP_COLUMNS.len() - 1) % TOP_COLUMNS.len()
+				}
+				Action::SplitNext => self.dash_state.splits.select_next(),
+				Action::SplitGrow => self.dash_state.splits.move_selected(1),
+				Action::SplitShrink => self.dash_state.splits.move_selected(-1),
+				Action::SplitReset => self.dash_state.splits.reset(),
+				Action::ScaleTimelineUp => self.scale_timeline_up(),
+				Action::ScaleTimelineDown => self.scale_timeline_down(),
+				Action::ScrollUp => self.handle_arrow_up(),
+				Action::ScrollDown => self.handle_arrow_down(),
+				Action::ScrollTop => self.handle_scroll_top(),
+				Action::ScrollBottom => self.handle_scroll_bottom(),
+				Action::FocusNext => self.change_focus_next(),
+				Action::FocusPrevious => self.change_focus_previous(),
+				Action::FocusWidgetNext => self.change_focus_widget(1),
+				Action::FocusWidgetPrevious => self.change_focus_widget(-1),
+				Action::TogglePause => {
+					if let Some(monitor) = self.get_monitor_with_focus() {
+						if let Err(e) = monitor.toggle_pause() {
+							error!("failed to resume ingest: {}", e);
+						}
+					}
+				}
+				Action::TogglePin | Action::ClearPins => {
+					if let Some(monitor) = self.get_monitor_with_focus() {
+						match action {
+							Action::TogglePin => monitor.toggle_pin(),
+							_ => monitor.pins.clear(),
+						}
+						monitor.dirty = true;
+					}
+					self.save_pins();
+				}
+				Action::ToggleMetricsOnly => {
+					if let Some(monitor) = self.get_monitor_with_focus() {
+						monitor.toggle_metrics_only();
+					}
+				}
+				Action::ToggleAligned => self.dash_state.aligned = !self.dash_state.aligned,
+				Action::ToggleParserView => {
+					self.dash_state.parser_view = !self.dash_state.parser_view;
+					self.dash_state.fit_focus();
+				}
+				Action::ToggleDebugCapture => self.dash_state.debug_capture = !self.dash_state.debug_capture,
+				Action::ToggleLowPower => self.dash_state.low_power = !self.dash_state.low_power,
+				Action::CloseGone => self.close_gone_monitor(),
+				Action::OpenSelfLog => match self.opt.self_log {
+					Some(_) => self.self_log_requested = true,
+					None => self.report_error(AppError::new("opening vdash's log", "no --self-log FILE was given")),
+				},
+				Action::ToggleNodeConfig => {
+					self.dash_state.show_node_config = !self.dash_state.show_node_config;
+					if let Some(monitor) = self.get_monitor_with_focus() {
+						monitor.dirty = true;
+					}
+				}
+				Action::CopyRewardKey => self.copy_reward_key(),
+				Action::CycleGrouping => self.dash_state.summary_groups.cycle(),
+				Action::ToggleGroup => {
+					if let Some(monitor) = self.monitors.get(&self.logfile_with_focus) {
+						let group = self.dash_state.summary_groups.group_of(monitor);
+						self.dash_state.summary_groups.toggle(&group);
+					}
+				}
+				Action::ToggleAllGroups => {
+					let groups = &self.dash_state.summary_groups;
+					let names: Vec<Option<String>> = self
+						.shown_logfile_names()
+						.iter()
+						.filter_map(|logfile| self.monitors.get(logfile))
+						.map(|monitor| groups.group_of(monitor))
+						.collect();
+					self.dash_state.summary_groups.toggle_all(names);
+				}
+			}
+		}
+		if matches!(action, Action::SplitGrow | Action::SplitShrink | Action::SplitReset) {
+			self.save_splits();
+		}
+	}
+
+	///! A mouse press or drag, which can move a split of the node view
+	pub fn handle_mouse(&mut self, input: MouseInput) {
+		if self.dash_state.low_power
+			|| self.dash_state.main_view != DashViewMain::DashNode
+			|| self.keymap.context() != InputContext::Normal
+		{
+			return;
+		}
+		if self.dash_state.splits.mouse(input) {
+			self.save_splits();
 		}
 	}
 
-	pub fn handle_arrow_down(&mut self) {
-		if let Some(monitor) = self.get_monitor_with_focus() {
-			do_bracketed_next_previous(&mut monitor.content, true);
-		} else if self.opt.debug_window {
-			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, true);
+	///! Put the focused node's reward key on the terminal's clipboard
+	fn copy_reward_key(&mut self) {
+		let key = self.get_monitor_with_focus().and_then(|monitor| monitor.metrics.reward_key.clone());
+		let result = match &key {
+			Some(key) => clipboard::copy(key).map_err(|e| e.to_string()),
+			None => Err(String::from("the node hasn't logged a reward key")),
+		};
+		match result {
+			Ok(()) => info!("Copied reward key {} to the clipboard", key.unwrap_or_default()),
+			Err(e) => self.report_error(AppError::new("copying the reward key", e)),
 		}
 	}
 
+	fn save_splits(&mut self) {
+		self.session.set_splits(&self.dash_state.splits);
+		self.save_session();
+	}
+
 	pub fn scale_timeline_up(&mut self) {
 		if self.dash_state.active_timeline == 0 {
 			return;
@@ -337,6 +1533,23 @@ impl App {
 	}
 }
 
+///! Future returned by App::next_line()
+///!
+///! Unlike the future of Lines::recv(), this borrows only the line sources,
+///! and has no drop glue, so the main loop can still use App in the branches
+///! of select!
+pub struct NextLine<'a> {
+	lines: &'a mut Lines,
+}
+
+impl Future for NextLine<'_> {
+	type Output = Option<Result<(String, String), std::io::Error>>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.lines.poll_recv(cx)
+	}
+}
+
 /// Move selection forward or back without wrapping at start or end
 fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
 	if next {
@@ -358,6 +1571,15 @@ fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
 	}
 }
 
+/// Move selection to the first or last item
+fn do_bracketed_first_last(list: &mut StatefulList<String>, last: bool) {
+	if list.items.is_empty() {
+		return;
+	}
+	let index = if last { list.items.len() - 1 } else { 0 };
+	list.state.select(Some(index));
+}
+
 fn exit_with_usage(reason: &str) -> Result<App, std::io::Error> {
 	println!(
 		"Try '{} --help' for more information.",
@@ -403,14 +1625,14 @@ pub struct ChunkStoreStat {
 
 pub struct ChunkStoreStatsAll {
 	pub chunk_store_stats: Vec<ChunkStoreStat>,
-	pub total_used: u64,
+	pub total_used: Stat<u64>,
 }
 
 impl ChunkStoreStatsAll {
 	pub fn new() -> ChunkStoreStatsAll {
 		ChunkStoreStatsAll {
 			chunk_store_stats: Vec::<ChunkStoreStat>::new(),
-			total_used: 0,
+			total_used: Stat::default(),
 		}
 	}
 }
@@ -419,7 +1641,7 @@ const USED_SPACE_FILENAME: &str = "used_space";
 
 pub fn update_chunk_store_stats(chunk_stores_path: &PathBuf, chunk_store_stats: &mut ChunkStoreStatsAll) {
 	chunk_store_stats.chunk_store_stats = Vec::<ChunkStoreStat>::new();
-	chunk_store_stats.total_used = 0;
+	let mut total_used = 0;
 
 	// let path_str = match chunk_stores_path.to_str() {
 	// 	Some(path_str) => path_str,
@@ -441,7 +1663,7 @@ pub fn update_chunk_store_stats(chunk_stores_path: &PathBuf, chunk_store_stats:
 					let mut buffer = vec![];
 					let _ = record.read_to_end(&mut buffer).unwrap();
 					if let Ok(size) = bincode::deserialize::<u64>(&buffer) {
-						chunk_store_stats.total_used += size;
+						total_used += size;
 						space_used = size;
 					};
 					// debug_log!(format!("stat {} used {} bytes", &spec.dir_name, space_used).as_str());
@@ -450,25 +1672,124 @@ pub fn update_chunk_store_stats(chunk_stores_path: &PathBuf, chunk_store_stats:
 				Err(_) => {},
 		}
 	}
+	chunk_store_stats.total_used.set(total_used);
 }
 
 use fs2::{statvfs, FsStats};
 
 pub struct LogMonitor {
 	pub index: usize,
+	///! From --label, shown in the node's heading
+	pub label: Option<String>,
+	///! From --tag and [tags] in the config file
+	pub tags: Vec<String>,
 	pub content: StatefulList<String>,
 	max_content: usize, // Limit number of lines in content
+	retain: Option<Duration>, // Or keep lines within this time of the most recent
+	pub fade: Option<Duration>, // Lines are shown dimmer as they age, for this long
+	///! When the ages of lines were last shown, as they change with time
+	pub faded_at: Option<DateTime<Utc>>,
 	pub has_focus: bool,
 	pub logfile: String,
 	pub chunk_store_fsstats: Option<FsStats>,
 	pub chunk_store_pathbuf: PathBuf,
 	pub chunk_store: ChunkStoreStatsAll,
+	///! Size of the node's --data-dir, when measured
+	pub data_dir_size: Option<u64>,
 	pub metrics: NodeMetrics,
-	pub metrics_status: StatefulList<String>,
 	pub is_debug_dashboard_log: bool,
+	pub paused: Option<PausedIngest>,
+	pub last_pause: Option<String>,
+	pub metrics_only: Option<MetricsOnly>,
+	pub last_metrics_only: Option<String>,
+	flood_lines: usize,
+	line_rate: LineRate,
+	///! Lines per second, updated each second
+	pub lines_per_second: Stat<u64>,
+	pub last_status: Option<NodeStatus>,
+	///! De-bounces events passed to hooks
+	pub flapping: FlapDetector,
+	pub follows_latest: bool,
+	latency_pattern: Option<Regex>,
+	///! Patterns given by --watch, and the values each has captured
+	pub watch_patterns: Vec<WatchPattern>,
+	pub watched: Vec<ValueSeries>,
+	///! Lines matching each alert rule, counted to raise an alert for a cluster of them
+	pub escalations: Vec<Escalation>,
+	///! Lines pinned above the logfile view, oldest first
+	pub pins: Vec<String>,
+	///! Which lines are added to content
+	pub filter: LineFilter,
+	///! Level of the most recent line with one, for the lines after it
+	filter_level: Option<String>,
+	///! Changed since the dashboard was last drawn
+	pub dirty: bool,
+	///! Fields changed in each line of content, kept up to date as lines are added
+	pub changed_fields: StateDiff,
+	///! The time of each line of content, kept up to date as lines are added
+	pub line_times: LineTimes,
+	///! Where each line of content is in the logfile
+	pub provenance: VecDeque<Provenance>,
+	///! Where the next line read will be in the logfile
+	next_provenance: Provenance,
+	///! The lines of content in view, as only those are drawn
+	pub viewport: Viewport,
+	///! Lines loaded are limited to those logged within --since and --until
+	load_since: Option<DateTime<Utc>>,
+	load_until: Option<DateTime<Utc>>,
+	///! Bytes of the logfile read, from where --journal recovery resumes
+	pub logfile_position: u64,
+	///! The first and last lines read, to recognise the logfile's content when resuming
+	pub content_marks: ContentMarks,
+	///! Lines at the end of content which arrived live, rather than loaded at startup
+	pub live_lines: usize,
+	///! How lines reach this monitor
+	pub watch: Watch,
+	///! Stops the task or thread following the logfile, if this monitor started one
+	watcher: Option<WatchHandle>,
+	pub watch_check: WatchCheck,
+	///! Whether the logfile has been deleted, see file_check::Presence
+	pub presence: Presence,
+	///! Health score from 0 to 100, see App::update_health()
+	pub health: u8,
+	///! The percentage of this run of vdash the node has been up, see App::update_availability()
+	pub availability: Availability,
+	///! Metrics checked against the config's thresholds, see App::update_health()
+	pub metric_levels: Vec<MetricLevel>,
+	///! Metrics as of an earlier time, shown instead of the live metrics
+	pub time_travel: Option<TimeTravel>,
+}
+
+///! Lines held back while a LogMonitor's ingest is paused
+pub struct PausedIngest {
+	lines: Vec<String>,
+	dropped: usize,
+}
+
+///! Limit on lines held while paused, beyond which lines are dropped
+static PAUSED_LINES_MAX: usize = 10000;
+
+///! Limit on lines pinned for each node
+const PINS_MAX: usize = 5;
+
+///! Shades of grey a line passes through as it ages (see --fade-minutes)
+pub const FADE_STEPS: u8 = 14;
+
+///! Lines which update metrics but aren't added to content, to keep the
+///! dashboard responsive when a node floods its logfile
+pub struct MetricsOnly {
+	///! Started by a flood (--flood-lines) rather than by the user
+	automatic: bool,
+	elided: usize,
 }
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+///! Live lines counted since start, to detect a flood
+struct LineRate {
+	start: Instant,
+	lines: usize,
+}
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 static NEXT_MONITOR: AtomicUsize = AtomicUsize::new(0);
 
 impl LogMonitor {
@@ -489,19 +1810,135 @@ impl LogMonitor {
 
 		LogMonitor {
 			index,
+			label: NodeLabel::find(&opt.label, index + 1, &f),
+			tags: tags::node_tags(&opt.tag, index + 1, &f),
 			logfile: f,
 			max_content: max_lines,
+			retain: opt.retain_minutes.map(|minutes| Duration::minutes(minutes as i64)),
+			fade: match opt.fade_minutes {
+				0 => None,
+				minutes => Some(Duration::minutes(minutes as i64)),
+			},
+			faded_at: None,
 			chunk_store_fsstats: None,
 			chunk_store_pathbuf,
 			chunk_store: ChunkStoreStatsAll::new(),
-			metrics: NodeMetrics::new(&opt),
+			data_dir_size: None,
+			metrics: LogMonitor::new_metrics(opt),
 			content: StatefulList::with_items(vec![]),
 			has_focus: false,
-			metrics_status: StatefulList::with_items(vec![]),
 			is_debug_dashboard_log,
+			paused: None,
+			last_pause: None,
+			metrics_only: None,
+			last_metrics_only: None,
+			flood_lines: opt.flood_lines,
+			line_rate: LineRate {
+				start: Instant::now(),
+				lines: 0,
+			},
+			lines_per_second: Stat::default(),
+			last_status: None,
+			flapping: FlapDetector::new(),
+			follows_latest: false,
+			latency_pattern: opt.latency_pattern.clone(),
+			watch_patterns: opt.watch.clone(),
+			watched: opt.watch.iter().map(|_| ValueSeries::default()).collect(),
+			escalations: Vec::new(),
+			pins: Vec::new(),
+			filter: LineFilter::new(),
+			filter_level: None,
+			dirty: true,
+			changed_fields: StateDiff::default(),
+			line_times: LineTimes::default(),
+			provenance: VecDeque::new(),
+			next_provenance: Provenance::at(0),
+			viewport: Viewport::default(),
+			load_since: opt.since.map(|since| since.0),
+			load_until: opt.until.map(|until| until.0),
+			logfile_position: 0,
+			content_marks: ContentMarks::default(),
+			live_lines: 0,
+			watch: Watch::Native,
+			watcher: None,
+			watch_check: WatchCheck::new(),
+			presence: Presence::default(),
+			health: 100,
+			availability: Availability::default(),
+			metric_levels: Vec::new(),
+			time_travel: None,
+		}
+	}
+
+	fn new_metrics(opt: &Opt) -> NodeMetrics {
+		let mut metrics = NodeMetrics::new(opt.timeline_steps, opt.assume_offset);
+		if opt.parser == PARSER_PLAIN {
+			metrics.log_format = LogFormat::Plain;
+		}
+		metrics
+	}
+
+	///! Follow the logfile from position, stopping any task or thread already following it
+	pub async fn watch_logfile(
+		&mut self,
+		position: u64,
+		poll_interval: Option<std::time::Duration>,
+		tx: LineSender,
+	) -> std::io::Result<()> {
+		self.stop_watching();
+		let (watch, watcher) = ingest::watch_logfile(&self.logfile, position, poll_interval, tx).await?;
+		self.watch = watch;
+		self.watcher = Some(watcher);
+		self.watch_check = WatchCheck::new();
+		Ok(())
+	}
+
+	pub fn stop_watching(&mut self) {
+		if let Some(watcher) = self.watcher.take() {
+			watcher.cancel();
 		}
 	}
 
+	///! Discard content and metrics, and load the logfile again
+	pub fn reload(&mut self, opt: &Opt, dash_state: &mut DashState) -> std::io::Result<()> {
+		self.content = StatefulList::with_items(vec![]);
+		self.line_times = LineTimes::default();
+		self.provenance.clear();
+		self.live_lines = 0;
+		self.metrics = LogMonitor::new_metrics(opt);
+		self.logfile_position = 0;
+		self.content_marks.clear();
+		self.load_logfile(dash_state)
+	}
+
+	///! Restore state recovered from a --journal and read the rest of the logfile
+	///!
+	///! If the logfile no longer holds the lines journaled, such as when it is
+	///! shorter or has been rewritten, it has been replaced, so is loaded from
+	///! the start instead.
+	///! Bytes read from the logfile are added to loaded as it loads.
+	pub fn recover(&mut self, state: Recovered, loaded: &AtomicU64) -> std::io::Result<()> {
+		if !state.marks.are_held_by(&self.logfile, state.position) {
+			info!("Logfile {} no longer holds the lines journaled, loading it again", self.logfile);
+			return self.load(loaded);
+		}
+
+		self.content = StatefulList::with_items(state.content);
+		// The journal doesn't record where lines were, until reading resumes at its position
+		self.provenance = vec![Provenance::default(); self.content.items.len()].into();
+		self.next_provenance = Provenance::default();
+		self.live_lines = 0;
+		self.metrics = state.metrics;
+		self.metrics.loading = true;
+		self.line_times.rebuild(&self.content.items, &self.metrics.assumed_offset);
+		for line in state.lines.iter() {
+			self.append_to_content(line)?;
+		}
+		self.logfile_position = state.position;
+		self.content_marks = state.marks;
+		self.load(loaded)
+	}
+
 	pub fn update_chunk_store_fsstats(&mut self) {
 		self.chunk_store_fsstats = match statvfs(&self.chunk_store_pathbuf) {
 			Ok(fsstats) => Some(fsstats),
@@ -509,25 +1946,52 @@ impl LogMonitor {
 		};
 	}
 
+	///! The fraction of the chunk store's filesystem which is used, if known
+	pub fn disk_used(&self) -> Option<f64> {
+		self.chunk_store_fsstats.as_ref().and_then(|fsstats| {
+			if fsstats.total_space() == 0 {
+				return None;
+			}
+			Some(1.0 - fsstats.available_space() as f64 / fsstats.total_space() as f64)
+		})
+	}
+
+	///! Load the logfile from logfile_position, which is the start unless recovering or with --tail-lines
 	pub fn load_logfile(&mut self, dash_state: &mut DashState) -> std::io::Result<()> {
-		use std::io::{BufRead, BufReader};
+		self.load(&AtomicU64::new(0))?;
+		if self.is_debug_dashboard_log {
+			for line in self.content.items.iter() {
+				dash_state._debug_window(line);
+			}
+		}
+		Ok(())
+	}
+
+	///! Load the logfile from logfile_position, adding the bytes read to loaded as it goes
+	///!
+	///! This only touches the monitor, so monitors can load in parallel (see backfill).
+	pub fn load(&mut self, loaded: &AtomicU64) -> std::io::Result<()> {
+		use std::io::{BufReader, Seek, SeekFrom};
 
 		let f = File::open(self.logfile.to_string());
-		let f = match f {
+		let mut f = match f {
 			Ok(file) => file,
-			Err(_e) => return Ok(()), // It's ok for a logfile not to exist yet
+			Err(_e) => {
+				self.metrics.loading = false;
+				return Ok(()); // It's ok for a logfile not to exist yet
+			}
 		};
 
 		self.update_chunk_store_fsstats();
-		let f = BufReader::new(f);
+		f.seek(SeekFrom::Start(self.logfile_position))?;
+		// What is skipped counts as loaded, for the progress shown
+		loaded.store(self.logfile_position, Ordering::Relaxed);
+		let mut f = BufReader::new(f);
 
-		for line in f.lines() {
-			let line = line.expect("Unable to read line");
-			self.append_to_content(&line)?;
-			if self.is_debug_dashboard_log {
-				dash_state._debug_window(&line);
-			}
-		}
+		self.metrics.loading = true;
+		self.next_provenance = Provenance::at(self.logfile_position);
+		let read = self.load_lines(&mut f, loaded);
+		self.metrics.loading = false;
 
 		if self.content.items.len() > 0 {
 			self.content
@@ -535,674 +1999,450 @@ impl LogMonitor {
 				.select(Some(self.content.items.len() - 1));
 		}
 
-		Ok(())
+		read
 	}
 
-	pub fn append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
-		if self.line_filter(&text) {
-			self._append_to_content(text)?; // Show in TUI
-			if self.is_debug_dashboard_log {
-				return Ok(());
+	///! Add each line read from f, as for spawn_logfile replacing any bytes which aren't UTF-8
+	fn load_lines(&mut self, f: &mut impl std::io::BufRead, loaded: &AtomicU64) -> std::io::Result<()> {
+		let mut in_window = self.load_since.is_none();
+		let mut bytes = Vec::<u8>::new();
+		loop {
+			bytes.clear();
+			let length = f.read_until(b'\n', &mut bytes)?;
+			if length == 0 {
+				break;
 			}
-			self.metrics.gather_metrics(&text)?;
+			let offset = self.logfile_position;
+			self.logfile_position += length as u64;
+			loaded.fetch_add(length as u64, Ordering::Relaxed);
+			let provenance = self.next_provenance;
+			let line = String::from_utf8_lossy(&bytes);
+			let line = line.trim_end_matches(&['\n', '\r'][..]);
+			self.content_marks.record(offset, line);
+			in_window = self.in_load_window(line, in_window);
+			if in_window {
+				self.append_to_content(line)?;
+			}
+			// The length read, which includes the line ending as it is
+			self.next_provenance = provenance.next(length as u64);
 		}
 		Ok(())
 	}
 
-	pub fn _append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
-		self.content.items.push(text.to_string());
-		let len = self.content.items.len();
-		if len > self.max_content {
-			self.content.items = self.content.items.split_off(len - self.max_content);
-		} else {
-			self.content.state.select(Some(len - 1));
+	///! Whether a line being loaded was logged within --since and --until
+	///!
+	///! Lines without a timestamp go with the line before, given as previous.
+	fn in_load_window(&self, line: &str, previous: bool) -> bool {
+		if self.load_since.is_none() && self.load_until.is_none() {
+			return true;
 		}
-		Ok(())
-	}
-
-	// Some logfile lines are too numerous to include so we ignore them
-	// Returns true if the line is to be processed
-	fn line_filter(&mut self, _line: &str) -> bool {
-		true
-	}
-}
-
-use regex::Regex;
-lazy_static::lazy_static! {
-	static ref LOG_LINE_PATTERN: Regex =
-		Regex::new(r"(?P<module>^\[[A-Z,a-z,_,-]*\]) (?P<category>[A-Z]{4,6}) (?P<time_string>[^ ]{35}) (?P<source>\[.*\]) (?P<message>.*)").expect("The regex failed to compile. This is a bug.");
-}
-
-#[derive(PartialEq)]
-pub enum NodeAgebracket {
-	Unknown,
-	Infant,
-	Adult,
-	Elder,
-}
-
-///! Maintains one or more 'marching bucket' histories for
-///! a given metric, each with its own duration and granularity.
-///!
-///! A BucketSet is used to hold the history of values with
-///! a given bucket_duration and maximum number of buckets.
-///!
-///! A BucketSet begins with a single bucket of fixed
-///! duration holding the initial metric value. New buckets
-///! are added as time progresses until the number of buckets
-///! covers the total duration of the BucketSet. At this
-///! point the oldest bucket is removed when a new bucket is
-///! added, so that the total duration remains constant and
-///! the specified maximum number of buckets is never
-///! exceeded.
-///!
-///! By adding more than one BucketSet, a given metric can be
-///! recorded for different durations and with different
-///! granularities. E.g. 60 * 1s buckets covers a minute
-///! and 60 * 1m buckets covers an hour, and so on.
-pub struct TimelineSet {
-	name: String,
-	bucket_sets: HashMap<&'static str, BucketSet>,
-}
-
-pub struct BucketSet {
-	pub bucket_time: Option<DateTime<Utc>>,
-	pub total_duration: Duration,
-	pub bucket_duration: Duration,
-	pub max_buckets: usize,
-	pub buckets: Vec<u64>,
-}
-
-impl TimelineSet {
-	pub fn new(name: String) -> TimelineSet {
-		TimelineSet {
-			name,
-			bucket_sets: HashMap::<&'static str, BucketSet>::new(),
+		match LogEntry::decode(line, &self.metrics.assumed_offset, self.metrics.most_recent).and_then(|entry| entry.time) {
+			Some(time) => {
+				!matches!(self.load_since, Some(since) if time < since)
+					&& !matches!(self.load_until, Some(until) if time > until)
+			}
+			None => previous,
 		}
 	}
 
-	pub fn get_name(&self) -> &String {
-		&self.name
-	}
-
-	pub fn add_bucket_set(&mut self, name: &'static str, duration: Duration, max_buckets: usize) {
-		self.bucket_sets
-			.insert(name, BucketSet::new(duration, max_buckets));
+	///! Pause ingest, holding back new lines, or resume by processing them
+	pub fn toggle_pause(&mut self) -> Result<(), std::io::Error> {
+		self.dirty = true;
+		match self.paused.take() {
+			None => {
+				self.paused = Some(PausedIngest {
+					lines: Vec::<String>::new(),
+					dropped: 0,
+				});
+				self.last_pause = None;
+			}
+			Some(paused) => {
+				for line in paused.lines.iter() {
+					self.append_to_content(line)?;
+				}
+				self.last_pause = Some(format!(
+					"resumed: {} lines batched, {} dropped",
+					paused.lines.len(),
+					paused.dropped
+				));
+			}
+		}
+		Ok(())
 	}
 
-	pub fn get_bucket_set(&mut self, bucket_set_name: &str) -> Option<&BucketSet> {
-		self.bucket_sets.get(bucket_set_name)
+	///! Describe the pause state for display
+	pub fn pause_status(&self) -> Option<String> {
+		match &self.paused {
+			Some(paused) => Some(format!(
+				"PAUSED: {} lines held, {} dropped",
+				paused.lines.len(),
+				paused.dropped
+			)),
+			None => self.last_pause.clone(),
+		}
 	}
 
-	///! Update all bucket_sets with new current time
+	///! Pin the selected line above the logfile view, or unpin it if pinned
 	///!
-	///! Call significantly more frequently than the smallest BucketSet duration
-	fn update_current_time(&mut self, new_time: Option<DateTime<Utc>>) {
-		for (_name, bs) in self.bucket_sets.iter_mut() {
-			if let Some(mut bucket_time) = bs.bucket_time {
-				if let Some(new_time) = new_time {
-					let mut end_time = bucket_time + bs.bucket_duration;
-
-					while end_time.lt(&new_time) {
-						// Start new bucket
-						bs.bucket_time = Some(end_time);
-						bucket_time = end_time;
-						end_time = bucket_time + bs.bucket_duration;
-
-						bs.buckets.push(0);
-						if bs.buckets.len() > bs.max_buckets {
-							bs.buckets.remove(0);
-						}
-					}
-				}
-			} else {
-				bs.bucket_time = new_time;
-			}
-		}
-	}
-
-	fn increment_value(&mut self, time: Option<DateTime<Utc>>) {
-		// debug_log!("increment_value()");
-		if let Some(time) = time {
-			for (_name, bs) in self.bucket_sets.iter_mut() {
-				// debug_log!(format!("name       : {}", _name).as_str());
-				let mut index = Some(bs.buckets.len() - 1);
-				// debug_log!(format!("time       : {}", time).as_str());
-				if let Some(bucket_time) = bs.bucket_time {
-				// debug_log!(format!("bucket_time: {}", bucket_time).as_str());
-					if time.lt(&bucket_time) {
-						// Use the closest bucket to this time
-						// debug_log!("increment (closest bucket)");
-						let time_difference = (bucket_time - time).num_nanoseconds();
-						let bucket_duration = bs.bucket_duration.num_nanoseconds();
-						if time_difference.and(bucket_duration).is_some() {
-							let buckets_behind = time_difference.unwrap() / bucket_duration.unwrap();
-							if buckets_behind as usize > bs.buckets.len() {
-								// debug_log!(format!("increment DISCARDED buckets_behind: {}", buckets_behind).as_str());
-								index = None;
-							} else {
-								// debug_log!(format!("increment INCLUDED buckets_behind: {}", buckets_behind).as_str());
-								index = Some(bs.buckets.len() - 1 - buckets_behind as usize);
-							}
-						}
-					}
-				}
-				if let Some(index) = index {
-					// debug_log!(format!("increment index: {}", index).as_str());
-					bs.buckets[index] += 1;
+	///! Pinning more than PINS_MAX lines unpins the oldest.
+	pub fn toggle_pin(&mut self) {
+		let line = match self.content.state.selected().and_then(|selected| self.content.items.get(selected)) {
+			Some(line) => line.clone(),
+			None => return,
+		};
+		match self.pins.iter().position(|pin| *pin == line) {
+			Some(pin) => {
+				self.pins.remove(pin);
+			}
+			None => {
+				self.pins.push(line);
+				if self.pins.len() > PINS_MAX {
+					self.pins.remove(0);
 				}
 			}
-		} else {
-			debug_log!("increment FAIL");
 		}
 	}
-}
 
-impl BucketSet {
-	pub fn new(bucket_duration: Duration, max_buckets: usize) -> BucketSet {
-		BucketSet {
-			bucket_duration,
-			max_buckets,
-			total_duration: bucket_duration * max_buckets as i32,
+	///! Filter the lines shown, including those already shown
+	///!
+	///! Lines the filter removes from content are not restored by a later filter.
+	pub fn set_filter(&mut self, filter: LineFilter) {
+		let shown = filter.shown(&self.content.items);
+		let historical = self.content.items.len() - self.live_lines;
+		self.live_lines = shown[historical..].iter().filter(|shown| **shown).count();
+		self.provenance = std::mem::take(&mut self.provenance)
+			.into_iter()
+			.zip(shown.iter())
+			.filter_map(|(provenance, shown)| if *shown { Some(provenance) } else { None })
+			.collect();
+		self.line_times.times = std::mem::take(&mut self.line_times.times)
+			.into_iter()
+			.zip(shown.iter())
+			.filter_map(|(time, shown)| if *shown { Some(time) } else { None })
+			.collect();
+		let items: Vec<String> = std::mem::take(&mut self.content.items)
+			.into_iter()
+			.zip(shown)
+			.filter_map(|(line, shown)| if shown { Some(line) } else { None })
+			.collect();
+		// A new list, as the old one's scroll offset may be beyond the lines left
+		self.content = StatefulList::with_items(items);
+		self.content.state.select(self.content.items.len().checked_sub(1));
+		self.changed_fields.rebuild(&self.content.items);
+		self.viewport = Viewport::default();
+		self.filter = filter;
+		self.dirty = true;
+	}
 
-			bucket_time: None,
-			buckets: vec![0; max_buckets],
+	///! A problem with how the logfile is watched, for the logfile's title
+	pub fn watch_status(&self) -> Option<String> {
+		match self.watch {
+			Watch::Native if self.watch_check.is_missing_changes() => {
+				Some(String::from("lines missed? try --poll-interval"))
+			}
+			Watch::Polled { fallback: true, .. } => Some(String::from("polling, couldn't watch")),
+			_ => None,
 		}
 	}
 
-	pub fn set_bucket_value(&mut self, value: u64) {
-		let index = self.buckets.len() - 1;
-		self.buckets[index] = value;
+	///! How long the logfile has been deleted without being recreated, once it is gone
+	pub fn gone_for(&self, now: Instant) -> Option<std::time::Duration> {
+		self.presence.gone_for(now)
 	}
 
-	pub fn increment_value(&mut self) {
-		let index = self.buckets.len() - 1;
-		self.buckets[index] += 1;
+	///! Warning to show when the logfile has gone, leaving the metrics frozen
+	pub fn gone_warning(&self) -> Option<String> {
+		let minutes = self.gone_for(Instant::now())?.as_secs() / 60;
+		Some(format!(
+			"Logfile deleted {}m ago and not recreated, so this node's metrics are frozen as they were. Press 'K' to close it.",
+			minutes
+		))
 	}
 
-	pub fn buckets(&self) -> &Vec<u64> {
-		&self.buckets
+	///! Select the line logged nearest to time
+	pub fn goto_time(&mut self, time: DateTime<Utc>) {
+		if let Some(index) = goto::nearest_line(&self.content.items, &self.metrics.assumed_offset, time) {
+			self.content.state.select(Some(index));
+			self.dirty = true;
+		}
 	}
 
-	pub fn buckets_mut(&mut self) -> &mut Vec<u64> {
-		&mut self.buckets
+	///! Show metrics as they were when the selected line was logged, checkpointing them each column, see time_travel
+	pub fn start_time_travel(&mut self, timeline_steps: usize, column: Duration) {
+		let selected = self.content.state.selected().and_then(|selected| self.content.items.get(selected));
+		let time = selected
+			.and_then(|line| LogEntry::decode(line, &self.metrics.assumed_offset, None))
+			.and_then(|entry| entry.time)
+			.unwrap_or_else(Utc::now);
+		self.time_travel = TimeTravel::new(&self.content.items, &self.metrics, timeline_steps, column, time);
+		self.dirty = true;
 	}
-}
 
-pub struct NodeMetrics {
-	pub node_started: Option<DateTime<Utc>>,
-	pub running_message: Option<String>,
-	pub running_version: Option<String>,
-	pub category_count: HashMap<String, usize>,
-	pub activity_history: Vec<ActivityEntry>,
-	pub log_history: Vec<LogEntry>,
-
-	pub puts_timeline: TimelineSet,
-	pub gets_timeline: TimelineSet,
-	pub errors_timeline: TimelineSet, // TODO add code to collect and display
-
-	pub most_recent: Option<DateTime<Utc>>,
-	pub agebracket: NodeAgebracket,
-	pub section_prefix: String,
-	pub node_age: usize,
-	pub node_name: String,
-	pub adults: usize,
-	pub elders: usize,
-	pub activity_gets: u64,
-	pub activity_puts: u64,
-	pub activity_errors: u64,
-
-	pub debug_logfile: Option<NamedTempFile>,
-	parser_output: String,
-}
-
-impl NodeMetrics {
-	fn new(opt: &Opt) -> NodeMetrics {
-		let mut puts_timeline = TimelineSet::new("PUTS".to_string());
-		let mut gets_timeline = TimelineSet::new("GETS".to_string());
-		let mut errors_timeline = TimelineSet::new("ERRORS".to_string());
-		for timeline in [&mut puts_timeline, &mut gets_timeline, &mut errors_timeline].iter_mut() {
-			for i in 0..TIMELINES.len() {
-				if let Some(spec) = TIMELINES.get(i) {
-					timeline.add_bucket_set(spec.0, spec.1, opt.timeline_steps);
-				}
-			}
+	pub fn time_travel_by(&mut self, by: Duration) {
+		if let Some(travel) = &mut self.time_travel {
+			travel.step(&self.content.items, by);
+			let time = travel.time;
+			self.goto_time(time);
 		}
-
-		let mut metrics = NodeMetrics {
-			// Start
-			node_started: None,
-			running_message: None,
-			running_version: None,
-
-			// Logfile entries
-			activity_history: Vec::<ActivityEntry>::new(),
-			log_history: Vec::<LogEntry>::new(),
-			most_recent: None,
-
-			// Timelines / Sparklines
-			puts_timeline,
-			gets_timeline,
-			errors_timeline,
-
-			// Counts
-			category_count: HashMap::new(),
-			activity_gets: 0,
-			activity_puts: 0,
-			activity_errors: 0,
-
-			// State (node)
-			agebracket: NodeAgebracket::Unknown,
-			section_prefix: String::from(""),
-			node_age: 0,
-			node_name: String::from(""),
-
-			// State (network)
-			adults: 0,
-			elders: 0,
-
-			// Debug
-			debug_logfile: None,
-			parser_output: String::from("-"),
-		};
-		metrics.update_timelines(Some(Utc::now()));
-		metrics
 	}
 
-	pub fn agebracket_string(&self) -> String {
-		match self.agebracket {
-			NodeAgebracket::Infant => "Infant".to_string(),
-			NodeAgebracket::Adult => "Adult".to_string(),
-			NodeAgebracket::Elder => "Elder".to_string(),
-			NodeAgebracket::Unknown => "Unknown".to_string(),
-		}
-	}
-
-	fn reset_metrics(&mut self) {
-		self.agebracket = NodeAgebracket::Infant;
-		self.section_prefix = String::from("");
-		self.node_age = 0;
-		self.node_name = String::from("");
-		self.adults = 0;
-		self.elders = 0;
-		self.activity_gets = 0;
-		self.activity_puts = 0;
-		self.activity_errors = 0;
-	}
-
-	///! Process a line from a SAFE Node logfile.
-	///! May add a LogEntry to the NodeMetrics::log_history vector.
-	///! Use a created LogEntry to update metrics.
-	pub fn gather_metrics(&mut self, line: &str) -> Result<(), std::io::Error> {
-		// For debugging LogEntry::decode()
-		let mut parser_result = format!("LogEntry::decode() failed on: {}", line);
-		if let Some(mut entry) = LogEntry::decode(line).or_else(|| self.parse_start(line)) {
-			if entry.time.is_none() {
-				entry.time = self.most_recent;
-			} else {
-				self.most_recent = entry.time;
+	///! Whether the filter shows text, a line being added to content
+	fn filter_shows(&mut self, text: &str) -> bool {
+		if let Some(level) = filter::line_level(text) {
+			if self.filter_level.as_deref() != Some(level) {
+				self.filter_level = Some(level.to_string());
 			}
-
-			self.update_timelines(self.most_recent);
-			self.parser_output = entry.parser_output.clone();
-			self.process_logfile_entry(&entry); // May overwrite self.parser_output
-			parser_result = self.parser_output.clone();
-			self.log_history.push(entry);
-
-			// TODO Trim log_history
 		}
-
-		// --debug-dashboard - prints parser results for a single logfile
-		// to a temp logfile which is displayed in the adjacent window.
-		debug_log!(&parser_result);
-
-		Ok(())
+		self.filter.is_empty() || self.filter.shows(text, self.filter_level.as_deref())
 	}
 
-	pub fn update_timelines(&mut self, now: Option<DateTime<Utc>>) {
-		for timeline in &mut [
-			&mut self.puts_timeline,
-			&mut self.gets_timeline,
-			&mut self.errors_timeline,
-		]
-		.iter_mut()
-		{
-			timeline.update_current_time(now);
+	pub fn filter_status(&self) -> Option<String> {
+		match self.filter.is_empty() {
+			true => None,
+			false => Some(format!("FILTER: {}", self.filter.describe())),
 		}
 	}
 
-	///! Returm a LogEntry and capture metadata for logfile node start:
-	///!	'Running safe-node v0.24.0'
-	pub fn parse_start(&mut self, line: &str) -> Option<LogEntry> {
-		let running_prefix = String::from("Running sn_node ");
-
-		if line.starts_with(&running_prefix) {
-			self.running_message = Some(line.to_string());
-			self.running_version = Some(line[running_prefix.len()..].to_string());
-			self.node_started = self.most_recent;
-			let parser_output = format!(
-				"START at {}",
-				self.most_recent
-					.map_or(String::from("None"), |m| format!("{}", m))
-			);
-
-			self.reset_metrics();
-			return Some(LogEntry {
-				logstring: String::from(line),
-				category: String::from("START"),
-				time: self.most_recent,
-				source: String::from(""),
-				message: line.to_string(),
-				parser_output,
-			});
+	///! Show only metrics from new lines, or show lines again
+	pub fn toggle_metrics_only(&mut self) {
+		match self.metrics_only {
+			None => self.start_metrics_only(false),
+			Some(_) => self.end_metrics_only(),
 		}
-
-		None
 	}
 
-	///! Process a logfile entry
-	///! Returns true if the line has been processed and can be discarded
-	pub fn process_logfile_entry(&mut self, entry: &LogEntry) -> bool {
-		return self.parse_data_response(
-			&entry,
-			"Running as Node: SendToSection [ msg: MsgEnvelope { message: QueryResponse { response: QueryResponse::",
-		) || self.parse_gets_and_puts(&entry) || self.parse_states(&entry);
+	fn start_metrics_only(&mut self, automatic: bool) {
+		self.dirty = true;
+		self.metrics_only = Some(MetricsOnly { automatic, elided: 0 });
+		self.last_metrics_only = None;
 	}
 
-	///! TODO: Review and update these tests
-	///! TODO: see forum conversation https://safenetforum.org/t/vdash-safe-node-dashboard-safe-vault-run-baby-fleming-t/32630/38
-	fn parse_gets_and_puts(&mut self, entry: &LogEntry) -> bool {
-		if entry.message.contains("Handling NodeDuty: ReadChunk") {
-			self.count_get(entry.time);
-			return true;
-		} else if entry.message.contains("Wrote data from message") {
-			self.count_put(entry.time);
-			return true;
-			// TODO: delete the following checks once the new test network is out
-		} else if entry.message.contains("Writing chunk succeeded") {
-			self.count_put(entry.time);
-			return true;
-		} else if entry.message.starts_with("MapStorage: Writing chunk PASSED") {
-			self.count_put(entry.time);
-			return true;
+	fn end_metrics_only(&mut self) {
+		self.dirty = true;
+		if let Some(metrics_only) = self.metrics_only.take() {
+			self.last_metrics_only = Some(format!("showing lines again, {} were not shown", metrics_only.elided));
 		}
-		return false;
 	}
 
-	///! Update data metrics from a handler response logfile entry
-	///! Returns true if the line has been processed and can be discarded
-	fn parse_data_response(&mut self, entry: &LogEntry, pattern: &str) -> bool {
-		if let Some(mut response_start) = entry.logstring.find(pattern) {
-			response_start += pattern.len();
-			let mut response = "";
-
-			if let Some(response_end) = entry.logstring[response_start..].find(",") {
-				response = entry.logstring.as_str()[response_start..response_start + response_end]
-					.as_ref();
-				if !response.is_empty() {
-					let activity_entry = ActivityEntry::new(entry, response);
-					self.activity_history.push(activity_entry);
-					self.parser_output = format!("node activity: {}", response);
-				}
-			}
-			if response.is_empty() {
-				self.parser_output = format!("failed to parse_data_response: {}", entry.logstring);
-			};
+	///! Count a live line, starting metrics only mode if the rate exceeds --flood-lines
+	fn count_live_line(&mut self) {
+		self.update_line_rate();
+		self.line_rate.lines += 1;
+		if self.flood_lines > 0 && self.line_rate.lines > self.flood_lines && self.metrics_only.is_none() {
+			info!("Logfile {} exceeded {} lines per second, showing metrics only", self.logfile, self.flood_lines);
+			self.start_metrics_only(true);
+		}
+	}
 
-			return true;
-		};
-		return false;
+	///! Start counting again each second, ending metrics only mode started by a
+	///! flood once the rate falls below half of --flood-lines
+	///!
+	///! Called on each tick as well as each line, so that the end of a flood is
+	///! noticed even if no more lines arrive.
+	pub fn update_line_rate(&mut self) {
+		let now = Instant::now();
+		let elapsed = now.duration_since(self.line_rate.start);
+		if elapsed < std::time::Duration::from_secs(1) {
+			return;
+		}
+		let per_second = self.line_rate.lines as f64 / elapsed.as_secs_f64();
+		if matches!(self.metrics_only, Some(MetricsOnly { automatic: true, .. })) && per_second < self.flood_lines as f64 / 2.0 {
+			self.end_metrics_only();
+		}
+		self.lines_per_second.set(per_second.round() as u64);
+		self.line_rate = LineRate { start: now, lines: 0 };
 	}
 
-	///! Capture state updates from a logfile entry
-	///! Returns true if the line has been processed and can be discarded
-	fn parse_states(&mut self, entry: &LogEntry) -> bool {
-		if entry.category.eq("ERROR") {
-			self.count_error(entry.time);
+	///! Whether lines have aged enough to be shown dimmer since last drawn
+	pub fn fade_due(&self) -> bool {
+		match (self.fade, self.faded_at) {
+			(Some(fade), Some(faded_at)) => Utc::now() - faded_at >= fade / FADE_STEPS as i32,
+			_ => false,
 		}
+	}
 
-		let &content = &entry.logstring.as_str();
-		if let Some(elders) = self.parse_usize("No. of Elders:", content) {
-			self.elders = elders;
-			self.parser_output = format!("ELDERS: {}", elders);
-			return true;
-		};
+	///! Describe metrics only mode for display
+	pub fn metrics_only_status(&self) -> Option<String> {
+		match &self.metrics_only {
+			Some(metrics_only) => Some(format!(
+				"{}: {} lines not shown",
+				if metrics_only.automatic { "FLOOD, METRICS ONLY" } else { "METRICS ONLY" },
+				metrics_only.elided
+			)),
+			None => self.last_metrics_only.clone(),
+		}
+	}
 
-		if let Some(adults) = self.parse_usize("No. of Adults:", &entry.logstring) {
-			self.adults = adults;
-			self.parser_output = format!("ADULTS: {}", adults);
-			return true;
-		};
+	///! Add a line as sent by a source, where length is the bytes it took in its logfile if read from one
+	pub fn append_line(&mut self, text: &str, length: Option<u64>) -> Result<(), std::io::Error> {
+		let provenance = self.next_provenance;
+		let appended = self.append_to_content(text);
+		if let Some(length) = length {
+			self.next_provenance = provenance.next(length);
+		}
+		appended
+	}
 
-		// TODO: review as things stabilise during Fleming testnets
-		// Pre-Fleming testnets code with additions for Fleming T4.1
-		if let Some(agebracket) = self
-			.parse_word("Node promoted to ", &entry.logstring)
-			.or(self.parse_word("We are ", &entry.logstring))
-			.or(self.parse_word("New RoutingEvent received. Current role:", &entry.logstring))
-		{
-			self.agebracket = match agebracket.as_str() {
-				"Infant" => NodeAgebracket::Infant,
-				"Adult" => NodeAgebracket::Adult,
-				"Elder" => NodeAgebracket::Elder,
-				_ => {
-					debug_log!(self.parser_output.as_str());
-					NodeAgebracket::Unknown
-				}
-			};
-			if self.agebracket != NodeAgebracket::Unknown {
-				self.parser_output = format!("Node agebracket: {}", agebracket);
+	pub fn append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
+		self.dirty = true;
+		if !self.metrics.loading {
+			self.watch_check.line();
+		}
+		if let Some(paused) = &mut self.paused {
+			if paused.lines.len() < PAUSED_LINES_MAX {
+				paused.lines.push(text.to_string());
 			} else {
-				self.parser_output = format!("FAILED to parse agebracket in: {}", &entry.logstring);
+				paused.dropped += 1;
+				self.next_provenance = self.next_provenance.next(text.len() as u64 + 1);
 			}
+			return Ok(());
+		}
+		let provenance = self.next_provenance;
+		self.next_provenance = provenance.next(text.len() as u64 + 1);
 
-			if let Some(section_prefix) = self.parse_word("section prefix:", &entry.logstring) {
-				self.parser_output = format!("section prefix: {}", &section_prefix);
-				self.section_prefix = section_prefix;
-			} else {
-				self.parser_output = format!("FAILED to parse section prefix in: {}", &entry.logstring);
-			}
+		if !self.metrics.loading {
+			self.count_live_line();
+		}
 
-			if let Some(node_age) = self.parse_usize("age:", &entry.logstring) {
-				self.parser_output = format!("age: {}", node_age);
-				self.node_age = node_age;
-			} else {
-				self.parser_output = format!("FAILED to parse node age in: {}", &entry.logstring);
+		if self.line_filter(&text) {
+			let shown = self.filter_shows(text);
+			match &mut self.metrics_only {
+				Some(metrics_only) => metrics_only.elided += 1,
+				None if shown => self._append_to_content(text, provenance)?, // Show in TUI
+				None => {}
 			}
-
-			if let Some(node_name) = self.parse_word("node name:", &entry.logstring) {
-				self.parser_output = format!("node name: {}", &node_name);
-				self.node_name = node_name;
-			} else {
-				self.parser_output = format!("FAILED to parse node name in: {}", &entry.logstring);
+			if self.is_debug_dashboard_log || self.metrics.log_format == LogFormat::Plain {
+				return Ok(());
 			}
+			self.metrics.gather_metrics(&text)?;
+			if let Some(millis) = self.parse_latency(text) {
+				self.metrics.count_latency(millis);
+			}
+			self.record_watched(text);
+			self.check_escalations(text);
 
-			return true;
-		};
-
-		// TODO: probably needs deprecating as of T4.1 except perhaps for this agebracket check which needs review
-		// Fleming Testnet 3 based
-		if entry.logstring.contains("The network is not accepting nodes right now")
-		{
-			self.agebracket = NodeAgebracket::Infant;
-			self.parser_output = format!("Age updated to: Infant");
-			return true;
-		}
-
-		if entry.logstring.contains("Handling NodeDuty: WriteChunk") {
-			self.agebracket = NodeAgebracket::Adult;
-			self.parser_output = format!("Age updated to: Adult");
-			return true;
-		}
-
-		if entry.logstring.contains("as an Elder") {
-			self.agebracket = NodeAgebracket::Elder;
-			self.parser_output = format!("Age updated to: Elder");
-			return true;
+			// --debug-dashboard - prints parser results for a single logfile
+			// to a temp logfile which is displayed in the adjacent window.
+			if let Some(parser_result) = self.metrics.parser_history.back() {
+				debug_log!(parser_result);
+			}
 		}
-
-		false
+		Ok(())
 	}
 
-	fn parse_usize(&mut self, prefix: &str, content: &str) -> Option<usize> {
-		if let Some(position) = content.find(prefix) {
-			let word: Vec<&str> = content[position + prefix.len()..]
-				.trim()
-				.splitn(2, |c| c == ' ' || c == ',')
-				.collect();
-			if word.len() > 0 {
-				match word[0].parse::<usize>() {
-					Ok(value) => return Some(value),
-					Err(_e) => self.parser_output = format!("failed to parse '{}' as usize from: '{}'", word[0], &content[position + prefix.len()..]),
-				}
+	pub fn _append_to_content(&mut self, text: &str, provenance: Provenance) -> Result<(), std::io::Error> {
+		self.changed_fields.push(text);
+		self.line_times.push(text, &self.metrics.assumed_offset);
+		self.provenance.push_back(provenance);
+		self.content.items.push(text.to_string());
+		if !self.metrics.loading {
+			self.live_lines += 1;
+		}
+		let len = self.content.items.len();
+		// The line just added counts, though its metrics are gathered after
+		let most_recent = self.metrics.most_recent.max(self.line_times.get(len - 1));
+		let expired = match (self.retain, most_recent) {
+			(Some(retain), Some(most_recent)) => self.lines_before(most_recent - retain).min(len),
+			_ => len.saturating_sub(self.max_content),
+		};
+		if expired > 0 {
+			if let Some(travel) = &mut self.time_travel {
+				travel.forget_lines(&self.content.items[..expired]);
 			}
+			self.content.items.drain(..expired);
+			self.changed_fields.drain_front(expired);
+			self.line_times.drain_front(expired);
+			self.provenance.drain(..expired.min(self.provenance.len()));
+			self.live_lines = self.live_lines.min(self.content.items.len());
+		} else {
+			self.content.state.select(Some(len - 1));
 		}
-		None
+		Ok(())
 	}
 
-	fn parse_word(&mut self, prefix: &str, content: &str) -> Option<String> {
-		if let Some(start) = content.find(prefix) {
-			let word: Vec<&str> = content[start + prefix.len()..]
-				.trim_start()
-				.splitn(2, |c| c == ' ' || c == ',')
-				.collect();
-			if word.len() > 0 {
-				return Some(word[0].to_string());
-			} else {
-				self.parser_output = format!("failed to parse word at: '{}'", &content[start..]);
+	///! Count the lines at the start of content logged before time
+	///!
+	///! Continuation lines go with the entry they continue, and any lines before
+	///! the first timestamp with the line after them.
+	fn lines_before(&self, time: DateTime<Utc>) -> usize {
+		let mut count = 0;
+		for (index, line_time) in self.line_times.times.iter().enumerate() {
+			match line_time {
+				Some(line_time) if *line_time < time => count = index + 1,
+				Some(_) => break,
+				None => {}
 			}
 		}
-		None
+		count
 	}
 
-	fn count_get(&mut self, time: Option<DateTime<Utc>>) {
-		self.activity_gets += 1;
-		self.gets_timeline.increment_value(time);
+	///! Whether --latency-pattern was given, so that latencies are counted
+	pub fn measures_latency(&self) -> bool {
+		self.latency_pattern.is_some()
 	}
 
-	fn count_put(&mut self, time: Option<DateTime<Utc>>) {
-		self.activity_puts += 1;
-		self.puts_timeline.increment_value(time);
+	///! The latency in milliseconds logged by line, captured by --latency-pattern
+	fn parse_latency(&self, line: &str) -> Option<f64> {
+		let captures = self.latency_pattern.as_ref()?.captures(line)?;
+		captures.get(1)?.as_str().parse().ok()
 	}
 
-	fn count_error(&mut self, time: Option<DateTime<Utc>>) {
-		self.activity_errors += 1;
-		self.errors_timeline.increment_value(time);
+	///! Record the values of --watch patterns which match line, at the line's time
+	fn record_watched(&mut self, line: &str) {
+		let time = match self.metrics.most_recent {
+			Some(time) => time,
+			None => return,
+		};
+		for (pattern, series) in self.watch_patterns.iter().zip(self.watched.iter_mut()) {
+			if let Some(value) = pattern.value(line) {
+				series.record(time, value);
+			}
+		}
 	}
 
-	///! TODO
-	pub fn parse_logentry_counts(&mut self, entry: &LogEntry) {
-		// Categories ('INFO', 'WARN' etc)
-		if !entry.category.is_empty() {
-			let count = match self.category_count.get(&entry.category) {
-				Some(count) => count + 1,
-				None => 1,
-			};
-			self.category_count.insert(entry.category.clone(), count);
+	///! Count line for each alert rule it matches, at the line's time, queueing an event for a cluster
+	fn check_escalations(&mut self, line: &str) {
+		let time = self.metrics.most_recent.unwrap_or_else(Utc::now);
+		for escalation in self.escalations.iter_mut() {
+			if let Some(event) = escalation.check(line, time) {
+				self.metrics.event(event.event, event.detail);
+			}
 		}
 	}
-}
-
-///! Node activity for node activity_history
-pub struct ActivityEntry {
-	pub message: String,
-	pub activity: String,
-	pub logstring: String,
-	pub category: String, // First word, "Running", "INFO", "WARN" etc
-	pub time: Option<DateTime<Utc>>,
-	pub source: String,
 
-	pub parser_output: String,
-}
-
-impl ActivityEntry {
-	pub fn new(entry: &LogEntry, activity: &str) -> ActivityEntry {
-		ActivityEntry {
-			message: entry.message.clone(),
-			activity: activity.to_string(),
-			logstring: entry.logstring.clone(),
-			category: entry.category.clone(),
-			time: entry.time,
-			source: entry.source.clone(),
-
-			parser_output: String::from(""),
-		}
+	// Some logfile lines are too numerous to include so we ignore them
+	// Returns true if the line is to be processed
+	fn line_filter(&mut self, _line: &str) -> bool {
+		true
 	}
 }
 
-///! Decoded logfile entries for a node log history
-pub struct LogEntry {
-	pub logstring: String,
-	pub category: String, // First word, "Running", "INFO", "WARN" etc
-	pub time: Option<DateTime<Utc>>,
-	pub source: String,
-	pub message: String,
+///! How long a promotion to Elder is celebrated
+const CELEBRATION: std::time::Duration = std::time::Duration::from_secs(5);
 
-	pub parser_output: String,
+///! A node's promotion to Elder, shown in a banner over the dashboard for a few seconds
+pub struct Celebration {
+	pub node: usize,
+	pub logfile: String,
+	started: Instant,
 }
 
-impl LogEntry {
-	///! Decode node logfile lines of the form:
-	///! 	[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5
-	///!	[sn_node] ERROR 2020-12-18T16:33:54.237345352+00:00 [src/utils.rs:52] Failed to load auto dump db at /home/mrh/.safe/node/baby-fleming-nodes/sn-node-genesis/transfers/f67c2e75cbce0a6097187cdf95be1c0963ad34105d643cbb00aa1f0e8b113761.db: No such file or directory (os error 2)
-	///!
-	pub fn decode(line: &str) -> Option<LogEntry> {
-		let mut _test_entry = LogEntry {
-			logstring: String::from(line),
-			category: String::from("test"),
-			time: None,
-			source: String::from(""),
-			message: String::from(""),
-			parser_output: String::from("decode()..."),
-		};
-
-		if line.is_empty() {
-			return None;
+impl Celebration {
+	pub fn new(node: usize, logfile: &str, started: Instant) -> Celebration {
+		Celebration {
+			node,
+			logfile: logfile.to_string(),
+			started,
 		}
-
-		LogEntry::parse_logfile_line(line)
 	}
 
-	///! Parse a line of the form:
-	///! 	[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5
-	///!	[sn_node] ERROR 2020-12-18T16:33:54.237345352+00:00 [src/utils.rs:52] Failed to load auto dump db at /home/mrh/.safe/node/baby-fleming-nodes/sn-node-genesis/transfers/f67c2e75cbce0a6097187cdf95be1c0963ad34105d643cbb00aa1f0e8b113761.db: No such file or directory (os error 2)
-	fn parse_logfile_line(line: &str) -> Option<LogEntry> {
-		if let Some(captures) = LOG_LINE_PATTERN.captures(line) {
-			let module = captures.name("module").map_or("", |m| m.as_str());
-			let category = captures.name("category").map_or("", |m| m.as_str());
-			let time_string = captures.name("time_string").map_or("", |m| m.as_str());
-			let source = captures.name("source").map_or("", |m| m.as_str());
-			let message = captures.name("message").map_or("", |m| m.as_str());
-			let mut time_str = String::from("None");
-
-			let mut time_utc: Option<DateTime<Utc>> = None;
-
-			match DateTime::parse_from_str(time_string, "%+") {
-				Ok(time) => {
-					time_utc = Some(time.with_timezone(&Utc));
-					time_str = format!("{}", time);
-					Some(time)
-				}
-				Err(e) => {
-					debug_log!(format!("ERROR parsing logfile time: {}", e).as_str());
-					None
-				}
-			};
-			let parser_output = format!(
-				"m: {}, c: {}, t: {}, s: {}, m: {}",
-				module, category, time_str, source, message
-			);
+	///! Whether a node event is a promotion to Elder
+	pub fn is_due(event: &NodeEvent) -> bool {
+		event.event == hooks::EVENT_PROMOTION && event.detail == "Elder"
+	}
 
-			return Some(LogEntry {
-				logstring: String::from(line),
-				category: String::from(category),
-				time: time_utc,
-				source: String::from(source),
-				message: String::from(message),
-				parser_output,
-			});
-		}
-		None
+	pub fn is_over(&self, now: Instant) -> bool {
+		now.duration_since(self.started) >= CELEBRATION
 	}
 }
 
@@ -1211,30 +2451,68 @@ impl LogEntry {
 pub enum DashViewMain {
 	DashSummary,
 	DashNode,
+	DashTop,
+	DashRegex,
+	DashSearch,
+	DashTable,
 	DashDebug,
 }
 
-lazy_static::lazy_static! {
-	pub static ref TIMELINES: std::vec::Vec<(&'static str, Duration)> = vec!(
-		("1 second columns", Duration::seconds(1)),
-		("1 minute columns", Duration::minutes(1)),
-		("1 hour columns", Duration::hours(1)),
-		("1 day columns", Duration::days(1)),
-		("1 week columns", Duration::days(7)),
-		("1 year columns", Duration::days(365)),
-	);
-}
 
 pub struct DashState {
 	pub main_view: DashViewMain,
 	pub active_timeline: usize,
+	///! Index into TOP_COLUMNS of the column the top view is sorted by
+	pub top_sort: usize,
+	pub regex_playground: RegexPlayground,
+	pub search: Search,
+	///! The focused node's filter being edited, when the editor is open
+	pub filter_editor: Option<FilterEditor>,
+	///! The time to go to in the focused node's logfile, when the prompt is open
+	pub goto_prompt: Option<GotoPrompt>,
+	///! The line shown in full, when the popup is open
+	pub line_detail: Option<LineDetail>,
+	///! The focused node's control actions, when the popup is open
+	pub control_menu: Option<ControlMenu>,
+	///! The alert list opened with '!', see alerts.rs
+	pub alert_list: Option<AlertList>,
+	///! Errors from the event loop, see App::report()
+	pub error_toasts: ErrorToasts,
+	///! Columns from the config file for the table view
+	pub columns: Vec<Column>,
 	pub dash_node_focus: String,
+	pub no_color: bool,
+	pub node_widgets: Vec<NodeWidget>,
+	///! Whether the node view expands the node's config above its logfile
+	pub show_node_config: bool,
+	///! Whether the node view shows lines in aligned columns rather than as logged
+	pub aligned: bool,
+	///! Whether the node view shows each line beside what the parser makes of it
+	pub parser_view: bool,
+	///! The widget of the view which keys such as up and down act on, moved by Tab
+	pub focus: Focus,
+	///! How the summary groups nodes, and which groups are collapsed
+	pub summary_groups: SummaryGroups,
+	///! Sizes of the node view's splits, see splits.rs
+	pub splits: Splits,
+	///! Throughput and timings of vdash itself, for the debug view
+	pub self_metrics: SelfMetrics,
+	///! A promotion to Elder being celebrated, see --celebrate
+	pub celebration: Option<Celebration>,
+	///! Seconds of clock skew above which a node view warns of it (0 for never)
+	pub clock_skew_secs: i64,
+	///! Latency in milliseconds which satisfies a request, for Apdex scores (see --apdex-ms)
+	pub apdex_ms: f64,
+	///! Only nodes with this tag are shown, see tags.rs
+	pub tag_filter: Option<String>,
+	///! Whether only the summary is drawn, every LOW_POWER_REDRAW_SECS, see App::redraw_interval()
+	pub low_power: bool,
 
 	// For --debug-window option
 	pub debug_window_list: StatefulList<String>,
 	pub debug_window: bool,
-	pub debug_window_has_focus: bool,
-	max_debug_window: usize,
+	///! Whether messages are added to the debug window, toggled with 'd'
+	pub debug_capture: bool,
 }
 
 impl DashState {
@@ -1243,26 +2521,51 @@ impl DashState {
 		DashState {
 			main_view: DashViewMain::DashNode,
 			active_timeline: 0,
+			top_sort: TOP_SORT_DEFAULT,
+			regex_playground: RegexPlayground::new(),
+			search: Search::new(),
+			filter_editor: None,
+			goto_prompt: None,
+			line_detail: None,
+			control_menu: None,
+			alert_list: None,
+			error_toasts: ErrorToasts::default(),
+			columns: Vec::new(),
 			dash_node_focus: String::new(),
+			no_color: false,
+			node_widgets: Config::default().node_panel.widgets,
+			show_node_config: false,
+			aligned: true,
+			parser_view: false,
+			focus: Focus::default(),
+			summary_groups: SummaryGroups::default(),
+			splits: Splits::default(),
+			self_metrics: SelfMetrics::default(),
+			celebration: None,
+			clock_skew_secs: 0,
+			apdex_ms: 0.0,
+			tag_filter: None,
+			low_power: false,
 
 			debug_window: false,
-			debug_window_has_focus: false,
 			debug_window_list: StatefulList::new(),
-			max_debug_window: 100,
+			debug_capture: true,
 		}
 	}
 
+	///! The widgets of the main view which can take the focus, in Tab order
+	pub fn focus_order(&self) -> Vec<Focus> {
+		focus::order(&self.main_view, self.parser_view, self.debug_window)
+	}
+
+	///! Keep the focus on a widget of the main view, as views change and widgets open and close
+	pub fn fit_focus(&mut self) {
+		self.focus = focus::fit(&self.focus_order(), self.focus);
+	}
+
 	pub fn _debug_window(&mut self, text: &str) {
-		self.debug_window_list.items.push(text.to_string());
-		let len = self.debug_window_list.items.len();
-
-		if len > self.max_debug_window {
-			self.debug_window_list.items = self
-				.debug_window_list
-				.items
-				.split_off(len - self.max_debug_window);
-		} else {
-			self.debug_window_list.state.select(Some(len - 1));
+		if self.debug_capture {
+			self.debug_window_list.push_capped(text.to_string(), DEBUG_WINDOW_LINES_MAX);
 		}
 	}
 }
@@ -1285,6 +2588,7 @@ pub fn set_main_view(view: DashViewMain, app: &mut App) {
 	save_focus(app);
 	app.dash_state.main_view = view;
 	restore_focus(app);
+	app.dash_state.fit_focus();
 }
 
 pub fn save_focus(app: &mut App) {
@@ -1295,6 +2599,10 @@ pub fn save_focus(app: &mut App) {
 				app.dash_state.dash_node_focus = focus;
 			}
 		}
+		DashViewMain::DashTop => {}
+		DashViewMain::DashRegex => {}
+		DashViewMain::DashSearch => {}
+		DashViewMain::DashTable => {}
 		DashViewMain::DashDebug => {}
 	}
 }
@@ -1305,6 +2613,10 @@ pub fn restore_focus(app: &mut App) {
 		DashViewMain::DashNode => {
 			app.set_logfile_with_focus(app.dash_state.dash_node_focus.clone())
 		}
+		DashViewMain::DashTop => {}
+		DashViewMain::DashRegex => {}
+		DashViewMain::DashSearch => {}
+		DashViewMain::DashTable => {}
 		DashViewMain::DashDebug => {
 			if let Some(debug_logfile) = app.get_debug_dashboard_logfile() {
 				app.set_logfile_with_focus(debug_logfile);
@@ -1312,3 +2624,42 @@ pub fn restore_focus(app: &mut App) {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pauses_and_resumes_ingest() {
+		let opt = Opt::from_iter(&["vdash", "node.log"]);
+		let mut monitor = LogMonitor::new(&opt, "node.log".to_string(), opt.lines_max);
+		monitor.append_to_content("before").unwrap();
+		monitor.toggle_pause().unwrap();
+		for line in 0..PAUSED_LINES_MAX + 2 {
+			monitor.append_to_content(&format!("held {}", line)).unwrap();
+		}
+		assert_eq!(monitor.content.items, vec!["before"]);
+		let status = format!("PAUSED: {} lines held, 2 dropped", PAUSED_LINES_MAX);
+		assert_eq!(monitor.pause_status(), Some(status));
+
+		monitor.toggle_pause().unwrap();
+		assert!(monitor.paused.is_none());
+		let last = format!("held {}", PAUSED_LINES_MAX - 1);
+		assert_eq!(monitor.content.items.last(), Some(&last));
+		let status = format!("resumed: {} lines batched, 2 dropped", PAUSED_LINES_MAX);
+		assert_eq!(monitor.pause_status(), Some(status));
+	}
+
+	#[test]
+	fn loads_lines_which_arent_utf8() {
+		let dir = tempfile::tempdir().unwrap();
+		let logfile = dir.path().join("node.log");
+		std::fs::write(&logfile, b"first\n\xff second\r\nthird\n").unwrap();
+		let opt = Opt::from_iter(&["vdash", "node.log"]);
+		let mut monitor = LogMonitor::new(&opt, logfile.to_str().unwrap().to_string(), opt.lines_max);
+		monitor.load(&AtomicU64::new(0)).unwrap();
+		assert_eq!(monitor.content.items, ["first", "\u{fffd} second", "third"]);
+		assert_eq!(monitor.logfile_position, 22);
+		assert!(!monitor.metrics.loading);
+	}
+}
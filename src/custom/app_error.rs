@@ -0,0 +1,128 @@
+///! Errors in the dashboard's event loop, shown rather than ending the session
+///!
+///! One bad event, such as a line which can't be journaled or a replaced
+///! logfile which can't be reloaded, shouldn't take down a monitoring
+///! session. Such errors become an AppError, which is logged and shown in a
+///! toast over the dashboard for TOAST_SECS while the session carries on.
+use chrono::{DateTime, Local, Utc};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, Paragraph, Wrap},
+	Frame,
+};
+
+///! How long the toast for an error is shown
+const TOAST_SECS: u64 = 8;
+///! Most recent errors kept
+const ERRORS_MAX: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct AppError {
+	///! What was being done, such as 'reloading replaced logfile'
+	pub context: String,
+	pub message: String,
+	pub time: DateTime<Utc>,
+}
+
+impl AppError {
+	pub fn new(context: &str, error: impl fmt::Display) -> AppError {
+		AppError {
+			context: context.to_string(),
+			message: error.to_string(),
+			time: Utc::now(),
+		}
+	}
+}
+
+impl fmt::Display for AppError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.context, self.message)
+	}
+}
+
+#[derive(Default)]
+pub struct ErrorToasts {
+	///! Most recent last
+	pub errors: VecDeque<AppError>,
+	///! Errors this session, including those no longer kept
+	pub count: usize,
+	shown_at: Option<Instant>,
+}
+
+impl ErrorToasts {
+	pub fn push(&mut self, error: AppError) {
+		self.errors.push_back(error);
+		if self.errors.len() > ERRORS_MAX {
+			self.errors.pop_front();
+		}
+		self.count += 1;
+		self.shown_at = Some(Instant::now());
+	}
+
+	///! The error to show, while its toast lasts
+	pub fn current(&self) -> Option<&AppError> {
+		self.current_at(Instant::now())
+	}
+
+	fn current_at(&self, now: Instant) -> Option<&AppError> {
+		match self.shown_at {
+			Some(shown_at) if now.duration_since(shown_at) < Duration::from_secs(TOAST_SECS) => self.errors.back(),
+			_ => None,
+		}
+	}
+}
+
+pub fn draw_error_toast<B: Backend>(f: &mut Frame<B>, toasts: &ErrorToasts) {
+	let error = match toasts.current() {
+		Some(error) => error,
+		None => return,
+	};
+	let size = f.size();
+	let width = size.width.min(60);
+	let height = 5.min(size.height);
+	let area = Rect::new(size.x + size.width - width, size.y + size.height - height, width, height);
+	f.render_widget(Clear, area);
+
+	let title = match toasts.count {
+		1 => String::from("Error"),
+		count => format!("Error ({} this session)", count),
+	};
+	let text = vec![
+		Spans::from(Span::styled(
+			format!("{} {}", error.time.with_timezone(&Local).format("%H:%M:%S"), error.context),
+			Style::default().add_modifier(Modifier::BOLD),
+		)),
+		Spans::from(error.message.as_str()),
+	];
+	let toast_widget = Paragraph::new(text)
+		.style(Style::default().fg(Color::Red))
+		.block(Block::default().borders(Borders::ALL).title(title))
+		.wrap(Wrap { trim: true });
+	f.render_widget(toast_widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn toasts_latest_error_for_a_while() {
+		let mut toasts = ErrorToasts::default();
+		assert!(toasts.current().is_none());
+		for i in 0..ERRORS_MAX + 5 {
+			toasts.push(AppError::new("reading logfile", format!("error {}", i)));
+		}
+		assert_eq!(toasts.errors.len(), ERRORS_MAX);
+		assert_eq!(toasts.count, ERRORS_MAX + 5);
+		assert_eq!(toasts.current().map(|error| error.to_string()), Some(format!("reading logfile: error {}", ERRORS_MAX + 4)));
+		let later = Instant::now() + Duration::from_secs(TOAST_SECS);
+		assert!(toasts.current_at(later).is_none());
+	}
+}
@@ -0,0 +1,69 @@
+///! Availability: the percentage of the session a node has been up
+///!
+///! Each tick a node is sampled as up, or as down while it is STALLED or its
+///! logfile has gone, and the time since the previous sample is counted as it
+///! was then. The time up over the time monitored is shown in the summary and
+///! the --snapshot-http summary, an SLA-like figure for each node. It covers
+///! this run of vdash only, from once its logfiles have been loaded.
+use std::time::{Duration, Instant};
+
+use vault_metrics::NodeStatus;
+
+#[derive(Debug, Default)]
+pub struct Availability {
+	///! When last sampled, and whether the node was up then
+	sampled: Option<(Instant, bool)>,
+	up: Duration,
+	monitored: Duration,
+}
+
+impl Availability {
+	pub fn sample(&mut self, now: Instant, up: bool) {
+		if let Some((sampled, was_up)) = self.sampled {
+			let elapsed = now.saturating_duration_since(sampled);
+			self.monitored += elapsed;
+			if was_up {
+				self.up += elapsed;
+			}
+		}
+		self.sampled = Some((now, up));
+	}
+
+	///! Percent of the time monitored the node was up, once any time has passed
+	pub fn percent(&self) -> Option<f64> {
+		match self.monitored.as_secs_f64() {
+			monitored if monitored > 0.0 => Some(self.up.as_secs_f64() * 100.0 / monitored),
+			_ => None,
+		}
+	}
+
+	///! e.g. '99.8%', or '-' until any time has passed
+	pub fn describe(&self) -> String {
+		self.percent().map_or(String::from("-"), |percent| format!("{:.1}%", percent))
+	}
+}
+
+pub fn is_up(status: NodeStatus, gone: bool) -> bool {
+	!gone && status != NodeStatus::Stalled
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn weights_up_and_down_by_time() {
+		let start = Instant::now();
+		let at = |seconds: u64| start + Duration::from_secs(seconds);
+		let mut availability = Availability::default();
+		availability.sample(at(0), true);
+		assert_eq!(availability.describe(), "-");
+
+		// Up for 30s, then down for 10s, counted as the node was at each sample
+		availability.sample(at(30), is_up(NodeStatus::Stalled, false));
+		availability.sample(at(40), is_up(NodeStatus::Warn, false));
+		assert_eq!(availability.percent(), Some(75.0));
+		availability.sample(at(160), is_up(NodeStatus::Ok, true));
+		assert_eq!(availability.describe(), "93.8%");
+	}
+}
@@ -0,0 +1,234 @@
+///! Loading of existing logfiles in parallel, by a pool of worker threads
+///!
+///! Parsing a multi-GB history is bound by the CPU, so loading one logfile
+///! after another leaves most cores idle. Instead each worker takes the next
+///! logfile still to load and loads it into its monitor, while the bytes read
+///! from each logfile are shown as progress. A monitor is only touched by the
+///! worker loading it, so monitors are merged back into the App, in the order
+///! the logfiles were given, once all have loaded.
+///!
+///! With --tail-lines only the end of each logfile is loaded. Where that
+///! starts is found by reading blocks back from the end of the logfile (see
+///! tail_offset), so the gigabytes before it are never read.
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Error, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::app::LogMonitor;
+use super::journal::Recovered;
+
+///! How often progress is shown while loading
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+///! Bytes read at a time when reading back from the end of a logfile
+const TAIL_BLOCK_BYTES: u64 = 64 * 1024;
+
+///! How far loading of one logfile has got
+pub struct Progress {
+	pub logfile: String,
+	pub size: u64,
+	///! Bytes read so far
+	pub loaded: AtomicU64,
+	pub done: AtomicBool,
+}
+
+impl Progress {
+	pub fn new(logfile: &str) -> Progress {
+		Progress {
+			logfile: logfile.to_string(),
+			size: std::fs::metadata(logfile).map_or(0, |metadata| metadata.len()),
+			loaded: AtomicU64::new(0),
+			done: AtomicBool::new(false),
+		}
+	}
+
+	pub fn percent(&self) -> u64 {
+		match self.size {
+			0 => 100,
+			size => (self.loaded.load(Ordering::Relaxed) * 100 / size).min(100),
+		}
+	}
+}
+
+///! Number of workers for --load-workers, where 0 is one per core
+pub fn workers(load_workers: usize) -> usize {
+	match load_workers {
+		0 => thread::available_parallelism().map_or(1, |cores| cores.get()),
+		workers => workers,
+	}
+}
+
+///! Load each monitor's logfile, or restore it from its recovered journal
+///! state, across up to workers threads, returning the monitors in order
+pub fn load_logfiles(loads: Vec<(LogMonitor, Option<Recovered>)>, workers: usize) -> Result<Vec<LogMonitor>, Error> {
+	let progress: Vec<Progress> = loads.iter().map(|(monitor, _)| Progress::new(&monitor.logfile)).collect();
+	let count = loads.len();
+	let queue: Mutex<VecDeque<(usize, LogMonitor, Option<Recovered>)>> = Mutex::new(
+		loads
+			.into_iter()
+			.enumerate()
+			.map(|(index, (monitor, recovered))| (index, monitor, recovered))
+			.collect(),
+	);
+	let loaded: Mutex<Vec<Option<Result<LogMonitor, Error>>>> = Mutex::new((0..count).map(|_| None).collect());
+
+	let show_progress = std::io::stdout().is_terminal();
+	let started = Instant::now();
+	thread::scope(|scope| {
+		// Workers signal each logfile done, so that a quick load isn't held up to the next progress update
+		let (done_tx, done_rx) = mpsc::channel::<()>();
+		let (queue, progress, loaded) = (&queue, &progress, &loaded);
+		let pool: Vec<thread::ScopedJoinHandle<()>> = (0..workers.clamp(1, count.max(1)))
+			.map(|_| {
+				let done_tx = done_tx.clone();
+				scope.spawn(move || loop {
+					let next = queue.lock().unwrap().pop_front();
+					let (index, mut monitor, recovered) = match next {
+						Some(next) => next,
+						None => break,
+					};
+					let result = match recovered {
+						Some(state) => monitor.recover(state, &progress[index].loaded),
+						None => monitor.load(&progress[index].loaded),
+					};
+					progress[index].done.store(true, Ordering::Relaxed);
+					loaded.lock().unwrap()[index] = Some(result.map(|_| monitor));
+					let _ = done_tx.send(());
+				})
+			})
+			.collect();
+		drop(done_tx);
+
+		let mut reported = vec![false; count];
+		loop {
+			let all_done = progress.iter().all(|progress| progress.done.load(Ordering::Relaxed));
+			for (progress, reported) in progress.iter().zip(reported.iter_mut()) {
+				if !*reported && progress.done.load(Ordering::Relaxed) {
+					*reported = true;
+					if show_progress {
+						print!("\r\x1b[K");
+					}
+					println!("Loaded {} in {:.1}s", progress.logfile, started.elapsed().as_secs_f64());
+				}
+			}
+			// A worker which panicked leaves its logfile never done, and scope() passes on the panic
+			if all_done || pool.iter().all(|worker| worker.is_finished()) {
+				break;
+			}
+			if show_progress {
+				print!("\r\x1b[K{}", progress_line(progress));
+				let _ = std::io::stdout().flush();
+			}
+			let _ = done_rx.recv_timeout(PROGRESS_INTERVAL);
+		}
+	});
+
+	loaded
+		.into_inner()
+		.unwrap()
+		.into_iter()
+		.map(|result| result.expect("A logfile was left unloaded. This is a bug."))
+		.collect()
+}
+
+///! Where the last lines of the logfile begin, reading blocks back from its end
+pub fn tail_offset(logfile: &str, lines: usize) -> Result<u64, Error> {
+	let mut file = File::open(logfile)?;
+	let length = file.metadata()?.len();
+	if lines == 0 {
+		return Ok(length);
+	}
+	let mut block = vec![0u8; TAIL_BLOCK_BYTES as usize];
+	let mut newlines = 0;
+	let mut end = length;
+	while end > 0 {
+		let start = end.saturating_sub(TAIL_BLOCK_BYTES);
+		let block = &mut block[..(end - start) as usize];
+		file.seek(SeekFrom::Start(start))?;
+		file.read_exact(block)?;
+		for (index, byte) in block.iter().enumerate().rev() {
+			let at = start + index as u64;
+			// A newline ending the logfile ends its last line, rather than starting another
+			if *byte != b'\n' || at + 1 == length {
+				continue;
+			}
+			newlines += 1;
+			if newlines == lines {
+				return Ok(at + 1);
+			}
+		}
+		end = start;
+	}
+	Ok(0)
+}
+
+///! The logfiles still loading, with how far each has got
+fn progress_line(progress: &[Progress]) -> String {
+	let loading: Vec<String> = progress
+		.iter()
+		.filter(|progress| !progress.done.load(Ordering::Relaxed) && progress.loaded.load(Ordering::Relaxed) > 0)
+		.map(|progress| format!("{} {}%", progress.logfile, progress.percent()))
+		.collect();
+	let done = progress.iter().filter(|progress| progress.done.load(Ordering::Relaxed)).count();
+	format!("Loading {}/{}: {}", done, progress.len(), loading.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::{Opt, StructOpt};
+
+	#[test]
+	fn loads_logfiles_in_parallel() {
+		let dir = tempfile::tempdir().unwrap();
+		let opt = Opt::from_iter(&["vdash", "node.log"]);
+		let loads: Vec<(LogMonitor, Option<Recovered>)> = (0..5)
+			.map(|n| {
+				let logfile = dir.path().join(format!("node{}.log", n));
+				let lines: String = (0..=n)
+					.map(|line| format!("[sn_node] INFO 2021-01-20T18:00:0{}.000000+00:00 [src/a.rs:1] node {}\n", line, n))
+					.collect();
+				std::fs::write(&logfile, lines).unwrap();
+				(LogMonitor::new(&opt, logfile.to_str().unwrap().to_string(), 100), None)
+			})
+			.collect();
+
+		let monitors = load_logfiles(loads, 3).unwrap();
+		for (n, monitor) in monitors.iter().enumerate() {
+			assert!(monitor.logfile.ends_with(&format!("node{}.log", n)));
+			assert_eq!(monitor.content.items.len(), n + 1);
+			assert!(!monitor.metrics.loading);
+		}
+
+		let progress = Progress::new(&monitors[4].logfile);
+		assert_eq!(progress.percent(), 0);
+		progress.loaded.store(progress.size, Ordering::Relaxed);
+		assert_eq!(progress.percent(), 100);
+	}
+
+	#[test]
+	fn finds_the_last_lines() {
+		let dir = tempfile::tempdir().unwrap();
+		let logfile = dir.path().join("node.log");
+		let logfile = logfile.to_str().unwrap();
+		// Lines which cross the blocks read
+		let line = "x".repeat(TAIL_BLOCK_BYTES as usize / 3);
+		let lines: Vec<String> = (0..10).map(|n| format!("{} {}", n, line)).collect();
+		std::fs::write(logfile, lines.join("\n") + "\n").unwrap();
+
+		let offset = tail_offset(logfile, 3).unwrap() as usize;
+		let content = std::fs::read_to_string(logfile).unwrap();
+		assert_eq!(content[offset..].lines().collect::<Vec<&str>>(), &lines[7..]);
+		assert_eq!(tail_offset(logfile, 20).unwrap(), 0);
+		assert_eq!(tail_offset(logfile, 0).unwrap(), content.len() as u64);
+
+		// Without a newline at the end
+		std::fs::write(logfile, "first\nsecond\nthird").unwrap();
+		assert_eq!(tail_offset(logfile, 2).unwrap(), 6);
+		assert!(tail_offset("/nonexistent/node.log", 2).is_err());
+	}
+}
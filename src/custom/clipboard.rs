@@ -0,0 +1,47 @@
+///! Copying text to the clipboard of the terminal vdash runs in
+///!
+///! The text is sent in an OSC 52 escape sequence, which most terminals (and
+///! tmux with set-clipboard on) put on the clipboard, including over SSH
+///! where vdash has no clipboard of its own. The sequence draws nothing, so a
+///! terminal without it just ignores the copy.
+use std::io::Write;
+
+const BASE64_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn copy(text: &str) -> std::io::Result<()> {
+	let mut stdout = std::io::stdout();
+	stdout.write_all(osc52(text).as_bytes())?;
+	stdout.flush()
+}
+
+fn osc52(text: &str) -> String {
+	format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+	let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+	for chunk in bytes.chunks(3) {
+		let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| group | (*byte as u32) << (16 - 8 * i));
+		for i in 0..4 {
+			match i <= chunk.len() {
+				true => encoded.push(BASE64_DIGITS[(group >> (18 - 6 * i) & 0x3f) as usize] as char),
+				false => encoded.push('='),
+			}
+		}
+	}
+	encoded
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encodes_text_for_the_terminal() {
+		assert_eq!(base64(b""), "");
+		assert_eq!(base64(b"f"), "Zg==");
+		assert_eq!(base64(b"fo"), "Zm8=");
+		assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+		assert_eq!(osc52("8f3a"), "\x1b]52;c;OGYzYQ==\x07");
+	}
+}
@@ -0,0 +1,229 @@
+///! Table view: the focused node's recent lines in columns
+///!
+///! Opened with 'c', the table shows each line's time, level, module and
+///! message in aligned columns, which is easier to scan than raw lines.
+///! Further columns are extracted by regex, defined in the config file:
+///!
+///!     [[columns]]
+///!     name = "Age"
+///!     pattern = 'Our Age: (\d+)'
+///!     width = 4
+///!
+///! A column shows its pattern's first capture group, or the whole match if
+///! it has none, and is blank where the pattern doesn't match. The width is
+///! optional. The table follows the node view's selected line, so scrolling
+///! with 'j' and 'k' works as in the node view.
+use chrono::Local;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+use super::app::{DashState, LogMonitor};
+use super::text::{self, Align};
+use vault_metrics::{LogEntry, LOG_LINE_PATTERN};
+
+use tui::{
+	backend::Backend,
+	style::{Color, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, List, ListItem},
+	Frame,
+};
+
+///! Headings and widths of the columns before any from the config
+static STANDARD_COLUMNS: [(&str, usize); 3] = [("Time", 8), ("Level", 5), ("Module", 24)];
+
+///! Width of a config column which doesn't give one, unless its name is wider
+const COLUMN_WIDTH_DEFAULT: usize = 12;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColumnConfig {
+	name: String,
+	pattern: String,
+	width: Option<usize>,
+}
+
+///! A column extracted from lines by regex, from [[columns]] in the config file
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "ColumnConfig")]
+pub struct Column {
+	pub name: String,
+	regex: Regex,
+	pub width: usize,
+}
+
+impl TryFrom<ColumnConfig> for Column {
+	type Error = String;
+
+	fn try_from(config: ColumnConfig) -> Result<Column, String> {
+		let regex = Regex::new(&config.pattern).map_err(|e| format!("column '{}': {}", config.name, e))?;
+		let width = config
+			.width
+			.unwrap_or_else(|| text::width(&config.name).max(COLUMN_WIDTH_DEFAULT));
+		Ok(Column {
+			name: config.name,
+			regex,
+			width,
+		})
+	}
+}
+
+impl Column {
+	///! The value of this column in line, or "" if the pattern doesn't match
+	pub fn extract<'a>(&self, line: &'a str) -> &'a str {
+		match self.regex.captures(line) {
+			Some(captures) => captures.get(1).or_else(|| captures.get(0)).map_or("", |m| m.as_str()),
+			None => "",
+		}
+	}
+}
+
+fn capture<'a>(captures: &Captures<'a>, name: &str) -> &'a str {
+	captures.name(name).map_or("", |m| m.as_str())
+}
+
+///! The fields of line: time, level, module, each of columns and then the message
+///!
+///! The time is the time of day as logged, and the module is the source file
+///! and line (the logger's own module is the same for every line). Lines not
+///! in the node's format, such as continuations, are all message.
+pub fn table_row<'a>(line: &'a str, columns: &[Column]) -> Vec<&'a str> {
+	let captures = LOG_LINE_PATTERN.captures(line);
+	let mut row = match &captures {
+		Some(captures) => {
+			let time = capture(captures, "time_string");
+			vec![
+				time.get(11..19).unwrap_or(time),
+				capture(captures, "category"),
+				capture(captures, "source").trim_matches(&['[', ']'][..]),
+			]
+		}
+		None => vec![""; STANDARD_COLUMNS.len()],
+	};
+	row.extend(columns.iter().map(|column| column.extract(line)));
+	row.push(captures.as_ref().map_or(line, |captures| capture(captures, "message")));
+	row
+}
+
+///! Widths of the level, time and source before the message of an aligned line
+const ALIGNED_WIDTHS: (usize, usize, usize) = (5, 12, 20);
+
+///! The level, time and source of entry in aligned columns, to go before its message
+///!
+///! The node view shows lines this way, so that messages start in the same
+///! column. The time is in local time to the millisecond, whatever offset
+///! the node logged, and the source is shortened from the start so that the
+///! file name and line number remain.
+pub fn aligned_prefix(entry: &LogEntry) -> String {
+	let (level_width, time_width, source_width) = ALIGNED_WIDTHS;
+	let time = entry
+		.time
+		.map_or(String::new(), |time| time.with_timezone(&Local).format("%H:%M:%S%.3f").to_string());
+	let source = entry.source.trim_matches(&['[', ']'][..]);
+	format!(
+		"{} {} {} ",
+		text::fit(&entry.category, level_width, Align::Left),
+		text::pad(&time, time_width, Align::Left),
+		text::pad(&text::truncate_start(source, source_width), source_width, Align::Left)
+	)
+}
+
+fn level_colour(level: &str) -> Color {
+	match level {
+		"ERROR" => Color::Red,
+		"WARN" => Color::Yellow,
+		_ => Color::Reset,
+	}
+}
+
+pub fn draw_table_dash<B: Backend>(f: &mut Frame<B>, dash_state: &DashState, monitor: Option<&LogMonitor>) {
+	let area = f.size();
+	let mut widths: Vec<usize> = STANDARD_COLUMNS.iter().map(|(_, width)| *width).collect();
+	widths.extend(dash_state.columns.iter().map(|column| column.width));
+	// The message has what remains, inside the borders and after a space per column
+	let used: usize = widths.iter().map(|width| width + 1).sum();
+	widths.push((area.width as usize).saturating_sub(2 + used));
+
+	let mut headings: Vec<&str> = STANDARD_COLUMNS.iter().map(|(heading, _)| *heading).collect();
+	headings.extend(dash_state.columns.iter().map(|column| column.name.as_str()));
+	headings.push("Message");
+	let header: String = headings
+		.iter()
+		.zip(widths.iter())
+		.map(|(heading, width)| format!("{} ", text::fit(heading, *width, Align::Left)))
+		.collect();
+	let mut items = vec![ListItem::new(Spans::from(Span::styled(header, Style::default().fg(Color::Yellow))))];
+
+	let (logfile, lines, selected) = match monitor {
+		Some(monitor) => (
+			monitor.logfile.as_str(),
+			&monitor.content.items[..],
+			monitor.content.state.selected(),
+		),
+		None => ("no node", &[][..], None),
+	};
+	// Rows which fit below the header, ending at the node view's selected line
+	let height = (area.height as usize).saturating_sub(3);
+	let end = selected.map_or(lines.len(), |selected| (selected + 1).min(lines.len()));
+	for line in lines[end.saturating_sub(height)..end].iter() {
+		let row = table_row(line, &dash_state.columns);
+		let spans: Vec<Span> = row
+			.iter()
+			.zip(widths.iter())
+			.enumerate()
+			.map(|(column, (value, width))| {
+				let cell = format!("{} ", text::fit(value, *width, Align::Left));
+				match column {
+					1 => Span::styled(cell, Style::default().fg(level_colour(value))),
+					_ => Span::raw(cell),
+				}
+			})
+			.collect();
+		items.push(ListItem::new(Spans::from(spans)));
+	}
+
+	let title = format!("Table ({})", logfile);
+	let table_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(table_widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Deserialize)]
+	struct Columns {
+		columns: Vec<Column>,
+	}
+
+	#[test]
+	fn extracts_columns() {
+		let config: Columns = toml::from_str(
+			r#"
+			[[columns]]
+			name = "Age"
+			pattern = 'Our Age: (\d+)'
+			[[columns]]
+			name = "Elders"
+			pattern = 'Elders: \d+'
+			"#,
+		)
+		.unwrap();
+		let line = "[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5";
+		assert_eq!(
+			table_row(line, &config.columns),
+			vec!["14:33:49", "INFO", "src/node/mod.rs:97", "5", "", "Our Age: 5"]
+		);
+		assert_eq!(table_row("  continued", &config.columns), vec!["", "", "", "", "", "  continued"]);
+
+		assert!(toml::from_str::<Columns>("[[columns]]\nname = 'Bad'\npattern = '('").is_err());
+
+		let line = "[sn_node] WARN 2020-12-18T14:33:49.799447454+00:00 [src/node/routing/approved.rs:1234] Lost peer";
+		let entry = LogEntry::decode(line, &vault_metrics::AssumedOffset::Local, None).unwrap();
+		let prefix = aligned_prefix(&entry);
+		assert!(prefix.starts_with("WARN  "));
+		assert!(prefix.ends_with(" …ng/approved.rs:1234 "));
+		assert_eq!(text::width(&prefix), 5 + 1 + 12 + 1 + 20 + 1);
+	}
+}
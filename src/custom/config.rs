@@ -0,0 +1,180 @@
+///! Configuration file of user preferences (--config)
+///!
+///! Settings which suit a file better than the command line, in TOML. For
+///! example, to choose the widgets shown above each node's timeline:
+///!
+///!     [node_panel]
+///!     widgets = ["counts", "rates", "errors", "storage"]
+///!
+///! See hooks.rs for [[hooks]], escalation.rs for [[alert_rules]], columns.rs
+///! for [[columns]], node_control.rs for [[actions]], profile.rs for
+///! [profile.NAME], tags.rs for [tags] and vault_metrics::health for [health].
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use super::columns::Column;
+use super::escalation::AlertRule;
+use super::hooks::HookConfig;
+use super::node_control::ActionConfig;
+use super::opt::Opt;
+use super::profile::Profile;
+use vault_metrics::{HealthWeights, Thresholds};
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+	pub node_panel: NodePanelConfig,
+	pub hooks: Vec<HookConfig>,
+	///! Patterns whose lines raise an alert when enough cluster together
+	pub alert_rules: Vec<AlertRule>,
+	pub columns: Vec<Column>,
+	pub health: HealthWeights,
+	///! Warn and crit levels of metrics, which colour them and lower the health score
+	pub thresholds: Thresholds,
+	///! Node control commands, in place of node_control::default_actions()
+	pub actions: Vec<ActionConfig>,
+	///! Settings for each network, by name, selected with --profile
+	pub profile: BTreeMap<String, Profile>,
+	///! The nodes given each tag, by name as for --data-dir (see --tag)
+	pub tags: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodePanelConfig {
+	///! Widgets shown left to right
+	pub widgets: Vec<NodeWidget>,
+}
+
+impl Default for NodePanelConfig {
+	fn default() -> NodePanelConfig {
+		NodePanelConfig {
+			widgets: vec![NodeWidget::Counts, NodeWidget::Errors, NodeWidget::Storage],
+		}
+	}
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeWidget {
+	///! Node state and GET/PUT/ERROR counts
+	Counts,
+	///! Counts in the last complete minute
+	Rates,
+	///! Chunk store usage gauges
+	Storage,
+	///! Elders and adults in the node's section
+	Peers,
+	///! Most frequent ERROR and WARN messages, grouped by template
+	Errors,
+	///! Heatmap of request latency over time (see --latency-pattern)
+	Latency,
+	///! Duplicate and replayed messages, by the peer sending them
+	Duplicates,
+	///! PUTs and GETs by the type of data, such as Blob or Map
+	Data,
+	///! Charts of the values captured by --watch patterns
+	Watched,
+	///! The key rewards are paid to, in full
+	Rewards,
+}
+
+///! A setting which is not valid, in the table headed by table (the index-th of them, for an array of tables)
+#[derive(Debug, PartialEq)]
+pub struct ConfigProblem {
+	pub table: String,
+	pub index: usize,
+	pub message: String,
+}
+
+impl ConfigProblem {
+	fn new(table: &str, index: usize, message: String) -> ConfigProblem {
+		ConfigProblem {
+			table: table.to_string(),
+			index,
+			message,
+		}
+	}
+}
+
+impl Config {
+	///! Load --config FILE, or the default config file if there is one
+	pub fn load(opt: &Opt) -> Result<Config, Error> {
+		let path = match Config::path(opt) {
+			Some(path) => path,
+			None => return Ok(Config::default()),
+		};
+		let text = std::fs::read_to_string(&path)
+			.map_err(|e| Error::new(e.kind(), format!("config file {}: {}", path.display(), e)))?;
+		let config = Config::parse(&text)
+			.map_err(|e| Error::new(ErrorKind::Other, format!("config file {}: {}", path.display(), e)))?;
+		match config.problems().into_iter().next() {
+			Some(problem) => Err(Error::new(
+				ErrorKind::Other,
+				format!("config file {}: {}", path.display(), problem.message),
+			)),
+			None => Ok(config),
+		}
+	}
+
+	///! The config file to load: --config FILE, or the default config file if it exists
+	pub fn path(opt: &Opt) -> Option<PathBuf> {
+		match &opt.config {
+			Some(config) => Some(PathBuf::from(config)),
+			None => default_path().filter(|path| path.exists()),
+		}
+	}
+
+	pub fn parse(text: &str) -> Result<Config, toml::de::Error> {
+		toml::from_str(text)
+	}
+
+	///! Settings which parse but are not valid, other than in profiles (see Profile::problems())
+	pub fn problems(&self) -> Vec<ConfigProblem> {
+		let mut problems = Vec::new();
+		for (index, hook) in self.hooks.iter().enumerate() {
+			if let Err(e) = hook.validate() {
+				problems.push(ConfigProblem::new("[[hooks]]", index, e.to_string()));
+			}
+		}
+		if let Err(e) = self.thresholds.validate() {
+			problems.push(ConfigProblem::new("[thresholds]", 0, e));
+		}
+		for (index, action) in self.actions.iter().enumerate() {
+			if let Err(e) = action.validate() {
+				problems.push(ConfigProblem::new("[[actions]]", index, e.to_string()));
+			}
+		}
+		problems
+	}
+
+	pub fn find_profile(&self, name: &str) -> Result<&Profile, Error> {
+		self.profile.get(name).ok_or_else(|| {
+			let names: Vec<&str> = self.profile.keys().map(String::as_str).collect();
+			let known = match names.is_empty() {
+				true => String::from("the config file has none"),
+				false => format!("profiles are: {}", names.join(", ")),
+			};
+			Error::new(ErrorKind::NotFound, format!("--profile {}: no [profile.{}] ({})", name, name, known))
+		})
+	}
+}
+
+///! The platform config directory's vdash/config.toml
+pub fn default_path() -> Option<PathBuf> {
+	config_dir().map(|dir| dir.join("config.toml"))
+}
+
+///! The platform config directory's vdash directory
+pub fn config_dir() -> Option<PathBuf> {
+	let config_dir = if cfg!(windows) {
+		std::env::var_os("APPDATA").map(PathBuf::from)
+	} else {
+		std::env::var_os("XDG_CONFIG_HOME")
+			.map(PathBuf::from)
+			.or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+	};
+	config_dir.map(|dir| dir.join("vdash"))
+}
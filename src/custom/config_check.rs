@@ -0,0 +1,124 @@
+///! Checking of the config file (--check-config)
+///!
+///! Loads the config file as vdash would at startup, but reports every problem
+///! rather than stopping at the first, each with the line of the config file
+///! it is on. This covers TOML syntax, unknown settings, column regexes,
+///! hooks, actions and thresholds, and the settings of every profile rather
+///! than only one selected with --profile. vdash then exits, non-zero if there
+///! were problems, so a config file can be checked before restarting vdash
+///! with it, or in a script.
+use std::io::Error;
+use std::path::Path;
+
+use super::config::{self, Config};
+use super::opt::Opt;
+
+///! Check the config file, print a report and exit
+pub fn run(opt: &Opt) -> std::io::Result<()> {
+	let path = match Config::path(opt) {
+		Some(path) => path,
+		None => {
+			let looked_for = config::default_path().map_or(String::from("no config directory"), |path| path.display().to_string());
+			println!("No config file ({}), so the defaults are used", looked_for);
+			return Ok(());
+		}
+	};
+	let text = std::fs::read_to_string(&path)
+		.map_err(|e| Error::new(e.kind(), format!("config file {}: {}", path.display(), e)))?;
+	let problems = check(&path, &text);
+	if problems.is_empty() {
+		println!("{}: OK", path.display());
+		return Ok(());
+	}
+	for problem in problems.iter() {
+		println!("{}", problem);
+	}
+	let plural = if problems.len() == 1 { "" } else { "s" };
+	println!("{} problem{} found", problems.len(), plural);
+	std::process::exit(1);
+}
+
+///! Each problem with the config file at path, whose content is text, as 'PATH:LINE: problem'
+pub fn check(path: &Path, text: &str) -> Vec<String> {
+	let at_line = |line: Option<usize>, message: &str| match line {
+		Some(line) => format!("{}:{}: {}", path.display(), line, message),
+		None => format!("{}: {}", path.display(), message),
+	};
+	let config = match Config::parse(text) {
+		Ok(config) => config,
+		Err(e) => {
+			// Syntax errors and unknown settings stop parsing, so only the first is found
+			let message = e.to_string();
+			let line = e.line_col().map(|(line, _)| line + 1);
+			let message = match (line, message.rfind(" at line ")) {
+				(Some(_), Some(location)) => &message[..location],
+				_ => message.as_str(),
+			};
+			return vec![at_line(line, message)];
+		}
+	};
+
+	let mut problems: Vec<String> = config
+		.problems()
+		.iter()
+		.map(|problem| at_line(table_line(text, &problem.table, problem.index), &problem.message))
+		.collect();
+	for (name, profile) in config.profile.iter() {
+		let line = table_line(text, &format!("[profile.{}]", name), 0);
+		for problem in profile.problems() {
+			problems.push(at_line(line, &format!("[profile.{}] {}", name, problem)));
+		}
+	}
+	problems
+}
+
+///! The line number (from 1) of the index-th table headed table, such as '[[hooks]]'
+fn table_line(text: &str, table: &str, index: usize) -> Option<usize> {
+	text.lines()
+		.enumerate()
+		.filter(|(_, line)| {
+			let header: String = line.chars().filter(|c| !c.is_whitespace() && *c != '"' && *c != '\'').collect();
+			// Allowing for a comment after the header
+			header == table || header.starts_with(&format!("{}#", table))
+		})
+		.nth(index)
+		.map(|(number, _)| number + 1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reports_every_problem_with_its_line() {
+		let path = Path::new("config.toml");
+		let text = r#"
+[[hooks]]
+on_event = "promotion"
+run = "./notify.sh"
+
+[[hooks]]
+on_event = "promoted"
+run = "./notify.sh"
+
+[thresholds]
+error_rate = { warn = 10.0, crit = 5.0 }
+
+[profile."testnet-7"]
+parser = "json"
+latency_pattern = 'took (\d+ms'
+"#;
+		let problems = check(path, text);
+		assert_eq!(problems.len(), 4);
+		assert!(problems[0].starts_with("config.toml:6: unknown hook event 'promoted'"));
+		assert!(problems[1].starts_with("config.toml:10: [thresholds] error_rate"));
+		assert!(problems[2].starts_with("config.toml:13: [profile.testnet-7] parser: unknown parser 'json'"));
+		assert!(problems[3].starts_with("config.toml:13: [profile.testnet-7] latency_pattern:"));
+
+		let problems = check(path, "[node_panel]\nwidgets = [\"counts\"]\n\n[[columns]]\nname = \"Age\"\npattern = '(\\d+'\n");
+		assert_eq!(problems.len(), 1);
+		assert!(problems[0].starts_with("config.toml:4: column 'Age'"), "{}", problems[0]);
+		assert!(check(path, "[node_panel]\nwidget = []\n")[0].starts_with("config.toml:1: unknown field `widget`"));
+		assert!(check(path, "[node_panel]\nwidgets = [\"data\"]\n").is_empty());
+	}
+}
@@ -0,0 +1,261 @@
+///! Daemon mode: headless monitoring with a dashboard that can attach and detach
+///!
+///! A daemon (--daemon SOCKET) keeps monitoring its logfiles when no terminal
+///! is open so that no metrics are missed. A dashboard started with
+///! --attach SOCKET is sent a snapshot of every LogMonitor, followed by each
+///! new logfile line as it arrives. Quitting the dashboard only detaches it,
+///! leaving the daemon running. On SIGTERM or SIGINT the daemon disconnects
+///! its dashboards and removes the socket.
+///!
+///! Each dashboard is written to by a thread of its own, so one which stalls
+///! is dropped without holding up the logfiles. Errors with a line are
+///! reported as by the dashboard rather than stopping the daemon.
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind};
+
+use super::app::{App, NodeMetrics};
+use super::ingest::LineSender;
+
+/// State of a LogMonitor as sent to a dashboard when it attaches
+pub struct MonitorSnapshot {
+	pub logfile: String,
+	pub content: Vec<String>,
+	pub metrics: NodeMetrics,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+enum Frame {
+	/// A LogMonitor's logfile and content, always followed by its NodeMetrics
+	Monitor { logfile: String, content: Vec<String> },
+	/// End of the snapshot sent to a newly attached dashboard
+	Attached,
+	/// A line appended to a monitored logfile
+	Line { logfile: String, line: String },
+}
+
+fn to_io_error(e: bincode::Error) -> Error {
+	Error::new(ErrorKind::Other, format!("vdash daemon connection: {}", e))
+}
+
+#[cfg(unix)]
+pub use self::unix::{attach, run_daemon};
+
+#[cfg(not(unix))]
+pub async fn run_daemon(_app: App, _socket: String) -> std::io::Result<()> {
+	Err(Error::new(ErrorKind::Other, "--daemon is only supported on unix"))
+}
+
+#[cfg(not(unix))]
+pub fn attach(_socket: &str, _tx: LineSender) -> std::io::Result<Vec<MonitorSnapshot>> {
+	Err(Error::new(ErrorKind::Other, "--attach is only supported on unix"))
+}
+
+#[cfg(unix)]
+mod unix {
+	use super::super::app_error::AppError;
+	use super::*;
+	use futures::{
+		future::FutureExt, // for `.fuse()`
+		pin_mut,
+		select,
+	};
+	use std::io::Write;
+	use std::os::unix::net::{UnixListener, UnixStream};
+	use std::sync::mpsc::{sync_channel, SyncSender};
+	use std::thread;
+	use std::time::Duration;
+	use tokio::sync::mpsc;
+
+	// A dashboard which stops reading is dropped rather than stalling its writer
+	const DASHBOARD_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+	///! Frames queued for a dashboard, beyond which it has stalled and is dropped
+	const DASHBOARD_QUEUE_MAX: usize = 4096;
+
+	///! An attached dashboard, whose frames are written by a thread of its own
+	struct Dashboard {
+		tx: SyncSender<Frame>,
+		stream: UnixStream,
+	}
+
+	impl Dashboard {
+		///! Write the snapshot to stream, followed by each frame sent
+		fn spawn(stream: UnixStream, snapshot: Vec<u8>) -> std::io::Result<Dashboard> {
+			stream.set_write_timeout(Some(DASHBOARD_WRITE_TIMEOUT))?;
+			let writer = stream.try_clone()?;
+			let (tx, rx) = sync_channel::<Frame>(DASHBOARD_QUEUE_MAX);
+			thread::spawn(move || {
+				if let Err(e) = (&writer).write_all(&snapshot) {
+					warn!("Failed to attach dashboard: {}", e);
+					return;
+				}
+				info!("Dashboard attached");
+				for frame in rx {
+					if let Err(e) = bincode::serialize_into(&writer, &frame) {
+						info!("Dashboard detached: {}", e);
+						break;
+					}
+				}
+				let _ = writer.shutdown(std::net::Shutdown::Both);
+			});
+			Ok(Dashboard { tx, stream })
+		}
+
+		///! Queue a frame, returning false once the dashboard has gone or stalled
+		fn send(&self, frame: &Frame) -> bool {
+			self.tx.try_send(frame.clone()).is_ok()
+		}
+	}
+
+	pub async fn run_daemon(mut app: App, socket: String) -> std::io::Result<()> {
+		let mut dashboard_rx = listen(&socket)?;
+		let mut dashboards = Vec::<Dashboard>::new();
+		println!("vdash daemon listening at {}", socket);
+		info!("Daemon listening at {}", socket);
+
+		let mut tick_rx = spawn_ticker(Duration::from_millis(app.opt.tick_rate));
+		loop {
+			let line_future = app.next_line().fuse();
+			let dashboard_future = dashboard_rx.recv().fuse();
+			let tick_future = tick_rx.recv().fuse();
+			pin_mut!(line_future, dashboard_future, tick_future);
+
+			select! {
+				line = line_future => {
+					match line {
+						Some(Ok((source, line, length))) => {
+							#[cfg(feature = "remote")]
+							app.add_syslog_monitor(&source);
+							let journaled = app.journal_line(&source, &line, length);
+							app.report("writing journal", journaled);
+							if let Some(monitor) = app.get_monitor_for_file_path(&source) {
+								let appended = monitor.append_line(&line, length);
+								let frame = Frame::Line { logfile: monitor.logfile.clone(), line: line.clone() };
+								dashboards.retain(|dashboard| dashboard.send(&frame));
+								app.report(&format!("adding line from {}", source), appended);
+								app.update_snapshot_summary();
+							}
+							app.merge_line(&source, &line);
+							app.run_hooks();
+						},
+						Some(Err(e)) => app.report_error(AppError::new("reading logfile", e)),
+						None => (),
+					}
+				},
+				stream = dashboard_future => {
+					if let Some(stream) = stream {
+						match snapshot(&app).and_then(|snapshot| Dashboard::spawn(stream, snapshot)) {
+							Ok(dashboard) => dashboards.push(dashboard),
+							Err(e) => warn!("Failed to attach dashboard: {}", e),
+						}
+					}
+				},
+				_ = tick_future => {
+					if app.shutdown.requested() {
+						app.shutdown();
+						for dashboard in dashboards.iter() {
+							let _ = dashboard.stream.shutdown(std::net::Shutdown::Both);
+						}
+						let _ = std::fs::remove_file(&socket);
+						info!("Daemon stopped");
+						return Ok(());
+					}
+					app.check_watches();
+					app.flush_merged();
+					app.watchdog.tick();
+					app.run_hooks();
+				}
+			}
+		}
+	}
+
+	/// Send a tick at tick_rate from a thread, for the watchdog and hooks
+	fn spawn_ticker(tick_rate: Duration) -> mpsc::UnboundedReceiver<()> {
+		let (tx, rx) = mpsc::unbounded_channel();
+		thread::spawn(move || {
+			while tx.send(()).is_ok() {
+				thread::sleep(tick_rate);
+			}
+		});
+		rx
+	}
+
+	/// Accept dashboard connections on a thread, passing each to the daemon
+	fn listen(socket: &str) -> std::io::Result<mpsc::UnboundedReceiver<UnixStream>> {
+		if UnixStream::connect(socket).is_ok() {
+			return Err(Error::new(
+				ErrorKind::AddrInUse,
+				format!("a vdash daemon is already listening at {}", socket),
+			));
+		}
+		let _ = std::fs::remove_file(socket); // Stale socket from an earlier daemon
+		let listener = UnixListener::bind(socket)?;
+
+		let (tx, rx) = mpsc::unbounded_channel();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				match stream {
+					Ok(stream) => {
+						if tx.send(stream).is_err() {
+							return;
+						}
+					}
+					Err(e) => warn!("Daemon failed to accept dashboard: {}", e),
+				}
+			}
+		});
+		Ok(rx)
+	}
+
+	///! The frames sent to a newly attached dashboard, serialized here so its writer needn't borrow the App
+	fn snapshot(app: &App) -> std::io::Result<Vec<u8>> {
+		let mut snapshot = Vec::new();
+		for logfile in app.logfile_names.iter() {
+			if let Some(monitor) = app.monitors.get(logfile) {
+				let frame = Frame::Monitor {
+					logfile: monitor.logfile.clone(),
+					content: monitor.content.items.clone(),
+				};
+				bincode::serialize_into(&mut snapshot, &frame).map_err(to_io_error)?;
+				bincode::serialize_into(&mut snapshot, &monitor.metrics).map_err(to_io_error)?;
+			}
+		}
+		bincode::serialize_into(&mut snapshot, &Frame::Attached).map_err(to_io_error)?;
+		Ok(snapshot)
+	}
+
+	///! Attach to the daemon at socket, returning its snapshot and sending each line received after as (logfile, line)
+	pub fn attach(socket: &str, tx: LineSender) -> std::io::Result<Vec<MonitorSnapshot>> {
+		let stream = UnixStream::connect(socket)?;
+		let mut snapshots = Vec::<MonitorSnapshot>::new();
+		loop {
+			match bincode::deserialize_from(&stream).map_err(to_io_error)? {
+				Frame::Monitor { logfile, content } => {
+					let metrics = bincode::deserialize_from(&stream).map_err(to_io_error)?;
+					snapshots.push(MonitorSnapshot {
+						logfile,
+						content,
+						metrics,
+					});
+				}
+				Frame::Attached => break,
+				Frame::Line { .. } => {
+					return Err(Error::new(ErrorKind::InvalidData, "unexpected line in daemon snapshot"))
+				}
+			}
+		}
+
+		thread::spawn(move || loop {
+			let line = match bincode::deserialize_from(&stream) {
+				Ok(Frame::Line { logfile, line }) => Ok((logfile, line, None)),
+				Ok(_) => continue,
+				Err(e) => Err(to_io_error(e)),
+			};
+			let disconnected = line.is_err();
+			if tx.send(line).is_err() || disconnected {
+				return;
+			}
+		});
+		Ok(snapshots)
+	}
+}
@@ -0,0 +1,127 @@
+///! Sampling of the size of each node's data directory on disk
+///!
+///! With --data-dir NAME=PATH, the size of PATH is measured every
+///! SAMPLE_INTERVAL and shown beside the node's chunk store, whose figures come
+///! from the used_space records the node keeps. The two should roughly agree,
+///! so a difference beyond DIVERGENCE_FRACTION is flagged, as it suggests
+///! leftover files or records which are out of date. NAME is the node's number
+///! in the dashboard, its logfile, or the name of the directory holding its
+///! logfile (such as sn-node-2 in a local test network). Directories are walked
+///! on a thread of their own as large chunk stores can take a while.
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+///! Fraction of the larger of two sizes by which they must differ to diverge
+const DIVERGENCE_FRACTION: f64 = 0.1;
+
+///! Differences smaller than this never diverge, as a new store has little in it
+const DIVERGENCE_MIN_BYTES: u64 = 1024 * 1024;
+
+///! A --data-dir option
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataDir {
+	pub name: String,
+	pub path: PathBuf,
+}
+
+impl FromStr for DataDir {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<DataDir, String> {
+		match s.split_once('=') {
+			Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok(DataDir {
+				name: name.to_string(),
+				path: PathBuf::from(path),
+			}),
+			_ => Err(format!("'{}' is not of the form NAME=PATH", s)),
+		}
+	}
+}
+
+impl DataDir {
+	///! Whether this is the data directory of the node numbered node (from 1) with logfile
+	pub fn is_for(&self, node: usize, logfile: &str) -> bool {
+		names_node(&self.name, node, logfile)
+	}
+}
+
+///! Whether name is the number of node (from 1), its logfile, or the name of its logfile's directory
+pub fn names_node(name: &str, node: usize, logfile: &str) -> bool {
+	let logfile_dir = Path::new(logfile)
+		.parent()
+		.and_then(|dir| dir.file_name())
+		.and_then(|name| name.to_str());
+	name == node.to_string() || name == logfile || Some(name) == logfile_dir
+}
+
+///! Total size of the files in and below path, not following symbolic links
+pub fn dir_size(path: &Path) -> std::io::Result<u64> {
+	let mut size = 0;
+	for entry in std::fs::read_dir(path)? {
+		let entry = entry?;
+		let metadata = entry.metadata()?;
+		if metadata.is_dir() {
+			size += dir_size(&entry.path())?;
+		} else {
+			size += metadata.len();
+		}
+	}
+	Ok(size)
+}
+
+///! Whether a size on disk differs too much from the size recorded by the node
+pub fn diverges(on_disk: u64, recorded: u64) -> bool {
+	let difference = on_disk.max(recorded) - on_disk.min(recorded);
+	difference > DIVERGENCE_MIN_BYTES && difference as f64 > on_disk.max(recorded) as f64 * DIVERGENCE_FRACTION
+}
+
+///! Measure each directory on a thread, returning a channel of the node
+///! index and size of each directory, or None if it couldn't be measured
+pub fn spawn_sampler(dirs: Vec<(usize, PathBuf)>) -> mpsc::UnboundedReceiver<(usize, Option<u64>)> {
+	let (tx, rx) = mpsc::unbounded_channel();
+	thread::spawn(move || loop {
+		for (index, path) in dirs.iter() {
+			let size = match dir_size(path) {
+				Ok(size) => Some(size),
+				Err(e) => {
+					warn!("failed to measure --data-dir {}: {}", path.display(), e);
+					None
+				}
+			};
+			if tx.send((*index, size)).is_err() {
+				return;
+			}
+		}
+		thread::sleep(SAMPLE_INTERVAL);
+	});
+	rx
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn measures_and_matches_data_dirs() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::create_dir_all(dir.path().join("chunks/immutable")).unwrap();
+		std::fs::write(dir.path().join("chunks/immutable/a"), vec![0; 1000]).unwrap();
+		std::fs::write(dir.path().join("used_space"), vec![0; 24]).unwrap();
+		assert_eq!(dir_size(dir.path()).unwrap(), 1024);
+
+		let data_dir: DataDir = "sn-node-2=/tmp/sn-node-2".parse().unwrap();
+		assert!(data_dir.is_for(5, "/home/me/nodes/sn-node-2/sn_node.log"));
+		assert!(!data_dir.is_for(2, "/home/me/nodes/sn-node-1/sn_node.log"));
+		assert!("2=/data".parse::<DataDir>().unwrap().is_for(2, "node.log"));
+		assert!("/data".parse::<DataDir>().is_err());
+
+		assert!(!diverges(100, 200));
+		assert!(!diverges(100_000_000, 95_000_000));
+		assert!(diverges(100_000_000, 50_000_000));
+	}
+}
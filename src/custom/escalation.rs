@@ -0,0 +1,149 @@
+///! Escalation rules: alerts for clusters of lines rather than single ones
+///!
+///! A single warning is usually transient, but many in a short time need
+///! attention. Rules in the config file send an `escalation` event, which
+///! raises an alert (see Alerts) and runs any hooks for it, when a pattern
+///! matches some number of a node's lines within some seconds:
+///!
+///!     [[alert_rules]]
+///!     name = "warnings"
+///!     pattern = ' WARN '
+///!     count = 5
+///!     seconds = 60
+///!
+///! Matches are counted in a BucketSet (see vault_metrics::timeline) of up to
+///! RULE_BUCKETS buckets spanning the rule's seconds, which moves on a bucket
+///! at a time. A rule fires once as its count is reached, and again only for
+///! a later cluster, once the count has fallen below it.
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+use super::hooks::{NodeEvent, EVENT_ESCALATION};
+use vault_metrics::timeline::BucketSet;
+
+///! Most buckets in which a rule's matches are counted, each at least a second
+const RULE_BUCKETS: u64 = 60;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AlertRuleConfig {
+	name: String,
+	pattern: String,
+	count: u64,
+	seconds: u64,
+}
+
+///! A rule from [[alert_rules]] in the config file
+#[derive(Deserialize, Clone, Debug)]
+#[serde(try_from = "AlertRuleConfig")]
+pub struct AlertRule {
+	pub name: String,
+	regex: Regex,
+	pub count: u64,
+	pub seconds: u64,
+}
+
+impl TryFrom<AlertRuleConfig> for AlertRule {
+	type Error = String;
+
+	fn try_from(config: AlertRuleConfig) -> Result<AlertRule, String> {
+		let regex = Regex::new(&config.pattern).map_err(|e| format!("alert rule '{}': {}", config.name, e))?;
+		if config.count == 0 || config.seconds == 0 {
+			return Err(format!("alert rule '{}': count and seconds must be at least 1", config.name));
+		}
+		Ok(AlertRule {
+			name: config.name,
+			regex,
+			count: config.count,
+			seconds: config.seconds,
+		})
+	}
+}
+
+///! A node's lines which match a rule, counted over the rule's seconds
+pub struct Escalation {
+	rule: AlertRule,
+	matches: BucketSet,
+	///! Whether the count has reached the rule's since it last fell below it
+	firing: bool,
+}
+
+impl Escalation {
+	pub fn new(rule: &AlertRule) -> Escalation {
+		let buckets = rule.seconds.min(RULE_BUCKETS);
+		let bucket_duration = Duration::milliseconds((rule.seconds * 1000 / buckets) as i64);
+		Escalation {
+			rule: rule.clone(),
+			matches: BucketSet::new(bucket_duration, buckets as usize),
+			firing: false,
+		}
+	}
+
+	///! Count line, logged at time, if it matches, returning an event if that reaches the rule's count
+	pub fn check(&mut self, line: &str, time: DateTime<Utc>) -> Option<NodeEvent> {
+		if !self.rule.regex.is_match(line) {
+			return None;
+		}
+		self.matches.update_current_time(Some(time));
+		if self.count() < self.rule.count {
+			self.firing = false;
+		}
+		self.matches.increment_at(time);
+		let count = self.count();
+		if self.firing || count < self.rule.count {
+			return None;
+		}
+		self.firing = true;
+		Some(NodeEvent {
+			event: EVENT_ESCALATION,
+			detail: format!("{}: {} lines within {}s", self.rule.name, count, self.rule.seconds),
+		})
+	}
+
+	///! Matches within the rule's seconds
+	pub fn count(&self) -> u64 {
+		self.matches.buckets().iter().sum()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rule(text: &str) -> Result<AlertRule, toml::de::Error> {
+		toml::from_str(text)
+	}
+
+	#[test]
+	fn fires_for_clusters_of_matches() {
+		let rule = rule("name = 'warnings'\npattern = ' WARN '\ncount = 3\nseconds = 60").unwrap();
+		let mut escalation = Escalation::new(&rule);
+		let start = Utc::now();
+		let at = |seconds: i64| start + Duration::seconds(seconds);
+
+		// Warnings spread out never reach the count
+		for minute in 0..3 {
+			assert!(escalation.check("[sn_node] WARN Dead peer", at(minute * 90)).is_none());
+		}
+		assert!(escalation.check("[sn_node] INFO Dead peer", at(200)).is_none());
+		assert!(escalation.check("[sn_node] WARN Dead peer", at(300)).is_none());
+		assert!(escalation.check("[sn_node] WARN Dead peer", at(310)).is_none());
+		let event = escalation.check("[sn_node] WARN Dead peer", at(320)).unwrap();
+		assert_eq!(event.event, EVENT_ESCALATION);
+		assert_eq!(event.detail, "warnings: 3 lines within 60s");
+		// Only once for the cluster
+		assert!(escalation.check("[sn_node] WARN Dead peer", at(330)).is_none());
+		assert_eq!(escalation.count(), 4);
+
+		// A later cluster fires again
+		for seconds in [500, 501].iter() {
+			assert!(escalation.check("[sn_node] WARN Dead peer", at(*seconds)).is_none());
+		}
+		assert!(escalation.check("[sn_node] WARN Dead peer", at(502)).is_some());
+
+		assert!(self::rule("name = 'w'\npattern = '('\ncount = 3\nseconds = 60").is_err());
+		assert!(self::rule("name = 'w'\npattern = 'WARN'\ncount = 0\nseconds = 60").is_err());
+	}
+}
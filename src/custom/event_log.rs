@@ -0,0 +1,120 @@
+///! Export of node events as newline-delimited JSON (--events-ndjson FILE)
+///!
+///! Each event passed to the hooks (a node starting, a promotion, an ERROR
+///! entry, a change of status, or events flapping) is appended to FILE as a
+///! JSON object on a line of its own, so that a SIEM or log pipeline can
+///! ingest what vdash makes of the logfiles. The fields are those of
+///! EventRecord, and any change to them is marked by a new EVENT_SCHEMA.
+///! FILE is opened for each event, which are few, so it can be rotated by
+///! renaming it.
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+
+use super::app::LogMonitor;
+use super::hooks::NodeEvent;
+
+///! Identifies the fields of each record
+pub static EVENT_SCHEMA: &str = "vdash-event-1";
+
+#[derive(Serialize)]
+pub struct EventRecord<'a> {
+	pub schema: &'static str,
+	///! When vdash detected the event, in RFC 3339 UTC
+	pub time: String,
+	///! The node's number in the dashboard
+	pub node: usize,
+	pub logfile: &'a str,
+	///! Empty until the node has logged them
+	pub node_name: &'a str,
+	pub section: &'a str,
+	///! One of hooks::EVENTS
+	pub event: &'static str,
+	pub detail: &'a str,
+	///! The node's status once the event occurred
+	pub status: &'static str,
+}
+
+impl<'a> EventRecord<'a> {
+	pub fn new(monitor: &'a LogMonitor, event: &'a NodeEvent, time: DateTime<Utc>) -> EventRecord<'a> {
+		EventRecord {
+			schema: EVENT_SCHEMA,
+			time: time.to_rfc3339_opts(SecondsFormat::Millis, true),
+			node: monitor.index + 1,
+			logfile: &monitor.logfile,
+			node_name: &monitor.metrics.node_name,
+			section: &monitor.metrics.section_prefix,
+			event: event.event,
+			detail: &event.detail,
+			status: monitor.metrics.status().label(),
+		}
+	}
+}
+
+pub struct EventLog {
+	path: String,
+}
+
+impl EventLog {
+	///! An EventLog appending to the file at path, which is created now so that a bad path is reported
+	pub fn open(path: &str) -> Result<EventLog, Error> {
+		open_for_append(path).map_err(|e| Error::new(e.kind(), format!("--events-ndjson {}: {}", path, e)))?;
+		Ok(EventLog { path: path.to_string() })
+	}
+
+	pub fn append(&self, record: &EventRecord) -> Result<(), Error> {
+		let mut line = serde_json::to_string(record).map_err(|e| Error::new(ErrorKind::Other, e))?;
+		line.push('\n');
+		// Written whole so that another process appending can't interleave part of a line
+		open_for_append(&self.path)
+			.and_then(|mut file| file.write_all(line.as_bytes()))
+			.map_err(|e| Error::new(e.kind(), format!("{}: {}", self.path, e)))
+	}
+}
+
+fn open_for_append(path: &str) -> Result<File, Error> {
+	OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::hooks::EVENT_PROMOTION;
+	use crate::custom::opt::{Opt, StructOpt};
+
+	#[test]
+	fn appends_records_as_lines() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("events.ndjson");
+		let path = path.to_str().unwrap();
+		let opt = Opt::from_iter(&["vdash", "node.log"]);
+		let mut monitor = LogMonitor::new(&opt, "node.log".to_string(), opt.lines_max);
+		monitor.metrics.node_name = "f67c2e..".to_string();
+		let event = NodeEvent {
+			event: EVENT_PROMOTION,
+			detail: "Adult".to_string(),
+		};
+		let time = DateTime::parse_from_rfc3339("2021-01-20T18:00:00Z").unwrap().with_timezone(&Utc);
+
+		let log = EventLog::open(path).unwrap();
+		log.append(&EventRecord::new(&monitor, &event, time)).unwrap();
+		// Rotated by renaming
+		let rotated = dir.path().join("events.ndjson.1");
+		std::fs::rename(path, &rotated).unwrap();
+		log.append(&EventRecord::new(&monitor, &event, time)).unwrap();
+		log.append(&EventRecord::new(&monitor, &event, time)).unwrap();
+		assert_eq!(std::fs::read_to_string(rotated).unwrap().lines().count(), 1);
+
+		let text = std::fs::read_to_string(path).unwrap();
+		let lines: Vec<&str> = text.lines().collect();
+		assert_eq!(lines.len(), 2);
+		let record: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+		assert_eq!(record["schema"], EVENT_SCHEMA);
+		assert_eq!(record["time"], "2021-01-20T18:00:00.000Z");
+		assert_eq!(record["logfile"], "node.log");
+		assert_eq!(record["node_name"], "f67c2e..");
+		assert_eq!(record["event"], "promotion");
+		assert_eq!(record["detail"], "Adult");
+	}
+}
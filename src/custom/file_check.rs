@@ -0,0 +1,316 @@
+///! Detection of logfiles replaced without a rename (e.g. copytruncate rotation)
+///!
+///! linemux follows appends and renames, but can miss a logfile being
+///! truncated and rewritten between its change events, or replaced by a
+///! new file at the same path. A thread periodically stats each logfile and
+///! checks a hash of its head, reporting any logfile whose inode or head has
+///! changed so that its LogMonitor can be reset and reloaded.
+///!
+///! The length of each logfile is also reported at each check, so that a
+///! watch which is missing changes can be noticed (see ingest::WatchCheck),
+///! or None if it is missing, so that a logfile deleted and never recreated
+///! can be noticed (see Presence).
+///!
+///! ContentMarks note the first and last lines read from a logfile, so that
+///! content already processed is recognised when a logfile is resumed from a
+///! --journal, or is reported replaced but holds what was read (such as when
+///! copied back into place), and reading continues from where it left off
+///! rather than parsing the logfile again and counting its lines twice.
+///!
+///! For --latest-in, another thread watches a directory for a newer logfile,
+///! such as one written by each run of a node.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+pub static CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+///! How long a logfile which existed must be missing to be gone, rather than being rotated
+pub static GONE_AFTER: Duration = Duration::from_secs(60);
+
+// Enough to include the first entries of a logfile, which don't change as it grows
+const HEAD_BYTES: usize = 4096;
+
+#[derive(Debug, PartialEq)]
+struct FileIdentity {
+	inode: u64,
+	len: u64,
+	head_len: usize,
+	head_hash: u64,
+}
+
+impl FileIdentity {
+	fn new(path: &str, head_len: usize) -> Option<FileIdentity> {
+		let metadata = std::fs::metadata(path).ok()?;
+		let mut head = Vec::<u8>::with_capacity(head_len);
+		File::open(path).ok()?.take(head_len as u64).read_to_end(&mut head).ok()?;
+
+		let mut hasher = DefaultHasher::new();
+		hasher.write(&head);
+		Some(FileIdentity {
+			inode: inode(&metadata),
+			len: metadata.len(),
+			head_len: head.len(),
+			head_hash: hasher.finish(),
+		})
+	}
+
+	///! Test whether the file at path is no longer the one identified by self
+	fn is_replaced_at(&self, path: &str) -> Option<bool> {
+		// Compare only as much of the head as was present before
+		let now = FileIdentity::new(path, self.head_len)?;
+		Some(now.inode != self.inode || now.len < self.len || now.head_hash != self.head_hash)
+	}
+}
+
+///! A line read from a logfile: where it starts and a hash of it, without its line ending
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct LineMark {
+	offset: u64,
+	hash: u64,
+}
+
+///! The first and last lines read from a logfile, to recognise content already processed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentMarks {
+	first: Option<LineMark>,
+	last: Option<LineMark>,
+}
+
+impl ContentMarks {
+	///! Note a line read from offset in the logfile, given without its line ending
+	pub fn record(&mut self, offset: u64, line: &str) {
+		let mark = LineMark {
+			offset,
+			hash: line_hash(line),
+		};
+		if self.first.is_none() {
+			self.first = Some(mark);
+		}
+		self.last = Some(mark);
+	}
+
+	pub fn clear(&mut self) {
+		*self = ContentMarks::default();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.first.is_none()
+	}
+
+	///! Whether the file at path holds the content marked, having been read up to position
+	///!
+	///! With no lines marked, only its length can be compared.
+	pub fn are_held_by(&self, path: &str, position: u64) -> bool {
+		let file = match File::open(path) {
+			Ok(file) => file,
+			Err(_) => return false,
+		};
+		match file.metadata() {
+			Ok(metadata) if metadata.len() >= position => {}
+			_ => return false,
+		}
+		let mut reader = BufReader::new(file);
+		self.first.iter().chain(self.last.iter()).all(|mark| {
+			let mut line = String::new();
+			reader.seek(SeekFrom::Start(mark.offset)).is_ok()
+				&& reader.read_line(&mut line).is_ok()
+				&& line_hash(line.trim_end_matches(&['\n', '\r'][..])) == mark.hash
+		})
+	}
+}
+
+///! FNV-1a, whose hashes unlike DefaultHasher's are the same in every build, as marks are journaled
+fn line_hash(line: &str) -> u64 {
+	line.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> u64 {
+	use std::os::unix::fs::MetadataExt;
+	metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> u64 {
+	0
+}
+
+///! Whether a logfile which existed has been deleted, and since when
+///!
+///! A logfile which has yet to be created is not gone, as it is ok for a
+///! logfile not to exist until its node starts.
+#[derive(Debug, Default)]
+pub struct Presence {
+	existed: bool,
+	missing_since: Option<Instant>,
+}
+
+impl Presence {
+	///! Note whether the logfile exists, at a check at now
+	pub fn check(&mut self, exists: bool, now: Instant) {
+		match exists {
+			true => {
+				self.existed = true;
+				self.missing_since = None;
+			}
+			false if self.existed && self.missing_since.is_none() => self.missing_since = Some(now),
+			false => {}
+		}
+	}
+
+	///! How long the logfile has been missing, once that is for GONE_AFTER or more
+	pub fn gone_for(&self, now: Instant) -> Option<Duration> {
+		let missing = now.duration_since(self.missing_since?);
+		if missing >= GONE_AFTER {
+			Some(missing)
+		} else {
+			None
+		}
+	}
+}
+
+///! Check logfiles on a thread, returning channels of those replaced and of the length of each (None if missing)
+pub fn spawn_checker(
+	logfiles: Vec<String>,
+) -> (mpsc::UnboundedReceiver<String>, mpsc::UnboundedReceiver<(String, Option<u64>)>) {
+	let (tx, rx) = mpsc::unbounded_channel();
+	let (lengths_tx, lengths_rx) = mpsc::unbounded_channel();
+	thread::spawn(move || {
+		let mut identities = HashMap::<String, FileIdentity>::new();
+		loop {
+			for logfile in logfiles.iter() {
+				let length = std::fs::metadata(logfile).ok().map(|metadata| metadata.len());
+				if lengths_tx.send((logfile.clone(), length)).is_err() {
+					return;
+				}
+				if let Some(identity) = identities.get(logfile) {
+					if identity.is_replaced_at(logfile) != Some(true) {
+						// Unchanged, or missing which linemux handles when it reappears
+						if identity.head_len == HEAD_BYTES {
+							continue;
+						}
+					} else if tx.send(logfile.clone()).is_err() {
+						return;
+					}
+				}
+
+				// New, replaced, or with a head still growing
+				if let Some(identity) = FileIdentity::new(logfile, HEAD_BYTES) {
+					identities.insert(logfile.clone(), identity);
+				}
+			}
+			thread::sleep(CHECK_INTERVAL);
+		}
+	});
+	(rx, lengths_rx)
+}
+
+///! The most recently modified file in dir, if any
+///!
+///! Hidden files are ignored, as are files in subdirectories.
+pub fn newest_file_in(dir: &str) -> Option<String> {
+	std::fs::read_dir(dir)
+		.ok()?
+		.filter_map(|entry| entry.ok())
+		.filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+		.filter_map(|entry| {
+			let metadata = entry.metadata().ok()?;
+			if !metadata.is_file() {
+				return None;
+			}
+			Some((metadata.modified().ok()?, entry.path()))
+		})
+		.max_by_key(|(modified, _)| *modified)
+		.and_then(|(_, path)| path.to_str().map(String::from))
+}
+
+///! Watch dir on a thread, returning a channel of each newer file to follow
+///!
+///! A file is only reported once, so a previous run's logfile being written
+///! after a new one starts doesn't switch back to it.
+pub fn spawn_latest_checker(dir: String, current: String) -> mpsc::UnboundedReceiver<String> {
+	let (tx, rx) = mpsc::unbounded_channel();
+	thread::spawn(move || {
+		let mut followed = vec![current];
+		loop {
+			thread::sleep(CHECK_INTERVAL);
+			if let Some(newest) = newest_file_in(&dir) {
+				if !followed.contains(&newest) {
+					if tx.send(newest.clone()).is_err() {
+						return;
+					}
+					followed.push(newest);
+				}
+			}
+		}
+	});
+	rx
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	#[test]
+	fn detects_truncate_and_rewrite() {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		let path = file.path().to_str().unwrap().to_string();
+		writeln!(file, "first entry").unwrap();
+
+		let identity = FileIdentity::new(&path, HEAD_BYTES).unwrap();
+		writeln!(file, "appended entry").unwrap();
+		assert_eq!(identity.is_replaced_at(&path), Some(false));
+
+		// copytruncate, then a new node writes more than before
+		std::fs::write(&path, "other entry\nother entry\nother entry\n").unwrap();
+		assert_eq!(identity.is_replaced_at(&path), Some(true));
+	}
+
+	#[test]
+	fn recognises_content_already_read() {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		let path = file.path().to_str().unwrap().to_string();
+		write!(file, "first entry\nsecond entry\n").unwrap();
+
+		let mut marks = ContentMarks::default();
+		assert!(marks.are_held_by(&path, 0));
+		marks.record(0, "first entry");
+		marks.record(12, "second entry");
+		assert!(marks.are_held_by(&path, 25));
+		assert!(!marks.are_held_by(&path, 26));
+		writeln!(file, "appended entry").unwrap();
+		assert!(marks.are_held_by(&path, 25));
+
+		// Rewritten with lines of the same lengths
+		std::fs::write(&path, "first entry\nsecond ENTRY\nappended entry\n").unwrap();
+		assert!(!marks.are_held_by(&path, 25));
+		assert!(!marks.are_held_by("/nonexistent/node.log", 0));
+	}
+
+	#[test]
+	fn logfiles_are_gone_once_missing_for_long_enough() {
+		let start = Instant::now();
+		let mut presence = Presence::default();
+		// Not yet created
+		presence.check(false, start);
+		assert_eq!(presence.gone_for(start + GONE_AFTER * 2), None);
+
+		presence.check(true, start);
+		presence.check(false, start + CHECK_INTERVAL);
+		presence.check(false, start + CHECK_INTERVAL * 2);
+		assert_eq!(presence.gone_for(start + CHECK_INTERVAL * 2), None);
+		assert_eq!(presence.gone_for(start + CHECK_INTERVAL + GONE_AFTER), Some(GONE_AFTER));
+
+		// Recreated, such as by rotation
+		presence.check(true, start + GONE_AFTER * 2);
+		assert_eq!(presence.gone_for(start + GONE_AFTER * 3), None);
+	}
+}
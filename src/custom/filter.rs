@@ -0,0 +1,265 @@
+///! Logfile filter: which of a node's lines its logfile view shows
+///!
+///! Opened with 'f', the filter editor builds a filter from terms and levels,
+///! previewing the node's current lines as they would be filtered. A term is
+///! text which lines must contain, or must not contain if typed with a leading
+///! '-' (case is ignored). A line is shown if it contains any of the include
+///! terms (or there are none), none of the exclude terms, and is of a level
+///! which isn't hidden. Lines without a level, such as continuations, go with
+///! the line before. A filter only affects what is shown, not the metrics.
+use super::app::LogMonitor;
+use super::keymap::InputKey;
+use vault_metrics::LOG_LINE_PATTERN;
+
+use tui::{
+	backend::Backend,
+	layout::{Constraint, Direction, Layout, Rect},
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+	Frame,
+};
+
+///! Levels which can be hidden, most severe first
+pub static LEVELS: [&str; 5] = ["ERROR", "WARN", "INFO", "DEBUG", "TRACE"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterTerm {
+	pub exclude: bool,
+	///! The text to look for, in lower case
+	pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineFilter {
+	pub terms: Vec<FilterTerm>,
+	pub hidden_levels: Vec<String>,
+}
+
+impl LineFilter {
+	pub fn new() -> LineFilter {
+		LineFilter::default()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.terms.is_empty() && self.hidden_levels.is_empty()
+	}
+
+	///! Add a term as typed, excluding if it starts with '-'
+	pub fn add_term(&mut self, typed: &str) {
+		let (exclude, text) = match typed.strip_prefix('-') {
+			Some(text) => (true, text),
+			None => (false, typed),
+		};
+		let text = text.trim().to_lowercase();
+		if !text.is_empty() {
+			self.terms.push(FilterTerm { exclude, text });
+		}
+	}
+
+	pub fn toggle_level(&mut self, level: &str) {
+		match self.hidden_levels.iter().position(|hidden| hidden == level) {
+			Some(index) => {
+				self.hidden_levels.remove(index);
+			}
+			None => self.hidden_levels.push(level.to_string()),
+		}
+	}
+
+	pub fn shows_level(&self, level: &str) -> bool {
+		!self.hidden_levels.iter().any(|hidden| hidden == level)
+	}
+
+	///! Whether line is shown, where level is its level or that of the line before
+	pub fn shows(&self, line: &str, level: Option<&str>) -> bool {
+		if let Some(level) = level {
+			if !self.shows_level(level) {
+				return false;
+			}
+		}
+		if self.terms.is_empty() {
+			return true;
+		}
+
+		let line = line.to_lowercase();
+		let mut included = None;
+		for term in self.terms.iter() {
+			let found = line.contains(&term.text);
+			match term.exclude {
+				true if found => return false,
+				true => {}
+				false => included = Some(included.unwrap_or(false) || found),
+			}
+		}
+		included.unwrap_or(true)
+	}
+
+	///! Whether each of lines is shown
+	pub fn shown(&self, lines: &[String]) -> Vec<bool> {
+		let mut level: Option<&str> = None;
+		lines
+			.iter()
+			.map(|line| {
+				level = line_level(line).or(level);
+				self.shows(line, level)
+			})
+			.collect()
+	}
+
+	///! A short description, such as "+sync -gossip !TRACE"
+	pub fn describe(&self) -> String {
+		let terms = self
+			.terms
+			.iter()
+			.map(|term| format!("{}{}", if term.exclude { '-' } else { '+' }, term.text));
+		let levels = self.hidden_levels.iter().map(|level| format!("!{}", level));
+		terms.chain(levels).collect::<Vec<String>>().join(" ")
+	}
+}
+
+///! The level of a line in the node's format, such as "INFO"
+pub fn line_level(line: &str) -> Option<&str> {
+	LOG_LINE_PATTERN
+		.captures(line)
+		.and_then(|captures| captures.name("category"))
+		.map(|m| m.as_str())
+}
+
+///! The filter being edited, previewed until it is applied
+pub struct FilterEditor {
+	pub draft: LineFilter,
+	///! The term being typed
+	pub input: String,
+	///! Whether keys go to the level toggles rather than the terms
+	pub levels_focused: bool,
+	///! Index into LEVELS of the level toggled by space
+	pub level_cursor: usize,
+}
+
+impl FilterEditor {
+	pub fn new(filter: &LineFilter) -> FilterEditor {
+		FilterEditor {
+			draft: filter.clone(),
+			input: String::new(),
+			levels_focused: false,
+			level_cursor: 0,
+		}
+	}
+
+	///! Handle a key, returning Some(filter) to apply or Some(None) to cancel
+	pub fn handle(&mut self, key: InputKey) -> Option<Option<LineFilter>> {
+		match key {
+			InputKey::Esc => return Some(None),
+			InputKey::Tab | InputKey::BackTab => self.levels_focused = !self.levels_focused,
+			InputKey::Enter if self.input.trim().is_empty() => return Some(Some(self.draft.clone())),
+			InputKey::Enter => {
+				self.draft.add_term(&self.input);
+				self.input.clear();
+			}
+			_ if self.levels_focused => match key {
+				InputKey::Left => self.level_cursor = self.level_cursor.saturating_sub(1),
+				InputKey::Right => self.level_cursor = (self.level_cursor + 1).min(LEVELS.len() - 1),
+				InputKey::Char(' ') => self.draft.toggle_level(LEVELS[self.level_cursor]),
+				_ => {}
+			},
+			InputKey::Char(c) => self.input.push(c),
+			// With nothing typed, backspace removes the last term
+			InputKey::Backspace if self.input.is_empty() => {
+				self.draft.terms.pop();
+			}
+			InputKey::Backspace => {
+				self.input.pop();
+			}
+			_ => {}
+		}
+		None
+	}
+}
+
+pub fn draw_filter_editor<B: Backend>(f: &mut Frame<B>, editor: &FilterEditor, monitor: Option<&LogMonitor>) {
+	let size = f.size();
+	let width = size.width.saturating_sub(8).max(size.width.min(40));
+	let height = size.height.saturating_sub(4);
+	let area = Rect::new(size.x + (size.width - width) / 2, size.y + 2, width, height);
+	f.render_widget(Clear, area);
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(4), Constraint::Min(0)].as_ref())
+		.split(area);
+
+	let focus_style = Style::default().bg(Color::LightGreen).add_modifier(Modifier::BOLD);
+	let mut terms = vec![Span::styled("Terms: ", Style::default().fg(Color::Yellow))];
+	for term in editor.draft.terms.iter() {
+		let colour = if term.exclude { Color::Red } else { Color::Green };
+		let sign = if term.exclude { '-' } else { '+' };
+		terms.push(Span::styled(format!("{}{} ", sign, term.text), Style::default().fg(colour)));
+	}
+	let input_style = if editor.levels_focused { Style::default() } else { focus_style };
+	terms.push(Span::styled(format!("{}_", editor.input), input_style));
+
+	let mut levels = vec![Span::styled("Levels: ", Style::default().fg(Color::Yellow))];
+	for (index, level) in LEVELS.iter().enumerate() {
+		let mark = if editor.draft.shows_level(level) { 'x' } else { ' ' };
+		let style = match editor.levels_focused && index == editor.level_cursor {
+			true => focus_style,
+			false => Style::default(),
+		};
+		levels.push(Span::styled(format!("[{}] {}", mark, level), style));
+		levels.push(Span::raw(" "));
+	}
+
+	let title = "Filter (enter adds a term, '-' to exclude, tab for levels, space toggles a level, enter applies, esc cancels)";
+	let editor_widget = Paragraph::new(vec![Spans::from(terms), Spans::from(levels)])
+		.block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(editor_widget, chunks[0]);
+
+	let lines = monitor.map_or(&[][..], |monitor| &monitor.content.items[..]);
+	let shown: Vec<&String> = lines
+		.iter()
+		.zip(editor.draft.shown(lines))
+		.filter_map(|(line, shown)| if shown { Some(line) } else { None })
+		.collect();
+	let rows = (chunks[1].height as usize).saturating_sub(2);
+	let items: Vec<ListItem> = shown[shown.len().saturating_sub(rows)..]
+		.iter()
+		.map(|line| ListItem::new(line.as_str()).style(Style::default().fg(Color::Black).bg(Color::White)))
+		.collect();
+	let preview_title = format!("Preview ({} of {} lines)", shown.len(), lines.len());
+	let preview_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(preview_title));
+	f.render_widget(preview_widget, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn filters_by_terms_and_level() {
+		let lines: Vec<String> = vec![
+			"[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] Syncing chunks",
+			"[sn_node] DEBUG 2021-01-20T18:00:01.000000+00:00 [src/a.rs:2] Syncing gossip",
+			"  continued",
+			"[sn_node] WARN 2021-01-20T18:00:02.000000+00:00 [src/a.rs:3] Peer lost",
+		]
+		.into_iter()
+		.map(String::from)
+		.collect();
+
+		let mut editor = FilterEditor::new(&LineFilter::new());
+		for key in "sync\n-GOSSIP\n".chars() {
+			let key = if key == '\n' { InputKey::Enter } else { InputKey::Char(key) };
+			assert_eq!(editor.handle(key), None);
+		}
+		assert_eq!(editor.draft.shown(&lines), vec![true, false, false, false]);
+		assert_eq!(editor.draft.describe(), "+sync -gossip");
+
+		editor.handle(InputKey::Backspace);
+		editor.handle(InputKey::Tab);
+		editor.handle(InputKey::Right);
+		editor.handle(InputKey::Right);
+		editor.handle(InputKey::Char(' '));
+		let filter = editor.handle(InputKey::Enter).unwrap().unwrap();
+		assert_eq!(filter.shown(&lines), vec![false, true, false, false]);
+		assert_eq!(filter.describe(), "+sync !INFO");
+	}
+}
@@ -0,0 +1,135 @@
+///! De-bounce of node events which flap, such as a status bouncing between OK and WARN
+///!
+///! When the same kind of state event (status, promotion or start) occurs
+///! FLAP_CHANGES times within FLAP_WINDOW, the node is flapping: a single
+///! 'flapping' event is passed on instead, and further events of that kind are
+///! held and counted. Once none has occurred for FLAP_WINDOW the most recent
+///! is passed on, so that hooks learn the state the node settled in.
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::hooks::{NodeEvent, EVENT_FLAPPING, EVENT_PROMOTION, EVENT_START, EVENT_STATUS};
+
+///! Events which report a change of state, and so can flap
+static STATE_EVENTS: [&str; 3] = [EVENT_STATUS, EVENT_PROMOTION, EVENT_START];
+
+///! Changes within FLAP_WINDOW which count as flapping
+const FLAP_CHANGES: usize = 5;
+const FLAP_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct FlapDetector {
+	kinds: HashMap<&'static str, Flap>,
+}
+
+#[derive(Default)]
+struct Flap {
+	///! Times of recent events of this kind, oldest first
+	recent: VecDeque<Instant>,
+	///! Events held while flapping, and the most recent of them
+	held: usize,
+	latest: Option<NodeEvent>,
+	flapping: bool,
+}
+
+impl FlapDetector {
+	pub fn new() -> FlapDetector {
+		FlapDetector::default()
+	}
+
+	///! The event to pass on in place of event, if any
+	pub fn debounce(&mut self, event: NodeEvent, now: Instant) -> Option<NodeEvent> {
+		if !STATE_EVENTS.contains(&event.event) {
+			return Some(event);
+		}
+		let flap = self.kinds.entry(event.event).or_default();
+		flap.recent.push_back(now);
+		while matches!(flap.recent.front(), Some(time) if now.duration_since(*time) > FLAP_WINDOW) {
+			flap.recent.pop_front();
+		}
+
+		if flap.flapping {
+			flap.held += 1;
+			flap.latest = Some(event);
+			return None;
+		}
+		if flap.recent.len() < FLAP_CHANGES {
+			return Some(event);
+		}
+		flap.flapping = true;
+		Some(NodeEvent {
+			event: EVENT_FLAPPING,
+			detail: format!("{} changed {} times in {}s", event.event, flap.recent.len(), FLAP_WINDOW.as_secs()),
+		})
+	}
+
+	///! End flapping which has stopped, returning the latest event held for each
+	pub fn settle(&mut self, now: Instant) -> Vec<NodeEvent> {
+		let mut settled = Vec::<NodeEvent>::new();
+		for flap in self.kinds.values_mut().filter(|flap| flap.flapping) {
+			if matches!(flap.recent.back(), Some(time) if now.duration_since(*time) > FLAP_WINDOW) {
+				flap.flapping = false;
+				flap.held = 0;
+				flap.recent.clear();
+				settled.extend(flap.latest.take());
+			}
+		}
+		settled
+	}
+
+	///! A description of any flapping, for display
+	pub fn status(&self) -> Option<String> {
+		let mut flapping: Vec<String> = self
+			.kinds
+			.iter()
+			.filter(|(_, flap)| flap.flapping)
+			.map(|(kind, flap)| format!("{} x{}", kind, flap.recent.len().max(FLAP_CHANGES + flap.held)))
+			.collect();
+		if flapping.is_empty() {
+			return None;
+		}
+		flapping.sort();
+		Some(format!("FLAPPING: {}", flapping.join(", ")))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::hooks::EVENT_ERROR;
+	use super::*;
+
+	fn status(detail: &str) -> NodeEvent {
+		NodeEvent {
+			event: EVENT_STATUS,
+			detail: detail.to_string(),
+		}
+	}
+
+	#[test]
+	fn reports_flapping_once_then_settles() {
+		let start = Instant::now();
+		let mut detector = FlapDetector::new();
+		let mut passed = Vec::<NodeEvent>::new();
+		for i in 0..9 {
+			let detail = if i % 2 == 0 { "WARN" } else { "OK" };
+			let now = start + Duration::from_secs(i);
+			passed.extend(detector.debounce(status(detail), now));
+		}
+		let events: Vec<&str> = passed.iter().map(|event| event.event).collect();
+		assert_eq!(events, vec![EVENT_STATUS, EVENT_STATUS, EVENT_STATUS, EVENT_STATUS, EVENT_FLAPPING]);
+		assert_eq!(detector.status().as_deref(), Some("FLAPPING: status x9"));
+
+		// Errors aren't states, so pass regardless
+		let error = NodeEvent {
+			event: EVENT_ERROR,
+			detail: String::new(),
+		};
+		assert!(detector.debounce(error, start + Duration::from_secs(9)).is_some());
+
+		assert!(detector.settle(start + Duration::from_secs(60)).is_empty());
+		let settled = detector.settle(start + Duration::from_secs(70));
+		assert_eq!(settled.len(), 1);
+		assert_eq!(settled[0].detail, "WARN");
+		assert_eq!(detector.status(), None);
+	}
+}
@@ -0,0 +1,93 @@
+///! Focus: which widget of a view takes the keys which act on a widget
+///!
+///! Tab moves the focus to the next widget of the view and Shift-Tab to the
+///! previous one, in the order they are laid out: in the node view the
+///! timeline, then the logfile, the parser view when it is open ('e') and the
+///! debug window with --debug-window; in the summary view the summary table.
+///! The focused widget has a bold green border. Up and down act on it: they
+///! scroll the logfile, parser view or debug window, zoom the timeline, and
+///! select the node in the summary table. Left and right still move between
+///! nodes, whatever has the focus.
+use tui::style::{Color, Modifier, Style};
+
+use super::app::DashViewMain;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Focus {
+	Summary,
+	Timeline,
+	#[default]
+	Logfile,
+	ParserView,
+	DebugWindow,
+}
+
+///! The widgets of view which can take the focus, in Tab order
+pub fn order(view: &DashViewMain, parser_view: bool, debug_window: bool) -> Vec<Focus> {
+	match view {
+		DashViewMain::DashSummary => vec![Focus::Summary],
+		DashViewMain::DashNode => {
+			let mut order = vec![Focus::Timeline, Focus::Logfile];
+			if parser_view {
+				order.push(Focus::ParserView);
+			}
+			if debug_window {
+				order.push(Focus::DebugWindow);
+			}
+			order
+		}
+		_ => Vec::new(),
+	}
+}
+
+///! The focus after focus, steps through order, wrapping at either end
+pub fn step(order: &[Focus], focus: Focus, steps: isize) -> Focus {
+	let len = order.len() as isize;
+	match order.iter().position(|f| *f == focus) {
+		Some(index) => order[(index as isize + steps).rem_euclid(len) as usize],
+		None => fit(order, focus),
+	}
+}
+
+///! focus if it is in order, or else the logfile where there is one, as that is what keys acted on before Tab
+pub fn fit(order: &[Focus], focus: Focus) -> Focus {
+	if order.contains(&focus) {
+		focus
+	} else if order.is_empty() || order.contains(&Focus::Logfile) {
+		Focus::Logfile
+	} else {
+		order[0]
+	}
+}
+
+///! The border of a widget, which stands out while it has the focus
+pub fn border_style(focused: bool) -> Style {
+	match focused {
+		true => Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+		false => Style::default(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tab_order_follows_the_layout() {
+		let node = order(&DashViewMain::DashNode, true, true);
+		assert_eq!(node, vec![Focus::Timeline, Focus::Logfile, Focus::ParserView, Focus::DebugWindow]);
+		assert_eq!(step(&node, Focus::Logfile, 1), Focus::ParserView);
+		assert_eq!(step(&node, Focus::DebugWindow, 1), Focus::Timeline);
+		assert_eq!(step(&node, Focus::Timeline, -1), Focus::DebugWindow);
+
+		// Closing the parser view moves its focus to the logfile
+		let node = order(&DashViewMain::DashNode, false, false);
+		assert_eq!(fit(&node, Focus::ParserView), Focus::Logfile);
+		assert_eq!(step(&node, Focus::Logfile, 1), Focus::Timeline);
+
+		let summary = order(&DashViewMain::DashSummary, false, true);
+		assert_eq!(fit(&summary, Focus::Timeline), Focus::Summary);
+		assert_eq!(step(&summary, Focus::Summary, 1), Focus::Summary);
+		assert_eq!(fit(&order(&DashViewMain::DashTop, false, false), Focus::Summary), Focus::Logfile);
+	}
+}
@@ -0,0 +1,149 @@
+///! Go to a time in the focused node's logfile view ('@')
+///!
+///! The prompt takes a time as for --since, either RFC 3339 or a duration
+///! before now such as '15m' (or '-15m'), and selects the line logged nearest
+///! to it. Lines are searched by bisection, so a long --retain-minutes history
+///! is quick to search, with lines without a timestamp going with the line
+///! before them.
+use chrono::{DateTime, Utc};
+use std::str::FromStr;
+use vault_metrics::{AssumedOffset, LogEntry};
+
+use super::keymap::InputKey;
+use super::opt::TimeBound;
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, Paragraph},
+	Frame,
+};
+
+#[derive(Default)]
+pub struct GotoPrompt {
+	///! The time being typed
+	pub input: String,
+	///! Why the time last entered wasn't understood
+	pub error: Option<String>,
+}
+
+impl GotoPrompt {
+	pub fn new() -> GotoPrompt {
+		GotoPrompt::default()
+	}
+
+	///! Handle a key, returning Some(time) to go to or Some(None) to cancel
+	pub fn handle(&mut self, key: InputKey) -> Option<Option<DateTime<Utc>>> {
+		match key {
+			InputKey::Esc => return Some(None),
+			InputKey::Enter => match parse_time(&self.input) {
+				Ok(time) => return Some(Some(time)),
+				Err(e) => self.error = Some(e),
+			},
+			InputKey::Char(c) => self.input.push(c),
+			InputKey::Backspace => {
+				self.input.pop();
+			}
+			_ => {}
+		}
+		None
+	}
+}
+
+///! A time as for --since, where a duration may be written with a leading '-'
+pub fn parse_time(text: &str) -> Result<DateTime<Utc>, String> {
+	let text = text.trim();
+	let text = text.strip_prefix('-').unwrap_or(text);
+	TimeBound::from_str(text).map(|bound| bound.0)
+}
+
+///! Index of the line logged nearest to time, if there are any lines
+pub fn nearest_line(lines: &[String], assumed_offset: &AssumedOffset, time: DateTime<Utc>) -> Option<usize> {
+	if lines.is_empty() {
+		return None;
+	}
+	// The time of a line, and the line it was logged by
+	let line_time = |index: usize| {
+		lines[..=index].iter().enumerate().rev().find_map(|(index, line)| {
+			let time = LogEntry::decode(line, assumed_offset, None).and_then(|entry| entry.time);
+			time.map(|time| (index, time))
+		})
+	};
+
+	// The first line logged at or after time
+	let (mut low, mut high) = (0, lines.len());
+	while low < high {
+		let middle = (low + high) / 2;
+		match line_time(middle) {
+			Some((_, middle_time)) if middle_time >= time => high = middle,
+			_ => low = middle + 1,
+		}
+	}
+
+	if low == lines.len() {
+		return Some(low - 1);
+	}
+	match (low.checked_sub(1).and_then(line_time), line_time(low)) {
+		(Some((before, before_time)), Some((_, after_time))) if time - before_time < after_time - time => Some(before),
+		_ => Some(low),
+	}
+}
+
+pub fn draw_goto_prompt<B: Backend>(f: &mut Frame<B>, prompt: &GotoPrompt) {
+	let size = f.size();
+	let width = size.width.min(70);
+	let area = Rect::new(size.x + (size.width - width) / 2, size.y + 2, width, 4.min(size.height));
+	f.render_widget(Clear, area);
+
+	let input = Spans::from(vec![
+		Span::styled("Time: ", Style::default().fg(Color::Yellow)),
+		Span::styled(format!("{}_", prompt.input), Style::default().bg(Color::LightGreen)),
+	]);
+	let message = match &prompt.error {
+		Some(error) => Span::styled(error.as_str(), Style::default().fg(Color::Red)),
+		None => Span::raw("e.g. '15m' ago or '2021-01-20T18:00:00Z'"),
+	};
+	let title = "Go to time (enter goes to the nearest line, esc cancels)";
+	let prompt_widget = Paragraph::new(vec![input, Spans::from(message)])
+		.block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(prompt_widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn finds_nearest_line() {
+		let lines: Vec<String> = vec![
+			"[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] first",
+			"  continued",
+			"[sn_node] INFO 2021-01-20T18:10:00.000000+00:00 [src/a.rs:1] second",
+			"[sn_node] INFO 2021-01-20T18:20:00.000000+00:00 [src/a.rs:1] third",
+		]
+		.into_iter()
+		.map(String::from)
+		.collect();
+		let offset = AssumedOffset::Fixed(0);
+		let at = |time: &str| nearest_line(&lines, &offset, parse_time(time).unwrap());
+
+		assert_eq!(at("2021-01-20T17:00:00Z"), Some(0));
+		assert_eq!(at("2021-01-20T18:04:00Z"), Some(0));
+		assert_eq!(at("2021-01-20T18:06:00Z"), Some(2));
+		assert_eq!(at("2021-01-20T18:16:00Z"), Some(3));
+		assert_eq!(at("-5m"), Some(3));
+		assert_eq!(nearest_line(&[], &offset, Utc::now()), None);
+
+		let mut prompt = GotoPrompt::new();
+		for c in "5x".chars() {
+			assert_eq!(prompt.handle(InputKey::Char(c)), None);
+		}
+		assert_eq!(prompt.handle(InputKey::Enter), None);
+		assert!(prompt.error.is_some());
+		prompt.handle(InputKey::Backspace);
+		prompt.handle(InputKey::Char('m'));
+		assert!(matches!(prompt.handle(InputKey::Enter), Some(Some(_))));
+	}
+}
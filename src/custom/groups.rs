@@ -0,0 +1,141 @@
+///! Groups of nodes in the summary, which can be collapsed to a line each
+///!
+///! The summary groups nodes by section once any section prefixes are known.
+///! 'H' groups them instead by their first tag (see Tags), then by the host
+///! they log from (syslog sources, see Syslog, with logfiles on this machine
+///! as 'local'), and back to sections. '[' collapses the focused node's group
+///! to its heading, which then gives the group's totals, and expands it again;
+///! ']' collapses every group, or expands them all once all are collapsed, so
+///! a large fleet fits a small terminal.
+use std::collections::BTreeSet;
+
+use super::app::LogMonitor;
+#[cfg(feature = "remote")]
+use super::syslog::SYSLOG_PREFIX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Grouping {
+	#[default]
+	Section,
+	Tag,
+	Host,
+}
+
+impl Grouping {
+	fn next(&self) -> Grouping {
+		match self {
+			Grouping::Section => Grouping::Tag,
+			Grouping::Tag => Grouping::Host,
+			Grouping::Host => Grouping::Section,
+		}
+	}
+
+	pub fn heading(&self) -> &'static str {
+		match self {
+			Grouping::Section => "Section",
+			Grouping::Tag => "Tag",
+			Grouping::Host => "Host",
+		}
+	}
+
+	///! The heading of a group without a name, such as nodes without a tag
+	pub fn unnamed(&self) -> &'static str {
+		match self {
+			Grouping::Section => "unknown",
+			Grouping::Tag => "untagged",
+			Grouping::Host => "local",
+		}
+	}
+}
+
+///! How the summary groups nodes, and which groups are collapsed
+#[derive(Debug, Default)]
+pub struct SummaryGroups {
+	pub grouping: Grouping,
+	///! Groups by name, the group without a name as ""
+	collapsed: BTreeSet<String>,
+}
+
+impl SummaryGroups {
+	///! Group by the next grouping, with every group expanded
+	pub fn cycle(&mut self) {
+		self.grouping = self.grouping.next();
+		self.collapsed.clear();
+	}
+
+	pub fn is_collapsed(&self, group: &Option<String>) -> bool {
+		self.collapsed.contains(group.as_deref().unwrap_or(""))
+	}
+
+	pub fn toggle(&mut self, group: &Option<String>) {
+		let group = group.clone().unwrap_or_default();
+		if !self.collapsed.remove(&group) {
+			self.collapsed.insert(group);
+		}
+	}
+
+	///! Collapse every one of groups, or expand them all if they already are
+	pub fn toggle_all(&mut self, groups: impl IntoIterator<Item = Option<String>>) {
+		let groups: BTreeSet<String> = groups.into_iter().map(Option::unwrap_or_default).collect();
+		match groups.is_subset(&self.collapsed) {
+			true => self.collapsed.clear(),
+			false => self.collapsed = groups,
+		}
+	}
+
+	///! The name of the group of monitor, if it has one
+	pub fn group_of(&self, monitor: &LogMonitor) -> Option<String> {
+		group_name(self.grouping, &monitor.metrics.section_prefix, &monitor.tags, &monitor.logfile)
+	}
+}
+
+///! The group of a node with section_prefix and tags which logs to source
+fn group_name(grouping: Grouping, section_prefix: &str, tags: &[String], source: &str) -> Option<String> {
+	match grouping {
+		Grouping::Section => Some(section_prefix.to_string()).filter(|prefix| !prefix.is_empty()),
+		Grouping::Tag => tags.first().cloned(),
+		Grouping::Host => host(source),
+	}
+}
+
+///! The host of a syslog source, 'syslog://HOST/APP'
+#[cfg(feature = "remote")]
+fn host(source: &str) -> Option<String> {
+	let host = source.strip_prefix(SYSLOG_PREFIX)?.split('/').next()?;
+	Some(host.to_string())
+}
+
+#[cfg(not(feature = "remote"))]
+fn host(_source: &str) -> Option<String> {
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn collapses_and_expands_groups() {
+		let tags = vec![String::from("droplet-3"), String::from("local")];
+		assert_eq!(group_name(Grouping::Section, "10", &tags, "node.log").as_deref(), Some("10"));
+		assert_eq!(group_name(Grouping::Section, "", &tags, "node.log"), None);
+		assert_eq!(group_name(Grouping::Tag, "10", &tags, "node.log").as_deref(), Some("droplet-3"));
+		assert_eq!(group_name(Grouping::Host, "10", &tags, "node.log"), None);
+		#[cfg(feature = "remote")]
+		assert_eq!(group_name(Grouping::Host, "", &[], "syslog://pi4/sn_node").as_deref(), Some("pi4"));
+
+		let mut groups = SummaryGroups::default();
+		let (ten, unknown) = (Some(String::from("10")), None);
+		groups.toggle(&ten);
+		assert!(groups.is_collapsed(&ten) && !groups.is_collapsed(&unknown));
+		groups.toggle_all(vec![ten.clone(), unknown.clone()]);
+		assert!(groups.is_collapsed(&ten) && groups.is_collapsed(&unknown));
+		groups.toggle_all(vec![ten.clone(), unknown.clone()]);
+		assert!(!groups.is_collapsed(&ten) && !groups.is_collapsed(&unknown));
+
+		groups.toggle(&ten);
+		groups.cycle();
+		assert_eq!(groups.grouping, Grouping::Tag);
+		assert!(!groups.is_collapsed(&ten));
+	}
+}
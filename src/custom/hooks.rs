@@ -0,0 +1,107 @@
+///! Hooks: user commands run when node events occur
+///!
+///! Configured in the config file, for example:
+///!
+///!     [[hooks]]
+///!     on_event = "promotion"
+///!     run = "./notify.sh {vault} {detail}"
+///!
+///! The command is split into words before {vault}, {logfile}, {event} and
+///! {detail} are substituted, so values from logfiles can't inject shell
+///! syntax. Commands run in the background and their output is discarded.
+use serde::Deserialize;
+use std::io::{Error, ErrorKind};
+use std::process::{Command, Stdio};
+use std::thread;
+
+pub use vault_metrics::{
+	NodeEvent, EVENTS, EVENT_CONTROL, EVENT_ERROR, EVENT_ESCALATION, EVENT_FLAPPING, EVENT_IDENTITY, EVENT_PROMOTION,
+	EVENT_START, EVENT_STATUS, IDENTITY_NEW,
+};
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+	pub on_event: String,
+	pub run: String,
+}
+
+impl HookConfig {
+	pub fn validate(&self) -> Result<(), Error> {
+		if !EVENTS.contains(&self.on_event.as_str()) {
+			return Err(Error::new(
+				ErrorKind::Other,
+				format!("unknown hook event '{}', expected one of: {}", self.on_event, EVENTS.join(", ")),
+			));
+		}
+		if split_command(&self.run).is_empty() {
+			return Err(Error::new(ErrorKind::Other, "hook has an empty 'run' command"));
+		}
+		Ok(())
+	}
+}
+
+///! Run each hook for event, without waiting for it to complete
+pub fn run_hooks(hooks: &[HookConfig], vault: usize, logfile: &str, event: &NodeEvent) {
+	for hook in hooks.iter().filter(|hook| hook.on_event == event.event) {
+		let vault = vault.to_string();
+		let args: Vec<String> = split_command(&hook.run)
+			.iter()
+			.map(|word| {
+				word
+					.replace("{vault}", &vault)
+					.replace("{logfile}", logfile)
+					.replace("{event}", event.event)
+					.replace("{detail}", &event.detail)
+			})
+			.collect();
+
+		match Command::new(&args[0])
+			.args(&args[1..])
+			.stdin(Stdio::null())
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+		{
+			Ok(mut child) => {
+				// Reap the child so it doesn't linger as a zombie
+				thread::spawn(move || child.wait());
+			}
+			Err(e) => warn!("hook '{}' failed to start: {}", hook.run, e),
+		}
+	}
+}
+
+///! Split a command into words at whitespace, except within quotes
+pub fn split_command(command: &str) -> Vec<String> {
+	let mut words = Vec::<String>::new();
+	let mut word: Option<String> = None;
+	let mut quote: Option<char> = None;
+	for c in command.chars() {
+		match quote {
+			Some(q) if c == q => quote = None,
+			Some(_) => word.get_or_insert_with(String::new).push(c),
+			None if c == '"' || c == '\'' => {
+				quote = Some(c);
+				word.get_or_insert_with(String::new);
+			}
+			None if c.is_whitespace() => words.extend(word.take()),
+			None => word.get_or_insert_with(String::new).push(c),
+		}
+	}
+	words.extend(word);
+	words
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn splits_quoted_words() {
+		assert_eq!(
+			split_command("./notify.sh  '{vault} is' \"{detail}\" ''"),
+			vec!["./notify.sh", "{vault} is", "{detail}", ""]
+		);
+	}
+}
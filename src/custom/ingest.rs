@@ -0,0 +1,445 @@
+///! Ingest of lines from each source on a task or thread of its own
+///!
+///! Every source of lines (each logfile, the --listen-syslog listener, or the
+///! daemon a dashboard is attached to) sends them to one channel as
+///! (source, line), which the main loop drains. So a source which is slow to
+///! read, such as a logfile on a network filesystem, holds up only its own
+///! lines rather than those of every source. Each logfile is followed by a
+///! task with a MuxedLines of its own, which wakes a thread of the logfile's
+///! own to read whatever has been added, so that each line is sent with the
+///! bytes it took in the logfile, line ending and all, and ingest can keep its
+///! place exactly. The reads are on threads because the binaries run tokio's
+///! basic scheduler, where a blocking read on a task would hold up every other
+///! source and the UI with it.
+///!
+///! MuxedLines relies on the platform's file change notifications, which some
+///! filesystems (such as NFS) don't deliver for changes made by other hosts.
+///! So a logfile can instead be polled (--poll-interval), by a thread which
+///! reads whatever has been added, and is polled anyway if it can't be
+///! watched, such as when the limit on inotify watches has been reached.
+///! WatchCheck notices a watch which is missing changes, so the node view can
+///! suggest polling. Following a logfile returns a WatchHandle, which stops the
+///! task or thread when the monitor moves on to another logfile or reloads.
+///!
+///! The channel counts the lines sent and received, giving the backlog of
+///! lines waiting for the main loop, see SelfMetrics.
+use futures::future::{abortable, AbortHandle};
+use futures::stream::StreamExt;
+use linemux::MuxedLines;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{
+	atomic::{AtomicBool, AtomicUsize, Ordering},
+	mpsc::TrySendError,
+	Arc,
+};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+///! A line from a source as (source, line, length), where source is the logfile
+///! for a file and length the bytes the line took in it, including its line
+///! ending (None for a line which wasn't read from a file)
+pub type Line = Result<(String, String, Option<u64>), Error>;
+
+///! Lines from all sources
+pub struct Lines {
+	rx: mpsc::UnboundedReceiver<Line>,
+	sent: Arc<AtomicUsize>,
+	received: usize,
+}
+
+impl Lines {
+	pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Line>> {
+		let polled = self.rx.poll_recv(cx);
+		if let Poll::Ready(Some(_)) = polled {
+			self.received += 1;
+		}
+		polled
+	}
+
+	pub async fn recv(&mut self) -> Option<Line> {
+		futures::future::poll_fn(|cx| self.poll_recv(cx)).await
+	}
+
+	///! Lines received since the channel was made
+	pub fn received(&self) -> usize {
+		self.received
+	}
+
+	///! Lines sent and not yet received
+	pub fn backlog(&self) -> usize {
+		self.sent.load(Ordering::Relaxed).saturating_sub(self.received)
+	}
+}
+
+#[derive(Clone)]
+pub struct LineSender {
+	tx: mpsc::UnboundedSender<Line>,
+	sent: Arc<AtomicUsize>,
+}
+
+impl LineSender {
+	pub fn send(&self, line: Line) -> Result<(), mpsc::error::SendError<Line>> {
+		// Counted first, so the backlog is never short of lines received
+		self.sent.fetch_add(1, Ordering::Relaxed);
+		self.tx.send(line).map_err(|e| {
+			self.sent.fetch_sub(1, Ordering::Relaxed);
+			e
+		})
+	}
+}
+
+pub fn channel() -> (LineSender, Lines) {
+	let (tx, rx) = mpsc::unbounded_channel();
+	let sent = Arc::new(AtomicUsize::new(0));
+	let tx = LineSender { tx, sent: sent.clone() };
+	(tx, Lines { rx, sent, received: 0 })
+}
+
+///! Interval for polling a logfile which can't be watched
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+///! Checks in a row in which a logfile grew without lines arriving, before its watch is said to be missing changes
+const MISSED_CHECKS: usize = 2;
+
+#[cfg(target_os = "linux")]
+static NATIVE_WATCH: &str = "inotify";
+#[cfg(target_os = "macos")]
+static NATIVE_WATCH: &str = "FSEvents";
+#[cfg(windows)]
+static NATIVE_WATCH: &str = "ReadDirChanges";
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+static NATIVE_WATCH: &str = "native";
+
+///! How a LogMonitor's lines reach it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Watch {
+	///! The platform's file change notifications, through MuxedLines
+	Native,
+	///! Polling, where fallback is set if the logfile couldn't be watched natively
+	Polled { interval: Duration, fallback: bool },
+	///! Sent by a remote node with --listen-syslog
+	#[cfg(feature = "remote")]
+	Syslog,
+	///! Sent by the daemon this dashboard is attached to
+	Daemon,
+}
+
+impl Watch {
+	pub fn describe(&self) -> String {
+		match self {
+			Watch::Native => NATIVE_WATCH.to_string(),
+			Watch::Polled { interval, .. } => format!("poll {}s", interval.as_secs_f64()),
+			#[cfg(feature = "remote")]
+			Watch::Syslog => String::from("syslog"),
+			Watch::Daemon => String::from("daemon"),
+		}
+	}
+}
+
+///! Stops the task or thread following a logfile, so that it sends no more lines
+pub enum WatchHandle {
+	Task(AbortHandle),
+	Thread(Arc<AtomicBool>),
+}
+
+impl WatchHandle {
+	pub fn cancel(&self) {
+		match self {
+			WatchHandle::Task(abort) => abort.abort(),
+			WatchHandle::Thread(stop) => stop.store(true, Ordering::Relaxed),
+		}
+	}
+}
+
+///! Follow logfile, natively unless polling every poll_interval, returning how it is watched
+///!
+///! position is how much of the logfile has been read already, from where polling starts.
+pub async fn watch_logfile(
+	logfile: &str,
+	position: u64,
+	poll_interval: Option<Duration>,
+	tx: LineSender,
+) -> Result<(Watch, WatchHandle), Error> {
+	if let Some(interval) = poll_interval {
+		let handle = spawn_polled_logfile(logfile, position, interval, tx);
+		return Ok((Watch::Polled { interval, fallback: false }, handle));
+	}
+	match spawn_logfile(logfile, position, tx.clone()).await {
+		Ok(handle) => Ok((Watch::Native, handle)),
+		// Such as a missing directory, which polling won't fix
+		Err(e) if e.kind() == ErrorKind::NotFound => Err(e),
+		Err(e) => {
+			warn!("failed to watch {}, polling it instead: {}", logfile, e);
+			let handle = spawn_polled_logfile(logfile, position, FALLBACK_POLL_INTERVAL, tx);
+			let watch = Watch::Polled {
+				interval: FALLBACK_POLL_INTERVAL,
+				fallback: true,
+			};
+			Ok((watch, handle))
+		}
+	}
+}
+
+///! Follow logfile on a task and thread of its own, sending each line added after position
+///!
+///! As for MuxedLines, the logfile need not exist yet but its directory must.
+///! The lines MuxedLines reads only wake the thread, which reads them again
+///! itself as for polling, since MuxedLines drops their line endings and
+///! fails on those which aren't UTF-8. Lines are sent with logfile as given
+///! for their source, rather than the path MuxedLines reports, which may be
+///! canonicalised.
+pub async fn spawn_logfile(logfile: &str, position: u64, tx: LineSender) -> Result<WatchHandle, Error> {
+	let mut lines = MuxedLines::new()?;
+	lines.add_file(logfile).await?;
+	let logfile = logfile.to_string();
+
+	// Wake ups waiting for the thread, of which one is enough as each read finds all the lines added
+	let (wake, woken) = std::sync::mpsc::sync_channel::<()>(1);
+	let reader_tx = tx.clone();
+	thread::spawn(move || {
+		let mut position = position;
+		let mut partial = Vec::<u8>::new();
+		// Until the task ends, such as on being cancelled
+		while woken.recv().is_ok() {
+			if let Ok(added) = read_added_lines(&logfile, &mut position, &mut partial) {
+				for (line, length) in added {
+					if reader_tx.send(Ok((logfile.clone(), line, Some(length)))).is_err() {
+						return;
+					}
+				}
+			}
+		}
+	});
+
+	let (task, abort) = abortable(async move {
+		while let Some(line) = lines.next().await {
+			match line {
+				// Read by the thread, so only the wake up matters
+				Err(e) if e.kind() == ErrorKind::InvalidData => {}
+				Err(e) => {
+					let _ = tx.send(Err(e));
+					return;
+				}
+				Ok(_) => {}
+			}
+			if let Err(TrySendError::Disconnected(_)) = wake.try_send(()) {
+				return;
+			}
+		}
+	});
+	tokio::spawn(task);
+	Ok(WatchHandle::Task(abort))
+}
+
+///! A logfile's path made absolute, with symlinks and '..' resolved where it
+///! exists (and is lower case on Windows), so that different names for the
+///! same logfile compare equal
+pub fn normalise_path(logfile: &str) -> PathBuf {
+	let path = Path::new(logfile);
+	let absolute = |path: &Path| std::env::current_dir().map_or_else(|_| path.to_path_buf(), |dir| dir.join(path));
+	let normalised = std::fs::canonicalize(path).unwrap_or_else(|_| match (path.parent(), path.file_name()) {
+		// A logfile yet to be created, in a directory which exists
+		(Some(parent), Some(name)) => {
+			let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+			std::fs::canonicalize(parent).map_or_else(|_| absolute(path), |parent| parent.join(name))
+		}
+		_ => absolute(path),
+	});
+	#[cfg(windows)]
+	let normalised = PathBuf::from(normalised.to_string_lossy().to_lowercase());
+	normalised
+}
+
+///! Follow logfile on a thread of its own, reading from position every interval
+pub fn spawn_polled_logfile(logfile: &str, position: u64, interval: Duration, tx: LineSender) -> WatchHandle {
+	let logfile = logfile.to_string();
+	let stop = Arc::new(AtomicBool::new(false));
+	let stopped = stop.clone();
+	thread::spawn(move || {
+		let mut position = position;
+		let mut partial = Vec::<u8>::new();
+		while !stopped.load(Ordering::Relaxed) {
+			// A missing logfile is fine, as it may not have been created yet
+			if let Ok(lines) = read_added_lines(&logfile, &mut position, &mut partial) {
+				for (line, length) in lines {
+					if stopped.load(Ordering::Relaxed) || tx.send(Ok((logfile.clone(), line, Some(length)))).is_err() {
+						return;
+					}
+				}
+			}
+			thread::sleep(interval);
+		}
+	});
+	WatchHandle::Thread(stop)
+}
+
+///! Lines added to logfile since position with the bytes each took, keeping
+///! any unfinished line in partial
+///!
+///! Each line has its line ending, \n or \r\n, removed and any bytes which
+///! aren't UTF-8 replaced, while its length counts the bytes as they were.
+///!
+///! A logfile which has shrunk has been replaced, and is reloaded on its
+///! replacement being noticed (see file_check), so it is read from its end.
+fn read_added_lines(logfile: &str, position: &mut u64, partial: &mut Vec<u8>) -> Result<Vec<(String, u64)>, Error> {
+	let mut file = File::open(logfile)?;
+	let length = file.metadata()?.len();
+	if length < *position {
+		*position = length;
+		partial.clear();
+	}
+	file.seek(SeekFrom::Start(*position))?;
+	*position += file.take(length - *position).read_to_end(partial)? as u64;
+
+	let mut lines = Vec::new();
+	while let Some(end) = partial.iter().position(|byte| *byte == b'\n') {
+		let line: Vec<u8> = partial.drain(..=end).collect();
+		let text = String::from_utf8_lossy(&line).trim_end_matches(&['\n', '\r'][..]).to_string();
+		lines.push((text, line.len() as u64));
+	}
+	Ok(lines)
+}
+
+///! Whether a native watch is keeping up with its logfile, checked as file_check reports its length
+#[derive(Default)]
+pub struct WatchCheck {
+	length: Option<u64>,
+	lines: usize,
+	missed: usize,
+}
+
+impl WatchCheck {
+	pub fn new() -> WatchCheck {
+		WatchCheck::default()
+	}
+
+	///! Count a line arriving
+	pub fn line(&mut self) {
+		self.lines += 1;
+	}
+
+	///! Check the logfile's length, which will have grown with any lines arriving
+	pub fn check(&mut self, length: u64) {
+		match self.length {
+			Some(previous) if length > previous && self.lines == 0 => self.missed += 1,
+			Some(_) if self.lines > 0 => self.missed = 0,
+			_ => {}
+		}
+		self.length = Some(length);
+		self.lines = 0;
+	}
+
+	pub fn is_missing_changes(&self) -> bool {
+		self.missed >= MISSED_CHECKS
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	#[tokio::test]
+	async fn each_logfile_sends_to_one_channel() {
+		let dir = tempfile::tempdir().unwrap();
+		let paths: Vec<String> = ["a.log", "b.log"]
+			.iter()
+			.map(|name| dir.path().join(name).to_string_lossy().to_string())
+			.collect();
+		let (tx, mut rx) = channel();
+		let mut handles = Vec::new();
+		for path in paths.iter() {
+			std::fs::write(path, "").unwrap();
+			handles.push(spawn_logfile(path, 0, tx.clone()).await.unwrap());
+		}
+
+		let mut file = std::fs::OpenOptions::new().append(true).open(&paths[1]).unwrap();
+		writeln!(file, "from b").unwrap();
+		let (source, line, length) = rx.recv().await.unwrap().unwrap();
+		assert_eq!((source.as_str(), line.as_str(), length), (paths[1].as_str(), "from b", Some(7)));
+		assert_eq!((rx.received(), rx.backlog()), (1, 0));
+
+		// A cancelled watch sends nothing more
+		handles[0].cancel();
+		let mut file = std::fs::OpenOptions::new().append(true).open(&paths[0]).unwrap();
+		writeln!(file, "from a").unwrap();
+		let mut file = std::fs::OpenOptions::new().append(true).open(&paths[1]).unwrap();
+		writeln!(file, "from b again").unwrap();
+		let (source, _, _) = rx.recv().await.unwrap().unwrap();
+		assert_eq!(source, paths[1]);
+	}
+
+	#[test]
+	fn stops_polling_when_cancelled() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("node.log");
+		let logfile = path.to_str().unwrap();
+		std::fs::write(logfile, "").unwrap();
+		let (tx, rx) = channel();
+		let handle = spawn_polled_logfile(logfile, 0, Duration::from_millis(10), tx);
+		handle.cancel();
+		thread::sleep(Duration::from_millis(30));
+		std::fs::write(logfile, "after\n").unwrap();
+		thread::sleep(Duration::from_millis(50));
+		assert_eq!(rx.backlog(), 0);
+	}
+
+	#[test]
+	fn normalises_names_of_one_logfile() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::create_dir(dir.path().join("logs")).unwrap();
+		let path = dir.path().join("logs").join("node.log");
+		std::fs::write(&path, "").unwrap();
+		let normalised = normalise_path(path.to_str().unwrap());
+		let dotted = dir.path().join("logs").join(".").join("..").join("logs").join("node.log");
+		assert_eq!(normalise_path(dotted.to_str().unwrap()), normalised);
+		#[cfg(unix)]
+		{
+			let link = dir.path().join("latest.log");
+			std::os::unix::fs::symlink(&path, &link).unwrap();
+			assert_eq!(normalise_path(link.to_str().unwrap()), normalised);
+		}
+
+		// Not yet created, so only its directory is resolved
+		let missing = dir.path().join("logs").join("..").join("logs").join("new.log");
+		assert_eq!(normalise_path(missing.to_str().unwrap()), normalised.with_file_name("new.log"));
+		assert_ne!(normalise_path("other.log"), normalised);
+	}
+
+	#[test]
+	fn polls_added_lines() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("node.log");
+		let logfile = path.to_str().unwrap();
+		std::fs::write(logfile, "loaded\n").unwrap();
+		let mut position = 7;
+		let mut partial = Vec::new();
+
+		let mut file = std::fs::OpenOptions::new().append(true).open(logfile).unwrap();
+		write!(file, "first\r\nsecond\nthi").unwrap();
+		let lines = read_added_lines(logfile, &mut position, &mut partial).unwrap();
+		assert_eq!(lines, vec![("first".to_string(), 7), ("second".to_string(), 7)]);
+		writeln!(file, "rd").unwrap();
+		let lines = read_added_lines(logfile, &mut position, &mut partial).unwrap();
+		assert_eq!(lines, vec![("third".to_string(), 6)]);
+
+		// A byte which isn't UTF-8 is replaced, but counted as the one byte it was
+		file.write_all(b"f\xffourth\r\n").unwrap();
+		let lines = read_added_lines(logfile, &mut position, &mut partial).unwrap();
+		assert_eq!(lines, vec![("f\u{fffd}ourth".to_string(), 10)]);
+		assert_eq!(position, std::fs::metadata(logfile).unwrap().len());
+
+		let mut check = WatchCheck::new();
+		for length in [10, 20, 30].iter() {
+			check.check(*length);
+		}
+		assert!(check.is_missing_changes());
+		check.line();
+		check.check(40);
+		assert!(!check.is_missing_changes());
+	}
+}
@@ -0,0 +1,246 @@
+///! Journal of ingested state for quick recovery (--journal FILE)
+///!
+///! The journal begins with a checkpoint of each LogMonitor: its parsed
+///! NodeMetrics, content and how far through its logfile it has read. Each
+///! line ingested after that is appended as a record, so when vdash restarts
+///! it restores the checkpoints, replays the few lines since and reads the
+///! logfiles only from where it left off, rather than parsing them again from
+///! the start. The first and last lines read are marked (see ContentMarks),
+///! so that a logfile which no longer holds them is loaded again from its
+///! start rather than resumed part way through other content. The journal is
+///! rewritten with fresh checkpoints at startup and every
+///! JOURNAL_CHECKPOINT_LINES lines, which keeps it small.
+///!
+///! Records are bincode, written one at a time without syncing, so a crash
+///! can lose the final records or leave one incomplete. Reading stops at the
+///! first record which fails to decode.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+use super::app::{LogMonitor, NodeMetrics};
+use super::file_check::ContentMarks;
+
+///! Identifies a journal, and the version of its records
+static JOURNAL_VERSION: &str = "vdash-journal-12";
+
+///! Lines journaled before the journal is rewritten with fresh checkpoints
+const JOURNAL_CHECKPOINT_LINES: usize = 10000;
+
+// Generic so that checkpoints can be written from borrowed metrics
+#[derive(Serialize, Deserialize)]
+enum Record<Metrics = NodeMetrics> {
+	///! First record, identifying the file as a journal of this version
+	Version(String),
+	///! The state of a LogMonitor after reading its logfile up to position
+	Checkpoint {
+		logfile: String,
+		position: u64,
+		marks: ContentMarks,
+		content: Vec<String>,
+		metrics: Metrics,
+	},
+	///! A line ingested since the checkpoint, the length bytes ending at position in the logfile
+	Line {
+		logfile: String,
+		position: u64,
+		length: u64,
+		line: String,
+	},
+}
+
+///! State of a LogMonitor recovered from a journal
+pub struct Recovered {
+	///! Bytes of the logfile ingested, from where reading resumes
+	pub position: u64,
+	///! The first and last lines read, including those to replay
+	pub marks: ContentMarks,
+	pub content: Vec<String>,
+	pub metrics: NodeMetrics,
+	///! Lines to replay, ingested after the checkpoint
+	pub lines: Vec<String>,
+}
+
+pub struct Journal {
+	path: PathBuf,
+	file: File,
+	lines_since_checkpoint: usize,
+}
+
+fn to_io_error(e: bincode::Error) -> Error {
+	Error::new(ErrorKind::Other, format!("vdash journal: {}", e))
+}
+
+impl Journal {
+	///! Start a journal at path with a checkpoint of each monitor, replacing any existing journal
+	pub fn create(path: &str, monitors: &HashMap<String, LogMonitor>) -> Result<Journal, Error> {
+		let path = PathBuf::from(path);
+		let file = write_checkpoints(&path, monitors)?;
+		Ok(Journal {
+			path,
+			file,
+			lines_since_checkpoint: 0,
+		})
+	}
+
+	///! Record a line of logfile, length bytes ending at position, before it is ingested
+	///!
+	///! If the journal has grown large it is first rewritten with checkpoints
+	///! of monitors, which must not yet include the line.
+	pub fn append_line(
+		&mut self,
+		logfile: &str,
+		position: u64,
+		length: u64,
+		line: &str,
+		monitors: &HashMap<String, LogMonitor>,
+	) -> Result<(), Error> {
+		if self.lines_since_checkpoint >= JOURNAL_CHECKPOINT_LINES {
+			self.checkpoint(monitors)?;
+		}
+
+		let record: Record = Record::Line {
+			logfile: logfile.to_string(),
+			position,
+			length,
+			line: line.to_string(),
+		};
+		// Written whole so that a crash can't interleave part of a record
+		self.file.write_all(&bincode::serialize(&record).map_err(to_io_error)?)?;
+		self.lines_since_checkpoint += 1;
+		Ok(())
+	}
+
+	///! Replace the journal with a checkpoint of each monitor
+	pub fn checkpoint(&mut self, monitors: &HashMap<String, LogMonitor>) -> Result<(), Error> {
+		self.file = write_checkpoints(&self.path, monitors)?;
+		self.lines_since_checkpoint = 0;
+		Ok(())
+	}
+
+	///! Finish with a checkpoint of each monitor, synced to disk, so a restart has no lines to replay
+	pub fn close(mut self, monitors: &HashMap<String, LogMonitor>) -> Result<(), Error> {
+		self.checkpoint(monitors)?;
+		self.file.sync_all()
+	}
+}
+
+///! Write the journal to a temporary file which then replaces path, returning it open for appending
+fn write_checkpoints(path: &Path, monitors: &HashMap<String, LogMonitor>) -> Result<File, Error> {
+	let dir = match path.parent() {
+		Some(dir) if !dir.as_os_str().is_empty() => dir,
+		_ => Path::new("."),
+	};
+	let mut file = NamedTempFile::new_in(dir)?;
+	let version: Record = Record::Version(JOURNAL_VERSION.to_string());
+	bincode::serialize_into(&mut file, &version).map_err(to_io_error)?;
+	for monitor in monitors.values() {
+		let record = Record::Checkpoint {
+			logfile: monitor.logfile.clone(),
+			position: monitor.logfile_position,
+			marks: monitor.content_marks.clone(),
+			content: monitor.content.items.clone(),
+			metrics: &monitor.metrics,
+		};
+		bincode::serialize_into(&mut file, &record).map_err(to_io_error)?;
+	}
+	file.flush()?;
+	file.persist(path).map_err(|e| e.error)?;
+	OpenOptions::new().append(true).open(path)
+}
+
+///! Read the state of each logfile from the journal at path, if there is one
+pub fn recover(path: &str) -> Result<HashMap<String, Recovered>, Error> {
+	let mut recovered = HashMap::<String, Recovered>::new();
+	let file = match File::open(path) {
+		Ok(file) => file,
+		Err(e) if e.kind() == ErrorKind::NotFound => return Ok(recovered),
+		Err(e) => return Err(Error::new(e.kind(), format!("journal {}: {}", path, e))),
+	};
+	let mut reader = BufReader::new(file);
+
+	match bincode::deserialize_from::<_, Record>(&mut reader) {
+		Ok(Record::Version(version)) if version == JOURNAL_VERSION => {}
+		_ => {
+			warn!("Ignoring journal {} which is empty or of an unknown version", path);
+			return Ok(recovered);
+		}
+	}
+
+	while let Ok(record) = bincode::deserialize_from::<_, Record>(&mut reader) {
+		match record {
+			Record::Version(_) => break,
+			Record::Checkpoint {
+				logfile,
+				position,
+				marks,
+				content,
+				metrics,
+			} => {
+				recovered.insert(
+					logfile,
+					Recovered {
+						position,
+						marks,
+						content,
+						metrics,
+						lines: Vec::new(),
+					},
+				);
+			}
+			Record::Line {
+				logfile,
+				position,
+				length,
+				line,
+			} => {
+				if let Some(state) = recovered.get_mut(&logfile) {
+					state.marks.record(position.saturating_sub(length), &line);
+					state.position = position;
+					state.lines.push(line);
+				}
+			}
+		}
+	}
+	Ok(recovered)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::{Opt, StructOpt};
+
+	#[test]
+	fn recovers_checkpoint_and_lines() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("journal");
+		let path = path.to_str().unwrap();
+		let opt = Opt::from_iter(&["vdash", "node.log"]);
+
+		let mut monitor = LogMonitor::new(&opt, "node.log".to_string(), opt.lines_max);
+		monitor.content.items.push("first".to_string());
+		monitor.logfile_position = 6;
+		monitor.content_marks.record(0, "first");
+		let mut monitors = HashMap::new();
+		monitors.insert(monitor.logfile.clone(), monitor);
+
+		let mut journal = Journal::create(path, &monitors).unwrap();
+		// Its length includes a \r, which the line as ingested doesn't
+		journal.append_line("node.log", 14, 8, "second", &monitors).unwrap();
+		// An incomplete record, as left by a crash, is ignored
+		journal.file.write_all(&[1, 0]).unwrap();
+
+		let recovered = recover(path).unwrap();
+		let state = &recovered["node.log"];
+		assert_eq!(state.content, vec!["first"]);
+		assert_eq!(state.lines, vec!["second"]);
+		assert_eq!(state.position, 14);
+		let mut marks = ContentMarks::default();
+		marks.record(0, "first");
+		marks.record(6, "second");
+		assert_eq!(state.marks, marks);
+	}
+}
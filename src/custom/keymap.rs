@@ -0,0 +1,473 @@
+///! Keymap: translates key presses into dashboard actions
+///!
+///! A small state machine supports vim-style numeric prefixes, so '10j'
+///! scrolls ten lines and '3<Tab>' moves focus three panels, as well as
+///! chords such as 'gg' (top) and 'G' (bottom). ':' opens the command palette.
+///!
+///! Keys mean different things in different contexts: dashboard keys in the
+///! normal context, and any key goes to a panel such as time travel or a popup
+///! such as a prompt while one is open. Contexts are entered on top of one
+///! another and left as they close, so a popup opened over a panel returns
+///! keys to the panel when it closes.
+use super::palette::Palette;
+
+///! Key presses, independent of the terminal backend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputKey {
+	Char(char),
+	Up,
+	Down,
+	Left,
+	Right,
+	Tab,
+	BackTab,
+	Esc,
+	Enter,
+	Backspace,
+	Other,
+}
+
+///! Mouse presses and drags, independent of the terminal backend, by column and row from zero
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseInput {
+	Press(u16, u16),
+	Drag(u16, u16),
+	Release,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+	Quit,
+	ViewSummary,
+	ViewNode,
+	ViewDebug,
+	ViewTop,
+	ViewRegex,
+	ViewTable,
+	///! A key typed into the regex playground
+	RegexEdit(InputKey),
+	ViewSearch,
+	///! A key typed into the search panel
+	SearchEdit(InputKey),
+	EditFilter,
+	///! A key typed into the filter editor
+	FilterEdit(InputKey),
+	GotoTime,
+	///! A key typed into the go to time prompt
+	GotoEdit(InputKey),
+	ShowLine,
+	///! A key pressed while the line detail popup is open
+	LineDetailKey(InputKey),
+	TimeTravel,
+	///! A key pressed while time travelling
+	TimeTravelKey(InputKey),
+	NodeControl,
+	///! A key pressed while the node control popup is open
+	NodeControlKey(InputKey),
+	TopSortNext,
+	TopSortPrevious,
+	ScaleTimelineUp,
+	ScaleTimelineDown,
+	ScrollUp,
+	ScrollDown,
+	ScrollTop,
+	ScrollBottom,
+	FocusNext,
+	FocusPrevious,
+	FocusWidgetNext,
+	FocusWidgetPrevious,
+	CycleTagFilter,
+	CycleGrouping,
+	ToggleGroup,
+	ToggleAllGroups,
+	TogglePause,
+	ToggleMetricsOnly,
+	TogglePin,
+	ClearPins,
+	ToggleNodeConfig,
+	CopyRewardKey,
+	ToggleAligned,
+	ToggleParserView,
+	OpenSelfLog,
+	ToggleLowPower,
+	ToggleDebugCapture,
+	CloseGone,
+	ViewAlerts,
+	///! A key pressed while the alert list is open
+	AlertsKey(InputKey),
+	SplitNext,
+	SplitGrow,
+	SplitShrink,
+	SplitReset,
+}
+
+///! Actions offered by the command palette, in order
+pub static ACTIONS: [Action; 46] = [
+	Action::ViewSummary,
+	Action::ViewNode,
+	Action::ViewTop,
+	Action::ViewRegex,
+	Action::ViewSearch,
+	Action::ViewTable,
+	Action::ViewDebug,
+	Action::EditFilter,
+	Action::GotoTime,
+	Action::ShowLine,
+	Action::TimeTravel,
+	Action::NodeControl,
+	Action::TopSortNext,
+	Action::TopSortPrevious,
+	Action::FocusNext,
+	Action::FocusPrevious,
+	Action::FocusWidgetNext,
+	Action::FocusWidgetPrevious,
+	Action::CycleTagFilter,
+	Action::CycleGrouping,
+	Action::ToggleGroup,
+	Action::ToggleAllGroups,
+	Action::ScaleTimelineUp,
+	Action::ScaleTimelineDown,
+	Action::ScrollUp,
+	Action::ScrollDown,
+	Action::ScrollTop,
+	Action::ScrollBottom,
+	Action::TogglePause,
+	Action::ToggleMetricsOnly,
+	Action::TogglePin,
+	Action::ClearPins,
+	Action::ToggleNodeConfig,
+	Action::CopyRewardKey,
+	Action::ToggleAligned,
+	Action::ToggleParserView,
+	Action::SplitNext,
+	Action::SplitGrow,
+	Action::SplitShrink,
+	Action::SplitReset,
+	Action::OpenSelfLog,
+	Action::ToggleLowPower,
+	Action::ToggleDebugCapture,
+	Action::CloseGone,
+	Action::ViewAlerts,
+	Action::Quit,
+];
+
+impl Action {
+	pub fn description(&self) -> &'static str {
+		match self {
+			Action::Quit => "Quit",
+			Action::ViewSummary => "Summary view",
+			Action::ViewNode => "Node view",
+			Action::ViewDebug => "Debug view",
+			Action::ViewTop => "Top view (nodes ordered by a live metric)",
+			Action::ViewRegex => "Regex playground (test a pattern on the focused node's lines)",
+			Action::ViewTable => "Table view (the focused node's recent lines in columns)",
+			Action::RegexEdit(_) => "Edit regex",
+			Action::ViewSearch => "Search every node's recent lines, finding XorNames however they are abbreviated",
+			Action::SearchEdit(_) => "Edit search",
+			Action::EditFilter => "Filter this node's logfile (include/exclude terms and levels)",
+			Action::FilterEdit(_) => "Edit filter",
+			Action::GotoTime => "Go to the line logged nearest a time in this node's logfile",
+			Action::GotoEdit(_) => "Edit time",
+			Action::ShowLine => "Show the selected line in full, with structs and JSON laid out",
+			Action::LineDetailKey(_) => "Scroll or close the line",
+			Action::TimeTravel => "Time travel: show this node's metrics as they were at an earlier time",
+			Action::TimeTravelKey(_) => "Move through time",
+			Action::NodeControl => "Control this node: restart, stop or update it (see [[actions]] in the config file)",
+			Action::NodeControlKey(_) => "Choose and confirm a node control action",
+			Action::TopSortNext => "Top view: sort by next column",
+			Action::TopSortPrevious => "Top view: sort by previous column",
+			Action::ScaleTimelineUp => "Timeline zoom in (shorter columns)",
+			Action::ScaleTimelineDown => "Timeline zoom out (longer columns)",
+			Action::ScrollUp => "Up in the focused widget (scroll, zoom the timeline in, or select the previous node)",
+			Action::ScrollDown => "Down in the focused widget (scroll, zoom the timeline out, or select the next node)",
+			Action::ScrollTop => "Scroll logfile to top",
+			Action::ScrollBottom => "Scroll logfile to bottom",
+			Action::FocusNext => "Next node",
+			Action::FocusPrevious => "Previous node",
+			Action::FocusWidgetNext => "Focus the next widget of the view (logfile, timeline, debug window...)",
+			Action::FocusWidgetPrevious => "Focus the previous widget of the view",
+			Action::CycleTagFilter => "Show only nodes with the next tag (see --tag), or all after the last",
+			Action::CycleGrouping => "Summary: group nodes by section, tag or host",
+			Action::ToggleGroup => "Summary: collapse or expand the focused node's group",
+			Action::ToggleAllGroups => "Summary: collapse every group to its totals, or expand them all",
+			Action::TogglePause => "Pause/resume ingest for this node",
+			Action::ToggleMetricsOnly => "Metrics only: update this node's metrics without showing its lines",
+			Action::TogglePin => "Pin/unpin the selected line above this node's logfile",
+			Action::ClearPins => "Unpin all of this node's lines",
+			Action::ToggleNodeConfig => "Show/hide the node's config above its logfile",
+			Action::CopyRewardKey => "Copy the node's reward key (wallet ID) to the clipboard",
+			Action::ToggleAligned => "Show lines in aligned columns, or as logged",
+			Action::ToggleParserView => "Show each line beside what the parser makes of it",
+			Action::OpenSelfLog => "Open vdash's own log (--self-log) as a node, to debug vdash",
+			Action::ToggleDebugCapture => "Pause/resume adding messages to the debug window (--debug-window)",
+			Action::ToggleLowPower => "Low power: show only the summary, redrawn every few seconds, while lines are still read",
+			Action::CloseGone => "Close this node, whose logfile has been deleted and not recreated",
+			Action::ViewAlerts => "Review alerts (errors, status changes and flapping) and acknowledge them",
+			Action::AlertsKey(_) => "Choose and acknowledge alerts",
+			Action::SplitNext => "Node view: select the next split to resize",
+			Action::SplitGrow => "Node view: move the selected split down or right",
+			Action::SplitShrink => "Node view: move the selected split up or left",
+			Action::SplitReset => "Node view: return every split to its default size",
+		}
+	}
+}
+
+// Stops a mistyped count tying up the UI
+const MAX_COUNT: usize = 9999;
+
+///! Where keys go, each context giving them its own meaning
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputContext {
+	///! Dashboard keys, with counts and chords
+	Normal,
+	///! A view which takes every key, until it is left
+	Panel(Panel),
+	///! A popup over the dashboard which takes every key, until it closes
+	Popup(Popup),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Panel {
+	///! Keys are typed into the regex playground, escape returns to the node view
+	Regex,
+	///! Keys are typed into the search, escape returns to the node view
+	Search,
+	TimeTravel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Popup {
+	Palette,
+	Filter,
+	Goto,
+	LineDetail,
+	NodeControl,
+	Alerts,
+}
+
+#[derive(Default)]
+pub struct KeyMap {
+	count: Option<usize>,
+	pending_g: bool,
+	palette: Option<Palette>,
+	///! Contexts entered on top of InputContext::Normal, the last having the keys
+	contexts: Vec<InputContext>,
+}
+
+impl KeyMap {
+	pub fn new() -> KeyMap {
+		KeyMap {
+			count: None,
+			pending_g: false,
+			palette: None,
+			contexts: Vec::new(),
+		}
+	}
+
+	///! The context keys go to
+	pub fn context(&self) -> InputContext {
+		self.contexts.last().copied().unwrap_or(InputContext::Normal)
+	}
+
+	///! Pass keys to context until it is left, abandoning any count or chord
+	pub fn enter(&mut self, context: InputContext) {
+		self.count = None;
+		self.pending_g = false;
+		if context != InputContext::Normal && self.context() != context {
+			self.contexts.push(context);
+		}
+	}
+
+	///! Leave context, and any entered on top of it
+	pub fn leave(&mut self, context: InputContext) {
+		if let Some(index) = self.contexts.iter().rposition(|entered| *entered == context) {
+			self.contexts.truncate(index);
+		}
+	}
+
+	///! Numeric prefix typed so far, for display
+	pub fn pending_count(&self) -> Option<usize> {
+		self.count
+	}
+
+	///! The command palette, when open
+	pub fn palette(&self) -> Option<&Palette> {
+		self.palette.as_ref()
+	}
+
+	///! Handle a key, returning an action and how many times to repeat it
+	pub fn handle(&mut self, key: InputKey) -> Option<(Action, usize)> {
+		match self.context() {
+			InputContext::Normal => self.handle_normal(key),
+			InputContext::Panel(Panel::Regex) => {
+				if key == InputKey::Esc {
+					self.leave(InputContext::Panel(Panel::Regex));
+					return Some((Action::ViewNode, 1));
+				}
+				Some((Action::RegexEdit(key), 1))
+			}
+			InputContext::Panel(Panel::Search) => {
+				if key == InputKey::Esc {
+					self.leave(InputContext::Panel(Panel::Search));
+					return Some((Action::ViewNode, 1));
+				}
+				Some((Action::SearchEdit(key), 1))
+			}
+			InputContext::Panel(Panel::TimeTravel) => Some((Action::TimeTravelKey(key), 1)),
+			InputContext::Popup(Popup::Palette) => {
+				let chosen = match &mut self.palette {
+					Some(palette) => palette.handle(key)?,
+					None => None,
+				};
+				self.palette = None;
+				self.leave(InputContext::Popup(Popup::Palette));
+				chosen.map(|action| (action, 1))
+			}
+			InputContext::Popup(Popup::Filter) => Some((Action::FilterEdit(key), 1)),
+			InputContext::Popup(Popup::Goto) => Some((Action::GotoEdit(key), 1)),
+			InputContext::Popup(Popup::LineDetail) => Some((Action::LineDetailKey(key), 1)),
+			InputContext::Popup(Popup::NodeControl) => Some((Action::NodeControlKey(key), 1)),
+			InputContext::Popup(Popup::Alerts) => Some((Action::AlertsKey(key), 1)),
+		}
+	}
+
+	fn handle_normal(&mut self, key: InputKey) -> Option<(Action, usize)> {
+		if key == InputKey::Char(':') {
+			self.palette = Some(Palette::new());
+			self.enter(InputContext::Popup(Popup::Palette));
+			return None;
+		}
+
+		if let InputKey::Char(c) = key {
+			if let Some(digit) = c.to_digit(10) {
+				// As in vim, '0' only continues a count
+				if digit != 0 || self.count.is_some() {
+					let count = self.count.unwrap_or(0) * 10 + digit as usize;
+					self.count = Some(count.min(MAX_COUNT));
+					self.pending_g = false;
+					return None;
+				}
+			}
+		}
+
+		if self.pending_g {
+			self.pending_g = false;
+			if key == InputKey::Char('g') {
+				self.count = None;
+				return Some((Action::ScrollTop, 1));
+			}
+		} else if key == InputKey::Char('g') {
+			self.pending_g = true;
+			return None;
+		}
+
+		let count = self.count.take().unwrap_or(1);
+		let action = match key {
+			InputKey::Char('q') | InputKey::Char('Q') => Action::Quit,
+			InputKey::Char('s') | InputKey::Char('S') => Action::ViewSummary,
+			InputKey::Char('v') | InputKey::Char('V') => Action::ViewNode,
+			InputKey::Char('D') => Action::ViewDebug,
+			InputKey::Char('t') | InputKey::Char('T') => Action::ViewTop,
+			InputKey::Char('>') => Action::TopSortNext,
+			InputKey::Char('<') => Action::TopSortPrevious,
+			InputKey::Char('/') => Action::ViewRegex,
+			InputKey::Char('?') => Action::ViewSearch,
+			InputKey::Char('#') => Action::CycleTagFilter,
+			InputKey::Char('H') => Action::CycleGrouping,
+			InputKey::Char('[') => Action::ToggleGroup,
+			InputKey::Char(']') => Action::ToggleAllGroups,
+			InputKey::Char('c') | InputKey::Char('C') => Action::ViewTable,
+			InputKey::Char('f') | InputKey::Char('F') => Action::EditFilter,
+			InputKey::Char('@') => Action::GotoTime,
+			InputKey::Enter => Action::ShowLine,
+			InputKey::Char('r') | InputKey::Char('R') => Action::TimeTravel,
+			InputKey::Char('x') | InputKey::Char('X') => Action::NodeControl,
+			InputKey::Char('p') | InputKey::Char('P') => Action::TogglePause,
+			InputKey::Char('m') | InputKey::Char('M') => Action::ToggleMetricsOnly,
+			InputKey::Char('b') => Action::TogglePin,
+			InputKey::Char('B') => Action::ClearPins,
+			InputKey::Char('n') | InputKey::Char('N') => Action::ToggleNodeConfig,
+			InputKey::Char('y') => Action::CopyRewardKey,
+			InputKey::Char('a') | InputKey::Char('A') => Action::ToggleAligned,
+			InputKey::Char('e') | InputKey::Char('E') => Action::ToggleParserView,
+			InputKey::Char('L') => Action::OpenSelfLog,
+			InputKey::Char('z') | InputKey::Char('Z') => Action::ToggleLowPower,
+			InputKey::Char('d') => Action::ToggleDebugCapture,
+			InputKey::Char('K') => Action::CloseGone,
+			InputKey::Char('!') => Action::ViewAlerts,
+			InputKey::Char('|') => Action::SplitNext,
+			InputKey::Char('=') => Action::SplitReset,
+
+			InputKey::Char('+') | InputKey::Char('i') | InputKey::Char('I') => Action::ScaleTimelineUp,
+			InputKey::Char('-') | InputKey::Char('o') | InputKey::Char('O') => Action::ScaleTimelineDown,
+
+			InputKey::Down | InputKey::Char('j') => Action::ScrollDown,
+			InputKey::Up | InputKey::Char('k') => Action::ScrollUp,
+			InputKey::Char('G') => Action::ScrollBottom,
+			InputKey::Right => Action::FocusNext,
+			InputKey::Left => Action::FocusPrevious,
+			InputKey::Tab => Action::FocusWidgetNext,
+			InputKey::BackTab => Action::FocusWidgetPrevious,
+			_ => return None,
+		};
+		Some((action, count))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn keys(keymap: &mut KeyMap, text: &str) -> Option<(Action, usize)> {
+		let mut result = None;
+		for c in text.chars() {
+			result = keymap.handle(InputKey::Char(c));
+		}
+		result
+	}
+
+	#[test]
+	fn counts_prefix_actions() {
+		let mut keymap = KeyMap::new();
+		assert_eq!(keys(&mut keymap, "10j"), Some((Action::ScrollDown, 10)));
+		assert_eq!(keys(&mut keymap, "k"), Some((Action::ScrollUp, 1)));
+		assert_eq!(keys(&mut keymap, "3"), None);
+		assert_eq!(keymap.handle(InputKey::Tab), Some((Action::FocusWidgetNext, 3)));
+		assert_eq!(keymap.handle(InputKey::BackTab), Some((Action::FocusWidgetPrevious, 1)));
+	}
+
+	#[test]
+	fn contexts_take_keys_until_left() {
+		let mut keymap = KeyMap::new();
+		assert_eq!(keys(&mut keymap, "2"), None);
+		keymap.enter(InputContext::Panel(Panel::TimeTravel));
+		assert_eq!(keys(&mut keymap, "j"), Some((Action::TimeTravelKey(InputKey::Char('j')), 1)));
+		keymap.enter(InputContext::Popup(Popup::NodeControl));
+		assert_eq!(keys(&mut keymap, "q"), Some((Action::NodeControlKey(InputKey::Char('q')), 1)));
+		keymap.leave(InputContext::Popup(Popup::NodeControl));
+		assert_eq!(keymap.context(), InputContext::Panel(Panel::TimeTravel));
+		keymap.leave(InputContext::Panel(Panel::TimeTravel));
+		// The count was abandoned on entering the panel
+		assert_eq!(keys(&mut keymap, "j"), Some((Action::ScrollDown, 1)));
+
+		keymap.enter(InputContext::Panel(Panel::Regex));
+		assert_eq!(keys(&mut keymap, ":"), Some((Action::RegexEdit(InputKey::Char(':')), 1)));
+		assert_eq!(keymap.handle(InputKey::Esc), Some((Action::ViewNode, 1)));
+		assert_eq!(keys(&mut keymap, ":"), None);
+		assert_eq!(keymap.context(), InputContext::Popup(Popup::Palette));
+		assert_eq!(keymap.handle(InputKey::Esc), None);
+		assert_eq!(keymap.context(), InputContext::Normal);
+	}
+
+	#[test]
+	fn chords_and_cancellation() {
+		let mut keymap = KeyMap::new();
+		assert_eq!(keys(&mut keymap, "gg"), Some((Action::ScrollTop, 1)));
+		assert_eq!(keys(&mut keymap, "G"), Some((Action::ScrollBottom, 1)));
+		assert_eq!(keys(&mut keymap, "gj"), Some((Action::ScrollDown, 1)));
+		assert_eq!(keys(&mut keymap, "5"), None);
+		assert_eq!(keymap.handle(InputKey::Esc), None);
+		assert_eq!(keys(&mut keymap, "j"), Some((Action::ScrollDown, 1)));
+		assert_eq!(keys(&mut keymap, "0"), None);
+	}
+}
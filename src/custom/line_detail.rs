@@ -0,0 +1,451 @@
+///! Line detail popup (enter on a line of the node view)
+///!
+///! Shows the selected line in full, its fields first and then its message,
+///! wrapped to the popup. Payloads embedded in the message, such as structs
+///! logged with Debug formatting or JSON, are pretty-printed with each field
+///! or item on a line of its own, indented by depth and coloured by kind. A
+///! payload is a balanced {..} or [..] holding fields or items (a ',' or a
+///! ':'), so that an address such as [::1]:12000 is left as it is.
+///!
+///! Above the line is where it is in its logfile, and below it the line
+///! exactly as logged, so that the same place can be found with grep or an
+///! editor when digging deeper. Below the line's source is the URL of its code
+///! (see source_link), for terminals which let you open URLs.
+use super::keymap::InputKey;
+use super::source_link;
+use vault_metrics::LOG_LINE_PATTERN;
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, Paragraph, Wrap},
+	Frame,
+};
+
+const INDENT: &str = "    ";
+
+///! Kinds of text in a pretty-printed message, each coloured differently
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token {
+	Text,
+	Punctuation,
+	///! A field name, or a JSON key
+	Key,
+	///! The name of a struct or enum variant
+	Name,
+	Str,
+	Number,
+	///! Such as true, None or Some
+	Literal,
+}
+
+pub type PrettyLine = Vec<(Token, String)>;
+
+///! Where a line is in its logfile, as far as is known, so that it can be
+///! found with other tools
+///!
+///! Offsets count a line ending of one byte, so are approximate for lines
+///! which arrive while vdash runs if lines end with CRLF. Neither is known for
+///! lines recovered from a --journal or received over syslog.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Provenance {
+	///! Bytes before the line
+	pub offset: Option<u64>,
+	///! Counting from 1
+	pub line: Option<u64>,
+}
+
+impl Provenance {
+	///! Where a file's lines start when read from offset, whose line number is only known at the start
+	pub fn at(offset: u64) -> Provenance {
+		Provenance {
+			offset: Some(offset),
+			line: if offset == 0 { Some(1) } else { None },
+		}
+	}
+
+	///! Where the line after this one starts, if this one is length bytes including its line ending
+	pub fn next(&self, length: u64) -> Provenance {
+		Provenance {
+			offset: self.offset.map(|offset| offset + length),
+			line: self.line.map(|line| line + 1),
+		}
+	}
+
+	pub fn describe(&self) -> String {
+		match (self.line, self.offset) {
+			(Some(line), Some(offset)) => format!("line {}, byte {}", line, offset),
+			(None, Some(offset)) => format!("byte {}", offset),
+			(Some(line), None) => format!("line {}", line),
+			(None, None) => "unknown".to_string(),
+		}
+	}
+}
+
+pub struct LineDetail {
+	pub line: String,
+	///! The logfile (or syslog source) the line is from
+	pub logfile: String,
+	pub provenance: Provenance,
+	///! Where the line's source is on GitHub, see link_source()
+	pub source_url: Option<String>,
+	///! Lines scrolled past at the top of the popup
+	pub scroll: u16,
+}
+
+impl LineDetail {
+	pub fn new(line: &str, logfile: &str, provenance: Provenance) -> LineDetail {
+		LineDetail {
+			line: line.to_string(),
+			logfile: logfile.to_string(),
+			provenance,
+			source_url: None,
+			scroll: 0,
+		}
+	}
+
+	///! Link the line's source to its code in version of sn_node, see source_link
+	pub fn link_source(&mut self, version: Option<&str>) {
+		self.source_url = LOG_LINE_PATTERN
+			.captures(&self.line)
+			.and_then(|captures| captures.name("source"))
+			.and_then(|source| source_link::source_url(source.as_str(), version));
+	}
+
+	///! Handle a key, returning true when the popup closes
+	pub fn handle(&mut self, key: InputKey) -> bool {
+		match key {
+			InputKey::Up | InputKey::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+			InputKey::Down | InputKey::Char('j') => self.scroll = self.scroll.saturating_add(1),
+			InputKey::Esc | InputKey::Enter | InputKey::Char('q') => return true,
+			_ => {}
+		}
+		false
+	}
+}
+
+///! The message split into lines, with its payloads pretty-printed
+pub fn pretty_print(message: &str) -> Vec<PrettyLine> {
+	let mut printer = Printer {
+		lines: vec![Vec::new()],
+		depth: 0,
+	};
+	let mut rest = message;
+	while let Some((start, end)) = find_payload(rest) {
+		printer.push(Token::Text, &rest[..start]);
+		printer.payload(&rest[start..end]);
+		rest = &rest[end..];
+	}
+	printer.push(Token::Text, rest);
+	printer.lines
+}
+
+///! Start and end of the first payload in text
+fn find_payload(text: &str) -> Option<(usize, usize)> {
+	for (start, _) in text.char_indices().filter(|(_, c)| *c == '{' || *c == '[') {
+		if let Some(length) = bracketed_length(&text[start..]) {
+			let payload = &text[start..start + length];
+			if payload.contains(',') || payload.contains(": ") || payload.contains("\":") {
+				return Some((start, start + length));
+			}
+		}
+	}
+	None
+}
+
+///! Length of the bracketed text which text starts with, if it is closed
+fn bracketed_length(text: &str) -> Option<usize> {
+	let mut depth = 0;
+	let mut quoted = false;
+	let mut escaped = false;
+	for (index, c) in text.char_indices() {
+		match c {
+			_ if escaped => escaped = false,
+			'\\' if quoted => escaped = true,
+			'"' => quoted = !quoted,
+			'{' | '[' if !quoted => depth += 1,
+			'}' | ']' if !quoted => {
+				depth -= 1;
+				if depth == 0 {
+					return Some(index + 1);
+				}
+			}
+			_ => {}
+		}
+	}
+	None
+}
+
+///! Length of the quoted string which text starts with, or all of text if it isn't closed
+fn quoted_length(text: &str) -> usize {
+	let mut escaped = false;
+	for (index, c) in text.char_indices().skip(1) {
+		match c {
+			_ if escaped => escaped = false,
+			'\\' => escaped = true,
+			'"' => return index + 1,
+			_ => {}
+		}
+	}
+	text.len()
+}
+
+fn is_word_char(c: char) -> bool {
+	c.is_alphanumeric() || c == '_' || c == '.' || c == '-'
+}
+
+struct Printer {
+	lines: Vec<PrettyLine>,
+	depth: usize,
+}
+
+impl Printer {
+	fn push(&mut self, token: Token, text: &str) {
+		if text.is_empty() {
+			return;
+		}
+		let line = self.lines.last_mut().expect("a printer always has a line");
+		match line.last_mut() {
+			Some((last, last_text)) if *last == token => last_text.push_str(text),
+			_ => line.push((token, text.to_string())),
+		}
+	}
+
+	fn at_line_start(&self) -> bool {
+		self.lines.last().into_iter().flatten().all(|(_, text)| text.trim().is_empty())
+	}
+
+	///! Start a line indented to depth, replacing the current line if nothing has been written on it
+	fn newline(&mut self) {
+		if self.at_line_start() {
+			self.lines.pop();
+		} else if let Some(line) = self.lines.last_mut() {
+			if let Some((_, text)) = line.last_mut() {
+				let trimmed = text.trim_end().len();
+				text.truncate(trimmed);
+			}
+		}
+		self.lines.push(vec![(Token::Text, INDENT.repeat(self.depth))]);
+	}
+
+	fn payload(&mut self, payload: &str) {
+		let next_char = |from: usize| payload[from..].trim_start().chars().next();
+		let mut index = 0;
+		while let Some(c) = payload[index..].chars().next() {
+			let rest = &payload[index..];
+			let mut length = c.len_utf8();
+			match c {
+				'"' => {
+					length = quoted_length(rest);
+					let token = if next_char(index + length) == Some(':') { Token::Key } else { Token::Str };
+					self.push(token, &rest[..length]);
+				}
+				'{' | '[' => {
+					let close = if c == '{' { '}' } else { ']' };
+					if next_char(index + 1) == Some(close) {
+						let after = &payload[index + 1..];
+						length = 1 + after.len() - after.trim_start().len() + 1;
+						self.push(Token::Punctuation, &format!("{}{}", c, close));
+					} else {
+						self.push(Token::Punctuation, &rest[..length]);
+						self.depth += 1;
+						self.newline();
+					}
+				}
+				'}' | ']' => {
+					self.depth = self.depth.saturating_sub(1);
+					self.newline();
+					self.push(Token::Punctuation, &rest[..length]);
+				}
+				',' => {
+					self.push(Token::Punctuation, ",");
+					self.newline();
+				}
+				_ if c.is_whitespace() => {
+					length = rest.len() - rest.trim_start().len();
+					if !self.at_line_start() {
+						self.push(Token::Text, " ");
+					}
+				}
+				_ if is_word_char(c) => {
+					// Paths such as sn_node::Config are one word
+					length = 0;
+					while let Some(c) = rest[length..].chars().next() {
+						if rest[length..].starts_with("::") {
+							length += 2;
+						} else if is_word_char(c) {
+							length += c.len_utf8();
+						} else {
+							break;
+						}
+					}
+					let word = &rest[..length];
+					let next = next_char(index + length);
+					let token = match word {
+						"true" | "false" | "null" | "None" | "Some" | "Ok" | "Err" => Token::Literal,
+						_ if next == Some(':') => Token::Key,
+						_ if word.starts_with(|c: char| c.is_ascii_digit()) => Token::Number,
+						_ if word.starts_with('-') && word[1..].starts_with(|c: char| c.is_ascii_digit()) => Token::Number,
+						_ if word.starts_with(char::is_uppercase) && matches!(next, Some('{') | Some('(') | Some('[')) => {
+							Token::Name
+						}
+						_ => Token::Text,
+					};
+					self.push(token, word);
+				}
+				':' | '(' | ')' => self.push(Token::Punctuation, &rest[..length]),
+				_ => self.push(Token::Text, &rest[..length]),
+			}
+			index += length;
+		}
+	}
+}
+
+fn token_style(token: Token) -> Style {
+	match token {
+		Token::Text => Style::default(),
+		Token::Punctuation => Style::default().fg(Color::Gray),
+		Token::Key => Style::default().fg(Color::Yellow),
+		Token::Name => Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
+		Token::Str => Style::default().fg(Color::Green),
+		Token::Number => Style::default().fg(Color::Magenta),
+		Token::Literal => Style::default().fg(Color::Cyan),
+	}
+}
+
+pub fn draw_line_detail<B: Backend>(f: &mut Frame<B>, detail: &LineDetail) {
+	let size = f.size();
+	let width = size.width.saturating_sub(8).max(size.width.min(40));
+	let height = size.height.saturating_sub(4);
+	let area = Rect::new(size.x + (size.width - width) / 2, size.y + 2, width, height);
+	f.render_widget(Clear, area);
+
+	let label_style = Style::default().fg(Color::Yellow);
+	let mut text = vec![
+		Spans::from(vec![Span::styled(format!("{:<8}", "File"), label_style), Span::raw(detail.logfile.as_str())]),
+		Spans::from(vec![Span::styled(format!("{:<8}", "At"), label_style), Span::raw(detail.provenance.describe())]),
+	];
+	let message = match LOG_LINE_PATTERN.captures(&detail.line) {
+		Some(captures) => {
+			for (label, field) in [("Module", "module"), ("Level", "category"), ("Time", "time_string"), ("Source", "source")].iter() {
+				let value = captures.name(field).map_or("", |value| value.as_str());
+				text.push(Spans::from(vec![
+					Span::styled(format!("{:<8}", label), label_style),
+					Span::raw(value.to_string()),
+				]));
+			}
+			// Plain text, as an OSC 8 hyperlink's escape sequences would upset tui's idea of the width of the row
+			if let Some(url) = &detail.source_url {
+				text.push(Spans::from(vec![Span::styled(format!("{:<8}", "Code"), label_style), Span::raw(url.as_str())]));
+			}
+			text.push(Spans::from(""));
+			captures.name("message").map_or("", |message| message.as_str())
+		}
+		None => detail.line.as_str(),
+	};
+	for line in pretty_print(message) {
+		let spans: Vec<Span> = line.into_iter().map(|(token, text)| Span::styled(text, token_style(token))).collect();
+		text.push(Spans::from(spans));
+	}
+	// Exactly as logged, to search for with grep or an editor
+	text.push(Spans::from(""));
+	text.push(Spans::from(Span::styled("Raw", label_style)));
+	text.push(Spans::from(detail.line.as_str()));
+
+	let title = "Line (up/down or j/k scroll, esc closes)";
+	let detail_widget = Paragraph::new(text)
+		.block(Block::default().borders(Borders::ALL).title(title))
+		.wrap(Wrap { trim: false })
+		.scroll((detail.scroll, 0));
+	f.render_widget(detail_widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pretty_prints_payloads() {
+		let message = r#"Node config: Config { wallet_id: Some("ab, 12"), ports: [12000, 12001], empty: {} } then {"a":1,"b":[true]}"#;
+		let lines = pretty_print(message);
+		let text: Vec<String> = lines
+			.iter()
+			.map(|line| line.iter().map(|(_, text)| text.as_str()).collect())
+			.collect();
+		assert_eq!(
+			text,
+			vec![
+				"Node config: Config {",
+				"    wallet_id: Some(\"ab, 12\"),",
+				"    ports: [",
+				"        12000,",
+				"        12001",
+				"    ],",
+				"    empty: {}",
+				"} then {",
+				"    \"a\":1,",
+				"    \"b\":[",
+				"        true",
+				"    ]",
+				"}",
+			]
+		);
+		assert_eq!(
+			lines[1][1..],
+			[
+				(Token::Key, "wallet_id".to_string()),
+				(Token::Punctuation, ":".to_string()),
+				(Token::Text, " ".to_string()),
+				(Token::Literal, "Some".to_string()),
+				(Token::Punctuation, "(".to_string()),
+				(Token::Str, "\"ab, 12\"".to_string()),
+				(Token::Punctuation, "),".to_string()),
+			]
+		);
+
+		let plain = "Connected to [::1]:12000 as [2001:db8::1]";
+		assert_eq!(pretty_print(plain), vec![vec![(Token::Text, plain.to_string())]]);
+	}
+
+	#[test]
+	fn records_where_lines_are() {
+		use crate::custom::app::LogMonitor;
+		use crate::custom::opt::{Opt, StructOpt};
+		use std::sync::atomic::AtomicU64;
+
+		let dir = tempfile::tempdir().unwrap();
+		let logfile = dir.path().join("node.log");
+		std::fs::write(&logfile, "first\r\nsecond\nthird\n").unwrap();
+		let opt = Opt::from_iter(&["vdash", "node.log"]);
+		let mut monitor = LogMonitor::new(&opt, logfile.to_str().unwrap().to_string(), 100);
+		monitor.load(&AtomicU64::new(0)).unwrap();
+		monitor.append_to_content("fourth").unwrap();
+
+		let described: Vec<String> = monitor.provenance.iter().map(Provenance::describe).collect();
+		assert_eq!(described, ["line 1, byte 0", "line 2, byte 7", "line 3, byte 14", "line 4, byte 20"]);
+		assert_eq!(Provenance::at(20).describe(), "byte 20");
+		assert_eq!(Provenance::default().next(10).describe(), "unknown");
+	}
+
+	#[test]
+	fn shows_the_url_of_the_source() {
+		use tui::{backend::TestBackend, Terminal};
+
+		let line = "[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5";
+		let mut detail = LineDetail::new(line, "node.log", Provenance::default());
+		detail.link_source(Some("0.24.0"));
+		let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+		terminal.draw(|f| draw_line_detail(f, &detail)).unwrap();
+
+		// As the terminal was sent them, so cells after the URL are drawn too
+		let buffer = terminal.backend().buffer();
+		let rows: Vec<String> = (0..20).map(|y| (0..100).map(|x| buffer.get(x, y).symbol.as_str()).collect()).collect();
+		let row = |label: &str| rows.iter().find(|row| row.contains(label)).unwrap().trim_end().to_string();
+		// The popup is 92 columns wide, leaving 90 inside its borders
+		let inside = |text: &str| format!("{:<90}│", text);
+		assert!(row("Source").ends_with(&inside("Source  [src/node/mod.rs:97]")));
+		assert!(row("Code").ends_with(&inside("Code    https://github.com/maidsafe/sn_node/blob/v0.24.0/src/node/mod.rs#L97")));
+	}
+}
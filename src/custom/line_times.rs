@@ -0,0 +1,111 @@
+///! The time of each line of a logfile view, found once as the line is added
+///!
+///! A line without a timestamp, such as the continuation of a multi-line
+///! entry, has the time of the entry it continues: the nearest line before it
+///! with one. Drawing the logfile and parser views, and expiring lines older
+///! than --retain-minutes, look times up here rather than decoding lines again
+///! each time.
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+use vault_metrics::{AssumedOffset, LogEntry};
+
+///! The times of a buffer of lines, kept up to date as lines are added at the
+///! end and expire from the start, as for StateDiff
+#[derive(Default)]
+pub struct LineTimes {
+	///! For each line, the time of its entry if known
+	pub times: VecDeque<Option<DateTime<Utc>>>,
+}
+
+impl LineTimes {
+	///! Find the times of lines afresh, such as when the buffer has been replaced
+	pub fn rebuild(&mut self, lines: &[String], assumed_offset: &AssumedOffset) {
+		self.times.clear();
+		for line in lines {
+			self.push(line, assumed_offset);
+		}
+	}
+
+	///! Rebuild unless there is a time for each of lines, a safety net for lines replaced without their times
+	pub fn fit(&mut self, lines: &[String], assumed_offset: &AssumedOffset) {
+		if self.times.len() != lines.len() {
+			self.rebuild(lines, assumed_offset);
+		}
+	}
+
+	pub fn push(&mut self, line: &str, assumed_offset: &AssumedOffset) {
+		let previous = self.times.back().copied().flatten();
+		let time = LogEntry::decode(line, assumed_offset, previous).and_then(|entry| entry.time);
+		self.times.push_back(time.or(previous));
+	}
+
+	///! Forget the first count lines, as they have expired from the buffer
+	pub fn drain_front(&mut self, count: usize) {
+		self.times.drain(..count.min(self.times.len()));
+	}
+
+	///! The time of the line at index
+	pub fn get(&self, index: usize) -> Option<DateTime<Utc>> {
+		self.times.get(index).copied().flatten()
+	}
+
+	///! The time of the line before index, which decoding the line at index may need
+	pub fn before(&self, index: usize) -> Option<DateTime<Utc>> {
+		index.checked_sub(1).and_then(|index| self.get(index))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::app::LogMonitor;
+	use crate::custom::opt::{Opt, StructOpt};
+
+	#[test]
+	fn continuations_have_their_entrys_time() {
+		let lines: Vec<String> = [
+			"a line before any timestamp",
+			"[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Failed to connect:",
+			"    connection refused",
+			"[sn_node] INFO 2020-12-18T14:33:50.799447454+00:00 [src/node/mod.rs:50] Our Age: 5",
+		]
+		.iter()
+		.map(|line| line.to_string())
+		.collect();
+
+		let mut times = LineTimes::default();
+		times.fit(&lines, &AssumedOffset::Fixed(0));
+		let seconds: Vec<Option<i64>> = times.times.iter().map(|time| time.map(|time| time.timestamp() % 60)).collect();
+		assert_eq!(seconds, [None, Some(49), Some(49), Some(50)]);
+		assert_eq!(times.before(3), times.get(1));
+		assert_eq!(times.before(0), None);
+
+		times.drain_front(2);
+		assert_eq!(times.times.len(), 2);
+		times.fit(&lines, &AssumedOffset::Fixed(0));
+		assert_eq!(times.times.len(), 4);
+	}
+
+	#[test]
+	fn retains_entries_with_their_continuations() {
+		let opt = Opt::from_iter(&["vdash", "--retain-minutes", "5", "node.log"]);
+		let mut monitor = LogMonitor::new(&opt, "node.log".to_string(), opt.lines_max);
+		let lines = [
+			"[sn_node] WARN 2021-01-20T18:00:00.000000+00:00 [src/node/mod.rs:97] Failed to connect:",
+			"    connection refused",
+			"[sn_node] WARN 2021-01-20T18:03:00.000000+00:00 [src/node/mod.rs:97] Failed to connect:",
+			"    connection reset",
+		];
+		for line in lines.iter() {
+			monitor.append_to_content(line).unwrap();
+		}
+		assert_eq!(monitor.content.items.len(), 4);
+
+		// Five minutes after the second entry, the first and its continuation are dropped
+		monitor.append_to_content("[sn_node] INFO 2021-01-20T18:08:00.000000+00:00 [src/node/mod.rs:50] Our Age: 5").unwrap();
+		assert_eq!(monitor.content.items[..2], lines[2..]);
+		assert_eq!(monitor.content.items.len(), 3);
+		assert_eq!(monitor.line_times.times.len(), 3);
+	}
+}
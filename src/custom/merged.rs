@@ -0,0 +1,105 @@
+///! Merged logfile: every node's new lines in one rolling file (--write-merged FILE)
+///!
+///! Some use vdash mainly to merge many logfiles into one. Each line is
+///! written prefixed with its node's label (or number), in the order the lines
+///! were logged rather than the order they arrived. So lines are held for
+///! MERGE_DELAY_SECS, letting those from a node slow to deliver them be put in
+///! order, though one logged ahead of the local clock is held no longer than
+///! MERGE_HOLD_MAX_SECS. A line without a time of its own, such as part of a
+///! multi-line entry, takes the time of the entry before it. Lines loaded at
+///! startup aren't written, so restarting vdash doesn't repeat them. The file
+///! is rotated as for --self-log when it reaches MERGED_BYTES_MAX.
+use chrono::{DateTime, Duration, Utc};
+use std::io::Error;
+use std::path::Path;
+
+use super::self_log::RotatingFile;
+
+///! Seconds a line is held for lines logged before it to arrive
+pub const MERGE_DELAY_SECS: i64 = 5;
+///! Seconds a line logged ahead of the local clock is held at most
+pub const MERGE_HOLD_MAX_SECS: i64 = 30;
+///! Size at which the merged logfile is rotated
+pub const MERGED_BYTES_MAX: u64 = 100 * 1024 * 1024;
+
+struct PendingLine {
+	time: DateTime<Utc>,
+	///! Order of arrival, keeping lines logged at the same time in order
+	seq: u64,
+	arrived: DateTime<Utc>,
+	text: String,
+}
+
+pub struct MergedLog {
+	file: RotatingFile,
+	pending: Vec<PendingLine>,
+	next_seq: u64,
+}
+
+impl MergedLog {
+	pub fn open(path: &str) -> Result<MergedLog, Error> {
+		Ok(MergedLog {
+			file: RotatingFile::open(Path::new(path), MERGED_BYTES_MAX)?,
+			pending: Vec::new(),
+			next_seq: 0,
+		})
+	}
+
+	///! Hold a line from the node with label, logged at time if known, which arrived now
+	pub fn push(&mut self, label: &str, time: Option<DateTime<Utc>>, line: &str, now: DateTime<Utc>) {
+		self.pending.push(PendingLine {
+			time: time.unwrap_or(now),
+			seq: self.next_seq,
+			arrived: now,
+			text: format!("[{}] {}", label, line),
+		});
+		self.next_seq += 1;
+	}
+
+	///! Write the lines which have been held long enough, or all of them, in the order logged
+	pub fn flush(&mut self, now: DateTime<Utc>, all: bool) -> Result<(), Error> {
+		let logged_by = now - Duration::seconds(MERGE_DELAY_SECS);
+		let arrived_by = now - Duration::seconds(MERGE_HOLD_MAX_SECS);
+		let (mut due, held): (Vec<PendingLine>, Vec<PendingLine>) = self
+			.pending
+			.drain(..)
+			.partition(|line| all || line.time <= logged_by || line.arrived <= arrived_by);
+		self.pending = held;
+		due.sort_by(|a, b| a.time.cmp(&b.time).then(a.seq.cmp(&b.seq)));
+		for line in due.iter() {
+			self.file.write_line(&line.text)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn writes_lines_in_the_order_logged() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("merged.log");
+		let mut merged = MergedLog::open(path.to_str().unwrap()).unwrap();
+		let now = Utc::now();
+		let ago = |secs: i64| Some(now - Duration::seconds(secs));
+
+		merged.push("genesis", ago(5), "first", now);
+		merged.push("genesis", ago(5), "  continued", now);
+		merged.push("node 2", ago(7), "earlier", now);
+		merged.push("node 2", ago(1), "recent", now);
+		merged.push("node 3", Some(now + Duration::minutes(5)), "ahead", now);
+		merged.flush(now, false).unwrap();
+		let written = std::fs::read_to_string(&path).unwrap();
+		assert_eq!(written, "[node 2] earlier\n[genesis] first\n[genesis]   continued\n");
+
+		// Held lines follow once due, and the clock ahead no longer than the hold
+		merged.flush(now + Duration::seconds(MERGE_HOLD_MAX_SECS), false).unwrap();
+		let written = std::fs::read_to_string(&path).unwrap();
+		assert!(written.ends_with("[node 2] recent\n[node 3] ahead\n"));
+		merged.push("node 2", None, "undated", now);
+		merged.flush(now, true).unwrap();
+		assert!(std::fs::read_to_string(&path).unwrap().ends_with("[node 2] undated\n"));
+	}
+}
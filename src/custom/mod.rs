@@ -1,4 +1,60 @@
+pub mod alerts;
 pub mod app;
+pub mod app_error;
+pub mod availability;
+pub mod backfill;
+pub mod clipboard;
+pub mod columns;
+pub mod config;
+pub mod config_check;
+pub mod daemon;
+#[cfg(feature = "exporters")]
+pub mod event_log;
+pub mod data_dir;
+pub mod escalation;
+pub mod file_check;
+pub mod filter;
+pub mod flapping;
+pub mod focus;
+pub mod goto;
+pub mod groups;
+pub mod hooks;
+pub mod ingest;
+pub mod journal;
+pub mod keymap;
+pub mod line_detail;
+pub mod line_times;
+#[cfg(feature = "exporters")]
+pub mod merged;
+pub mod node_control;
 pub mod opt;
+pub mod palette;
+pub mod parser_view;
+pub mod profile;
+pub mod regex_playground;
+pub mod search;
+pub mod self_log;
+pub mod self_metrics;
+pub mod session;
+pub mod shutdown;
+pub mod splits;
+#[cfg(feature = "exporters")]
+pub mod snapshot;
+pub mod source_link;
+pub mod startup;
+pub mod state_diff;
+pub mod status_check;
+#[cfg(feature = "remote")]
+pub mod syslog;
+pub mod tags;
+pub mod terminal;
+pub mod text;
+pub mod time_travel;
 pub mod ui;
 pub mod ui_debug;
+#[cfg(test)]
+mod ui_tests;
+pub mod ui_top;
+pub mod viewport;
+pub mod watchdog;
+pub mod watched;
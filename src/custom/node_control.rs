@@ -0,0 +1,374 @@
+///! Node control ('x'): run safe CLI actions on the focused node
+///!
+///! The node view offers actions such as restarting, stopping or updating the
+///! node, each a command run after confirmation. Without [[actions]] in the
+///! config file the actions are `safe node restart`, `safe node stop` and
+///! `safe node update`, each given the focused node's directory with
+///! `--node-path {node_dir}` so they act on that node rather than the CLI's
+///! default one. They can be replaced by any commands, for example:
+///!
+///!     [[actions]]
+///!     name = "logs"
+///!     run = "./collect-logs.sh {vault} {logfile}"
+///!
+///! As for hooks, the command is split into words before {vault}, {logfile},
+///! {node_dir} (the logfile's directory) and {node_name} are substituted, and
+///! run without a shell. Each command runs in the background and its result
+///! and output become a 'control' node event, which is shown in the popup and
+///! goes to the hooks and any --events-ndjson file like any other event.
+use serde::Deserialize;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use tokio::sync::mpsc;
+
+use super::hooks::split_command;
+use super::keymap::InputKey;
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, Paragraph, Wrap},
+	Frame,
+};
+
+///! Lines of a command's output kept, from its end
+const OUTPUT_LINES_MAX: usize = 20;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ActionConfig {
+	pub name: String,
+	pub run: String,
+}
+
+impl ActionConfig {
+	pub fn validate(&self) -> Result<(), Error> {
+		if self.name.trim().is_empty() {
+			return Err(Error::new(ErrorKind::Other, "action has an empty 'name'"));
+		}
+		if split_command(&self.run).is_empty() {
+			return Err(Error::new(
+				ErrorKind::Other,
+				format!("action '{}' has an empty 'run' command", self.name),
+			));
+		}
+		Ok(())
+	}
+
+	///! The command's words for a node, with its details substituted
+	pub fn command(&self, vault: usize, logfile: &str, node_name: &str) -> Vec<String> {
+		let vault = vault.to_string();
+		let node_dir = match Path::new(logfile).parent() {
+			Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+			_ => String::from("."),
+		};
+		split_command(&self.run)
+			.iter()
+			.map(|word| {
+				word
+					.replace("{vault}", &vault)
+					.replace("{logfile}", logfile)
+					.replace("{node_dir}", &node_dir)
+					.replace("{node_name}", node_name)
+			})
+			.collect()
+	}
+}
+
+///! The safe CLI's node commands for the node's own directory, offered when the config file has no [[actions]]
+pub fn default_actions() -> Vec<ActionConfig> {
+	["restart", "stop", "update"]
+		.iter()
+		.map(|name| ActionConfig {
+			name: name.to_string(),
+			run: format!("safe node {} --node-path {{node_dir}}", name),
+		})
+		.collect()
+}
+
+///! The result of running an action's command
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionOutput {
+	pub logfile: String,
+	pub name: String,
+	pub command: String,
+	///! The exit status, or why the command couldn't run
+	pub result: Result<String, String>,
+	///! Whether the command ran and exited successfully
+	pub succeeded: bool,
+	///! The end of stdout and stderr
+	pub output: Vec<String>,
+}
+
+impl ActionOutput {
+	///! The exit status, or why the command couldn't run
+	pub fn result_text(&self) -> &str {
+		match &self.result {
+			Ok(status) => status,
+			Err(e) => e,
+		}
+	}
+
+	///! Detail of the 'control' event
+	pub fn detail(&self) -> String {
+		let mut detail = format!("{} ({}): {}", self.name, self.command, self.result_text());
+		for line in self.output.iter() {
+			detail.push('\n');
+			detail.push_str(line);
+		}
+		detail
+	}
+}
+
+///! Run command for the node logging to logfile, sending its output when it completes
+pub fn run_action(name: &str, command: Vec<String>, logfile: &str, tx: mpsc::UnboundedSender<ActionOutput>) {
+	let mut output = ActionOutput {
+		logfile: logfile.to_string(),
+		name: name.to_string(),
+		command: command.join(" "),
+		result: Err(String::from("no command")),
+		succeeded: false,
+		output: Vec::new(),
+	};
+	thread::spawn(move || {
+		if let Some((program, args)) = command.split_first() {
+			match Command::new(program).args(args).stdin(Stdio::null()).output() {
+				Ok(result) => {
+					output.result = Ok(result.status.to_string());
+					output.succeeded = result.status.success();
+					let text = String::from_utf8_lossy(&result.stdout).to_string() + &String::from_utf8_lossy(&result.stderr);
+					let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+					let start = lines.len().saturating_sub(OUTPUT_LINES_MAX);
+					output.output = lines[start..].iter().map(|line| line.to_string()).collect();
+				}
+				Err(e) => output.result = Err(format!("failed to start: {}", e)),
+			}
+		}
+		let _ = tx.send(output);
+	});
+}
+
+///! The actions offered, and the outputs of those run
+pub struct NodeControl {
+	pub actions: Vec<ActionConfig>,
+	outputs_tx: mpsc::UnboundedSender<ActionOutput>,
+	outputs: mpsc::UnboundedReceiver<ActionOutput>,
+}
+
+impl NodeControl {
+	///! Offer actions, or default_actions() if there are none
+	pub fn new(actions: Vec<ActionConfig>) -> NodeControl {
+		let (outputs_tx, outputs) = mpsc::unbounded_channel();
+		NodeControl {
+			actions: if actions.is_empty() { default_actions() } else { actions },
+			outputs_tx,
+			outputs,
+		}
+	}
+
+	///! Run the action at index for a node, in the background
+	pub fn run(&self, index: usize, vault: usize, logfile: &str, node_name: &str) {
+		if let Some(action) = self.actions.get(index) {
+			let command = action.command(vault, logfile, node_name);
+			run_action(&action.name, command, logfile, self.outputs_tx.clone());
+		}
+	}
+
+	///! Outputs of the actions which have completed since last called
+	pub fn completed(&mut self) -> Vec<ActionOutput> {
+		let mut completed = Vec::new();
+		while let Ok(output) = self.outputs.try_recv() {
+			completed.push(output);
+		}
+		completed
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage {
+	Choosing,
+	///! Asking whether to run the chosen action
+	Confirming,
+	///! Waiting for the chosen action to complete
+	Running,
+	Done(ActionOutput),
+}
+
+///! What a key does to the control popup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuResult {
+	///! Run the action at this index
+	Run(usize),
+	Close,
+}
+
+pub struct ControlMenu {
+	///! The node's logfile
+	pub logfile: String,
+	pub vault: usize,
+	pub names: Vec<String>,
+	pub selected: usize,
+	pub stage: Stage,
+	///! The chosen action's command for this node
+	pub command: String,
+}
+
+impl ControlMenu {
+	pub fn new(logfile: &str, vault: usize, actions: &[ActionConfig]) -> ControlMenu {
+		ControlMenu {
+			logfile: logfile.to_string(),
+			vault,
+			names: actions.iter().map(|action| action.name.clone()).collect(),
+			selected: 0,
+			stage: Stage::Choosing,
+			command: String::new(),
+		}
+	}
+
+	///! Handle a key, given the command each action would run
+	pub fn handle(&mut self, key: InputKey, command: impl Fn(usize) -> String) -> Option<MenuResult> {
+		match self.stage {
+			Stage::Choosing => match key {
+				InputKey::Esc | InputKey::Char('q') => return Some(MenuResult::Close),
+				InputKey::Up | InputKey::Char('k') => self.selected = self.selected.saturating_sub(1),
+				InputKey::Down | InputKey::Char('j') => {
+					self.selected = (self.selected + 1).min(self.names.len().saturating_sub(1))
+				}
+				InputKey::Enter | InputKey::Char(_) => {
+					if let InputKey::Char(c) = key {
+						match c.to_digit(10) {
+							Some(digit) if digit >= 1 && (digit as usize) <= self.names.len() => {
+								self.selected = digit as usize - 1
+							}
+							_ => return None,
+						}
+					}
+					if self.selected < self.names.len() {
+						self.command = command(self.selected);
+						self.stage = Stage::Confirming;
+					}
+				}
+				_ => {}
+			},
+			Stage::Confirming => match key {
+				InputKey::Char('y') | InputKey::Char('Y') => {
+					self.stage = Stage::Running;
+					return Some(MenuResult::Run(self.selected));
+				}
+				InputKey::Char('n') | InputKey::Char('N') | InputKey::Esc => self.stage = Stage::Choosing,
+				_ => {}
+			},
+			Stage::Running | Stage::Done(..) => match key {
+				InputKey::Esc | InputKey::Enter | InputKey::Char('q') | InputKey::Char('x') => {
+					return Some(MenuResult::Close)
+				}
+				_ => {}
+			},
+		}
+		None
+	}
+
+	///! Show the output of the action this menu ran
+	pub fn complete(&mut self, output: &ActionOutput) {
+		if self.stage == Stage::Running && output.logfile == self.logfile && output.command == self.command {
+			self.stage = Stage::Done(output.clone());
+		}
+	}
+}
+
+pub fn draw_control_menu<B: Backend>(f: &mut Frame<B>, menu: &ControlMenu) {
+	let size = f.size();
+	let width = size.width.min(80);
+	let height = size.height.min(match &menu.stage {
+		Stage::Done(output) => output.output.len() as u16 + 5,
+		_ => menu.names.len() as u16 + 6,
+	});
+	let area = Rect::new(size.x + (size.width - width) / 2, size.y + 2, width, height);
+	f.render_widget(Clear, area);
+
+	let bold = Style::default().add_modifier(Modifier::BOLD);
+	let mut text = vec![Spans::from(Span::raw(menu.logfile.as_str()))];
+	let title = match &menu.stage {
+		Stage::Choosing => {
+			for (index, name) in menu.names.iter().enumerate() {
+				let style = match index == menu.selected {
+					true => bold.fg(Color::Yellow),
+					false => Style::default(),
+				};
+				text.push(Spans::from(Span::styled(format!("{} {}", index + 1, name), style)));
+			}
+			"Control node (enter or 1-9 chooses, esc closes)"
+		}
+		Stage::Confirming => {
+			text.push(Spans::from(vec![
+				Span::raw("Run "),
+				Span::styled(menu.command.as_str(), bold),
+				Span::raw(format!(" for node {}?", menu.vault)),
+			]));
+			text.push(Spans::from(Span::styled("[y]es, [n]o", Style::default().fg(Color::Yellow))));
+			"Control node: confirm"
+		}
+		Stage::Running => {
+			text.push(Spans::from(Span::styled(menu.command.as_str(), bold)));
+			text.push(Spans::from("Running... (esc closes, the result goes to the node's events)"));
+			"Control node: running"
+		}
+		Stage::Done(output) => {
+			text.push(Spans::from(Span::styled(menu.command.as_str(), bold)));
+			let colour = if output.succeeded { Color::Green } else { Color::Red };
+			text.push(Spans::from(Span::styled(output.result_text(), Style::default().fg(colour))));
+			text.extend(output.output.iter().map(|line| Spans::from(line.as_str())));
+			"Control node: done (esc closes)"
+		}
+	};
+	let menu_widget = Paragraph::new(text)
+		.block(Block::default().borders(Borders::ALL).title(title))
+		.wrap(Wrap { trim: false });
+	f.render_widget(menu_widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn confirms_and_runs_actions() {
+		let actions = default_actions();
+		let restart = actions[0].command(2, "/var/log/sn/node.log", "");
+		assert_eq!(restart, vec!["safe", "node", "restart", "--node-path", "/var/log/sn"]);
+		let custom = ActionConfig {
+			name: String::from("echo"),
+			run: String::from("echo '{vault} {node_name}' {node_dir}"),
+		};
+		let command = custom.command(2, "/var/log/sn/node.log", "f67c2e..");
+		assert_eq!(command, vec!["echo", "2 f67c2e..", "/var/log/sn"]);
+
+		let mut menu = ControlMenu::new("node.log", 2, &actions);
+		let command_of = |index: usize| actions[index].command(2, "node.log", "").join(" ");
+		assert_eq!(menu.handle(InputKey::Char('j'), command_of), None);
+		assert_eq!(menu.handle(InputKey::Enter, command_of), None);
+		assert_eq!(menu.command, "safe node stop --node-path .");
+		// Anything but 'y' doesn't run it
+		assert_eq!(menu.handle(InputKey::Enter, command_of), None);
+		assert_eq!(menu.handle(InputKey::Char('n'), command_of), None);
+		assert_eq!(menu.stage, Stage::Choosing);
+		assert_eq!(menu.handle(InputKey::Char('3'), command_of), None);
+		assert_eq!(menu.handle(InputKey::Char('y'), command_of), Some(MenuResult::Run(2)));
+
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		run_action("echo", command.clone(), "node.log", tx);
+		let output = loop {
+			match rx.try_recv() {
+				Ok(output) => break output,
+				Err(_) => thread::sleep(std::time::Duration::from_millis(10)),
+			}
+		};
+		assert!(output.succeeded);
+		assert_eq!(output.output, vec!["2 f67c2e.. /var/log/sn"]);
+		assert!(output.detail().starts_with("echo (echo 2 f67c2e.. /var/log/sn): exit status: 0\n"));
+	}
+}
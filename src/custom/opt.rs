@@ -5,6 +5,23 @@
 pub static MIN_TIMELINE_STEPS: usize = 10;
 
 pub use structopt::StructOpt;
+use chrono::{DateTime, Duration, Utc};
+use std::fmt::Display;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Error};
+use regex::Regex;
+use std::path::PathBuf;
+use std::str::FromStr;
+use vault_metrics::{AssumedOffset, PARSERS};
+
+use super::config::Config;
+use super::data_dir::{self, DataDir};
+use super::profile::Profile;
+use super::status_check;
+use super::watched::WatchPattern;
+
+///! Optional file of VAULT_DASH_* settings, read from the current directory
+pub static DOTENV_FILE: &str = ".env";
 
 #[derive(StructOpt, Debug)]
 #[structopt(
@@ -12,30 +29,542 @@ pub use structopt::StructOpt;
 )]
 pub struct Opt {
 	/// Maximum number of lines to keep for each logfile
-	#[structopt(short = "l", long, default_value = "100")]
+	#[structopt(short = "l", long, default_value = "100", env = "VAULT_DASH_LINES_MAX")]
 	pub lines_max: usize,
 
+	/// Keep logfile lines from the N minutes before the most recent, instead of --lines-max lines
+	#[structopt(long, value_name = "N", env = "VAULT_DASH_RETAIN_MINUTES")]
+	pub retain_minutes: Option<u32>,
+
+	/// Dim logfile lines as they age, to grey N minutes after they were logged (0 for never)
+	#[structopt(long, value_name = "N", default_value = "10", env = "VAULT_DASH_FADE_MINUTES")]
+	pub fade_minutes: u32,
+
+	/// Regex matching lines which log a request's latency, capturing it in milliseconds, for the latency widget
+	#[structopt(long, value_name = "REGEX", env = "VAULT_DASH_LATENCY_PATTERN")]
+	pub latency_pattern: Option<Regex>,
+
+	/// Latency in milliseconds up to which a request satisfies, for the Apdex score (tolerated up to 4 times it)
+	#[structopt(long, value_name = "MS", default_value = "500", env = "VAULT_DASH_APDEX_MS")]
+	pub apdex_ms: f64,
+
+	/// Chart the number a regex captures in a group named 'value', as NAME=REGEX (repeat for each), see watched.rs
+	#[structopt(long, value_name = "NAME=REGEX", number_of_values = 1, env = "VAULT_DASH_WATCH")]
+	pub watch: Vec<WatchPattern>,
+
+	/// Lines per second from a node above which its lines update metrics but aren't shown (0 for never)
+	#[structopt(long, value_name = "N", default_value = "1000", env = "VAULT_DASH_FLOOD_LINES")]
+	pub flood_lines: usize,
+
+	/// Warn when a node's log times are more than N seconds from local time (0 for never)
+	#[structopt(long, value_name = "N", default_value = "30", env = "VAULT_DASH_CLOCK_SKEW_SECS")]
+	pub clock_skew_secs: i64,
+
 	/// Event update tick in milliseconds
-	#[structopt(long, default_value = "200")]
+	#[structopt(long, default_value = "200", env = "VAULT_DASH_TICK_RATE")]
 	pub tick_rate: u64,
 
 	/// Steps (width) of each timeline, helps tweak right justification.
-	#[structopt(short, long, default_value = "210")]
+	#[structopt(short, long, default_value = "210", env = "VAULT_DASH_TIMELINE_STEPS")]
 	pub timeline_steps: usize,
 
-	/// Ignore any existing logfile content
+	/// Ignore any existing logfile content [env: VAULT_DASH_IGNORE_EXISTING]
 	#[structopt(short, long)]
 	pub ignore_existing: bool,
 
-	/// One or more logfiles to monitor
+	/// Load only the last N lines of each existing logfile, found by reading back from its end so the rest isn't read
+	#[structopt(long, value_name = "N", conflicts_with = "ignore-existing", env = "VAULT_DASH_TAIL_LINES")]
+	pub tail_lines: Option<usize>,
+
+	/// Threads loading existing logfile content, one logfile each at a time (0 for one per core)
+	#[structopt(long, value_name = "N", default_value = "0", env = "VAULT_DASH_LOAD_WORKERS")]
+	pub load_workers: usize,
+
+	/// One or more logfiles to monitor (comma separated in VAULT_DASH_LOGFILES) [env: VAULT_DASH_LOGFILES]
 	#[structopt(name = "LOGFILE")]
 	pub files: Vec<String>,
 
-	/// Show a debug window to the right of the logfile view in main dashboard
+	/// Offset of logfile timestamps which don't include one: 'local', 'utc' or e.g. '+01:00'
+	#[structopt(long, value_name = "OFFSET", default_value = "local", env = "VAULT_DASH_ASSUME_OFFSET")]
+	pub assume_offset: AssumedOffset,
+
+	/// Load only existing lines logged since TIME: e.g. '2h' (ago, or s, m, d, w) or '2021-01-20T10:00:00Z'
+	#[structopt(long, value_name = "TIME", env = "VAULT_DASH_SINCE")]
+	pub since: Option<TimeBound>,
+
+	/// Load only existing lines logged until TIME, given as for --since
+	#[structopt(long, value_name = "TIME", env = "VAULT_DASH_UNTIL")]
+	pub until: Option<TimeBound>,
+
+	/// Monitor the newest file in DIR, switching to any newer file which appears (e.g. a logfile per run)
+	#[structopt(long, value_name = "DIR", conflicts_with = "daemon", env = "VAULT_DASH_LATEST_IN")]
+	pub latest_in: Option<String>,
+
+	/// Poll logfiles for new lines every SECS rather than watching them, for filesystems such as NFS whose changes aren't always notified
+	#[structopt(long, value_name = "SECS", parse(try_from_str = parse_poll_interval), env = "VAULT_DASH_POLL_INTERVAL")]
+	pub poll_interval: Option<std::time::Duration>,
+
+	/// Measure a node's data directory, to compare with its chunk store records. NAME is the node's number, logfile, or logfile's directory name (repeat for each node, or separate with commas in the environment) [env: VAULT_DASH_DATA_DIR]
+	#[structopt(long, value_name = "NAME=PATH", number_of_values = 1)]
+	pub data_dir: Vec<DataDir>,
+
+	/// Write the time to FILE every second or so, for supervisors which check that vdash is running
+	#[structopt(long, value_name = "FILE", env = "VAULT_DASH_LIVENESS_FILE")]
+	pub liveness_file: Option<String>,
+
+	/// Show a debug window to the right of the logfile view in main dashboard [env: VAULT_DASH_DEBUG_WINDOW]
 	#[structopt(short, long)]
 	pub debug_window: bool,
 
-	/// Parses first logfile, prints results to second and shows side-by-side (logtail-crossterm only)
+	/// Write vdash's own log to FILE, rotated at 10 MB, instead of stderr where it garbles the dashboard ('L' opens it)
+	#[structopt(long, value_name = "FILE", env = "VAULT_DASH_SELF_LOG")]
+	pub self_log: Option<PathBuf>,
+
+	/// Parses first logfile, prints results to second and shows side-by-side (logtail-crossterm only) [env: VAULT_DASH_DEBUG_DASHBOARD]
 	#[structopt(long)]
 	pub debug_dashboard: bool,
+
+	/// Configuration file (default: vdash/config.toml in the user's config directory)
+	#[structopt(long, value_name = "FILE", env = "VAULT_DASH_CONFIG")]
+	pub config: Option<String>,
+
+	/// Check the config file, listing any problems by line, and exit (non-zero if there are problems)
+	#[structopt(long)]
+	pub check_config: bool,
+
+	/// Use the logfiles and settings of [profile.NAME] in the config file, such as for a particular network
+	#[structopt(long, value_name = "NAME", env = "VAULT_DASH_PROFILE")]
+	pub profile: Option<String>,
+
+	/// Label a node in its heading. NAME is as for --data-dir (repeat for each node, or separate with commas in the environment) [env: VAULT_DASH_LABELS]
+	#[structopt(long, value_name = "NAME=LABEL", number_of_values = 1)]
+	pub label: Vec<NodeLabel>,
+
+	/// Tag a node, such as with its host, to show only nodes with a tag (see --only-tag). NAME is as for --data-dir (repeat for each node and tag, or separate with commas in the environment) [env: VAULT_DASH_TAGS]
+	#[structopt(long, value_name = "NAME=TAG", number_of_values = 1)]
+	pub tag: Vec<NodeLabel>,
+
+	/// Show only nodes tagged TAG (see --tag) in every view, until '#' moves on to the next tag
+	#[structopt(long, value_name = "TAG", env = "VAULT_DASH_ONLY_TAG")]
+	pub only_tag: Option<String>,
+
+	/// Logfile parser: 'sn_node' gathers node metrics, 'plain' only shows the logfile
+	#[structopt(long, default_value = "sn_node", possible_values = &PARSERS, env = "VAULT_DASH_PARSER")]
+	pub parser: String,
+
+	/// Show status with glyphs and labels only, without colour [env: VAULT_DASH_NO_COLOR or NO_COLOR]
+	#[structopt(long)]
+	pub no_color: bool,
+
+	/// Draw in the terminal's main screen, for terminals without an alternate screen [env: VAULT_DASH_INLINE]
+	#[structopt(long)]
+	pub inline: bool,
+
+	/// Show only the summary, redrawn every few seconds, to save CPU and battery ('z' toggles) [env: VAULT_DASH_LOW_POWER]
+	#[structopt(long)]
+	pub low_power: bool,
+
+	/// Celebrate a node's promotion to Elder with a banner across the dashboard for a few seconds [env: VAULT_DASH_CELEBRATE]
+	#[structopt(long)]
+	pub celebrate: bool,
+
+	/// Run without a terminal UI, monitoring logfiles and serving a dashboard at SOCKET (see --attach)
+	#[structopt(long, value_name = "SOCKET", conflicts_with = "attach", env = "VAULT_DASH_DAEMON")]
+	pub daemon: Option<String>,
+
+	/// Journal parsed state to FILE, from which a restart recovers instead of parsing whole logfiles again
+	#[structopt(long, value_name = "FILE", conflicts_with = "attach", env = "VAULT_DASH_JOURNAL")]
+	pub journal: Option<String>,
+
+	/// Append each node event (start, promotion, error, status or flapping) to FILE as newline-delimited JSON, for SIEM and log pipelines
+	#[structopt(long, value_name = "FILE", conflicts_with = "attach", env = "VAULT_DASH_EVENTS_NDJSON")]
+	pub events_ndjson: Option<String>,
+
+	/// Write every node's new lines to FILE, each prefixed with its node's label or number, in the order logged. FILE is rotated to FILE.1 at 100 MiB
+	#[structopt(long, value_name = "FILE", conflicts_with = "attach", env = "VAULT_DASH_WRITE_MERGED")]
+	pub write_merged: Option<String>,
+
+	/// Accept syslog messages (RFC 5424 or 3164) over UDP and TCP at ADDR (e.g. 0.0.0.0:5514), monitoring each host and app they come from
+	#[structopt(long, value_name = "ADDR", conflicts_with = "attach", env = "VAULT_DASH_LISTEN_SYSLOG")]
+	pub listen_syslog: Option<String>,
+
+	/// Parser for lines received with --listen-syslog (default: --parser)
+	#[structopt(long, value_name = "PARSER", possible_values = &PARSERS, requires = "listen-syslog", env = "VAULT_DASH_SYSLOG_PARSER")]
+	pub syslog_parser: Option<String>,
+
+	/// Most hosts and apps monitored with --listen-syslog, after which messages from any others are dropped
+	#[structopt(long, value_name = "N", default_value = "32", env = "VAULT_DASH_SYSLOG_SOURCES_MAX")]
+	pub syslog_sources_max: usize,
+
+	/// Most TCP connections open at once with --listen-syslog, after which any others are refused
+	#[structopt(long, value_name = "N", default_value = "16", env = "VAULT_DASH_SYSLOG_CONNECTIONS_MAX")]
+	pub syslog_connections_max: usize,
+
+	/// Attach the dashboard to a vdash daemon listening at SOCKET instead of monitoring logfiles directly
+	#[structopt(long, value_name = "SOCKET", env = "VAULT_DASH_ATTACH")]
+	pub attach: Option<String>,
+
+	/// Serve a read-only snapshot of the summary at ADDR (e.g. 127.0.0.1:8080) as HTML (/) and JSON (/summary.json)
+	#[structopt(long, value_name = "ADDR", env = "VAULT_DASH_SNAPSHOT_HTTP")]
+	pub snapshot_http: Option<String>,
+
+	/// Require TOKEN for --snapshot-http, as '?token=TOKEN' or an 'Authorization: Bearer TOKEN' header
+	#[structopt(long, value_name = "TOKEN", requires = "snapshot-http", env = "VAULT_DASH_SNAPSHOT_TOKEN", hide_env_values = true)]
+	pub snapshot_token: Option<String>,
+
+	#[structopt(subcommand)]
+	pub command: Option<Command>,
+}
+
+// Commands run instead of the dashboard
+#[derive(StructOpt, Debug)]
+pub enum Command {
+	/// Copy logfile IN to OUT with XorNames, IP addresses and wallet IDs replaced by pseudonyms, for sharing
+	Scrub {
+		#[structopt(name = "IN")]
+		input: PathBuf,
+		#[structopt(name = "OUT")]
+		output: PathBuf,
+	},
+	/// Print the status of the node logging to each FILE, exiting 0 if all are OK, 1 if any warn and 2 if any have errors or are stalled
+	Status {
+		#[structopt(name = "FILE", required = true)]
+		files: Vec<PathBuf>,
+	},
+}
+
+impl Command {
+	pub fn run(&self, opt: &Opt) -> std::io::Result<()> {
+		match self {
+			Command::Scrub { input, output } => {
+				let scrubber = vault_metrics::scrub::scrub_logfile(input, output)?;
+				println!("Wrote {} with {} replaced", output.display(), scrubber.summary());
+				Ok(())
+			}
+			Command::Status { files } => status_check::run(opt, files),
+		}
+	}
+}
+
+impl Opt {
+	///! Parse options from the command line, falling back to VAULT_DASH_*
+	///! environment variables, which may be set in a .env file.
+	///!
+	///! Precedence is: command line, environment, .env file, defaults.
+	pub fn from_args_and_env() -> Opt {
+		load_dotenv(DOTENV_FILE);
+		let args = match expand_arg_files(std::env::args_os()) {
+			Ok(args) => args,
+			Err(e) => {
+				eprintln!("error: {}", e);
+				std::process::exit(1);
+			}
+		};
+		let mut opt = Opt::from_iter(args.clone());
+
+		// A profile's settings are variables too, so options are parsed again with them
+		if let Some(name) = &opt.profile {
+			match Config::load(&opt).and_then(|config| config.find_profile(name).map(Profile::apply)) {
+				Ok(()) => opt = Opt::from_iter(args),
+				Err(e) => {
+					eprintln!("error: {}", e);
+					std::process::exit(1);
+				}
+			}
+		}
+
+		// clap's env support only applies to options taking a value, so flags are read here
+		opt.ignore_existing |= env_flag("VAULT_DASH_IGNORE_EXISTING");
+		opt.debug_window |= env_flag("VAULT_DASH_DEBUG_WINDOW");
+		opt.debug_dashboard |= env_flag("VAULT_DASH_DEBUG_DASHBOARD");
+		opt.no_color |= env_flag("VAULT_DASH_NO_COLOR");
+		opt.inline |= env_flag("VAULT_DASH_INLINE");
+		opt.low_power |= env_flag("VAULT_DASH_LOW_POWER");
+		opt.celebrate |= env_flag("VAULT_DASH_CELEBRATE");
+
+		// Only lists given in the environment are split on commas, so that a value on the command line may have one
+		if let Err(e) = opt.lists_from_env() {
+			eprintln!("error: {}", e);
+			std::process::exit(1);
+		}
+
+		// See https://no-color.org
+		if let Some(value) = std::env::var_os("NO_COLOR") {
+			opt.no_color |= !value.is_empty();
+		}
+		if let Err(e) = opt.check_features() {
+			eprintln!("error: {}", e);
+			std::process::exit(1);
+		}
+		opt
+	}
+
+	///! Take list options not given on the command line from the environment
+	fn lists_from_env(&mut self) -> Result<(), String> {
+		if self.files.is_empty() {
+			self.files = env_list("VAULT_DASH_LOGFILES")?;
+		}
+		if self.data_dir.is_empty() {
+			self.data_dir = env_list("VAULT_DASH_DATA_DIR")?;
+		}
+		if self.label.is_empty() {
+			self.label = env_list("VAULT_DASH_LABELS")?;
+		}
+		if self.tag.is_empty() {
+			self.tag = env_list("VAULT_DASH_TAGS")?;
+		}
+		Ok(())
+	}
+
+	///! Reject options needing a feature left out of this build, rather than ignore them
+	pub fn check_features(&self) -> Result<(), String> {
+		let options = [
+			("--events-ndjson", self.events_ndjson.is_some(), "exporters", cfg!(feature = "exporters")),
+			("--snapshot-http", self.snapshot_http.is_some(), "exporters", cfg!(feature = "exporters")),
+			("--write-merged", self.write_merged.is_some(), "exporters", cfg!(feature = "exporters")),
+			("--listen-syslog", self.listen_syslog.is_some(), "remote", cfg!(feature = "remote")),
+		];
+		match options.iter().find(|(_, used, _, built)| *used && !*built) {
+			Some((option, _, feature, _)) => Err(format!("{} needs vdash built with the '{}' feature", option, feature)),
+			None => Ok(()),
+		}
+	}
+}
+
+///! A --label option
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeLabel {
+	pub name: String,
+	pub label: String,
+}
+
+impl FromStr for NodeLabel {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<NodeLabel, String> {
+		match s.split_once('=') {
+			Some((name, label)) if !name.is_empty() && !label.is_empty() => Ok(NodeLabel {
+				name: name.to_string(),
+				label: label.to_string(),
+			}),
+			_ => Err(format!("'{}' is not of the form NAME=LABEL", s)),
+		}
+	}
+}
+
+impl NodeLabel {
+	///! The label given to the node numbered node (from 1) with logfile, if any
+	pub fn find(labels: &[NodeLabel], node: usize, logfile: &str) -> Option<String> {
+		labels
+			.iter()
+			.find(|label| data_dir::names_node(&label.name, node, logfile))
+			.map(|label| label.label.clone())
+	}
+
+	///! Every label given to the node, as for find()
+	pub fn find_all(labels: &[NodeLabel], node: usize, logfile: &str) -> Vec<String> {
+		labels
+			.iter()
+			.filter(|label| data_dir::names_node(&label.name, node, logfile))
+			.map(|label| label.label.clone())
+			.collect()
+	}
+}
+
+///! A time for --since or --until, given in RFC 3339 or as a duration before now
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeBound(pub DateTime<Utc>);
+
+impl FromStr for TimeBound {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<TimeBound, String> {
+		if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+			return Ok(TimeBound(time.with_timezone(&Utc)));
+		}
+
+		let invalid = || format!("invalid time '{}', expected e.g. '90m', '2h', '1d' or '2021-01-20T10:00:00Z'", s);
+		let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+		let count: i64 = s[..split].parse().map_err(|_| invalid())?;
+		let duration = match &s[split..] {
+			"s" => Duration::seconds(count),
+			"m" => Duration::minutes(count),
+			"h" => Duration::hours(count),
+			"d" => Duration::days(count),
+			"w" => Duration::weeks(count),
+			_ => return Err(invalid()),
+		};
+		Ok(TimeBound(Utc::now() - duration))
+	}
+}
+
+///! The command line with each @FILE replaced by the arguments listed in FILE
+///!
+///! FILE gives an argument on each line, ignoring blank lines and '#'
+///! comments, where an option may share its line with its value (such as
+///! '--lines-max 200'). Otherwise a line is one argument as it stands, so
+///! logfile paths may contain spaces.
+fn expand_arg_files(args: impl IntoIterator<Item = OsString>) -> Result<Vec<OsString>, Error> {
+	let mut expanded = Vec::new();
+	for (index, arg) in args.into_iter().enumerate() {
+		// The first argument is the program
+		let path = match arg.to_str().and_then(|arg| arg.strip_prefix('@')) {
+			Some(path) if index > 0 => path.to_string(),
+			_ => {
+				expanded.push(arg);
+				continue;
+			}
+		};
+		let text = std::fs::read_to_string(&path)
+			.map_err(|e| Error::new(e.kind(), format!("argument file {}: {}", path, e)))?;
+		for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+			match line.split_once(char::is_whitespace) {
+				Some((option, value)) if option.starts_with('-') => {
+					expanded.push(option.into());
+					expanded.push(value.trim_start().into());
+				}
+				_ => expanded.push(line.into()),
+			}
+		}
+	}
+	Ok(expanded)
+}
+
+fn parse_poll_interval(s: &str) -> Result<std::time::Duration, String> {
+	match s.parse::<f64>() {
+		Ok(secs) if secs >= 0.1 && secs.is_finite() => Ok(std::time::Duration::from_secs_f64(secs)),
+		_ => Err(format!("invalid interval '{}', expected seconds of at least 0.1, e.g. '2' or '0.5'", s)),
+	}
+}
+
+///! The values of a list option in a variable, separated by commas
+fn env_list<T: FromStr>(name: &str) -> Result<Vec<T>, String>
+where
+	T::Err: Display,
+{
+	match std::env::var(name) {
+		Ok(values) => split_list(&values).map_err(|e| format!("{}: {}", name, e)),
+		Err(_) => Ok(Vec::new()),
+	}
+}
+
+fn split_list<T: FromStr>(values: &str) -> Result<Vec<T>, String>
+where
+	T::Err: Display,
+{
+	values
+		.split(',')
+		.filter(|value| !value.is_empty())
+		.map(|value| value.parse().map_err(|e: T::Err| e.to_string()))
+		.collect()
+}
+
+fn env_flag(name: &str) -> bool {
+	match std::env::var(name) {
+		Ok(value) => !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false" | "no" | "off"),
+		Err(_) => false,
+	}
+}
+
+///! Set variables from a KEY=VALUE file without overriding the environment
+///!
+///! Blank lines and '#' comments are ignored, as are a leading 'export ' and
+///! quotes around the value. A missing file is not an error.
+fn load_dotenv(path: &str) {
+	let file = match std::fs::File::open(path) {
+		Ok(file) => file,
+		Err(_) => return,
+	};
+
+	for line in BufReader::new(file).lines() {
+		let line = match line {
+			Ok(line) => line,
+			Err(_) => return,
+		};
+		if let Some((key, value)) = parse_dotenv_line(&line) {
+			if std::env::var_os(&key).is_none() {
+				std::env::set_var(key, value);
+			}
+		}
+	}
+}
+
+fn parse_dotenv_line(line: &str) -> Option<(String, String)> {
+	let line = line.trim();
+	if line.is_empty() || line.starts_with('#') {
+		return None;
+	}
+	let line = line.strip_prefix("export ").unwrap_or(line);
+	let mut parts = line.splitn(2, '=');
+	let key = parts.next()?.trim();
+	let value = parts.next()?.trim();
+	if key.is_empty() {
+		return None;
+	}
+
+	let value = if value.len() >= 2
+		&& ((value.starts_with('"') && value.ends_with('"')) || (value.starts_with('\'') && value.ends_with('\'')))
+	{
+		&value[1..value.len() - 1]
+	} else {
+		value
+	};
+	Some((key.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_dotenv_lines() {
+		assert_eq!(
+			parse_dotenv_line("export VAULT_DASH_TICK_RATE = \"500\""),
+			Some((String::from("VAULT_DASH_TICK_RATE"), String::from("500")))
+		);
+		assert_eq!(
+			parse_dotenv_line("VAULT_DASH_LOGFILES=a.log,b.log"),
+			Some((String::from("VAULT_DASH_LOGFILES"), String::from("a.log,b.log")))
+		);
+		assert_eq!(parse_dotenv_line("# VAULT_DASH_TICK_RATE=500"), None);
+		assert_eq!(parse_dotenv_line("no value"), None);
+	}
+
+	#[test]
+	fn splits_only_lists_from_the_environment() {
+		let opt = Opt::from_iter(&["vdash", "--label", "1=genesis,adult", "/logs/node,1.log"]);
+		assert_eq!(opt.files, ["/logs/node,1.log"]);
+		assert_eq!(opt.label, [NodeLabel { name: String::from("1"), label: String::from("genesis,adult") }]);
+
+		assert_eq!(split_list::<String>("a.log,,b.log"), Ok(vec![String::from("a.log"), String::from("b.log")]));
+		assert_eq!(split_list::<NodeLabel>("1=a,2=b").map(|labels| labels.len()), Ok(2));
+		assert!(split_list::<NodeLabel>("1=a,b").is_err());
+	}
+
+	#[test]
+	fn parses_time_bounds() {
+		let time = "2021-01-20T10:00:00+01:00".parse::<TimeBound>().unwrap();
+		assert_eq!(time.0.to_rfc3339(), "2021-01-20T09:00:00+00:00");
+		let hours_ago = Utc::now() - "2h".parse::<TimeBound>().unwrap().0;
+		assert!((hours_ago - Duration::hours(2)).num_seconds().abs() < 5);
+		assert!("2x".parse::<TimeBound>().is_err());
+		assert!("h".parse::<TimeBound>().is_err());
+	}
+
+	#[test]
+	fn expands_arg_files() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("args.txt");
+		std::fs::write(&path, "# fleet\n--lines-max 200\n\nnode 1/sn_node.log\n  --ignore-existing\n").unwrap();
+		let arg_file = format!("@{}", path.display());
+
+		let args = expand_arg_files(["vdash", "a.log", &arg_file].iter().map(OsString::from)).unwrap();
+		assert_eq!(args, ["vdash", "a.log", "--lines-max", "200", "node 1/sn_node.log", "--ignore-existing"]);
+		let opt = Opt::from_iter(args);
+		assert_eq!(opt.files, ["a.log", "node 1/sn_node.log"]);
+		assert_eq!(opt.lines_max, 200);
+		assert!(opt.ignore_existing);
+
+		assert!(expand_arg_files(["vdash", "@missing.txt"].iter().map(OsString::from)).is_err());
+	}
 }
@@ -0,0 +1,94 @@
+///! Command palette: opened with ':' to find and run any action by name
+///!
+///! Typed text is fuzzy matched against each action's description, so that
+///! features stay discoverable as key bindings multiply. Up/down (or tab)
+///! select a match, enter runs it and escape closes the palette.
+use super::keymap::{Action, InputKey, ACTIONS};
+
+#[derive(Default)]
+pub struct Palette {
+	pub query: String,
+	pub selected: usize,
+}
+
+impl Palette {
+	pub fn new() -> Palette {
+		Palette {
+			query: String::new(),
+			selected: 0,
+		}
+	}
+
+	///! Actions matching the query, best first
+	pub fn matches(&self) -> Vec<Action> {
+		let mut scored: Vec<(i64, usize, Action)> = ACTIONS
+			.iter()
+			.enumerate()
+			.filter_map(|(order, action)| {
+				fuzzy_score(&self.query, action.description()).map(|score| (score, order, *action))
+			})
+			.collect();
+		scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+		scored.into_iter().map(|(_, _, action)| action).collect()
+	}
+
+	///! Handle a key, returning Some(action) once one is chosen, or Some(None) to close
+	pub fn handle(&mut self, key: InputKey) -> Option<Option<Action>> {
+		match key {
+			InputKey::Esc => return Some(None),
+			InputKey::Enter => return Some(self.matches().get(self.selected).copied()),
+			InputKey::Backspace => {
+				self.query.pop();
+				self.selected = 0;
+			}
+			InputKey::Char(c) => {
+				self.query.push(c);
+				self.selected = 0;
+			}
+			InputKey::Down | InputKey::Tab if self.selected + 1 < self.matches().len() => self.selected += 1,
+			InputKey::Up | InputKey::BackTab => self.selected = self.selected.saturating_sub(1),
+			_ => {}
+		}
+		None
+	}
+}
+
+///! Score text as a match for query, or None if the query's characters aren't all
+///! present in order (ignoring case). Consecutive and word-start matches score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+	let text: Vec<char> = text.to_lowercase().chars().collect();
+	let mut score = 0;
+	let mut position = 0;
+	let mut previous_match: Option<usize> = None;
+	for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+		let found = position + text[position..].iter().position(|&c| c == q)?;
+		score += 1;
+		if previous_match == Some(found.wrapping_sub(1)) {
+			score += 5;
+		}
+		if found == 0 || text[found - 1] == ' ' {
+			score += 3;
+		}
+		previous_match = Some(found);
+		position = found + 1;
+	}
+	Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fuzzy_matches_in_order() {
+		assert!(fuzzy_score("sv", "Summary view").is_some());
+		assert!(fuzzy_score("vs", "Summary view").is_none());
+		assert!(fuzzy_score("sum", "Summary view") > fuzzy_score("smy", "Summary view"));
+
+		let mut palette = Palette::new();
+		for c in "debug".chars() {
+			palette.handle(InputKey::Char(c));
+		}
+		assert_eq!(palette.handle(InputKey::Enter), Some(Some(Action::ViewDebug)));
+	}
+}
@@ -0,0 +1,99 @@
+///! Parser view: each logfile line beside what the parser makes of it
+///!
+///! Toggled with 'e' for whichever node is in view, the logfile splits into
+///! the raw lines on the left and, on the right, the fields decoded from the
+///! same lines: module, level, time (with any offset assumed), source and
+///! message. The two sides share the logfile's viewport, so they scroll
+///! together and the selected line is highlighted in both. Lines the parser
+///! doesn't recognise, such as continuations, are marked as such, which
+///! makes it easy to see why a node's metrics are missing or wrong.
+use chrono::{DateTime, Utc};
+use vault_metrics::{AssumedOffset, LogEntry};
+
+use super::app::LogMonitor;
+use super::focus;
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	text::Spans,
+	widgets::{Block, Borders, List, ListItem, ListState},
+	Frame,
+};
+
+///! What the parser makes of a line, given the time of the line before
+pub fn interpret(line: &str, assumed_offset: &AssumedOffset, previous: Option<DateTime<Utc>>) -> (String, Option<LogEntry>) {
+	let entry = LogEntry::decode(line, assumed_offset, previous);
+	let interpretation = match &entry {
+		Some(entry) if entry.time.is_none() => format!("{} (time not understood)", entry.parser_output),
+		Some(entry) => entry.parser_output.clone(),
+		None if line.is_empty() => String::from("(empty)"),
+		None => String::from("not decoded (continuation or unknown format)"),
+	};
+	(interpretation, entry)
+}
+
+///! Draw the interpretation of the lines in the logfile's viewport, which draw_logfile() has just placed
+pub fn draw_parser_view<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &mut LogMonitor, focused: bool) {
+	let content = &monitor.content.items;
+	let selected = monitor.content.state.selected();
+	let window = monitor.viewport.window(content.len(), selected, area.height.saturating_sub(2) as usize);
+	let assumed_offset = monitor.metrics.assumed_offset;
+	monitor.line_times.fit(content, &assumed_offset);
+	let line_times = &monitor.line_times;
+	// Lines stay one to one with the logfile's, divider included
+	let historical = content.len() - monitor.live_lines;
+	let items: Vec<ListItem> = content[window.clone()]
+		.iter()
+		.enumerate()
+		.map(|(index, line)| {
+			let (interpretation, entry) = interpret(line, &assumed_offset, line_times.before(window.start + index));
+			let colour = if entry.is_some() { Color::Black } else { Color::Red };
+			let mut lines = vec![Spans::from(interpretation)];
+			if window.start + index + 1 == historical {
+				lines.push(Spans::from(""));
+			}
+			ListItem::new(lines).style(Style::default().fg(colour).bg(Color::White))
+		})
+		.collect();
+
+	let highlight_style = match monitor.has_focus {
+		true => Style::default().bg(Color::LightGreen).add_modifier(Modifier::BOLD),
+		false => Style::default().add_modifier(Modifier::BOLD),
+	};
+	let title = "Parsed ('e' to close)";
+	let widget = List::new(items)
+		.block(Block::default().borders(Borders::ALL).border_style(focus::border_style(focused)).title(title))
+		.highlight_style(highlight_style);
+	let mut state = ListState::default();
+	state.select(selected.map(|selected| selected.saturating_sub(window.start)));
+	f.render_stateful_widget(widget, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::TimeZone;
+
+	#[test]
+	fn interprets_lines() {
+		let offset = AssumedOffset::Fixed(3600);
+		let (fields, entry) = interpret(
+			"[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/node/mod.rs:97] Our Age: 5",
+			&offset,
+			None,
+		);
+		assert_eq!(entry.unwrap().time, Some(Utc.ymd(2021, 1, 20).and_hms(18, 0, 0)));
+		assert!(fields.contains("c: INFO") && fields.contains("s: [src/node/mod.rs:97]") && fields.ends_with("m: Our Age: 5"));
+
+		let (fields, _) = interpret("[sn_node] INFO 2021-01-20T18:00:00.000000 [src/a.rs:1] entry", &offset, None);
+		assert!(fields.contains("assumed Fixed(3600)"));
+
+		let previous = Some(Utc.ymd(2021, 1, 20).and_hms(18, 0, 0));
+		let (fields, _) = interpret("[sn_node] INFO yesterday-afternoon [src/a.rs:1] entry", &offset, previous);
+		assert!(fields.ends_with("(time not understood)"));
+		let (fields, entry) = interpret("    at src/a.rs:1", &offset, previous);
+		assert!(fields.starts_with("not decoded") && entry.is_none());
+	}
+}
@@ -0,0 +1,157 @@
+///! Named profiles in the config file, selected with --profile
+///!
+///! Users switch between networks, such as a local test network and a public
+///! testnet, whose logfiles are in different places and may need different
+///! parsers, labels and thresholds. A profile bundles these settings:
+///!
+///!     [profile.local]
+///!     files = ["~/.safe/node/baby-fleming-nodes/sn-node-genesis/sn_node.log"]
+///!     labels = { "1" = "genesis" }
+///!
+///!     [profile.testnet-7]
+///!     files = ["/var/log/sn_node.log"]
+///!     flood_lines = 5000
+///!     clock_skew_secs = 60
+///!
+///! Each setting is that of the option with the same name. A profile gives
+///! way to the command line, the environment and the .env file, as it sets
+///! only the VAULT_DASH_* variables which are not already set, so any option
+///! can still be given for a single run.
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use vault_metrics::{AssumedOffset, PARSERS};
+
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+	pub files: Vec<String>,
+	pub parser: Option<String>,
+	pub syslog_parser: Option<String>,
+	pub assume_offset: Option<String>,
+	pub latency_pattern: Option<String>,
+	pub apdex_ms: Option<f64>,
+	///! Node labels, by the node's number, logfile, or logfile's directory name (see --label)
+	pub labels: BTreeMap<String, String>,
+	///! Data directories, by node as for labels (see --data-dir)
+	pub data_dirs: BTreeMap<String, String>,
+	pub lines_max: Option<usize>,
+	pub retain_minutes: Option<u32>,
+	pub fade_minutes: Option<u32>,
+	pub flood_lines: Option<usize>,
+	pub clock_skew_secs: Option<i64>,
+	pub poll_interval: Option<f64>,
+}
+
+impl Profile {
+	///! The environment variables of the options the profile sets, and their values
+	pub fn variables(&self) -> Vec<(&'static str, String)> {
+		let pairs = |map: &BTreeMap<String, String>| {
+			map.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<String>>().join(",")
+		};
+		let mut variables = Vec::new();
+		if !self.files.is_empty() {
+			variables.push(("VAULT_DASH_LOGFILES", self.files.iter().map(|file| expand_home(file)).collect::<Vec<String>>().join(",")));
+		}
+		if !self.labels.is_empty() {
+			variables.push(("VAULT_DASH_LABELS", pairs(&self.labels)));
+		}
+		if !self.data_dirs.is_empty() {
+			variables.push(("VAULT_DASH_DATA_DIR", pairs(&self.data_dirs)));
+		}
+		let settings = [
+			("VAULT_DASH_PARSER", self.parser.clone()),
+			("VAULT_DASH_SYSLOG_PARSER", self.syslog_parser.clone()),
+			("VAULT_DASH_ASSUME_OFFSET", self.assume_offset.clone()),
+			("VAULT_DASH_LATENCY_PATTERN", self.latency_pattern.clone()),
+			("VAULT_DASH_APDEX_MS", self.apdex_ms.map(|value| value.to_string())),
+			("VAULT_DASH_LINES_MAX", self.lines_max.map(|value| value.to_string())),
+			("VAULT_DASH_RETAIN_MINUTES", self.retain_minutes.map(|value| value.to_string())),
+			("VAULT_DASH_FADE_MINUTES", self.fade_minutes.map(|value| value.to_string())),
+			("VAULT_DASH_FLOOD_LINES", self.flood_lines.map(|value| value.to_string())),
+			("VAULT_DASH_CLOCK_SKEW_SECS", self.clock_skew_secs.map(|value| value.to_string())),
+			("VAULT_DASH_POLL_INTERVAL", self.poll_interval.map(|value| value.to_string())),
+		];
+		for (variable, value) in settings.iter() {
+			if let Some(value) = value {
+				variables.push((variable, value.clone()));
+			}
+		}
+		variables
+	}
+
+	///! Settings which would be rejected once the profile is selected
+	pub fn problems(&self) -> Vec<String> {
+		let mut problems = Vec::new();
+		for (setting, parser) in [("parser", &self.parser), ("syslog_parser", &self.syslog_parser)].iter() {
+			if let Some(parser) = parser {
+				if !PARSERS.contains(&parser.as_str()) {
+					problems.push(format!("{}: unknown parser '{}', expected one of: {}", setting, parser, PARSERS.join(", ")));
+				}
+			}
+		}
+		if let Some(offset) = &self.assume_offset {
+			if let Err(e) = offset.parse::<AssumedOffset>() {
+				problems.push(format!("assume_offset: {}", e));
+			}
+		}
+		if let Some(pattern) = &self.latency_pattern {
+			if let Err(e) = Regex::new(pattern) {
+				problems.push(format!("latency_pattern: {}", e));
+			}
+		}
+		problems
+	}
+
+	///! Set the profile's variables which are not already set
+	pub fn apply(&self) {
+		for (variable, value) in self.variables() {
+			if std::env::var_os(variable).is_none() {
+				std::env::set_var(variable, value);
+			}
+		}
+	}
+}
+
+///! A path with a leading '~' replaced by the home directory, as files in a config file aren't expanded by a shell
+fn expand_home(path: &str) -> String {
+	match (path.strip_prefix("~/"), std::env::var("HOME")) {
+		(Some(rest), Ok(home)) => format!("{}/{}", home.trim_end_matches('/'), rest),
+		_ => path.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::custom::config::Config;
+
+	#[test]
+	fn profiles_set_options() {
+		let config: Config = toml::from_str(
+			r#"
+			[profile.local]
+			files = ["/tmp/genesis/sn_node.log", "/tmp/node-2/sn_node.log"]
+			labels = { "1" = "genesis", "node-2" = "adult" }
+			flood_lines = 5000
+
+			[profile.testnet-7]
+			parser = "plain"
+			"#,
+		)
+		.unwrap();
+
+		let local = &config.profile["local"];
+		assert_eq!(
+			local.variables(),
+			[
+				("VAULT_DASH_LOGFILES", String::from("/tmp/genesis/sn_node.log,/tmp/node-2/sn_node.log")),
+				("VAULT_DASH_LABELS", String::from("1=genesis,node-2=adult")),
+				("VAULT_DASH_FLOOD_LINES", String::from("5000")),
+			]
+		);
+		assert_eq!(config.profile["testnet-7"].variables(), [("VAULT_DASH_PARSER", String::from("plain"))]);
+		assert!(config.find_profile("testnet-8").unwrap_err().to_string().contains("local, testnet-7"));
+		assert!(toml::from_str::<Config>("[profile.local]\nfiles = []\nlines = 5\n").is_err());
+	}
+}
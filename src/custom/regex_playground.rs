@@ -0,0 +1,197 @@
+///! Regex playground: test a pattern against the focused node's recent lines
+///!
+///! Opened with '/', typed text is compiled as a regex and matched against
+///! the lines in the focused node's logfile view as you type. Matching lines
+///! are listed newest first, with matches highlighted and the first match's
+///! capture groups beneath, to help in writing patterns for the config file.
+///! Up/down scroll the matches and escape returns to the node view.
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+use super::app::{DashState, LogMonitor};
+use super::keymap::InputKey;
+
+use tui::{
+	backend::Backend,
+	layout::{Constraint, Direction, Layout},
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, List, ListItem, Paragraph},
+	Frame,
+};
+
+///! Limit on the compiled size of a pattern, to keep typing responsive
+const PATTERN_SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Default)]
+pub struct RegexPlayground {
+	pub pattern: String,
+	///! The compiled pattern, or why it failed to compile
+	regex: Option<Result<Regex, String>>,
+	///! Matching lines skipped, scrolling back from the newest
+	pub scroll: usize,
+}
+
+///! A line matching the pattern
+pub struct LineMatch<'a> {
+	pub line: &'a str,
+	///! Byte ranges of every match in line
+	pub matches: Vec<Range<usize>>,
+	///! Name (or number) and value of each capture group in the first match
+	pub captures: Vec<(String, Option<&'a str>)>,
+}
+
+impl RegexPlayground {
+	pub fn new() -> RegexPlayground {
+		RegexPlayground::default()
+	}
+
+	///! Edit the pattern or scroll the matches
+	pub fn handle(&mut self, key: InputKey) {
+		match key {
+			InputKey::Char(c) => self.pattern.push(c),
+			InputKey::Backspace => {
+				self.pattern.pop();
+			}
+			InputKey::Up => self.scroll += 1,
+			InputKey::Down => self.scroll = self.scroll.saturating_sub(1),
+			_ => return,
+		}
+		if let InputKey::Char(_) | InputKey::Backspace = key {
+			self.scroll = 0;
+			self.regex = match self.pattern.is_empty() {
+				true => None,
+				false => Some(
+					RegexBuilder::new(&self.pattern)
+						.size_limit(PATTERN_SIZE_LIMIT)
+						.build()
+						.map_err(|e| e.to_string()),
+				),
+			};
+		}
+	}
+
+	///! Why the pattern doesn't compile, if it doesn't
+	pub fn error(&self) -> Option<&str> {
+		match &self.regex {
+			Some(Err(e)) => Some(e),
+			_ => None,
+		}
+	}
+
+	///! Lines matching the pattern, newest first
+	pub fn matches<'a>(&self, lines: &'a [String]) -> Vec<LineMatch<'a>> {
+		let regex = match &self.regex {
+			Some(Ok(regex)) => regex,
+			_ => return Vec::new(),
+		};
+		let names: Vec<String> = regex
+			.capture_names()
+			.enumerate()
+			.skip(1)
+			.map(|(i, name)| name.map_or(i.to_string(), String::from))
+			.collect();
+
+		lines
+			.iter()
+			.rev()
+			.filter_map(|line| {
+				let captures = regex.captures(line)?;
+				Some(LineMatch {
+					line,
+					matches: regex.find_iter(line).map(|m| m.range()).collect(),
+					captures: names
+						.iter()
+						.enumerate()
+						.map(|(i, name)| (name.clone(), captures.get(i + 1).map(|m| m.as_str())))
+						.collect(),
+				})
+			})
+			.collect()
+	}
+}
+
+pub fn draw_regex_dash<B: Backend>(f: &mut Frame<B>, dash_state: &DashState, monitor: Option<&LogMonitor>) {
+	let playground = &dash_state.regex_playground;
+	let rows = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(4), Constraint::Min(0)].as_ref())
+		.split(f.size());
+
+	let empty = Vec::<String>::new();
+	let (logfile, lines) = match monitor {
+		Some(monitor) => (monitor.logfile.as_str(), &monitor.content.items),
+		None => ("no node", &empty),
+	};
+	let matches = playground.matches(lines);
+
+	let status = match playground.error() {
+		Some(error) => Span::styled(error.lines().last().unwrap_or(error).to_string(), Style::default().fg(Color::Red)),
+		None if playground.pattern.is_empty() => Span::raw("Type a regex to match against this node's recent lines"),
+		None => Span::raw(format!("{} of {} recent lines match", matches.len(), lines.len())),
+	};
+	let input = Paragraph::new(vec![
+		Spans::from(vec![
+			Span::styled("/", Style::default().fg(Color::Yellow)),
+			Span::raw(playground.pattern.as_str()),
+			Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+		]),
+		Spans::from(status),
+	])
+	.block(Block::default().borders(Borders::ALL).title(format!("Regex Playground ({})", logfile)));
+	f.render_widget(input, rows[0]);
+
+	let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+	let capture_style = Style::default().fg(Color::Cyan);
+	let mut items = Vec::<ListItem>::new();
+	for line_match in matches.iter().skip(playground.scroll) {
+		let mut spans = Vec::<Span>::new();
+		let mut end = 0;
+		for range in line_match.matches.iter() {
+			spans.push(Span::raw(&line_match.line[end..range.start]));
+			spans.push(Span::styled(&line_match.line[range.clone()], match_style));
+			end = range.end;
+		}
+		spans.push(Span::raw(&line_match.line[end..]));
+		let mut text = vec![Spans::from(spans)];
+		if !line_match.captures.is_empty() {
+			let captures: Vec<String> = line_match
+				.captures
+				.iter()
+				.map(|(name, value)| format!("{}: {}", name, value.map_or(String::from("(none)"), |v| format!("{:?}", v))))
+				.collect();
+			text.push(Spans::from(Span::styled(format!("    {}", captures.join("  ")), capture_style)));
+		}
+		items.push(ListItem::new(text));
+	}
+
+	let title = match playground.scroll {
+		0 => String::from("Matches, newest first"),
+		scroll => format!("Matches, newest first ({} newer above)", scroll),
+	};
+	let matches_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(matches_widget, rows[1]);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn matches_with_captures_newest_first() {
+		let lines: Vec<String> = ["Our Age: 5", "No. of Elders: 7", "Our Age: 6"].iter().map(|s| s.to_string()).collect();
+		let mut playground = RegexPlayground::new();
+		for c in r"Age: (?P<age>\d)(x)?".chars() {
+			playground.handle(InputKey::Char(c));
+		}
+		let matches = playground.matches(&lines);
+		assert_eq!(matches.len(), 2);
+		assert_eq!(matches[0].line, "Our Age: 6");
+		assert_eq!(matches[0].matches, vec![4..10]);
+		assert_eq!(matches[0].captures, vec![(String::from("age"), Some("6")), (String::from("2"), None)]);
+
+		playground.handle(InputKey::Char('('));
+		assert!(playground.error().is_some());
+		assert!(playground.matches(&lines).is_empty());
+	}
+}
@@ -0,0 +1,312 @@
+///! Search: find text in the recent lines of every node ('?')
+///!
+///! Type text and press enter to list the lines containing it, grouped by node
+///! and newest first, with the text highlighted. Case is ignored. A 64 digit
+///! hex XorName is rarely logged in full, as nodes abbreviate names to their
+///! first few digits followed by '..' (such as '552f45..'), so searching for a
+///! name also finds its abbreviations, and searching for an abbreviation also
+///! finds the names it could stand for. Six or more hex digits without the
+///! '..' may be text such as a hash, so they find both the text and the names
+///! they could begin. Up/down scroll the results and escape returns to the
+///! node view.
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+use super::app::{LogMonitor, DEBUG_WINDOW_NAME};
+use super::keymap::InputKey;
+
+use tui::{
+	backend::Backend,
+	layout::{Constraint, Direction, Layout},
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, List, ListItem, Paragraph},
+	Frame,
+};
+
+///! Hex digits of an XorName
+const XOR_NAME_DIGITS: usize = 64;
+///! Fewest hex digits taken as an abbreviated XorName
+const XOR_NAME_DIGITS_MIN: usize = 6;
+///! Lines listed for each node, newest first, though all are counted
+const RESULTS_PER_NODE_MAX: usize = 200;
+
+lazy_static::lazy_static! {
+	static ref HEX_RUN: Regex = Regex::new(r"\b[0-9a-fA-F]{6,}\b(\.\.)?").expect("The regex failed to compile. This is a bug.");
+}
+
+///! What is being searched for
+#[derive(Debug)]
+pub enum Query {
+	///! Text, ignoring case
+	Text(Regex),
+	///! An XorName, or the first digits of one if abbreviated
+	XorName { digits: String, abbreviated: bool },
+	///! Hex digits which could be text or the first digits of an XorName, finding either
+	Hex { text: Regex, digits: String },
+}
+
+impl Query {
+	pub fn parse(text: &str) -> Option<Query> {
+		let text = text.trim();
+		if text.is_empty() {
+			return None;
+		}
+		let digits = text.strip_suffix("..").unwrap_or(text);
+		let is_hex = digits.len() >= XOR_NAME_DIGITS_MIN
+			&& digits.len() <= XOR_NAME_DIGITS
+			&& digits.chars().all(|c| c.is_ascii_hexdigit());
+		let abbreviated = digits.len() < XOR_NAME_DIGITS;
+		if is_hex && (!abbreviated || digits.len() < text.len()) {
+			return Some(Query::XorName {
+				digits: digits.to_lowercase(),
+				abbreviated,
+			});
+		}
+		let text_regex = RegexBuilder::new(&regex::escape(text)).case_insensitive(true).build().ok()?;
+		match is_hex {
+			true => Some(Query::Hex {
+				text: text_regex,
+				digits: digits.to_lowercase(),
+			}),
+			false => Some(Query::Text(text_regex)),
+		}
+	}
+
+	///! Byte ranges of each mention in line
+	pub fn find(&self, line: &str) -> Vec<Range<usize>> {
+		match self {
+			Query::Text(regex) => regex.find_iter(line).map(|m| m.range()).collect(),
+			Query::XorName { digits, abbreviated } => find_xor_names(digits, *abbreviated, line),
+			Query::Hex { text, digits } => {
+				let mut ranges: Vec<Range<usize>> = text.find_iter(line).map(|m| m.range()).collect();
+				ranges.extend(find_xor_names(digits, true, line));
+				merge_ranges(ranges)
+			}
+		}
+	}
+}
+
+///! Byte ranges of the names in line which could be the same as digits
+fn find_xor_names(digits: &str, abbreviated: bool, line: &str) -> Vec<Range<usize>> {
+	HEX_RUN
+		.find_iter(line)
+		.filter(|m| {
+			let text = m.as_str();
+			let candidate = text.strip_suffix("..").unwrap_or(text).to_lowercase();
+			// An abbreviated candidate is '..' after the digits, a full one is 64 digits
+			let candidate_abbreviated = text.ends_with("..") && candidate.len() < XOR_NAME_DIGITS;
+			same_name(digits, abbreviated, &candidate, candidate_abbreviated)
+		})
+		.map(|m| m.range())
+		.collect()
+}
+
+///! Ranges in order, with any which overlap joined, to be highlighted in turn
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+	ranges.sort_by_key(|range| range.start);
+	let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+	for range in ranges {
+		match merged.last_mut() {
+			Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+			_ => merged.push(range),
+		}
+	}
+	merged
+}
+
+///! Whether two names, either of which may be the first digits of a name, could be the same
+fn same_name(a: &str, a_abbreviated: bool, b: &str, b_abbreviated: bool) -> bool {
+	let ((shorter, shorter_abbreviated), longer) = match a.len() <= b.len() {
+		true => ((a, a_abbreviated), b),
+		false => ((b, b_abbreviated), a),
+	};
+	match shorter.len() == longer.len() {
+		true => shorter == longer,
+		false => shorter_abbreviated && longer.starts_with(shorter),
+	}
+}
+
+pub struct SearchMatch {
+	pub line: String,
+	pub matches: Vec<Range<usize>>,
+}
+
+///! The lines of one node which matched
+pub struct NodeResults {
+	pub node: String,
+	///! Newest first, up to RESULTS_PER_NODE_MAX
+	pub lines: Vec<SearchMatch>,
+	///! All lines which matched
+	pub count: usize,
+}
+
+#[derive(Default)]
+pub struct Search {
+	pub input: String,
+	///! What the results are for, once a search has been run
+	pub searched: Option<String>,
+	pub results: Vec<NodeResults>,
+	///! Result rows skipped, scrolling down from the top
+	pub scroll: usize,
+}
+
+impl Search {
+	pub fn new() -> Search {
+		Search::default()
+	}
+
+	///! Edit the text or scroll the results, returning true when the search should be run
+	pub fn handle(&mut self, key: InputKey) -> bool {
+		match key {
+			InputKey::Char(c) => self.input.push(c),
+			InputKey::Backspace => {
+				self.input.pop();
+			}
+			InputKey::Up => self.scroll = self.scroll.saturating_sub(1),
+			InputKey::Down => self.scroll += 1,
+			InputKey::Enter => return true,
+			_ => {}
+		}
+		false
+	}
+
+	///! Search the lines of each monitor, in the order given, and then those of any debug window
+	pub fn run(&mut self, monitors: &[&LogMonitor], debug_window: Option<&[String]>) {
+		self.scroll = 0;
+		self.results.clear();
+		self.searched = Some(self.input.clone());
+		let query = match Query::parse(&self.input) {
+			Some(query) => query,
+			None => return,
+		};
+		for monitor in monitors.iter() {
+			self.search_lines(&query, node_heading(monitor), &monitor.content.items);
+		}
+		if let Some(lines) = debug_window {
+			self.search_lines(&query, String::from(DEBUG_WINDOW_NAME), lines);
+		}
+	}
+
+	fn search_lines(&mut self, query: &Query, node: String, lines: &[String]) {
+		let mut results = NodeResults {
+			node,
+			lines: Vec::new(),
+			count: 0,
+		};
+		for line in lines.iter().rev() {
+			let matches = query.find(line);
+			if matches.is_empty() {
+				continue;
+			}
+			results.count += 1;
+			if results.lines.len() < RESULTS_PER_NODE_MAX {
+				results.lines.push(SearchMatch {
+					line: line.clone(),
+					matches,
+				});
+			}
+		}
+		if results.count > 0 {
+			self.results.push(results);
+		}
+	}
+}
+
+fn node_heading(monitor: &LogMonitor) -> String {
+	match &monitor.label {
+		Some(label) => format!("Node {:>2} {} ({})", monitor.index + 1, label, monitor.logfile),
+		None => format!("Node {:>2} ({})", monitor.index + 1, monitor.logfile),
+	}
+}
+
+pub fn draw_search_dash<B: Backend>(f: &mut Frame<B>, search: &Search) {
+	let rows = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(4), Constraint::Min(0)].as_ref())
+		.split(f.size());
+
+	let lines: usize = search.results.iter().map(|results| results.count).sum();
+	let status = match &search.searched {
+		None => String::from("Type text or an XorName and press enter to search every node's recent lines"),
+		Some(searched) => match Query::parse(searched) {
+			Some(Query::XorName { .. }) => format!("{} lines on {} nodes mention the XorName", lines, search.results.len()),
+			_ => format!("{} lines on {} nodes match", lines, search.results.len()),
+		},
+	};
+	let input = Paragraph::new(vec![
+		Spans::from(vec![
+			Span::styled("?", Style::default().fg(Color::Yellow)),
+			Span::raw(search.input.as_str()),
+			Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+		]),
+		Spans::from(status),
+	])
+	.block(Block::default().borders(Borders::ALL).title("Search All Nodes"));
+	f.render_widget(input, rows[0]);
+
+	let heading_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+	let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+	let mut items = Vec::<ListItem>::new();
+	for results in search.results.iter() {
+		let shown = match results.count > results.lines.len() {
+			true => format!(", newest {} shown", results.lines.len()),
+			false => String::new(),
+		};
+		let lines = if results.count == 1 { "line" } else { "lines" };
+		items.push(ListItem::new(Spans::from(Span::styled(
+			format!("{}: {} {}{}", results.node, results.count, lines, shown),
+			heading_style,
+		))));
+		for search_match in results.lines.iter() {
+			let line = &search_match.line;
+			let mut spans = vec![Span::raw("  ")];
+			let mut end = 0;
+			for range in search_match.matches.iter() {
+				spans.push(Span::raw(&line[end..range.start]));
+				spans.push(Span::styled(&line[range.clone()], match_style));
+				end = range.end;
+			}
+			spans.push(Span::raw(&line[end..]));
+			items.push(ListItem::new(Spans::from(spans)));
+		}
+	}
+	let scroll = search.scroll.min(items.len().saturating_sub(1));
+	let title = match scroll {
+		0 => String::from("Results by node, newest first"),
+		scroll => format!("Results by node, newest first ({} rows above)", scroll),
+	};
+	let results_widget = List::new(items.split_off(scroll)).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(results_widget, rows[1]);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn finds_xor_names_and_their_abbreviations() {
+		let name = "552f45b7e0f1a5d0fbd4b7de8a5f36d5b2c1c43bdd3a62a1d0c7e5f3e8b6a901";
+		let line = format!("Sent to 552f45.. and 552f46.., stored {} at 552F45B7..", name);
+		let full = Query::parse(name).unwrap();
+		let found: Vec<&str> = full.find(&line).into_iter().map(|range| &line[range]).collect();
+		assert_eq!(found, ["552f45..", name, "552F45B7.."]);
+
+		// An abbreviation finds the full name and abbreviations it agrees with
+		let abbreviated = Query::parse("552f45b7..").unwrap();
+		assert_eq!(abbreviated.find(&line).len(), 3);
+		// Hex digits without '..' find the names they could begin, and the text
+		let hex = Query::parse("552f46").unwrap();
+		assert_eq!(hex.find(&line), vec![21..29]);
+		let hex = Query::parse("a5d0fb").unwrap();
+		assert!(matches!(hex, Query::Hex { .. }));
+		let found: Vec<&str> = hex.find(&line).into_iter().map(|range| &line[range]).collect();
+		assert_eq!(found, ["a5d0fb"]);
+		// 64 digits which aren't a match aren't mistaken for an abbreviation
+		assert!(Query::parse(&name.replace("a901", "a902")).unwrap().find(&line).len() == 2);
+
+		let text = Query::parse("Stored").unwrap();
+		assert_eq!(text.find(&line), vec![31..37]);
+		assert!(Query::parse("  ").is_none());
+	}
+}
@@ -0,0 +1,150 @@
+///! vdash's own log, written to a rotating file with --self-log
+///!
+///! env_logger writes to stderr, which corrupts the dashboard, so with
+///! --self-log its output goes to a file instead. RUST_LOG filters it as
+///! usual, defaulting to 'info' as a self log is for seeing what vdash is up
+///! to. Lines are written in the format sn_node logs in, so that the file can
+///! be opened as a monitor ('L') and read like any node's logfile. When the
+///! file reaches SELF_LOG_BYTES_MAX it is moved to '<path>.1', replacing any
+///! older one, and a new file is started.
+use chrono::{SecondsFormat, Utc};
+use log::{Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+///! Size at which the self log is rotated
+pub const SELF_LOG_BYTES_MAX: u64 = 10 * 1024 * 1024;
+
+///! Send log output to path if given, otherwise to stderr as env_logger does by default
+pub fn init(path: Option<&Path>) -> Result<(), Error> {
+	let path = match path {
+		Some(path) => path,
+		None => {
+			env_logger::init();
+			return Ok(());
+		}
+	};
+	let filter = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).build();
+	let max_level = filter.filter();
+	let logger = SelfLog {
+		filter,
+		file: Mutex::new(RotatingFile::open(path, SELF_LOG_BYTES_MAX)?),
+	};
+	log::set_boxed_logger(Box::new(logger)).map_err(|e| Error::new(ErrorKind::Other, format!("--self-log: {}", e)))?;
+	log::set_max_level(max_level);
+	Ok(())
+}
+
+struct SelfLog {
+	///! Only used for its RUST_LOG filter
+	filter: env_logger::Logger,
+	file: Mutex<RotatingFile>,
+}
+
+impl Log for SelfLog {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		self.filter.enabled(metadata)
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.filter.matches(record) {
+			return;
+		}
+		// Ignoring errors, as there is nowhere left to report them
+		if let Ok(mut file) = self.file.lock() {
+			let _ = file.write_line(&format_line(record));
+		}
+	}
+
+	fn flush(&self) {
+		if let Ok(mut file) = self.file.lock() {
+			let _ = file.file.flush();
+		}
+	}
+}
+
+///! A record as sn_node would log it, such as
+///! '[vdash] INFO 2021-01-20T18:00:00.000000000+00:00 [src/custom/app.rs:120] Started'
+fn format_line(record: &Record) -> String {
+	format!(
+		"[vdash] {} {} [{}:{}] {}",
+		record.level(),
+		Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, false),
+		record.file().unwrap_or_else(|| record.target()),
+		record.line().unwrap_or(0),
+		record.args()
+	)
+}
+
+///! A file appended to until it reaches bytes_max, then moved aside to '<path>.1'
+pub struct RotatingFile {
+	path: PathBuf,
+	file: File,
+	written: u64,
+	bytes_max: u64,
+}
+
+impl RotatingFile {
+	pub fn open(path: &Path, bytes_max: u64) -> Result<RotatingFile, Error> {
+		let file = OpenOptions::new().create(true).append(true).open(path)?;
+		let written = file.metadata()?.len();
+		Ok(RotatingFile {
+			path: path.to_path_buf(),
+			file,
+			written,
+			bytes_max,
+		})
+	}
+
+	pub fn write_line(&mut self, line: &str) -> Result<(), Error> {
+		if self.written > 0 && self.written + line.len() as u64 >= self.bytes_max {
+			self.rotate()?;
+		}
+		writeln!(self.file, "{}", line)?;
+		self.written += line.len() as u64 + 1;
+		Ok(())
+	}
+
+	fn rotate(&mut self) -> Result<(), Error> {
+		let mut rotated = self.path.clone().into_os_string();
+		rotated.push(".1");
+		std::fs::rename(&self.path, rotated)?;
+		*self = RotatingFile::open(&self.path, self.bytes_max)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use vault_metrics::{AssumedOffset, LogEntry};
+
+	#[test]
+	fn rotates_self_log() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("vdash.log");
+		let mut file = RotatingFile::open(&path, 100).unwrap();
+		for n in 0..5 {
+			file.write_line(&format!("line {} of the self log", n)).unwrap();
+		}
+		let rotated = std::fs::read_to_string(dir.path().join("vdash.log.1")).unwrap();
+		let current = std::fs::read_to_string(&path).unwrap();
+		assert_eq!(rotated.lines().count(), 4);
+		assert_eq!(current, "line 4 of the self log\n");
+
+		let line = format_line(
+			&Record::builder()
+				.args(format_args!("Started"))
+				.level(log::Level::Info)
+				.file(Some("src/custom/app.rs"))
+				.line(Some(120))
+				.build(),
+		);
+		let entry = LogEntry::decode(&line, &AssumedOffset::Fixed(0), None).unwrap();
+		assert_eq!(entry.category, "INFO");
+		assert_eq!(entry.message, "Started");
+		assert!(entry.time.is_some());
+	}
+}
@@ -0,0 +1,170 @@
+///! Metrics of vdash itself, to tell whether vdash or a node is the bottleneck
+///!
+///! Shown in the debug view ('D') beside the sizes of vdash's structures: the
+///! lines read each second from every source, how many of them the parser
+///! failed to decode, the lines waiting in the ingest channel for the main loop
+///! (see ingest), resident memory, and how long frames take to draw. A large
+///! backlog, or slow frames, mean vdash is falling behind its nodes rather than
+///! the nodes being slow.
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, List, ListItem},
+	Frame,
+};
+
+///! Frames whose times are kept, for the mean and slowest
+const FRAMES_MAX: usize = 100;
+
+///! Rows drawn, including the border
+pub const SELF_METRICS_ROWS: u16 = 8;
+
+///! Backlog and mean frame time beyond which vdash is said to be falling behind
+const BACKLOG_BEHIND: usize = 1000;
+const FRAME_BEHIND: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+pub struct SelfMetrics {
+	///! When the rates were last worked out, and the totals then
+	sampled: Option<Instant>,
+	received: usize,
+	undecoded: u64,
+
+	pub lines_per_second: f64,
+	pub undecoded_per_second: f64,
+	///! Percent of lines parsed which didn't decode, since vdash started
+	pub undecoded_percent: f64,
+	pub backlog: usize,
+	///! The greatest backlog in the second before the rates were last worked out
+	pub backlog_peak: usize,
+	backlog_max: usize,
+	frame_times: VecDeque<Duration>,
+}
+
+impl SelfMetrics {
+	///! Note the totals from the ingest channel and the parser, working out rates each second
+	///!
+	///! received is lines received from the channel, and backlog those waiting
+	///! in it, while gathered is lines given to the parser of which undecoded
+	///! failed to decode.
+	pub fn sample(&mut self, now: Instant, received: usize, backlog: usize, gathered: u64, undecoded: u64) {
+		self.backlog = backlog;
+		self.backlog_max = self.backlog_max.max(backlog);
+		self.undecoded_percent = match gathered {
+			0 => 0.0,
+			_ => undecoded as f64 * 100.0 / gathered as f64,
+		};
+		let sampled = match self.sampled {
+			Some(sampled) => sampled,
+			None => {
+				self.sampled = Some(now);
+				self.received = received;
+				self.undecoded = undecoded;
+				return;
+			}
+		};
+		let elapsed = now.duration_since(sampled).as_secs_f64();
+		if elapsed < 1.0 {
+			return;
+		}
+		// Totals fall as monitors close
+		self.lines_per_second = received.saturating_sub(self.received) as f64 / elapsed;
+		self.undecoded_per_second = undecoded.saturating_sub(self.undecoded) as f64 / elapsed;
+		self.backlog_peak = self.backlog_max;
+		self.backlog_max = backlog;
+		self.sampled = Some(now);
+		self.received = received;
+		self.undecoded = undecoded;
+	}
+
+	pub fn record_frame(&mut self, time: Duration) {
+		self.frame_times.push_back(time);
+		while self.frame_times.len() > FRAMES_MAX {
+			self.frame_times.pop_front();
+		}
+	}
+
+	///! The mean and slowest of recent frame times
+	pub fn frame_time(&self) -> Option<(Duration, Duration)> {
+		let slowest = *self.frame_times.iter().max()?;
+		let mean = self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32;
+		Some((mean, slowest))
+	}
+}
+
+///! From /proc on Linux
+pub fn resident_memory_kb() -> Option<u64> {
+	let status = std::fs::read_to_string("/proc/self/status").ok()?;
+	let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+	line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn push_metric(items: &mut Vec<ListItem>, metric: &str, value: String) {
+	items.push(ListItem::new(Spans::from(vec![
+		Span::styled(format!("{:<16}", metric), Style::default().fg(Color::Blue)),
+		Span::raw(value),
+	])));
+}
+
+pub fn draw_self_metrics<B: Backend>(f: &mut Frame<B>, area: Rect, metrics: &SelfMetrics) {
+	let mut items = Vec::<ListItem>::new();
+	push_metric(&mut items, "Lines/s", format!("{:.0}", metrics.lines_per_second));
+	push_metric(
+		&mut items,
+		"Undecoded",
+		format!("{:.1}/s, {:.1}% of lines", metrics.undecoded_per_second, metrics.undecoded_percent),
+	);
+	push_metric(&mut items, "Backlog", format!("{} lines (peak {})", metrics.backlog, metrics.backlog_peak));
+	let memory = match resident_memory_kb() {
+		Some(kb) => format!("{} kB", kb),
+		None => String::from("unknown"),
+	};
+	push_metric(&mut items, "Resident memory", memory);
+	let frames = match metrics.frame_time() {
+		Some((mean, slowest)) => format!("{:.1}ms mean, {:.1}ms slowest", millis(mean), millis(slowest)),
+		None => String::from("-"),
+	};
+	push_metric(&mut items, "Frame time", frames);
+	let behind = metrics.backlog > BACKLOG_BEHIND || matches!(metrics.frame_time(), Some((mean, _)) if mean > FRAME_BEHIND);
+	if behind {
+		items.push(ListItem::new(Spans::from(Span::styled(
+			"vdash is falling behind its nodes",
+			Style::default().fg(Color::Red),
+		))));
+	}
+
+	let widget = List::new(items).block(Block::default().borders(Borders::ALL).title("vdash (ingest and drawing)"));
+	f.render_widget(widget, area);
+}
+
+fn millis(time: Duration) -> f64 {
+	time.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rates_from_totals_each_second() {
+		let start = Instant::now();
+		let mut metrics = SelfMetrics::default();
+		metrics.sample(start, 100, 0, 100, 0);
+		metrics.sample(start + Duration::from_millis(500), 400, 30, 400, 4);
+		assert_eq!(metrics.lines_per_second, 0.0);
+		assert_eq!(metrics.undecoded_percent, 1.0);
+		metrics.sample(start + Duration::from_secs(2), 700, 10, 700, 14);
+		assert_eq!((metrics.lines_per_second, metrics.undecoded_per_second), (300.0, 7.0));
+		assert_eq!((metrics.backlog, metrics.backlog_peak), (10, 30));
+
+		assert_eq!(metrics.frame_time(), None);
+		metrics.record_frame(Duration::from_millis(2));
+		metrics.record_frame(Duration::from_millis(6));
+		assert_eq!(metrics.frame_time(), Some((Duration::from_millis(4), Duration::from_millis(6))));
+	}
+}
@@ -0,0 +1,164 @@
+///! Session state which persists from one run of vdash to the next
+///!
+///! Saved as TOML in vdash/session.toml, beside the default config file,
+///! whenever it changes. This holds the lines pinned in each node's logfile
+///! view, by logfile path, including those of logfiles not monitored in this
+///! run, the alerts raised and whether they have been acknowledged, and the
+///! sizes of the node view's splits. Unlike the config file, it is written by
+///! vdash rather than the user.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+use super::alerts::Alerts;
+use super::config;
+use super::splits::Splits;
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Session {
+	///! Pinned lines of each logfile, by canonical path
+	pins: HashMap<String, Vec<String>>,
+	///! Recent alerts, see alerts.rs
+	alerts: Alerts,
+	///! The node view's splits, once resized, see splits.rs
+	splits: Splits,
+	#[serde(skip)]
+	path: Option<PathBuf>,
+}
+
+impl Session {
+	///! Load the session file, starting a new session if there isn't one
+	///!
+	///! A session which can't be read is replaced, since it only holds conveniences.
+	pub fn load() -> Session {
+		match config::config_dir() {
+			Some(dir) => Session::load_from(&dir.join("session.toml")),
+			None => Session::default(),
+		}
+	}
+
+	fn load_from(path: &Path) -> Session {
+		let mut session = match std::fs::read_to_string(path) {
+			Ok(text) => toml::from_str(&text).unwrap_or_else(|e| {
+				warn!("Ignoring session file {}: {}", path.display(), e);
+				Session::default()
+			}),
+			Err(_) => Session::default(),
+		};
+		session.path = Some(path.to_path_buf());
+		session
+	}
+
+	///! Write the session file, replacing it whole so that a crash can't leave it incomplete
+	pub fn save(&self) -> Result<(), Error> {
+		let path = match &self.path {
+			Some(path) => path,
+			None => return Ok(()),
+		};
+		let dir = path.parent().unwrap_or_else(|| Path::new("."));
+		std::fs::create_dir_all(dir)?;
+		let text = toml::to_string(self).map_err(|e| Error::new(ErrorKind::Other, format!("session: {}", e)))?;
+		let mut file = NamedTempFile::new_in(dir)?;
+		file.write_all(text.as_bytes())?;
+		file.persist(path).map_err(|e| e.error)?;
+		Ok(())
+	}
+
+	pub fn pins(&self, logfile: &str) -> Vec<String> {
+		self.pins.get(&logfile_key(logfile)).cloned().unwrap_or_default()
+	}
+
+	pub fn set_pins(&mut self, logfile: &str, pins: &[String]) {
+		let key = logfile_key(logfile);
+		if pins.is_empty() {
+			self.pins.remove(&key);
+		} else {
+			self.pins.insert(key, pins.to_vec());
+		}
+	}
+
+	pub fn alerts(&self) -> &Alerts {
+		&self.alerts
+	}
+
+	pub fn alerts_mut(&mut self) -> &mut Alerts {
+		&mut self.alerts
+	}
+
+	pub fn splits(&self) -> &Splits {
+		&self.splits
+	}
+
+	pub fn set_splits(&mut self, splits: &Splits) {
+		self.splits = splits.clone();
+	}
+}
+
+///! The same logfile may be given by different relative paths
+fn logfile_key(logfile: &str) -> String {
+	match std::fs::canonicalize(logfile) {
+		Ok(path) => path.to_string_lossy().to_string(),
+		Err(_) => logfile.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::hooks::{NodeEvent, EVENT_ERROR};
+	use chrono::Utc;
+
+	#[test]
+	fn saves_and_loads_pins() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("vdash").join("session.toml");
+		let mut session = Session::load_from(&path);
+		session.set_pins("node.log", &["[sn_node] INFO Running sn_node \"0.25\"".to_string()]);
+		session.set_pins("other.log", &[]);
+		session.save().unwrap();
+
+		let session = Session::load_from(&path);
+		assert_eq!(session.pins("node.log"), vec!["[sn_node] INFO Running sn_node \"0.25\""]);
+		assert!(session.pins("other.log").is_empty());
+	}
+
+	#[test]
+	fn saves_and_loads_alerts() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("session.toml");
+		let mut session = Session::load_from(&path);
+		let event = NodeEvent {
+			event: EVENT_ERROR,
+			detail: String::from("Dead peer"),
+		};
+		session.alerts_mut().raise(1, "node1.log", &event, Utc::now());
+		session.alerts_mut().raise(2, "node2.log", &event, Utc::now());
+		session.alerts_mut().acknowledge(0);
+		session.save().unwrap();
+
+		let session = Session::load_from(&path);
+		assert_eq!(session.alerts().len(), 2);
+		assert_eq!(session.alerts().pending(), 1);
+		assert_eq!(session.alerts().latest_pending().map(|alert| alert.node), Some(1));
+	}
+
+	#[test]
+	fn saves_and_loads_splits() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("session.toml");
+		let mut session = Session::load_from(&path);
+		let mut splits = Splits::default();
+		splits.select_next();
+		splits.select_next();
+		splits.move_selected(5);
+		session.set_splits(&splits);
+		session.save().unwrap();
+
+		let session = Session::load_from(&path);
+		assert_eq!(session.splits().side_constraints(), splits.side_constraints());
+		assert_eq!(session.splits().node_constraints(), Splits::default().node_constraints());
+	}
+}
@@ -0,0 +1,73 @@
+///! Orderly shutdown on 'q', SIGTERM or SIGINT
+///!
+///! Rather than dying mid-write, vdash notices a signal on its next tick and
+///! shuts down as it does for 'q': App::shutdown() writes the final --journal
+///! checkpoint and the session, and only then is the terminal restored (or,
+///! with --daemon, attached dashboards are disconnected and the socket removed).
+///! If that takes longer than SHUTDOWN_TIMEOUT, for example writing to a hung
+///! network filesystem, vdash exits anyway.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Shutdown {
+	requested: Arc<AtomicBool>,
+}
+
+impl Shutdown {
+	///! Catch SIGTERM and SIGINT, so that they request a shutdown
+	pub fn new() -> Shutdown {
+		let requested = Arc::new(AtomicBool::new(false));
+		for signal in [signal_hook::SIGTERM, signal_hook::SIGINT].iter() {
+			if let Err(e) = signal_hook::flag::register(*signal, Arc::clone(&requested)) {
+				warn!("failed to catch signal {}: {}", signal, e);
+			}
+		}
+		Shutdown { requested }
+	}
+
+	///! Whether a signal has asked vdash to stop
+	pub fn requested(&self) -> bool {
+		self.requested.load(Ordering::Relaxed)
+	}
+
+	///! Exit after SHUTDOWN_TIMEOUT unless the process has ended by then
+	pub fn start_timeout(&self) {
+		thread::spawn(|| {
+			thread::sleep(SHUTDOWN_TIMEOUT);
+			eprintln!("vdash: shutdown took over {}s, exiting", SHUTDOWN_TIMEOUT.as_secs());
+			std::process::exit(1);
+		});
+	}
+}
+
+impl Default for Shutdown {
+	fn default() -> Shutdown {
+		Shutdown::new()
+	}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn signal_requests_shutdown() {
+		let shutdown = Shutdown::new();
+		assert!(!shutdown.requested());
+		let pid = std::process::id().to_string();
+		let status = std::process::Command::new("kill").args(["-TERM", &pid]).status().unwrap();
+		assert!(status.success());
+		// Delivery is asynchronous
+		for _ in 0..100 {
+			if shutdown.requested() {
+				break;
+			}
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert!(shutdown.requested());
+	}
+}
@@ -0,0 +1,285 @@
+///! Read-only web snapshot of the dashboard summary (--snapshot-http)
+///!
+///! Serves the summary as at the latest update, as HTML at '/' and as
+///! JSON at '/summary.json'. There are no live updates, so the page suits
+///! embedding in a status page or checking with curl.
+///!
+///! Each connection is answered on a short-lived thread of its own, up to
+///! CONNECTIONS_MAX at once, with its request head bounded and its reads and
+///! writes timed out so that a slow or hostile client can't hold the others up.
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{
+	atomic::{AtomicUsize, Ordering},
+	Arc, Mutex,
+};
+use std::thread;
+use std::time::Duration;
+
+use vault_metrics::RecentError;
+
+use super::app::App;
+
+#[derive(Serialize)]
+pub struct NodeSummary {
+	pub node: usize,
+	pub logfile: String,
+	pub status: String,
+	///! Percent of this run of vdash the node has been up, see Availability
+	pub availability: Option<f64>,
+	pub section: String,
+	pub role: String,
+	pub age: usize,
+	pub gets: u64,
+	pub puts: u64,
+	pub errors: u64,
+	///! Failed network sends and connections, also counted in errors if logged as ERROR
+	pub network_errors: u64,
+	pub last_error: Option<RecentError>,
+	pub outliers: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Summary {
+	pub updated: Option<DateTime<Utc>>,
+	pub nodes: Vec<NodeSummary>,
+}
+
+impl Summary {
+	pub fn new(app: &App) -> Summary {
+		let mut nodes = Vec::<NodeSummary>::new();
+		for logfile in app.logfile_names.iter() {
+			if let Some(monitor) = app.monitors.get(logfile) {
+				if monitor.is_debug_dashboard_log {
+					continue;
+				}
+				let metrics = &monitor.metrics;
+				nodes.push(NodeSummary {
+					node: monitor.index + 1,
+					logfile: monitor.logfile.clone(),
+					status: metrics.status().to_string(),
+					availability: monitor.availability.percent(),
+					section: metrics.section_prefix.clone(),
+					role: metrics.agebracket_string(),
+					age: metrics.node_age,
+					gets: metrics.activity_gets,
+					puts: metrics.activity_puts,
+					errors: metrics.activity_errors,
+					network_errors: metrics.network_errors.count,
+					last_error: metrics.last_error().cloned(),
+					outliers: metrics.outliers(),
+				});
+			}
+		}
+
+		Summary {
+			updated: Some(Utc::now()),
+			nodes,
+		}
+	}
+
+	fn to_html(&self) -> String {
+		let mut rows = String::new();
+		for node in self.nodes.iter() {
+			rows.push_str(&format!(
+				"<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+				node.node,
+				escape_html(&node.logfile),
+				escape_html(&node.status),
+				node.availability.map_or(String::from("-"), |percent| format!("{:.1}%", percent)),
+				escape_html(&node.role),
+				node.age,
+				node.gets,
+				node.puts,
+				node.errors,
+				node.last_error.as_ref().map_or(String::new(), |error| match error.time {
+					Some(time) => format!("{} {}", time, escape_html(&error.message)),
+					None => escape_html(&error.message),
+				}),
+				escape_html(&node.outliers.join("; ")),
+			));
+		}
+		let updated = self
+			.updated
+			.map_or(String::from("never"), |updated| format!("{}", updated));
+
+		format!(
+			"<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>vdash summary</title></head><body>\n\
+			<h1>vdash summary</h1>\n<p>Updated: {}</p>\n<table border=\"1\">\n\
+			<tr><th>Node</th><th>Logfile</th><th>Status</th><th>Availability</th><th>Role</th><th>Age</th><th>GETS</th><th>PUTS</th><th>ERRORS</th><th>Last Error</th><th>Outliers</th></tr>\n\
+			{}</table>\n</body></html>\n",
+			updated, rows
+		)
+	}
+}
+
+fn escape_html(text: &str) -> String {
+	text
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+pub type SharedSummary = Arc<Mutex<Summary>>;
+
+///! Connections answered at once, beyond which others are refused
+const CONNECTIONS_MAX: usize = 16;
+
+///! Most bytes read of a request line and its headers
+const REQUEST_HEAD_MAX: u64 = 8192;
+
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+///! Serve the summary at addr on a thread, returning the summary for the App to update
+pub fn serve(addr: &str, token: Option<String>) -> std::io::Result<SharedSummary> {
+	let listener = TcpListener::bind(addr)?;
+	let summary = Arc::new(Mutex::new(Summary {
+		updated: None,
+		nodes: Vec::new(),
+	}));
+
+	let served_summary = summary.clone();
+	let token = Arc::new(token);
+	thread::spawn(move || {
+		let open = Arc::new(AtomicUsize::new(0));
+		for stream in listener.incoming() {
+			match stream {
+				// Dropping the stream closes it
+				Ok(_) if open.load(Ordering::Relaxed) >= CONNECTIONS_MAX => {
+					warn!("snapshot-http refused a connection, as {} are open", CONNECTIONS_MAX)
+				}
+				Ok(stream) => {
+					open.fetch_add(1, Ordering::Relaxed);
+					let open = open.clone();
+					let summary = served_summary.clone();
+					let token = token.clone();
+					thread::spawn(move || {
+						if let Err(e) = respond(stream, &summary, &token) {
+							warn!("snapshot-http: {}", e);
+						}
+						open.fetch_sub(1, Ordering::Relaxed);
+					});
+				}
+				Err(e) => warn!("snapshot-http accept failed: {}", e),
+			}
+		}
+	});
+	Ok(summary)
+}
+
+fn respond(stream: TcpStream, summary: &SharedSummary, token: &Option<String>) -> std::io::Result<()> {
+	stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+	stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+	let mut reader = BufReader::new((&stream).take(REQUEST_HEAD_MAX));
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+
+	let mut authorization = None;
+	loop {
+		let mut header = String::new();
+		if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+			break;
+		}
+		let mut parts = header.splitn(2, ':');
+		if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+			if name.trim().eq_ignore_ascii_case("authorization") {
+				authorization = Some(value.trim().to_string());
+			}
+		}
+	}
+	if reader.get_ref().limit() == 0 {
+		return write_response(
+			&stream,
+			"431 Request Header Fields Too Large",
+			"text/plain",
+			"Request Header Fields Too Large\n",
+		);
+	}
+
+	let mut words = request_line.split_whitespace();
+	let method = words.next().unwrap_or("");
+	let target = words.next().unwrap_or("/");
+	let mut target_parts = target.splitn(2, '?');
+	let path = target_parts.next().unwrap_or("/");
+	let query = target_parts.next().unwrap_or("");
+
+	if let Some(token) = token {
+		let bearer = format!("Bearer {}", token);
+		let query_token = format!("token={}", token);
+		let authorised = authorization.as_ref() == Some(&bearer)
+			|| query.split('&').any(|param| param == query_token);
+		if !authorised {
+			return write_response(&stream, "401 Unauthorized", "text/plain", "Unauthorized\n");
+		}
+	}
+
+	if method != "GET" {
+		return write_response(&stream, "405 Method Not Allowed", "text/plain", "Method Not Allowed\n");
+	}
+
+	// Rendered before writing, so the lock isn't held while a slow client reads
+	let rendered = match summary.lock() {
+		Ok(summary) => match path {
+			"/" | "/index.html" => Some(("text/html; charset=utf-8", summary.to_html())),
+			"/summary.json" => Some((
+				"application/json",
+				serde_json::to_string_pretty(&*summary).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?,
+			)),
+			_ => None,
+		},
+		Err(_) => return write_response(&stream, "500 Internal Server Error", "text/plain", "Error\n"),
+	};
+	match rendered {
+		Some((content_type, body)) => write_response(&stream, "200 OK", content_type, &body),
+		None => write_response(&stream, "404 Not Found", "text/plain", "Not Found\n"),
+	}
+}
+
+fn write_response(mut stream: &TcpStream, status: &str, content_type: &str, body: &str) -> std::io::Result<()> {
+	write!(
+		stream,
+		"HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+		status,
+		content_type,
+		body.len(),
+		body
+	)?;
+	stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn request(head: &str) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+		let (stream, _) = listener.accept().unwrap();
+		let summary = Arc::new(Mutex::new(Summary {
+			updated: None,
+			nodes: Vec::new(),
+		}));
+		let responder = thread::spawn(move || respond(stream, &summary, &None));
+		client.write_all(head.as_bytes()).unwrap();
+		let mut response = String::new();
+		client.read_to_string(&mut response).unwrap();
+		responder.join().unwrap().unwrap();
+		response
+	}
+
+	#[test]
+	fn bounds_the_request_head() {
+		let response = request("GET /summary.json HTTP/1.1\r\nHost: localhost\r\n\r\n");
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+		assert!(response.contains("\"nodes\": []"));
+
+		// Exactly the limit without its blank line, so nothing is left unread to reset the connection
+		let head = "GET / HTTP/1.1\r\nX-Padding: \r\n";
+		let padding = "x".repeat(REQUEST_HEAD_MAX as usize - head.len());
+		let response = request(&head.replace("X-Padding: ", &format!("X-Padding: {}", padding)));
+		assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+	}
+}
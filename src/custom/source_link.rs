@@ -0,0 +1,41 @@
+///! Links from a line's source (such as [src/node/mod.rs:97]) to its code
+///!
+///! The source of a node's line is where in sn_node it was logged, so the
+///! line detail popup gives the URL of that line on GitHub, at the tag of the
+///! version the node said it was running (or the main branch until it has).
+
+///! Where sn_node's source is browsed
+pub static SN_NODE_REPOSITORY: &str = "https://github.com/maidsafe/sn_node";
+
+///! The URL of the line of code a source refers to, if it is of the form [path:line]
+pub fn source_url(source: &str, version: Option<&str>) -> Option<String> {
+	let source = source.trim().trim_start_matches('[').trim_end_matches(']');
+	let (path, line) = source.rsplit_once(':')?;
+	if path.is_empty() || line.parse::<u32>().is_err() {
+		return None;
+	}
+	let tree = match version.and_then(|version| version.split_whitespace().next()) {
+		Some(version) => format!("v{}", version.trim_start_matches('v')),
+		None => String::from("main"),
+	};
+	Some(format!("{}/blob/{}/{}#L{}", SN_NODE_REPOSITORY, tree, path.trim_start_matches("./"), line))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn links_sources_to_the_running_version() {
+		assert_eq!(
+			source_url("[src/node/mod.rs:97]", Some("0.24.0")).as_deref(),
+			Some("https://github.com/maidsafe/sn_node/blob/v0.24.0/src/node/mod.rs#L97")
+		);
+		assert_eq!(
+			source_url("[src/utils.rs:52]", None).as_deref(),
+			Some("https://github.com/maidsafe/sn_node/blob/main/src/utils.rs#L52")
+		);
+		assert_eq!(source_url("[sn_routing::core]", Some("0.24.0")), None);
+		assert_eq!(source_url("", None), None);
+	}
+}
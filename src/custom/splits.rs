@@ -0,0 +1,294 @@
+///! Splits of the node view which can be resized, by mouse or keys
+///!
+///! The node view stacks its stats, timeline and bottom panel, and splits the
+///! bottom panel between the logfile and the debug window or parser view. The
+///! borders between them can be dragged with the mouse, or the split selected
+///! with '|' moved with '<' and '>' (which sort the top view in that view).
+///! '=' returns every split to its default. A split which has been moved keeps
+///! its position as a percentage of the area it divides, so it holds as the
+///! terminal is resized, and is kept in the session (see Session).
+use serde::{Deserialize, Serialize};
+
+use super::keymap::MouseInput;
+
+use tui::{
+	backend::Backend,
+	buffer::Buffer,
+	layout::{Constraint, Rect},
+	style::{Color, Style},
+	widgets::Widget,
+	Frame,
+};
+
+///! Rows of the node's stats and of its timeline, until they are moved
+const STATS_ROWS: u16 = 15;
+const TIMELINE_ROWS: u16 = 18;
+///! Percent of the bottom panel given to the logfile beside the debug window or parser view
+const SIDE_PERCENT: u16 = 50;
+
+///! The least percent of its area on either side of a split
+const SPLIT_PERCENT_MIN: u16 = 10;
+///! Percent a split moves for each '<' or '>'
+const SPLIT_STEP: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Split {
+	///! Between the stats and the timeline
+	#[default]
+	Stats,
+	///! Between the timeline and the bottom panel
+	Timeline,
+	///! Between the logfile and the debug window or parser view
+	Side,
+}
+
+impl Split {
+	fn next(&self) -> Split {
+		match self {
+			Split::Stats => Split::Timeline,
+			Split::Timeline => Split::Side,
+			Split::Side => Split::Stats,
+		}
+	}
+
+	fn is_vertical(&self) -> bool {
+		*self == Split::Side
+	}
+}
+
+///! Where a split was drawn: the area it divides and the row or column of its border
+#[derive(Debug, Clone, Copy)]
+struct Border {
+	split: Split,
+	area: Rect,
+	at: u16,
+}
+
+impl Border {
+	fn contains(&self, column: u16, row: u16) -> bool {
+		let area = self.area;
+		match self.split.is_vertical() {
+			true => column == self.at && row >= area.y && row < area.y + area.height,
+			false => row == self.at && column >= area.x && column < area.x + area.width,
+		}
+	}
+
+	///! The percent of the area before the border, were it at column or row
+	fn percent_at(&self, column: u16, row: u16) -> u16 {
+		let (offset, length) = match self.split.is_vertical() {
+			true => (column.saturating_sub(self.area.x), self.area.width),
+			false => (row.saturating_sub(self.area.y), self.area.height),
+		};
+		match length {
+			0 => 0,
+			_ => (offset as u32 * 100 / length as u32) as u16,
+		}
+	}
+
+	fn percent(&self) -> u16 {
+		self.percent_at(self.at, self.at)
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Splits {
+	///! Percent of the node view above the timeline, once moved
+	stats: Option<u16>,
+	///! Percent of the node view given to the timeline, once moved
+	timeline: Option<u16>,
+	side: u16,
+
+	///! The split '<' and '>' move
+	#[serde(skip)]
+	pub selected: Split,
+	///! Whether to mark the selected split, until another action
+	#[serde(skip)]
+	pub marked: bool,
+	#[serde(skip)]
+	dragging: Option<Split>,
+	///! Borders as last drawn, to find the one under the mouse
+	#[serde(skip)]
+	borders: Vec<Border>,
+}
+
+// Not derived, as the side split starts at SIDE_PERCENT
+impl Default for Splits {
+	fn default() -> Splits {
+		Splits {
+			stats: None,
+			timeline: None,
+			side: SIDE_PERCENT,
+			selected: Split::default(),
+			marked: false,
+			dragging: None,
+			borders: Vec::new(),
+		}
+	}
+}
+
+impl Splits {
+	///! Heights of the stats, timeline and bottom panel of the node view
+	pub fn node_constraints(&self) -> [Constraint; 3] {
+		[
+			self.stats.map_or(Constraint::Length(STATS_ROWS), Constraint::Percentage),
+			self.timeline.map_or(Constraint::Length(TIMELINE_ROWS), Constraint::Percentage),
+			Constraint::Min(0),
+		]
+	}
+
+	///! Widths of the logfile and the debug window or parser view beside it
+	pub fn side_constraints(&self) -> [Constraint; 2] {
+		[Constraint::Percentage(self.side), Constraint::Percentage(100 - self.side)]
+	}
+
+	///! Forget where the splits were drawn, before the node view is drawn again
+	pub fn clear_borders(&mut self) {
+		self.borders.clear();
+	}
+
+	///! Note where split was drawn, dividing area with its border at the row or column at
+	pub fn drawn(&mut self, split: Split, area: Rect, at: u16) {
+		self.borders.retain(|border| border.split != split);
+		self.borders.push(Border { split, area, at });
+	}
+
+	pub fn select_next(&mut self) {
+		self.selected = self.selected.next();
+		self.marked = true;
+	}
+
+	///! Move the selected split by steps, down or right if positive
+	pub fn move_selected(&mut self, steps: i32) {
+		let split = self.selected;
+		let percent = self.percent(split) as i32 + steps * SPLIT_STEP as i32;
+		self.set_percent(split, percent.max(0) as u16);
+		self.marked = true;
+	}
+
+	pub fn reset(&mut self) {
+		*self = Splits {
+			selected: self.selected,
+			borders: std::mem::take(&mut self.borders),
+			..Splits::default()
+		};
+	}
+
+	///! Follow the mouse dragging a border, returning true when a drag ends, so the splits can be saved
+	pub fn mouse(&mut self, input: MouseInput) -> bool {
+		match input {
+			MouseInput::Press(column, row) => {
+				self.dragging = self.borders.iter().find(|border| border.contains(column, row)).map(|border| border.split);
+				if let Some(split) = self.dragging {
+					self.selected = split;
+					self.marked = true;
+				}
+				false
+			}
+			MouseInput::Drag(column, row) => {
+				let border = self.dragging.and_then(|split| self.borders.iter().find(|border| border.split == split).copied());
+				if let Some(border) = border {
+					self.set_percent(border.split, border.percent_at(column, row));
+				}
+				false
+			}
+			MouseInput::Release => self.dragging.take().is_some(),
+		}
+	}
+
+	///! The percent of the area before a split, as moved or else as last drawn
+	fn percent(&self, split: Split) -> u16 {
+		let drawn = self.borders.iter().find(|border| border.split == split).map(Border::percent);
+		match split {
+			Split::Stats => self.stats.or(drawn).unwrap_or(SPLIT_PERCENT_MIN),
+			Split::Timeline => match (self.stats, self.timeline) {
+				(Some(stats), Some(timeline)) => stats + timeline,
+				_ => drawn.unwrap_or(2 * SPLIT_PERCENT_MIN),
+			},
+			Split::Side => self.side,
+		}
+	}
+
+	fn set_percent(&mut self, split: Split, percent: u16) {
+		let max = 100 - SPLIT_PERCENT_MIN;
+		match split {
+			Split::Stats => {
+				let timeline = self.timeline.unwrap_or(0);
+				self.stats = Some(percent.max(SPLIT_PERCENT_MIN).min(max.saturating_sub(timeline)));
+			}
+			Split::Timeline => {
+				// The timeline's border is below the stats, but its percent is of its own height
+				let stats = self.borders.iter().find(|border| border.split == Split::Stats).map_or(SPLIT_PERCENT_MIN, Border::percent);
+				let stats = self.stats.unwrap_or(stats);
+				self.stats = Some(stats);
+				self.timeline = Some(percent.saturating_sub(stats).max(SPLIT_PERCENT_MIN).min(max.saturating_sub(stats)));
+			}
+			Split::Side => self.side = percent.max(SPLIT_PERCENT_MIN).min(max),
+		}
+	}
+
+	///! Mark the selected split's border, after it has been selected or moved
+	pub fn draw_mark<B: Backend>(&self, f: &mut Frame<B>) {
+		if !self.marked {
+			return;
+		}
+		if let Some(border) = self.borders.iter().find(|border| border.split == self.selected) {
+			let area = border.area;
+			let line = match border.split.is_vertical() {
+				true => Rect::new(border.at, area.y, 1, area.height),
+				false => Rect::new(area.x, border.at, area.width, 1),
+			};
+			f.render_widget(Mark, line.intersection(f.size()));
+		}
+	}
+}
+
+///! Colours a border without redrawing it
+struct Mark;
+
+impl Widget for Mark {
+	fn render(self, area: Rect, buf: &mut Buffer) {
+		buf.set_style(area, Style::default().fg(Color::Cyan));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn moves_splits_by_keys_and_mouse() {
+		let mut splits = Splits::default();
+		assert_eq!(splits.node_constraints()[0], Constraint::Length(STATS_ROWS));
+		let node = Rect::new(0, 0, 100, 50);
+		splits.drawn(Split::Stats, node, 15);
+		splits.drawn(Split::Timeline, node, 33);
+		splits.drawn(Split::Side, Rect::new(0, 33, 100, 17), 50);
+
+		splits.move_selected(1);
+		assert_eq!(splits.node_constraints()[0], Constraint::Percentage(32));
+		splits.move_selected(-40);
+		assert_eq!(splits.node_constraints()[0], Constraint::Percentage(SPLIT_PERCENT_MIN));
+
+		// Dragging the timeline's border to row 40 of 50
+		assert!(!splits.mouse(MouseInput::Press(20, 33)));
+		assert_eq!(splits.selected, Split::Timeline);
+		splits.mouse(MouseInput::Drag(20, 40));
+		assert!(splits.mouse(MouseInput::Release));
+		assert_eq!(splits.node_constraints()[1], Constraint::Percentage(70));
+
+		// A press off every border drags nothing
+		splits.mouse(MouseInput::Press(20, 20));
+		splits.mouse(MouseInput::Drag(70, 40));
+		assert!(!splits.mouse(MouseInput::Release));
+
+		splits.mouse(MouseInput::Press(50, 40));
+		splits.mouse(MouseInput::Drag(70, 40));
+		splits.mouse(MouseInput::Release);
+		assert_eq!(splits.side_constraints(), [Constraint::Percentage(70), Constraint::Percentage(30)]);
+
+		splits.reset();
+		assert_eq!(splits.node_constraints()[1], Constraint::Length(TIMELINE_ROWS));
+		assert_eq!(splits.side_constraints()[0], Constraint::Percentage(SIDE_PERCENT));
+	}
+}
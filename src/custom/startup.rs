@@ -0,0 +1,219 @@
+///! Startup banner: what is about to be loaded, before the dashboard starts
+///!
+///! Each logfile is surveyed by parsing a sample from its start, which gives
+///! its format, the sn_node version it was logged by (if the sample includes
+///! a start), and from the time taken, an estimate of how long loading the
+///! whole file will take. The banner lists these, and when loading looks slow
+///! or a logfile doesn't look like a node's, asks whether to load history, to
+///! skip it (as --ignore-existing), or to quit, so that pointing vdash at a
+///! huge or wrong file by mistake costs no more than a keypress.
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use super::opt::Opt;
+use vault_metrics::{LogFormat, NodeMetrics, PARSER_PLAIN};
+
+///! Bytes parsed from the start of each logfile
+const SAMPLE_BYTES: u64 = 1024 * 1024;
+///! Estimated load time above which to ask before loading
+const PROMPT_LOAD_SECS: u64 = 10;
+///! Total size above which to ask before loading, as a large file may be the wrong one
+const PROMPT_BYTES: u64 = 512 * 1024 * 1024;
+
+pub struct FileSurvey {
+	pub logfile: String,
+	///! None if the logfile doesn't exist yet, which is fine
+	pub size: Option<u64>,
+	pub format: LogFormat,
+	///! From the first start of sn_node in the sample
+	pub version: Option<String>,
+	pub load_estimate: Duration,
+}
+
+impl FileSurvey {
+	pub fn new(opt: &Opt, logfile: &str) -> FileSurvey {
+		let mut survey = FileSurvey {
+			logfile: logfile.to_string(),
+			size: None,
+			format: LogFormat::Sniffing,
+			version: None,
+			load_estimate: Duration::from_secs(0),
+		};
+		let file = match File::open(logfile) {
+			Ok(file) => file,
+			Err(_) => return survey,
+		};
+		survey.size = file.metadata().ok().map(|metadata| metadata.len());
+
+		let mut metrics = NodeMetrics::new(opt.timeline_steps, opt.assume_offset);
+		if opt.parser == PARSER_PLAIN {
+			metrics.log_format = LogFormat::Plain;
+		}
+		metrics.loading = true;
+		let started = Instant::now();
+		let mut sampled = 0;
+		let mut reader = BufReader::new(file);
+		let mut line = String::new();
+		while sampled < SAMPLE_BYTES {
+			line.clear();
+			match reader.read_line(&mut line) {
+				Ok(0) | Err(_) => break,
+				Ok(length) => sampled += length as u64,
+			}
+			let _ = metrics.gather_metrics(line.trim_end_matches(&['\n', '\r'][..]));
+		}
+		survey.format = metrics.log_format;
+		survey.version = metrics.running_version;
+		if sampled > 0 {
+			let size = survey.size.unwrap_or(sampled).max(sampled);
+			survey.load_estimate = started.elapsed().mul_f64(size as f64 / sampled as f64);
+		}
+		survey
+	}
+
+	pub fn format_label(&self) -> &'static str {
+		match (self.size, self.format) {
+			(None, _) => "not found",
+			(Some(0), _) => "empty",
+			(_, LogFormat::SnNode) => "sn_node",
+			(_, LogFormat::Plain) => "plain",
+			(_, LogFormat::Sniffing) | (_, LogFormat::Unknown) => "unknown",
+		}
+	}
+
+	///! Whether the logfile has content which isn't recognised as a node's
+	pub fn is_unrecognised(&self) -> bool {
+		self.format_label() == "unknown"
+	}
+}
+
+///! What to do with the content already in the logfiles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Choice {
+	Load,
+	SkipHistory,
+	Quit,
+}
+
+///! Whether to ask before loading, because loading looks slow or a logfile looks wrong
+pub fn needs_confirmation(surveys: &[FileSurvey]) -> bool {
+	let total_size: u64 = surveys.iter().filter_map(|survey| survey.size).sum();
+	let total_estimate: Duration = surveys.iter().map(|survey| survey.load_estimate).sum();
+	total_size > PROMPT_BYTES
+		|| total_estimate > Duration::from_secs(PROMPT_LOAD_SECS)
+		|| surveys.iter().any(FileSurvey::is_unrecognised)
+}
+
+pub fn print_banner(surveys: &[FileSurvey]) {
+	println!("Loading {} files...", surveys.len());
+	println!("{:>10}  {:<9}  {:<10}  {:>8}  file", "size", "format", "version", "load");
+	for survey in surveys {
+		println!(
+			"{:>10}  {:<9}  {:<10}  {:>8}  {}",
+			survey.size.map_or("-".to_string(), format_size),
+			survey.format_label(),
+			survey.version.as_deref().unwrap_or("-"),
+			format_estimate(survey.load_estimate),
+			survey.logfile
+		);
+	}
+	let total: Duration = surveys.iter().map(|survey| survey.load_estimate).sum();
+	if surveys.len() > 1 {
+		println!("{:>43}  total", format_estimate(total));
+	}
+}
+
+///! Ask what to do with existing content, if stdin is a terminal, otherwise load it
+pub fn confirm() -> Choice {
+	if !std::io::stdin().is_terminal() {
+		return Choice::Load;
+	}
+	loop {
+		print!("Load history? [Y]es, [s]kip history (only show new lines), [q]uit: ");
+		let _ = std::io::stdout().flush();
+		let mut answer = String::new();
+		if std::io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+			return Choice::Quit;
+		}
+		if let Some(choice) = parse_choice(&answer) {
+			return choice;
+		}
+	}
+}
+
+fn parse_choice(answer: &str) -> Option<Choice> {
+	match answer.trim().to_lowercase().as_str() {
+		"" | "y" | "yes" => Some(Choice::Load),
+		"s" | "skip" => Some(Choice::SkipHistory),
+		"q" | "quit" | "n" | "no" => Some(Choice::Quit),
+		_ => None,
+	}
+}
+
+fn format_size(bytes: u64) -> String {
+	byte_unit::Byte::from_bytes(bytes as u128).get_appropriate_unit(false).format(1)
+}
+
+fn format_estimate(estimate: Duration) -> String {
+	match estimate.as_secs() {
+		0 => "<1s".to_string(),
+		secs if secs < 60 => format!("~{}s", secs),
+		secs => format!("~{}m{:02}s", secs / 60, secs % 60),
+	}
+}
+
+///! Survey the logfiles and show the banner, returning the user's choice
+pub fn show(opt: &Opt, logfiles: &[String]) -> Result<Choice, Error> {
+	let surveys: Vec<FileSurvey> = logfiles.iter().map(|logfile| FileSurvey::new(opt, logfile)).collect();
+	print_banner(&surveys);
+	// With --tail-lines loading is quick however large the logfiles, but not if one is wrong
+	let quick = opt.tail_lines.is_some() && !surveys.iter().any(FileSurvey::is_unrecognised);
+	if opt.ignore_existing || quick || !needs_confirmation(&surveys) {
+		return Ok(Choice::Load);
+	}
+	if let Some(survey) = surveys.iter().find(|survey| survey.is_unrecognised()) {
+		println!("{} doesn't look like a node logfile (try --parser plain)", survey.logfile);
+	}
+	Ok(confirm())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::StructOpt;
+
+	#[test]
+	fn surveys_logfiles() {
+		let dir = tempfile::tempdir().unwrap();
+		let node_log = dir.path().join("sn_node.log");
+		std::fs::write(
+			&node_log,
+			"[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] first\n\
+			Running sn_node v0.25.3\n\
+			[sn_node] INFO 2021-01-20T18:00:01.000000+00:00 [src/a.rs:1] second\n",
+		)
+		.unwrap();
+		let other = dir.path().join("other.txt");
+		std::fs::write(&other, "just some text\n".repeat(100)).unwrap();
+		let missing = dir.path().join("missing.log");
+		let logfiles: Vec<String> = [&node_log, &other, &missing].iter().map(|path| path.to_str().unwrap().to_string()).collect();
+
+		let opt = Opt::from_iter(&["vdash", "node.log"]);
+		let surveys: Vec<FileSurvey> = logfiles.iter().map(|logfile| FileSurvey::new(&opt, logfile)).collect();
+		assert_eq!(surveys[0].format_label(), "sn_node");
+		assert_eq!(surveys[0].version.as_deref(), Some("v0.25.3"));
+		assert_eq!(surveys[1].format_label(), "unknown");
+		assert_eq!(surveys[2].format_label(), "not found");
+		assert!(needs_confirmation(&surveys));
+		assert!(!needs_confirmation(&surveys[..1]));
+
+		let plain = Opt::from_iter(&["vdash", "--parser", "plain", "node.log"]);
+		assert_eq!(FileSurvey::new(&plain, &logfiles[1]).format_label(), "plain");
+
+		assert_eq!(parse_choice("\n"), Some(Choice::Load));
+		assert_eq!(parse_choice("S\n"), Some(Choice::SkipHistory));
+		assert_eq!(parse_choice("q"), Some(Choice::Quit));
+		assert_eq!(parse_choice("maybe"), None);
+	}
+}
@@ -0,0 +1,131 @@
+///! Highlighting of what changed in recurring state lines
+///!
+///! A node logs some state repeatedly, such as its age or periodic summaries.
+///! Each line is compared with the previous line from the same source with
+///! the same template (its message with numbers and IDs as placeholders), so
+///! that fields which changed, and slow drifts, stand out in the logfile view.
+use regex::Regex;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+use vault_metrics::LOG_LINE_PATTERN;
+
+lazy_static::lazy_static! {
+	// Hex IDs (words of hex digits including both a digit and a letter), or numbers
+	static ref STATE_FIELD: Regex =
+		Regex::new(r"\b(0x)?[0-9a-fA-F]*([0-9][0-9a-fA-F]*[a-fA-F]|[a-fA-F][0-9a-fA-F]*[0-9])[0-9a-fA-F]*\b|\d+(\.\d+)?").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Limit on templates remembered, beyond which comparisons start afresh
+static TEMPLATES_MAX: usize = 10000;
+
+///! For each line, the byte ranges of fields changed since the previous
+///! line with the same template
+pub fn changed_fields(lines: &[String]) -> Vec<Vec<Range<usize>>> {
+	let mut diff = StateDiff::default();
+	for line in lines {
+		diff.push(line);
+	}
+	diff.changed.into_iter().collect()
+}
+
+///! changed_fields() of a buffer of lines, kept up to date as lines are
+///! added at the end and expire from the start, so that a large buffer
+///! isn't compared afresh each time it changes
+#[derive(Default)]
+pub struct StateDiff {
+	///! For each line, the byte ranges of fields changed
+	pub changed: VecDeque<Vec<Range<usize>>>,
+	previous_fields: HashMap<String, Vec<String>>,
+}
+
+impl StateDiff {
+	///! Compare lines afresh, such as when the buffer has been replaced
+	pub fn rebuild(&mut self, lines: &[String]) {
+		*self = StateDiff::default();
+		for line in lines {
+			self.push(line);
+		}
+	}
+
+	pub fn push(&mut self, line: &str) {
+		let changed = self.diff_line(line);
+		self.changed.push_back(changed);
+	}
+
+	///! Forget the first count lines, as they have expired from the buffer
+	pub fn drain_front(&mut self, count: usize) {
+		self.changed.drain(..count.min(self.changed.len()));
+	}
+
+	fn diff_line(&mut self, line: &str) -> Vec<Range<usize>> {
+		let captures = match LOG_LINE_PATTERN.captures(line) {
+			Some(captures) => captures,
+			None => return Vec::new(),
+		};
+		let (source, message) = match (captures.name("source"), captures.name("message")) {
+			(Some(source), Some(message)) => (source, message),
+			_ => return Vec::new(),
+		};
+
+		let fields: Vec<regex::Match> = STATE_FIELD.find_iter(message.as_str()).collect();
+		if fields.is_empty() {
+			return Vec::new();
+		}
+		let template = format!("{} {}", source.as_str(), STATE_FIELD.replace_all(message.as_str(), "#"));
+		let values: Vec<String> = fields.iter().map(|field| field.as_str().to_string()).collect();
+
+		let changed = match self.previous_fields.get(&template) {
+			Some(previous) => fields
+				.iter()
+				.zip(previous.iter())
+				.filter(|(field, previous)| field.as_str() != previous.as_str())
+				.map(|(field, _)| message.start() + field.start()..message.start() + field.end())
+				.collect(),
+			None => Vec::new(),
+		};
+		if !self.previous_fields.contains_key(&template) && self.previous_fields.len() >= TEMPLATES_MAX {
+			self.previous_fields.clear();
+		}
+		self.previous_fields.insert(template, values);
+		changed
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn finds_changed_fields() {
+		let lines: Vec<String> = [
+			"[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Used space: 10 of 100",
+			"[sn_node] INFO 2020-12-18T14:33:50.799447454+00:00 [src/node/mod.rs:50] Our Age: 5",
+			"[sn_node] INFO 2020-12-18T14:33:51.799447454+00:00 [src/node/mod.rs:97] Used space: 12 of 100",
+		]
+		.iter()
+		.map(|line| line.to_string())
+		.collect();
+
+		let changed = changed_fields(&lines);
+		assert!(changed[0].is_empty() && changed[1].is_empty());
+		assert_eq!(changed[2].len(), 1);
+		assert_eq!(&lines[2][changed[2][0].clone()], "12");
+	}
+
+	#[test]
+	fn diffs_incrementally() {
+		let lines: Vec<String> = (0..6)
+			.map(|n| format!("[sn_node] INFO 2020-12-18T14:33:4{}.799447454+00:00 [src/node/mod.rs:50] Our Age: {}", n, n / 2))
+			.collect();
+
+		let mut diff = StateDiff::default();
+		for line in &lines {
+			diff.push(line);
+		}
+		diff.drain_front(2);
+		assert_eq!(diff.changed, changed_fields(&lines)[2..].to_vec());
+		diff.rebuild(&lines[2..]);
+		assert_eq!(diff.changed, changed_fields(&lines[2..]));
+	}
+}
@@ -0,0 +1,146 @@
+///! The status command: a one-shot check of logfiles, for scripts
+///!
+///! `vdash status FILE...` parses the recent history of each logfile as the
+///! dashboard would, prints one line per node with its status, and exits with
+///! a code for the worst of them: 0 if every node is OK, 1 if any warn, and 2
+///! if any have errors or are stalled, or a logfile can't be read. This suits
+///! cron jobs and monitoring systems such as Nagios.
+use chrono::{Duration, Utc};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use super::opt::Opt;
+use vault_metrics::{LogFormat, NodeMetrics, NodeStatus, PARSER_PLAIN};
+
+///! Bytes parsed from the end of each logfile, which covers the recent history status is decided by
+const TAIL_BYTES: u64 = 8 * 1024 * 1024;
+
+///! Exit codes, from the worst status of the nodes
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_WARN: i32 = 1;
+pub const EXIT_ERROR: i32 = 2;
+
+///! Check each logfile, print a line for each and exit with the code for the worst
+pub fn run(opt: &Opt, files: &[impl AsRef<Path>]) -> std::io::Result<()> {
+	let mut code = EXIT_OK;
+	for file in files {
+		let file = file.as_ref();
+		let (line, file_code) = match load_tail(opt, file) {
+			Ok(metrics) => (summary(&metrics), exit_code(metrics.status())),
+			Err(e) => (format!("{:<7} {}", "ERROR", e), EXIT_ERROR),
+		};
+		println!("{}: {}", file.display(), line);
+		code = code.max(file_code);
+	}
+	if code != EXIT_OK {
+		std::process::exit(code);
+	}
+	Ok(())
+}
+
+pub fn exit_code(status: NodeStatus) -> i32 {
+	match status {
+		NodeStatus::Ok => EXIT_OK,
+		NodeStatus::Warn => EXIT_WARN,
+		NodeStatus::Error | NodeStatus::Stalled => EXIT_ERROR,
+	}
+}
+
+///! Metrics of the recent history of the logfile at path, as of now
+fn load_tail(opt: &Opt, path: &Path) -> std::io::Result<NodeMetrics> {
+	let mut file = File::open(path)?;
+	let size = file.metadata()?.len();
+	let start = size.saturating_sub(TAIL_BYTES);
+	file.seek(SeekFrom::Start(start))?;
+
+	let mut metrics = NodeMetrics::new(opt.timeline_steps, opt.assume_offset);
+	if opt.parser == PARSER_PLAIN {
+		metrics.log_format = LogFormat::Plain;
+	}
+	metrics.loading = true;
+	let mut reader = BufReader::new(file);
+	let mut line = String::new();
+	// A tail from part way through the logfile starts part way through a line
+	if start > 0 {
+		reader.read_line(&mut line)?;
+	}
+	loop {
+		line.clear();
+		// A line of invalid UTF-8 is consumed before read_line() fails, so can be skipped
+		match reader.read_line(&mut line) {
+			Ok(0) => break,
+			Ok(_) => {}
+			Err(e) if e.kind() == std::io::ErrorKind::InvalidData => continue,
+			Err(e) => return Err(e),
+		}
+		let _ = metrics.gather_metrics(line.trim_end_matches(&['\n', '\r'][..]));
+	}
+	metrics.loading = false;
+	metrics.update_timelines(Some(Utc::now()));
+	Ok(metrics)
+}
+
+///! The status of a node and why, e.g. 'WARN    Elder, version 0.25.1, last logged 2m ago'
+pub fn summary(metrics: &NodeMetrics) -> String {
+	let status = metrics.status();
+	let mut details = vec![metrics.agebracket_string()];
+	if let Some(version) = &metrics.running_version {
+		details.push(format!("version {}", version));
+	}
+	details.push(match metrics.idle_time() {
+		Some(idle_time) => format!("last logged {} ago", format_idle(idle_time)),
+		None => String::from("nothing logged"),
+	});
+	match status {
+		NodeStatus::Error => {
+			if let Some(error) = metrics.last_error() {
+				details.push(format!("last error: {}", error.message));
+			}
+		}
+		NodeStatus::Warn => {
+			details.push(format!("outliers: {}", metrics.outliers().join(", ")));
+		}
+		_ => {}
+	}
+	format!("{:<7} {}", status.label(), details.join(", "))
+}
+
+fn format_idle(idle_time: Duration) -> String {
+	if idle_time.num_hours() > 0 {
+		format!("{}h", idle_time.num_hours())
+	} else if idle_time.num_minutes() > 0 {
+		format!("{}m", idle_time.num_minutes())
+	} else {
+		format!("{}s", idle_time.num_seconds().max(0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+	use structopt::StructOpt;
+
+	#[test]
+	fn summarises_recent_history() {
+		let opt = Opt::from_iter(&["vdash", "status", "node.log"]);
+		let line = |behind: i64, category: &str, message: &str| {
+			let time = Utc::now() - Duration::seconds(behind);
+			format!("[sn_node] {} {} [src/a.rs:1] {}", category, time.format("%Y-%m-%dT%H:%M:%S%.6f+00:00"), message)
+		};
+		let mut logfile = tempfile::NamedTempFile::new().unwrap();
+		writeln!(logfile, "{}", line(120, "INFO", "Started")).unwrap();
+		writeln!(logfile, "{}", line(90, "ERROR", "Dead peer")).unwrap();
+		let metrics = load_tail(&opt, logfile.path()).unwrap();
+		assert_eq!(exit_code(metrics.status()), EXIT_ERROR);
+		let summary = summary(&metrics);
+		assert!(summary.starts_with("ERROR "));
+		assert!(summary.contains("last logged 1m ago, last error: Dead peer"));
+
+		assert_eq!(exit_code(NodeStatus::Ok), EXIT_OK);
+		assert_eq!(exit_code(NodeStatus::Warn), EXIT_WARN);
+		assert_eq!(exit_code(NodeStatus::Stalled), EXIT_ERROR);
+		assert!(load_tail(&opt, Path::new("/nonexistent/node.log")).is_err());
+	}
+}
@@ -0,0 +1,337 @@
+///! Syslog listener, so that remote nodes can send their logs (--listen-syslog ADDR)
+///!
+///! Messages in RFC 5424 or RFC 3164 format are accepted over UDP and over
+///! TCP at ADDR, where TCP messages are framed by length (RFC 6587 octet
+///! counting) or end with a newline. Each distinct host and app is monitored
+///! as a logfile named syslog://HOST/APP, added when its first message
+///! arrives. The message is taken as a line of the node's logfile, so the
+///! node's logger should send its lines unchanged (for example with rsyslog's
+///! imfile), and the lines go through --syslog-parser (--parser by default).
+///! A host which isn't given is that of the sender.
+///!
+///! As anyone who can reach ADDR can send messages, and each host and app
+///! holds lines and metrics of its own, SyslogLimits caps the hosts and apps
+///! monitored and the TCP connections open, with what is dropped logged.
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read};
+use std::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::{
+	atomic::{AtomicUsize, Ordering},
+	Arc, Mutex,
+};
+use std::thread;
+
+use super::ingest::LineSender;
+
+///! Start of the name of the monitor for each host and app
+pub static SYSLOG_PREFIX: &str = "syslog://";
+
+///! Largest message accepted, as for UDP
+const MESSAGE_MAX: usize = 65535;
+
+#[derive(Debug, PartialEq)]
+pub struct SyslogMessage {
+	pub host: String,
+	pub app: String,
+	pub message: String,
+}
+
+impl SyslogMessage {
+	///! Name of the monitor for the host and app of this message
+	pub fn source(&self) -> String {
+		format!("{}{}/{}", SYSLOG_PREFIX, self.host, self.app)
+	}
+}
+
+pub fn is_syslog_source(source: &str) -> bool {
+	source.starts_with(SYSLOG_PREFIX)
+}
+
+///! Caps on what senders can make vdash hold (--syslog-sources-max, --syslog-connections-max)
+#[derive(Debug, Clone, Copy)]
+pub struct SyslogLimits {
+	///! Most hosts and apps monitored, after which messages from others are dropped
+	pub sources_max: usize,
+	///! Most TCP connections open at once, each on a thread of its own, after which others are refused
+	pub connections_max: usize,
+}
+
+///! The hosts and apps whose messages are accepted, the first up to a limit
+struct Sources {
+	accepted: HashSet<String>,
+	max: usize,
+	dropped: u64,
+}
+
+impl Sources {
+	fn new(max: usize) -> Sources {
+		Sources {
+			accepted: HashSet::new(),
+			max,
+			dropped: 0,
+		}
+	}
+
+	///! Whether to accept a message from source, logging as more are dropped
+	fn accept(&mut self, source: &str) -> bool {
+		if self.accepted.contains(source) {
+			return true;
+		}
+		if self.accepted.len() < self.max {
+			self.accepted.insert(source.to_string());
+			return true;
+		}
+		self.dropped += 1;
+		// Logged at 1, 2, 4.. so that a flood of them doesn't flood the log too
+		if self.dropped.is_power_of_two() {
+			warn!(
+				"dropped {} syslog messages from hosts and apps beyond --syslog-sources-max {}, the latest from {}",
+				self.dropped, self.max, source
+			);
+		}
+		false
+	}
+}
+
+///! Listen for syslog over UDP and TCP at addr, sending lines as (monitor name, line)
+pub fn listen(addr: &str, limits: SyslogLimits, tx: LineSender) -> std::io::Result<()> {
+	let udp = UdpSocket::bind(addr)?;
+	let tcp = TcpListener::bind(addr)?;
+	let sources = Arc::new(Mutex::new(Sources::new(limits.sources_max)));
+
+	let udp_tx = tx.clone();
+	let udp_sources = sources.clone();
+	thread::spawn(move || {
+		let mut buffer = vec![0; MESSAGE_MAX];
+		loop {
+			match udp.recv_from(&mut buffer) {
+				Ok((length, peer)) => {
+					if !send_message(&buffer[..length], peer.ip(), &udp_sources, &udp_tx) {
+						return;
+					}
+				}
+				Err(e) => warn!("syslog UDP receive failed: {}", e),
+			}
+		}
+	});
+
+	thread::spawn(move || {
+		let open = Arc::new(AtomicUsize::new(0));
+		for stream in tcp.incoming() {
+			match stream {
+				// Dropping the stream closes it
+				Ok(stream) if open.load(Ordering::Relaxed) >= limits.connections_max => warn!(
+					"refused syslog TCP connection from {}, as --syslog-connections-max {} are open",
+					stream.peer_addr().map_or_else(|_| String::from("unknown"), |peer| peer.to_string()),
+					limits.connections_max
+				),
+				Ok(stream) => {
+					open.fetch_add(1, Ordering::Relaxed);
+					let open = open.clone();
+					let sources = sources.clone();
+					let tx = tx.clone();
+					thread::spawn(move || {
+						receive_tcp(stream, &sources, &tx);
+						open.fetch_sub(1, Ordering::Relaxed);
+					});
+				}
+				Err(e) => warn!("syslog TCP connection failed: {}", e),
+			}
+		}
+	});
+	Ok(())
+}
+
+fn receive_tcp(stream: TcpStream, sources: &Mutex<Sources>, tx: &LineSender) {
+	let peer = match stream.peer_addr() {
+		Ok(peer) => peer.ip(),
+		Err(_) => return,
+	};
+	let mut reader = BufReader::new(stream);
+	loop {
+		match next_frame(&mut reader) {
+			Ok(Some(frame)) => {
+				if !send_message(&frame, peer, sources, tx) {
+					return;
+				}
+			}
+			Ok(None) => return,
+			Err(e) => {
+				warn!("syslog TCP connection from {} failed: {}", peer, e);
+				return;
+			}
+		}
+	}
+}
+
+///! Send each line of a message, returning false once nothing is receiving
+fn send_message(frame: &[u8], peer: IpAddr, sources: &Mutex<Sources>, tx: &LineSender) -> bool {
+	let frame = String::from_utf8_lossy(frame);
+	let message = match parse_message(&frame, &peer.to_string()) {
+		Some(message) => message,
+		None => {
+			debug!("ignoring syslog message which isn't RFC 5424 or RFC 3164: {}", frame);
+			return true;
+		}
+	};
+	let source = message.source();
+	if !sources.lock().map_or(false, |mut sources| sources.accept(&source)) {
+		return true;
+	}
+	message
+		.message
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.all(|line| tx.send(Ok((source.clone(), line.to_string(), None))).is_ok())
+}
+
+///! The next message from a TCP stream, framed by length or by a newline
+fn next_frame(reader: &mut impl BufRead) -> std::io::Result<Option<Vec<u8>>> {
+	let counted = match reader.fill_buf()?.first() {
+		Some(byte) => byte.is_ascii_digit(),
+		None => return Ok(None),
+	};
+
+	let mut frame = Vec::new();
+	if counted {
+		reader.by_ref().take(8).read_until(b' ', &mut frame)?;
+		let length = std::str::from_utf8(&frame)
+			.ok()
+			.and_then(|length| length.trim_end().parse::<usize>().ok())
+			.filter(|length| *length <= MESSAGE_MAX)
+			.ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid message length"))?;
+		frame = vec![0; length];
+		reader.read_exact(&mut frame)?;
+	} else {
+		reader.by_ref().take(MESSAGE_MAX as u64).read_until(b'\n', &mut frame)?;
+		while matches!(frame.last(), Some(b'\n') | Some(b'\r')) {
+			frame.pop();
+		}
+	}
+	Ok(Some(frame))
+}
+
+///! Parse an RFC 5424 or RFC 3164 message, where peer is the host of the sender
+pub fn parse_message(frame: &str, peer: &str) -> Option<SyslogMessage> {
+	let rest = frame.strip_prefix('<')?;
+	let priority_end = rest.find('>')?;
+	if priority_end == 0 || priority_end > 3 || !rest[..priority_end].chars().all(|c| c.is_ascii_digit()) {
+		return None;
+	}
+	let rest = &rest[priority_end + 1..];
+	match rest.strip_prefix("1 ") {
+		Some(rest) => parse_rfc5424(rest, peer),
+		None => Some(parse_rfc3164(rest, peer)),
+	}
+}
+
+///! TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG, where '-' is none
+fn parse_rfc5424(rest: &str, peer: &str) -> Option<SyslogMessage> {
+	let fields: Vec<&str> = rest.splitn(6, ' ').collect();
+	if fields.len() < 6 {
+		return None;
+	}
+	let given = |field: &str| if field == "-" { None } else { Some(field.to_string()) };
+
+	// Structured data is '-' or elements in brackets, whose quoted values may contain escaped ']'
+	let data = fields[5];
+	let message_start = if data.starts_with('-') {
+		1
+	} else {
+		let mut quoted = false;
+		let mut escaped = false;
+		let mut end = None;
+		for (index, c) in data.char_indices() {
+			match c {
+				_ if escaped => escaped = false,
+				'\\' if quoted => escaped = true,
+				'"' => quoted = !quoted,
+				']' if !quoted && !data[index + 1..].starts_with('[') => {
+					end = Some(index + 1);
+					break;
+				}
+				_ => {}
+			}
+		}
+		end?
+	};
+	let message = data[message_start..].strip_prefix(' ').unwrap_or(&data[message_start..]);
+
+	Some(SyslogMessage {
+		host: given(fields[1]).unwrap_or_else(|| peer.to_string()),
+		app: given(fields[2]).unwrap_or_else(|| String::from("-")),
+		message: message.trim_start_matches('\u{feff}').to_string(),
+	})
+}
+
+///! Mmm dd hh:mm:ss HOSTNAME TAG: MSG, where a sender may leave out the time or the host
+fn parse_rfc3164(rest: &str, peer: &str) -> SyslogMessage {
+	let bytes = rest.as_bytes();
+	let timestamped = bytes.len() > 16 && bytes[3] == b' ' && bytes[9] == b':' && bytes[12] == b':' && bytes[15] == b' ';
+	let rest = if timestamped { &rest[16..] } else { rest };
+
+	// A tag is a word ending with ':' or '[pid]:', so a word which isn't is the host
+	let is_tag = |word: &str| word.ends_with(':') || word.contains('[');
+	let (host, rest) = match rest.split_once(' ') {
+		Some((word, after)) if timestamped && !is_tag(word) => (word.to_string(), after),
+		_ => (peer.to_string(), rest),
+	};
+	let (app, message) = match rest.split_once(' ') {
+		Some((tag, message)) if is_tag(tag) => {
+			let app = tag.trim_end_matches(':');
+			let app = app.split('[').next().unwrap_or(app);
+			(app.to_string(), message)
+		}
+		_ => (String::from("-"), rest),
+	};
+
+	SyslogMessage {
+		host,
+		app,
+		message: message.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_and_frames_messages() {
+		let line = "[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] Syncing";
+		let message = |host: &str, app: &str| SyslogMessage {
+			host: host.to_string(),
+			app: app.to_string(),
+			message: line.to_string(),
+		};
+
+		let rfc5424 = format!("<165>1 2021-01-20T18:00:00Z node1 sn_node 4242 - [meta x=\"a\\]b\"] {}", line);
+		assert_eq!(parse_message(&rfc5424, "10.0.0.9"), Some(message("node1", "sn_node")));
+		let rfc5424 = format!("<165>1 - - - - - - {}", line);
+		assert_eq!(parse_message(&rfc5424, "10.0.0.9"), Some(message("10.0.0.9", "-")));
+		let rfc3164 = format!("<13>Jan 20 18:00:00 node2 sn_node[4242]: {}", line);
+		assert_eq!(parse_message(&rfc3164, "10.0.0.9"), Some(message("node2", "sn_node")));
+		let rfc3164 = format!("<13>Jan  2 18:00:00 sn_node: {}", line);
+		assert_eq!(parse_message(&rfc3164, "10.0.0.9"), Some(message("10.0.0.9", "sn_node")));
+		assert_eq!(parse_message(line, "10.0.0.9"), None);
+		assert_eq!(message("node1", "sn_node").source(), "syslog://node1/sn_node");
+
+		let mut stream = "11 <13>counted<13>by newline\r\n<13>last".as_bytes();
+		let mut frames = Vec::<String>::new();
+		while let Some(frame) = next_frame(&mut stream).unwrap() {
+			frames.push(String::from_utf8(frame).unwrap());
+		}
+		assert_eq!(frames, vec!["<13>counted", "<13>by newline", "<13>last"]);
+	}
+
+	#[test]
+	fn accepts_sources_up_to_the_limit() {
+		let mut sources = Sources::new(2);
+		assert!(sources.accept("syslog://node1/sn_node"));
+		assert!(sources.accept("syslog://node2/sn_node"));
+		assert!(!sources.accept("syslog://node3/sn_node"));
+		assert!(!sources.accept("syslog://node4/sn_node"));
+		assert!(sources.accept("syslog://node1/sn_node"));
+		assert_eq!((sources.accepted.len(), sources.dropped), (2, 2));
+	}
+}
@@ -0,0 +1,93 @@
+///! Tags: groups of nodes, such as by host, for showing only some of a fleet
+///!
+///! Nodes are tagged with --tag NAME=TAG, or in the [tags] section of the
+///! config file, where each tag lists the nodes it is given to:
+///!
+///!     [tags]
+///!     droplet-3 = ["1", "2"]
+///!     local = ["baby-fleming-nodes"]
+///!
+///! where nodes are named as for --data-dir. A node may have any number of
+///! tags. With a tag filter (--only-tag TAG, or cycled with '#') every view
+///! shows only the nodes with that tag.
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::opt::NodeLabel;
+
+///! The [tags] of the config file as --tag options
+pub fn config_tags(tags: &BTreeMap<String, Vec<String>>) -> Vec<NodeLabel> {
+	tags.iter()
+		.flat_map(|(tag, names)| {
+			names.iter().map(move |name| NodeLabel {
+				name: name.clone(),
+				label: tag.clone(),
+			})
+		})
+		.collect()
+}
+
+///! The tags given to the node numbered node (from 1) with logfile, in order and without repeats
+pub fn node_tags(tags: &[NodeLabel], node: usize, logfile: &str) -> Vec<String> {
+	let mut found = Vec::<String>::new();
+	for tag in NodeLabel::find_all(tags, node, logfile) {
+		if !found.contains(&tag) {
+			found.push(tag);
+		}
+	}
+	found
+}
+
+///! Whether a node with tags is shown with tag_filter
+pub fn shows(tag_filter: &Option<String>, tags: &[String]) -> bool {
+	match tag_filter {
+		Some(filter) => tags.contains(filter),
+		None => true,
+	}
+}
+
+///! The filter after tag_filter, cycling through the tags of every node in order and then none
+pub fn next_filter<'a>(tag_filter: &Option<String>, all_tags: impl Iterator<Item = &'a String>) -> Option<String> {
+	let all_tags: BTreeSet<&String> = all_tags.collect();
+	match tag_filter {
+		None => all_tags.into_iter().next().cloned(),
+		Some(filter) => all_tags.into_iter().find(|tag| *tag > filter).cloned(),
+	}
+}
+
+///! A view's title, noting any tag filter
+pub fn filtered_title(title: &str, tag_filter: &Option<String>) -> String {
+	match tag_filter {
+		Some(filter) => format!("{} (tag:{}, '#' for next)", title, filter),
+		None => title.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tags_nodes_and_cycles_filters() {
+		let mut config = BTreeMap::new();
+		config.insert(String::from("droplet-3"), vec![String::from("1"), String::from("node-02")]);
+		config.insert(String::from("local"), vec![String::from("1")]);
+		let mut tags = vec!["1=droplet-3".parse::<NodeLabel>().unwrap()];
+		tags.extend(config_tags(&config));
+		assert_eq!(node_tags(&tags, 1, "/var/log/node-01/sn_node.log"), ["droplet-3", "local"]);
+		assert_eq!(node_tags(&tags, 2, "/var/log/node-02/sn_node.log"), ["droplet-3"]);
+		assert!(node_tags(&tags, 3, "/var/log/node-03/sn_node.log").is_empty());
+
+		let node_1 = node_tags(&tags, 1, "/var/log/node-01/sn_node.log");
+		assert!(shows(&None, &[]));
+		assert!(shows(&Some(String::from("local")), &node_1));
+		assert!(!shows(&Some(String::from("local")), &[String::from("droplet-3")]));
+
+		let all_tags = [String::from("local"), String::from("droplet-3"), String::from("local")];
+		let first = next_filter(&None, all_tags.iter());
+		assert_eq!(first.as_deref(), Some("droplet-3"));
+		let second = next_filter(&first, all_tags.iter());
+		assert_eq!(second.as_deref(), Some("local"));
+		assert_eq!(next_filter(&second, all_tags.iter()), None);
+		assert_eq!(filtered_title("Summary", &second), "Summary (tag:local, '#' for next)");
+	}
+}
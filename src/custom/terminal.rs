@@ -0,0 +1,48 @@
+///! Setting up the terminal for the dashboard
+///!
+///! Some terminals, such as those built into some IDEs, lack an alternate
+///! screen, and there is no terminal at all when output is redirected. So the
+///! dashboard is drawn inline (in the main screen) when asked to with --inline
+///! or when TERM says the terminal can't do better, and each backend falls back
+///! to inline if entering the alternate screen fails. Without raw mode the
+///! dashboard can't be used, so vdash explains this rather than failing with
+///! the terminal half set up.
+use std::fmt::Display;
+use std::io::{Error, ErrorKind};
+
+use super::opt::Opt;
+
+///! Whether to draw on the alternate screen
+pub fn alternate_screen(opt: &Opt) -> bool {
+	uses_alternate_screen(opt.inline, std::env::var("TERM").ok().as_deref())
+}
+
+fn uses_alternate_screen(inline: bool, term: Option<&str>) -> bool {
+	!inline && term != Some("dumb")
+}
+
+///! Why the terminal couldn't be set up for the dashboard, given the error doing so
+pub fn setup_error(e: impl Display) -> Error {
+	Error::new(
+		ErrorKind::Other,
+		format!(
+			"vdash needs an interactive terminal, but couldn't set this one up ({}).\n\
+			Run vdash in a terminal window, or use --daemon to run it without one.",
+			e
+		),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn draws_inline_when_asked_or_on_dumb_terminals() {
+		assert!(uses_alternate_screen(false, Some("xterm-256color")));
+		assert!(uses_alternate_screen(false, None));
+		assert!(!uses_alternate_screen(true, Some("xterm-256color")));
+		assert!(!uses_alternate_screen(false, Some("dumb")));
+		assert!(setup_error("Inappropriate ioctl for device").to_string().contains("--daemon"));
+	}
+}
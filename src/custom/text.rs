@@ -0,0 +1,97 @@
+///! Text layout by display width rather than bytes or chars
+///!
+///! Node names, paths and messages may include non-ASCII characters, some of
+///! them (such as CJK and emoji) two columns wide, and combining marks which
+///! take none. Columns are padded and truncated by the width shown in the
+///! terminal so that they stay aligned, and only between characters.
+use std::borrow::Cow;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+#[derive(Clone, Copy)]
+pub enum Align {
+	Left,
+	Right,
+}
+
+///! The width of text in terminal columns
+pub fn width(text: &str) -> usize {
+	text.width()
+}
+
+///! Text shortened to at most width columns, ending with '…' if anything was removed
+pub fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+	if text.width() <= width {
+		return Cow::Borrowed(text);
+	}
+	if width == 0 {
+		return Cow::Borrowed("");
+	}
+
+	// Keep whole characters, with any zero width marks that follow them
+	let mut used = 0;
+	let mut end = 0;
+	for (i, c) in text.char_indices() {
+		let char_width = c.width().unwrap_or(0);
+		if used + char_width > width - 1 {
+			break;
+		}
+		used += char_width;
+		end = i + c.len_utf8();
+	}
+	Cow::Owned(format!("{}…", &text[..end]))
+}
+
+///! Text shortened to at most width columns, starting with '…' if anything was removed
+///!
+///! For paths and the like, whose end says the most.
+pub fn truncate_start(text: &str, width: usize) -> Cow<'_, str> {
+	if text.width() <= width {
+		return Cow::Borrowed(text);
+	}
+	if width == 0 {
+		return Cow::Borrowed("");
+	}
+
+	let mut used = 0;
+	let mut start = text.len();
+	for (i, c) in text.char_indices().rev() {
+		let char_width = c.width().unwrap_or(0);
+		if used + char_width > width - 1 {
+			break;
+		}
+		used += char_width;
+		start = i;
+	}
+	Cow::Owned(format!("…{}", &text[start..]))
+}
+
+///! Text padded with spaces to at least width columns, like format!("{:<width$}")
+pub fn pad(text: &str, width: usize, align: Align) -> String {
+	let padding = " ".repeat(width.saturating_sub(text.width()));
+	match align {
+		Align::Left => format!("{}{}", text, padding),
+		Align::Right => format!("{}{}", padding, text),
+	}
+}
+
+///! Text truncated or padded to exactly width columns
+pub fn fit(text: &str, width: usize, align: Align) -> String {
+	pad(&truncate(text, width), width, align)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fits_wide_and_combining_characters() {
+		assert_eq!(truncate("node", 4), "node");
+		assert_eq!(truncate("ノードの名前", 7), "ノード…");
+		assert_eq!(truncate("cafe\u{301} crème", 5), "cafe\u{301}…");
+		assert_eq!(fit("🚀 up", 6, Align::Right), " 🚀 up");
+		assert_eq!(width(&fit("ノードの名前", 6, Align::Left)), 6);
+		assert_eq!(pad("elder", 3, Align::Left), "elder");
+		assert_eq!(truncate_start("src/node/mod.rs:97", 10), "…mod.rs:97");
+		assert_eq!(truncate_start("ノードの名前", 5), "…名前");
+	}
+}
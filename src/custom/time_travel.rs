@@ -0,0 +1,267 @@
+///! Time travel ('r'): the focused node's metrics as they were at an earlier time
+///!
+///! The lines held for the node's logfile view are replayed, up to the time
+///! travelled to, into metrics of their own which the node view shows in
+///! place of the live metrics, while lines carry on arriving as usual. So
+///! the panels and timeline show what the dashboard looked like then, as far
+///! back as the lines held (see --retain-minutes to hold more). The metrics
+///! are checkpointed each column of the timeline, so moving replays only the
+///! lines from the nearest checkpoint before the time. Left and right
+///! move back and forward a column of the timeline, up and down ten, and 'i'
+///! and 'o' zoom the timeline to change the step. The selected line follows.
+use chrono::{DateTime, Duration, Local, Utc};
+use vault_metrics::{LogEntry, LogFormat, MetricLevel, NodeMetrics};
+
+use super::keymap::InputKey;
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	widgets::{Block, Borders, Paragraph},
+	Frame,
+};
+
+///! What a key does while time travelling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Travel {
+	///! Move by a number of timeline columns, back if negative
+	Step(i32),
+	ZoomIn,
+	ZoomOut,
+	///! Return to the live metrics
+	Close,
+}
+
+pub fn travel_command(key: InputKey) -> Option<Travel> {
+	match key {
+		InputKey::Left | InputKey::Char('h') => Some(Travel::Step(-1)),
+		InputKey::Right | InputKey::Char('l') => Some(Travel::Step(1)),
+		InputKey::Up | InputKey::Char('k') => Some(Travel::Step(-10)),
+		InputKey::Down | InputKey::Char('j') => Some(Travel::Step(10)),
+		InputKey::Char('i') | InputKey::Char('+') => Some(Travel::ZoomIn),
+		InputKey::Char('o') | InputKey::Char('-') => Some(Travel::ZoomOut),
+		InputKey::Esc | InputKey::Enter | InputKey::Char('r') | InputKey::Char('q') => Some(Travel::Close),
+		_ => None,
+	}
+}
+
+///! Most checkpoints kept, after which every other one is dropped
+const CHECKPOINTS_MAX: usize = 64;
+
+///! Metrics as of a point in the lines, from which replay can resume rather
+///! than starting again from the first line
+struct Checkpoint {
+	///! The number of lines replayed
+	index: usize,
+	///! The latest time of the lines replayed, so time travel to any time from
+	///! this on replays all of them
+	time: DateTime<Utc>,
+	///! The last line replayed, to tell whether the lines have changed under the checkpoint
+	last_line: Option<String>,
+	metrics: NodeMetrics,
+}
+
+impl Checkpoint {
+	fn fits(&self, lines: &[String]) -> bool {
+		match self.index {
+			0 => true,
+			index => lines.get(index - 1) == self.last_line.as_ref(),
+		}
+	}
+}
+
+pub struct TimeTravel {
+	///! The time travelled to
+	pub time: DateTime<Utc>,
+	///! Metrics as of time
+	pub metrics: NodeMetrics,
+	///! Health score as of time, see App::update_health()
+	pub health: u8,
+	///! Metrics checked against thresholds as of time
+	pub metric_levels: Vec<MetricLevel>,
+	///! Checkpoints in order of index, the first being before any line
+	checkpoints: Vec<Checkpoint>,
+	///! The least time between checkpoints, a column of the timeline
+	interval: Duration,
+}
+
+impl TimeTravel {
+	///! Travel to time, or as near as the lines allow, with live giving how the lines are parsed
+	pub fn new(
+		lines: &[String],
+		live: &NodeMetrics,
+		timeline_steps: usize,
+		interval: Duration,
+		time: DateTime<Utc>,
+	) -> Option<TimeTravel> {
+		let earliest = lines
+			.iter()
+			.find_map(|line| LogEntry::decode(line, &live.assumed_offset, None).and_then(|entry| entry.time))?;
+		let mut metrics = NodeMetrics::new_at(timeline_steps, live.assumed_offset, earliest);
+		if live.log_format == LogFormat::Plain {
+			metrics.log_format = LogFormat::Plain;
+		}
+		metrics.loading = true;
+		let mut travel = TimeTravel {
+			time,
+			metrics: metrics.clone(),
+			health: 100,
+			metric_levels: Vec::new(),
+			checkpoints: vec![Checkpoint { index: 0, time: earliest, last_line: None, metrics }],
+			interval,
+		};
+		travel.travel_to(lines, time);
+		Some(travel)
+	}
+
+	///! Time of the first line held, before which there is nothing to replay
+	pub fn earliest(&self) -> DateTime<Utc> {
+		self.checkpoints[0].time
+	}
+
+	///! Replay lines up to time, which is kept between the first line and now,
+	///! resuming from the latest checkpoint before time
+	pub fn travel_to(&mut self, lines: &[String], time: DateTime<Utc>) {
+		let time = time.max(self.earliest()).min(Utc::now());
+		self.checkpoints.retain(|checkpoint| checkpoint.fits(lines));
+		let resume = self.checkpoints.iter().rposition(|checkpoint| checkpoint.time <= time).unwrap_or(0);
+		let checkpoint = &self.checkpoints[resume];
+		let mut metrics = checkpoint.metrics.clone();
+		let mut latest = checkpoint.time;
+		let mut index = checkpoint.index;
+		for line in &lines[index.min(lines.len())..] {
+			let line_time = LogEntry::decode(line, &metrics.assumed_offset, None).and_then(|entry| entry.time);
+			if matches!(line_time, Some(line_time) if line_time > time) {
+				break;
+			}
+			let _ = metrics.gather_metrics(line);
+			index += 1;
+			latest = latest.max(line_time.unwrap_or(latest));
+			self.checkpoint(index, latest, line, &metrics);
+		}
+		metrics.loading = false;
+		metrics.as_of = Some(time);
+		metrics.update_timelines(Some(time));
+		self.metrics = metrics;
+		self.time = time;
+	}
+
+	///! Keep a checkpoint of metrics if it is a column of the timeline past the last one
+	fn checkpoint(&mut self, index: usize, time: DateTime<Utc>, line: &str, metrics: &NodeMetrics) {
+		let last = &self.checkpoints[self.checkpoints.len() - 1];
+		if index <= last.index || time < last.time + self.interval {
+			return;
+		}
+		self.checkpoints.push(Checkpoint { index, time, last_line: Some(line.to_string()), metrics: metrics.clone() });
+		if self.checkpoints.len() > CHECKPOINTS_MAX {
+			let mut keep = false;
+			self.checkpoints.retain(|_| {
+				keep = !keep;
+				keep
+			});
+		}
+	}
+
+	///! Lines expiring from the start of those held, which go into the first
+	///! checkpoint so that later checkpoints still resume from it
+	pub fn forget_lines(&mut self, expired: &[String]) {
+		let count = expired.len();
+		self.checkpoints.retain(|checkpoint| checkpoint.index == 0 || checkpoint.index > count);
+		for checkpoint in self.checkpoints.iter_mut().skip(1) {
+			checkpoint.index -= count;
+		}
+		let first = &mut self.checkpoints[0];
+		for line in expired {
+			let line_time = LogEntry::decode(line, &first.metrics.assumed_offset, None).and_then(|entry| entry.time);
+			let _ = first.metrics.gather_metrics(line);
+			first.time = first.time.max(line_time.unwrap_or(first.time));
+		}
+	}
+
+	pub fn step(&mut self, lines: &[String], by: Duration) {
+		self.travel_to(lines, self.time + by);
+	}
+}
+
+pub fn draw_time_travel_banner<B: Backend>(f: &mut Frame<B>, area: Rect, travel: &TimeTravel) {
+	let text = format!(
+		"Showing metrics as of {} (left/right one column, up/down ten, i/o zoom, esc returns to live)",
+		travel.time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")
+	);
+	let banner = Paragraph::new(text.as_str())
+		.style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+		.block(Block::default().borders(Borders::ALL).title("Time travel"));
+	f.render_widget(banner, area);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use vault_metrics::AssumedOffset;
+
+	#[test]
+	fn replays_lines_up_to_time() {
+		let lines: Vec<String> = vec![
+			"[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] first",
+			"[sn_node] ERROR 2021-01-20T18:10:00.000000+00:00 [src/a.rs:1] second",
+			"  continued",
+			"[sn_node] INFO 2021-01-20T18:20:00.000000+00:00 [src/a.rs:1] third",
+		]
+		.into_iter()
+		.map(String::from)
+		.collect();
+		let live = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+
+		let mut travel = TimeTravel::new(&lines, &live, 20, Duration::minutes(1), at("2021-01-20T18:05:00Z")).unwrap();
+		assert_eq!(travel.metrics.category_count.get("INFO"), Some(&1));
+		assert_eq!(travel.metrics.category_count.get("ERROR"), None);
+		assert_eq!(travel.metrics.idle_time(), Some(Duration::minutes(5)));
+
+		travel.step(&lines, Duration::minutes(10));
+		assert_eq!(travel.metrics.category_count.get("ERROR"), Some(&1));
+		assert_eq!(travel.metrics.category_count.get("INFO"), Some(&1));
+
+		// Not before the first line
+		travel.step(&lines, Duration::hours(-1));
+		assert_eq!(travel.time, at("2021-01-20T18:00:00Z"));
+		assert!(TimeTravel::new(&[], &live, 20, Duration::minutes(1), Utc::now()).is_none());
+
+		assert_eq!(travel_command(InputKey::Left), Some(Travel::Step(-1)));
+		assert_eq!(travel_command(InputKey::Esc), Some(Travel::Close));
+	}
+
+	#[test]
+	fn replays_from_checkpoints() {
+		let mut lines: Vec<String> = (0..30)
+			.map(|minute| format!("[sn_node] ERROR 2021-01-20T18:{:02}:00.000000+00:00 [src/a.rs:1] failed", minute))
+			.collect();
+		let live = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		let at = |minute: i64| DateTime::parse_from_rfc3339("2021-01-20T18:00:00Z").unwrap().with_timezone(&Utc) + Duration::minutes(minute);
+		let errors = |travel: &TimeTravel| travel.metrics.category_count.get("ERROR").copied();
+
+		// Going to the last line leaves a checkpoint every five minutes
+		let mut travel = TimeTravel::new(&lines, &live, 20, Duration::minutes(5), at(29)).unwrap();
+		assert_eq!(errors(&travel), Some(30));
+		assert_eq!(travel.checkpoints.iter().map(|checkpoint| checkpoint.index).collect::<Vec<usize>>(), [0, 6, 11, 16, 21, 26]);
+
+		// Stepping back resumes from a checkpoint, as replaying from the first line would
+		travel.step(&lines, Duration::minutes(-17));
+		assert_eq!(errors(&travel), Some(13));
+		assert_eq!(errors(&TimeTravel::new(&lines, &live, 20, Duration::minutes(5), at(12)).unwrap()), Some(13));
+
+		// Lines which expire still count, and checkpoints move with the lines
+		travel.forget_lines(&lines[..8]);
+		lines.drain(..8);
+		assert_eq!(travel.earliest(), at(7));
+		travel.step(&lines, Duration::minutes(2));
+		assert_eq!(errors(&travel), Some(15));
+
+		// A checkpoint whose lines have changed is dropped
+		lines[2] = "[sn_node] INFO 2021-01-20T18:10:00.000000+00:00 [src/a.rs:1] replaced".to_string();
+		travel.step(&lines, Duration::minutes(0));
+		assert_eq!(errors(&travel), Some(14));
+		assert_eq!(travel.checkpoints.iter().map(|checkpoint| checkpoint.index).collect::<Vec<usize>>(), [0, 8, 13, 18]);
+	}
+}
@@ -2,30 +2,525 @@
 ///
 /// Edit src/custom/ui.rs to create a customised fork of logtail-dash
 
-use super::app::{TIMELINES, App, DashState, DashViewMain, LogMonitor, DEBUG_WINDOW_NAME};
+use super::app::{TIMELINES, App, BucketSet, Celebration, DashState, DashViewMain, LogMonitor, NodeStatus, DEBUG_WINDOW_NAME, FADE_STEPS, OUTLIER_TIMELINE};
+use super::config::NodeWidget;
+use super::data_dir;
+use vault_metrics::{
+	add_counts, apdex_score, latency_bucket_label, level_of, node_config, Identity, Level, LogEntry, Metric, MetricLevel,
+	NetworkEstimate, NodeAddress, NodeMetrics, LATENCY_BUCKETS, LATENCY_MINUTES,
+};
+use super::palette::Palette;
+use super::parser_view::draw_parser_view;
+use super::tags;
+use super::text::{self, Align};
 use super::ui_debug::draw_dashboard as debug_draw_dashboard;
+use super::columns::{aligned_prefix, draw_table_dash};
+use super::filter::draw_filter_editor;
+use super::alerts::{draw_alert_list, draw_alerts_bar};
+use super::focus::{self, Focus};
+use super::groups::{Grouping, SummaryGroups};
+use super::app_error::draw_error_toast;
+use super::goto::draw_goto_prompt;
+use super::line_detail::draw_line_detail;
+use super::node_control::draw_control_menu;
+use super::regex_playground::draw_regex_dash;
+use super::search::draw_search_dash;
+use super::splits::Split;
+use super::time_travel::draw_time_travel_banner;
+use super::ui_top::draw_top_dash;
+use super::watched::draw_node_watched;
 
 #[path = "../widgets/mod.rs"]
 pub mod widgets;
 use self::widgets::sparkline::Sparkline2;
 use self::widgets::gauge::Gauge2;
-use std::collections::HashMap;
+use self::widgets::monochrome::Monochrome;
+use chrono::{DateTime, Duration, Local, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
 
 use tui::{
 	backend::Backend,
-	layout::{Constraint, Direction, Layout, Rect},
+	layout::{Alignment, Constraint, Direction, Layout, Rect},
 	style::{Color, Modifier, Style},
-	text::{Spans},
-	widgets::{Block, Borders, List, ListItem},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
 	Frame,
 };
 
 pub fn draw_dashboard<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+	let started = Instant::now();
+	// Log content isn't drawn at all in low power mode
+	if app.dash_state.low_power {
+		let shown = app.shown_logfile_names();
+		draw_summary_dash(f, &app.dash_state, &shown, &mut app.monitors);
+	} else {
+		draw_view(f, app);
+	}
+
+	if matches!(&app.dash_state.celebration, Some(celebration) if celebration.is_over(started)) {
+		app.dash_state.celebration = None;
+	}
+	if let Some(celebration) = &app.dash_state.celebration {
+		draw_celebration(f, celebration);
+	}
+
+	match &app.dash_state.alert_list {
+		Some(list) => draw_alert_list(f, list, app.session.alerts()),
+		None => draw_alerts_bar(f, app.session.alerts()),
+	}
+
+	draw_error_toast(f, &app.dash_state.error_toasts);
+
+	if let Some(palette) = app.keymap.palette() {
+		draw_palette(f, palette);
+	}
+
+	if app.dash_state.no_color {
+		f.render_widget(Monochrome, f.size());
+	}
+
+	for monitor in app.monitors.values_mut() {
+		monitor.dirty = false;
+	}
+	app.dash_state.self_metrics.record_frame(started.elapsed());
+}
+
+///! The main view, with any popup over it
+fn draw_view<B: Backend>(f: &mut Frame<B>, app: &mut App) {
 	match app.dash_state.main_view {
-		DashViewMain::DashSummary => {} //draw_summary_dash(f, dash_state, monitors),
+		DashViewMain::DashSummary => {
+			let shown = app.shown_logfile_names();
+			draw_summary_dash(f, &app.dash_state, &shown, &mut app.monitors)
+		}
 		DashViewMain::DashNode => draw_node_dash(f, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashTop => draw_top_dash(f, &app.dash_state, &app.shown_logfile_names(), &app.monitors),
+		DashViewMain::DashRegex => {
+			draw_regex_dash(f, &app.dash_state, app.monitors.get(&app.dash_state.dash_node_focus))
+		}
+		DashViewMain::DashSearch => draw_search_dash(f, &app.dash_state.search),
+		DashViewMain::DashTable => {
+			draw_table_dash(f, &app.dash_state, app.monitors.get(&app.dash_state.dash_node_focus))
+		}
 		DashViewMain::DashDebug => debug_draw_dashboard(f, &mut app.dash_state, &mut app.monitors),
 	}
+
+	if let Some(editor) = &app.dash_state.filter_editor {
+		draw_filter_editor(f, editor, app.monitors.get(&app.logfile_with_focus));
+	}
+
+	if let Some(prompt) = &app.dash_state.goto_prompt {
+		draw_goto_prompt(f, prompt);
+	}
+
+	if let Some(detail) = &app.dash_state.line_detail {
+		draw_line_detail(f, detail);
+	}
+
+	if let Some(menu) = &app.dash_state.control_menu {
+		draw_control_menu(f, menu);
+	}
+}
+
+fn draw_palette<B: Backend>(f: &mut Frame<B>, palette: &Palette) {
+	let size = f.size();
+	let width = size.width.min(60);
+	let height = size.height.min(16);
+	let area = Rect::new(size.x + (size.width - width) / 2, size.y + 2, width, height);
+
+	let mut items = vec![ListItem::new(vec![Spans::from(format!(": {}", palette.query))])
+		.style(Style::default().fg(Color::Yellow))];
+	for action in palette.matches() {
+		items.push(ListItem::new(vec![Spans::from(format!("  {}", action.description()))]));
+	}
+
+	let mut state = ListState::default();
+	state.select(Some(palette.selected + 1));
+	let palette_widget = List::new(items)
+		.block(Block::default().borders(Borders::ALL).title("Command Palette"))
+		.highlight_style(Style::default().bg(Color::LightGreen).add_modifier(Modifier::BOLD));
+	f.render_widget(Clear, area);
+	f.render_stateful_widget(palette_widget, area, &mut state);
+}
+
+pub fn status_colour(status: NodeStatus) -> Color {
+	match status {
+		NodeStatus::Ok => Color::Green,
+		NodeStatus::Warn => Color::Yellow,
+		NodeStatus::Error => Color::Red,
+		NodeStatus::Stalled => Color::Magenta,
+	}
+}
+
+fn draw_summary_dash<B: Backend>(
+	f: &mut Frame<B>,
+	dash_state: &DashState,
+	logfile_names: &[String],
+	monitors: &mut HashMap<String, LogMonitor>,
+) {
+	let mut items = Vec::<ListItem>::new();
+	items.push(
+		ListItem::new(vec![Spans::from(format_summary_row([
+			"Node", "Status", "Health", "Avail", "Role", "Age", "GETS", "PUTS", "ERRORS", "Address",
+			"Last Error", "Outliers",
+		]))])
+		.style(Style::default().fg(Color::Yellow)),
+	);
+
+	let summary_monitors: Vec<&LogMonitor> = logfile_names
+		.iter()
+		.filter_map(|logfile| monitors.get(logfile))
+		.filter(|monitor| !monitor.is_debug_dashboard_log)
+		.collect();
+
+	let mut network_card = format_network_card(&summary_monitors);
+	network_card.extend(format_fleet_apdex(&summary_monitors, dash_state.apdex_ms));
+	let rows = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(network_card.len() as u16 + 2), Constraint::Min(0)].as_ref())
+		.split(f.size());
+	let card_colour = if network_card.iter().any(|(_, colour)| *colour == Color::Red) {
+		Color::Red
+	} else {
+		Color::Reset
+	};
+	let card_items: Vec<ListItem> = network_card
+		.into_iter()
+		.map(|(line, colour)| ListItem::new(vec![Spans::from(line)]).style(Style::default().fg(colour)))
+		.collect();
+	let card_widget = List::new(card_items).block(
+		Block::default()
+			.borders(Borders::ALL)
+			.border_style(Style::default().fg(card_colour))
+			.title("Network"),
+	);
+	f.render_widget(card_widget, rows[0]);
+
+	let mut selected = None;
+	let groups = &dash_state.summary_groups;
+	let grouped = group_monitors(groups, &summary_monitors);
+	if !grouped.is_empty() {
+		for (name, group_monitors) in grouped.iter() {
+			let collapsed = groups.is_collapsed(name);
+			// A collapsed group stands in for its nodes, the focused node included
+			if collapsed && group_monitors.iter().any(|monitor| monitor.has_focus) {
+				selected = Some(items.len());
+			}
+			items.push(format_group_item(groups.grouping, name, group_monitors, collapsed));
+			if collapsed {
+				continue;
+			}
+			for monitor in group_monitors {
+				if monitor.has_focus {
+					selected = Some(items.len());
+				}
+				items.push(format_summary_item(monitor));
+			}
+		}
+	} else {
+		for monitor in summary_monitors {
+			if monitor.has_focus {
+				selected = Some(items.len());
+			}
+			items.push(format_summary_item(monitor));
+		}
+	}
+
+	// The focused node is selected while the table has the focus, so up and down are seen to move it
+	let focused = dash_state.focus == Focus::Summary;
+	let summary_widget = List::new(items)
+		.block(
+			Block::default()
+				.borders(Borders::ALL)
+				.border_style(focus::border_style(focused))
+				.title(tags::filtered_title(summary_title(dash_state), &dash_state.tag_filter)),
+		)
+		.highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+	let mut state = ListState::default();
+	state.select(selected.filter(|_| focused));
+	f.render_stateful_widget(summary_widget, rows[1], &mut state);
+}
+
+fn summary_title(dash_state: &DashState) -> &'static str {
+	match dash_state.low_power {
+		true => "Summary (low power, 'z' for all views)",
+		false => "Summary",
+	}
+}
+
+///! Most sections and disagreements listed on the network card
+const NETWORK_CARD_LINES_MAX: usize = 8;
+
+///! Lines of the network card: elders and adults reconciled from all nodes
+fn format_network_card(monitors: &[&LogMonitor]) -> Vec<(String, Color)> {
+	let estimate = NetworkEstimate::new(monitors.iter().map(|monitor| &monitor.metrics));
+	if estimate.sections.is_empty() {
+		return vec![(String::from("No elder or adult counts reported yet"), Color::Reset)];
+	}
+
+	let sections = if estimate.sections.len() == 1 { "section" } else { "sections" };
+	let mut lines = vec![(
+		format!(
+			"Estimated {} elders and {} adults in {} {}",
+			estimate.elders,
+			estimate.adults,
+			estimate.sections.len(),
+			sections
+		),
+		Color::Reset,
+	)];
+	let node_number = |node: usize| monitors[node].index + 1;
+	for section in estimate.sections.iter() {
+		let prefix = if section.prefix.is_empty() { "unknown" } else { &section.prefix };
+		lines.push((
+			format!(
+				"  Section {}: {} elders, {} adults (node {}, {})",
+				prefix,
+				section.report.elders,
+				section.report.adults,
+				node_number(section.report.node),
+				format_age(section.report.time)
+			),
+			Color::Reset,
+		));
+		for report in section.disagreements.iter() {
+			lines.push((
+				format!(
+					"    ⚠ node {} reports {} elders, {} adults ({}): possible partition",
+					node_number(report.node),
+					report.elders,
+					report.adults,
+					format_age(report.time)
+				),
+				Color::Red,
+			));
+		}
+	}
+
+	if lines.len() > NETWORK_CARD_LINES_MAX + 1 {
+		let hidden = lines.len() - NETWORK_CARD_LINES_MAX;
+		lines.truncate(NETWORK_CARD_LINES_MAX);
+		lines.push((format!("  ...and {} more", hidden), Color::Reset));
+	}
+	lines
+}
+
+///! The Apdex score of every node's requests together, and the lowest of any node's
+fn format_fleet_apdex(monitors: &[&LogMonitor], threshold_ms: f64) -> Option<(String, Color)> {
+	let now = Utc::now();
+	let mut counts = [0u64; LATENCY_BUCKETS];
+	let mut lowest: Option<(usize, f64)> = None;
+	let mut scored = 0;
+	for monitor in monitors.iter() {
+		let node_counts = monitor.metrics.latency.recent_counts(now, LATENCY_MINUTES);
+		if let Some(score) = apdex_score(&node_counts, threshold_ms) {
+			scored += 1;
+			if lowest.map_or(true, |(_, lowest)| score < lowest) {
+				lowest = Some((monitor.index, score));
+			}
+		}
+		add_counts(&mut counts, &node_counts);
+	}
+	let score = apdex_score(&counts, threshold_ms)?;
+	let requests: u64 = counts.iter().sum();
+	let mut line = format!(
+		"Apdex {:.2} over {} requests in {}m (satisfied within {}ms)",
+		score, requests, LATENCY_MINUTES, threshold_ms
+	);
+	if let (Some((index, lowest)), true) = (lowest, scored > 1) {
+		line.push_str(&format!(", lowest node {} at {:.2}", index + 1, lowest));
+	}
+	Some((line, apdex_colour(score)))
+}
+
+///! Colour of an Apdex score, by the usual ratings: good from 0.85, fair from 0.7 and poor below
+pub fn apdex_colour(score: f64) -> Color {
+	if score >= 0.85 {
+		Color::Reset
+	} else if score >= 0.7 {
+		Color::Yellow
+	} else {
+		Color::Red
+	}
+}
+
+///! How long ago time was, e.g. '3m ago'
+fn format_age(time: DateTime<Utc>) -> String {
+	let age = Utc::now() - time;
+	if age.num_hours() > 0 {
+		format!("{}h ago", age.num_hours())
+	} else if age.num_minutes() > 0 {
+		format!("{}m ago", age.num_minutes())
+	} else {
+		format!("{}s ago", age.num_seconds().max(0))
+	}
+}
+
+fn format_summary_item<'a>(monitor: &LogMonitor) -> ListItem<'a> {
+	let metrics = &monitor.metrics;
+	let status = metrics.status();
+	let gone = monitor.gone_for(Instant::now()).is_some();
+	// Metrics beyond their thresholds are outliers too
+	let breaches: Vec<&MetricLevel> = monitor.metric_levels.iter().filter(|level| level.level > Level::Ok).collect();
+	let mut outliers = metrics.outliers();
+	outliers.extend(breaches.iter().map(|breach| format!("{} ({})", breach, breach.level)));
+	let outliers = if outliers.is_empty() { "-".to_string() } else { outliers.join("; ") };
+	let address = match (&metrics.address.current, metrics.address.changed()) {
+		(Some(current), true) => format!("{}⚠", current),
+		(Some(current), false) => current.clone(),
+		(None, _) => "-".to_string(),
+	};
+	let last_error = match metrics.last_error() {
+		Some(error) => match error.time {
+			Some(time) => format!("{} {}", format_age(time), error.message),
+			None => error.message.clone(),
+		},
+		None => "-".to_string(),
+	};
+	let cells = format_summary_cells([
+		&(monitor.index + 1).to_string(),
+		&if gone { String::from("✝ GONE") } else { status.to_string() },
+		&monitor.health.to_string(),
+		&monitor.availability.describe(),
+		&metrics.agebracket_string(),
+		&metrics.node_age.to_string(),
+		&metrics.activity_gets.to_string(),
+		&metrics.activity_puts.to_string(),
+		&metrics.activity_errors.to_string(),
+		&address,
+		&last_error,
+		&outliers,
+	]);
+	// Cells with a colour of their own, the rest taking the status colour
+	let worst_breach = breaches.iter().map(|breach| breach.level).fold(Level::Ok, |worst, level| if level > worst { level } else { worst });
+	let colours = [
+		(2, Some(health_colour(monitor.health))),
+		(8, level_colour(level_of(&monitor.metric_levels, Metric::ErrorRate))),
+		(11, level_colour(worst_breach)),
+	];
+	let spans: Vec<Span> = cells
+		.iter()
+		.enumerate()
+		.map(|(index, cell)| match colours.iter().find(|(cell_index, _)| *cell_index == index) {
+			Some((_, Some(colour))) => Span::styled(cell.clone(), Style::default().fg(*colour)),
+			_ => Span::raw(cell.clone()),
+		})
+		.collect();
+	// A node whose logfile has gone is greyed out, its metrics being frozen
+	let colour = if gone { Color::DarkGray } else { status_colour(status) };
+	ListItem::new(vec![Spans::from(spans)]).style(Style::default().fg(colour))
+}
+
+pub fn health_colour(health: u8) -> Color {
+	match health {
+		80..=100 => Color::Green,
+		50..=79 => Color::Yellow,
+		_ => Color::Red,
+	}
+}
+
+///! The monitors of each group in order, once any node's group is known (such
+///! as its section prefix), with those of no group last
+fn group_monitors<'a>(groups: &SummaryGroups, monitors: &[&'a LogMonitor]) -> Vec<(Option<String>, Vec<&'a LogMonitor>)> {
+	let names: Vec<Option<String>> = monitors.iter().map(|monitor| groups.group_of(monitor)).collect();
+	if !names.iter().any(Option::is_some) {
+		return Vec::new();
+	}
+	let mut grouped = BTreeMap::<(bool, Option<String>), Vec<&LogMonitor>>::new();
+	for (monitor, name) in monitors.iter().zip(names) {
+		grouped.entry((name.is_none(), name)).or_default().push(*monitor);
+	}
+	grouped.into_iter().map(|((_, name), monitors)| (name, monitors)).collect()
+}
+
+fn format_group_item<'a>(grouping: Grouping, name: &Option<String>, monitors: &[&LogMonitor], collapsed: bool) -> ListItem<'a> {
+	let worst = monitors.iter().map(|monitor| monitor.metrics.status()).max().unwrap_or(NodeStatus::Ok);
+	ListItem::new(vec![Spans::from(group_heading(grouping, name, monitors, collapsed))])
+		.style(Style::default().fg(status_colour(worst)).add_modifier(Modifier::BOLD))
+}
+
+///! The heading of a group with a count of its nodes in each status, and its totals while it is collapsed
+fn group_heading(grouping: Grouping, name: &Option<String>, monitors: &[&LogMonitor], collapsed: bool) -> String {
+	let statuses: Vec<NodeStatus> = monitors.iter().map(|monitor| monitor.metrics.status()).collect();
+	let mut counts = Vec::<String>::new();
+	for status in [NodeStatus::Error, NodeStatus::Stalled, NodeStatus::Warn, NodeStatus::Ok].iter() {
+		let count = statuses.iter().filter(|s| *s == status).count();
+		if count > 0 {
+			counts.push(format!("{} {}", count, status));
+		}
+	}
+
+	let nodes = if monitors.len() == 1 { "node" } else { "nodes" };
+	let mut heading = format!(
+		"{} {} {} ({} {}): {}",
+		if collapsed { "▸" } else { "▾" },
+		grouping.heading(),
+		name.as_deref().unwrap_or(grouping.unnamed()),
+		monitors.len(),
+		nodes,
+		counts.join(", ")
+	);
+	if collapsed {
+		let total = |count: fn(&LogMonitor) -> u64| monitors.iter().map(|monitor| count(monitor)).sum::<u64>();
+		let health = monitors.iter().map(|monitor| monitor.health).min().unwrap_or(0);
+		heading.push_str(&format!(
+			"; GETS {}, PUTS {}, ERRORS {}, lowest health {}",
+			total(|monitor| monitor.metrics.activity_gets),
+			total(|monitor| monitor.metrics.activity_puts),
+			total(|monitor| monitor.metrics.activity_errors),
+			health
+		));
+	}
+	heading
+}
+
+// Columns: node, status, health, availability, role, age, gets, puts, errors, address, last error, outliers
+fn format_summary_row(columns: [&str; 12]) -> String {
+	format_summary_cells(columns).concat()
+}
+
+///! Each column fitted to its width, with the space after it, so that cells can be coloured apart
+fn format_summary_cells(columns: [&str; 12]) -> [String; 12] {
+	let [node, status, health, availability, role, age, gets, puts, errors, address, last_error, outliers] = columns;
+	[
+		text::fit(node, 4, Align::Right) + " ",
+		text::fit(status, 9, Align::Left) + " ",
+		text::fit(health, 6, Align::Right) + " ",
+		text::fit(availability, 6, Align::Right) + " ",
+		text::fit(role, 8, Align::Left) + " ",
+		text::fit(age, 4, Align::Right) + " ",
+		text::fit(gets, 8, Align::Right) + " ",
+		text::fit(puts, 8, Align::Right) + " ",
+		text::fit(errors, 8, Align::Right) + "  ",
+		text::fit(address, 22, Align::Left) + " ",
+		text::fit(last_error, 36, Align::Left) + " ",
+		outliers.to_string(),
+	]
+}
+
+///! What the node view shows of a node: its live metrics, or while time
+///! travelling those as of the time travelled to
+struct NodeView<'a> {
+	metrics: &'a NodeMetrics,
+	health: u8,
+	metric_levels: &'a [MetricLevel],
+}
+
+impl<'a> NodeView<'a> {
+	fn of(monitor: &'a LogMonitor) -> NodeView<'a> {
+		match &monitor.time_travel {
+			Some(travel) => NodeView {
+				metrics: &travel.metrics,
+				health: travel.health,
+				metric_levels: &travel.metric_levels,
+			},
+			None => NodeView {
+				metrics: &monitor.metrics,
+				health: monitor.health,
+				metric_levels: &monitor.metric_levels,
+			},
+		}
+	}
 }
 
 fn draw_node_dash<B: Backend>(
@@ -33,26 +528,49 @@ fn draw_node_dash<B: Backend>(
 	dash_state: &mut DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
-	// Horizonatal bands:
-	let constraints = [
-		Constraint::Length(12), // Stats summary and graphs
-		Constraint::Length(18), // Timeline
-		Constraint::Min(0),     // Bottom panel
-	];
+	// Horizonatal bands: stats summary and graphs, timeline and bottom panel
+	let constraints = dash_state.splits.node_constraints();
+	dash_state.splits.clear_borders();
 
 	let size = f.size();
-	let chunks = Layout::default()
-		.direction(Direction::Vertical)
-		.constraints(constraints.as_ref())
-		.split(size);
-
 	for entry in monitors.into_iter() {
 		let (logfile, mut monitor) = entry;
 		if monitor.has_focus {
-			// Stats and Graphs / Timeline / Logfile
-			draw_node(f, chunks[0], dash_state, &mut monitor);
-			draw_timeline(f, chunks[1], dash_state, &mut monitor);
-			draw_bottom_panel(f, chunks[2], dash_state, &logfile, &mut monitor);
+			let mut area = size;
+			let banner_rows = || {
+				Layout::default()
+					.direction(Direction::Vertical)
+					.constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+					.split(size)
+			};
+			if let Some(travel) = &monitor.time_travel {
+				let rows = banner_rows();
+				draw_time_travel_banner(f, rows[0], travel);
+				area = rows[1];
+			} else if let Some(warning) = monitor
+				.gone_warning()
+				.or_else(|| monitor.metrics.log_format_warning())
+				.or_else(|| clock_skew_warning(dash_state, monitor))
+			{
+				let rows = banner_rows();
+				draw_banner(f, rows[0], &warning);
+				area = rows[1];
+			}
+
+			let chunks = Layout::default()
+				.direction(Direction::Vertical)
+				.constraints(constraints.as_ref())
+				.split(area);
+			dash_state.splits.drawn(Split::Stats, area, chunks[1].y);
+			dash_state.splits.drawn(Split::Timeline, area, chunks[2].y);
+
+			// Stats and Graphs / Timeline / Config and Logfile
+			let view = NodeView::of(monitor);
+			draw_node(f, chunks[0], dash_state, monitor, &view);
+			draw_timeline(f, chunks[1], dash_state, view.metrics);
+			let bottom = draw_node_settings(f, chunks[2], dash_state, view.metrics);
+			draw_bottom_panel(f, bottom, dash_state, &logfile, &mut monitor);
+			dash_state.splits.draw_mark(f);
 			return;
 		}
 	}
@@ -60,87 +578,437 @@ fn draw_node_dash<B: Backend>(
 	draw_debug_window(f, size, dash_state);
 }
 
-fn draw_node<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState, monitor: &mut LogMonitor) {
-	// Columns:
-	let constraints = [
-		Constraint::Length(40), // Stats summary
-		Constraint::Min(10),    // Graphs
-	];
+fn clock_skew_warning(dash_state: &DashState, monitor: &LogMonitor) -> Option<String> {
+	if dash_state.clock_skew_secs <= 0 {
+		return None;
+	}
+	monitor.metrics.clock_skew_warning(chrono::Duration::seconds(dash_state.clock_skew_secs))
+}
+
+fn draw_banner<B: Backend>(f: &mut Frame<B>, area: Rect, text: &str) {
+	let banner = Paragraph::new(text)
+		.style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+		.block(Block::default().borders(Borders::ALL).title("Warning"))
+		.wrap(Wrap { trim: true });
+	f.render_widget(banner, area);
+}
+
+///! 'ELDER!' in large letters, for --celebrate
+static ELDER_BANNER: [&str; 5] = [
+	"█████ █     ████  █████ ████  █",
+	"█     █     █   █ █     █   █ █",
+	"████  █     █   █ ████  ████  █",
+	"█     █     █   █ █     █  █   ",
+	"█████ █████ ████  █████ █   █ █",
+];
+
+///! A node's promotion to Elder, in large letters over the middle of the screen
+fn draw_celebration<B: Backend>(f: &mut Frame<B>, celebration: &Celebration) {
+	let message = format!("Node {} ({}) has been promoted to Elder", celebration.node, celebration.logfile);
+	let size = f.size();
+	let width = (text::width(&message).max(text::width(ELDER_BANNER[0])) as u16 + 6).min(size.width);
+	let height = (ELDER_BANNER.len() as u16 + 5).min(size.height);
+	let area = Rect::new(
+		size.x + (size.width - width) / 2,
+		size.y + (size.height - height) / 2,
+		width,
+		height,
+	);
+
+	let banner_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+	let mut lines: Vec<Spans> = vec![Spans::from("")];
+	lines.extend(ELDER_BANNER.iter().map(|row| Spans::from(Span::styled(*row, banner_style))));
+	lines.push(Spans::from(""));
+	lines.push(Spans::from(message));
+	let banner = Paragraph::new(lines)
+		.alignment(Alignment::Center)
+		.block(Block::default().borders(Borders::ALL).title("Congratulations"));
+	f.render_widget(Clear, area);
+	f.render_widget(banner, area);
+}
+
+fn draw_node<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState, monitor: &LogMonitor, view: &NodeView) {
+	// Columns, as chosen in the config file
+	let widgets = dash_state.node_widgets.clone();
+	let constraints: Vec<Constraint> = widgets
+		.iter()
+		.map(|widget| match widget {
+			NodeWidget::Counts => Constraint::Length(40),
+			NodeWidget::Rates => Constraint::Length(30),
+			NodeWidget::Peers => Constraint::Length(30),
+			NodeWidget::Errors => Constraint::Length(50),
+			NodeWidget::Storage => Constraint::Min(10),
+			NodeWidget::Latency => Constraint::Length(62),
+			NodeWidget::Duplicates => Constraint::Length(40),
+			NodeWidget::Data => Constraint::Length(40),
+			NodeWidget::Watched => Constraint::Length(50),
+			NodeWidget::Rewards => Constraint::Length(36),
+		})
+		.collect();
 
 	let chunks = Layout::default()
 		.direction(Direction::Horizontal)
-		.constraints(constraints.as_ref())
+		.constraints(constraints.as_slice())
 		.split(area);
 
-	draw_node_stats(f, chunks[0], monitor);
-	draw_node_storage(f, chunks[1], dash_state, monitor);
+	for (widget, chunk) in widgets.iter().zip(chunks) {
+		match widget {
+			NodeWidget::Counts => draw_node_stats(f, chunk, monitor, view),
+			NodeWidget::Rates => draw_node_rates(f, chunk, monitor, view),
+			NodeWidget::Peers => draw_node_peers(f, chunk, monitor, view),
+			NodeWidget::Errors => draw_node_errors(f, chunk, monitor, view),
+			NodeWidget::Storage => draw_node_storage(f, chunk, dash_state, monitor, view),
+			NodeWidget::Latency => draw_node_latency(f, chunk, dash_state, monitor, view),
+			NodeWidget::Duplicates => draw_node_duplicates(f, chunk, monitor, view),
+			NodeWidget::Data => draw_node_data_types(f, chunk, monitor, view),
+			NodeWidget::Watched => draw_node_watched(f, chunk, monitor),
+			NodeWidget::Rewards => draw_node_rewards(f, chunk, monitor, view),
+		}
+	}
 }
 
-fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &mut LogMonitor) {
-	// TODO maybe add items to monitor.metrics_status and make items from that as in draw_logfile()
+fn draw_node_rates<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor, view: &NodeView) {
 	let mut items = Vec::<ListItem>::new();
-	push_subheading(&mut items, &"Node".to_string());
+	push_metric(&mut items, "Lines/s", &monitor.lines_per_second.to_string());
+	push_subheading(&mut items, &"Last minute".to_string());
+	for timeline in [
+		&view.metrics.puts_timeline,
+		&view.metrics.gets_timeline,
+		&view.metrics.errors_timeline,
+		&view.metrics.network_errors_timeline,
+	]
+	.iter()
+	{
+		let rate = timeline
+			.last_complete_bucket(OUTLIER_TIMELINE)
+			.map_or(String::from("-"), |count| count.to_string());
+		push_metric(&mut items, timeline.get_name(), &rate);
+	}
+
+	push_subheading(&mut items, &"Per minute, hour".to_string());
+	for category in rate_categories(view.metrics) {
+		if let Some((per_minute, per_hour)) = view.metrics.category_rates(&category) {
+			let level = match category.as_str() {
+				"ERROR" => level_of(&view.metric_levels, Metric::ErrorRate),
+				_ => Level::Ok,
+			};
+			push_metric_level(&mut items, &category, &format!("{}/m {}/h", per_minute, per_hour), level);
+		}
+	}
+
+	let heading = format!("Node {:>2} Rates", monitor.index + 1);
+	let rates_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(rates_widget, area);
+}
+
+///! Categories with rates, the usual levels most severe first and then any others by name
+fn rate_categories(metrics: &NodeMetrics) -> Vec<String> {
+	let levels = ["ERROR", "WARN", "INFO", "DEBUG", "TRACE"];
+	let mut categories: Vec<String> = metrics
+		.category_timelines
+		.keys()
+		.filter(|category| !levels.contains(&category.as_str()))
+		.cloned()
+		.collect();
+	categories.sort();
+	let mut ordered: Vec<String> = levels
+		.iter()
+		.filter(|level| metrics.category_timelines.contains_key(**level))
+		.map(|level| level.to_string())
+		.collect();
+	ordered.append(&mut categories);
+	ordered
+}
+
+fn category_abbreviation(category: &str) -> String {
+	match category {
+		"ERROR" => "ERR".to_string(),
+		"WARN" => "WRN".to_string(),
+		"DEBUG" => "DBG".to_string(),
+		"TRACE" => "TRC".to_string(),
+		_ => category.chars().take(3).collect(),
+	}
+}
+
+fn draw_node_errors<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor, view: &NodeView) {
+	let rows = area.height.saturating_sub(2) as usize;
+	let message_width = area.width.saturating_sub(2 + 7) as usize;
+	let items: Vec<ListItem> = view
+		.metrics
+		.error_templates
+		.top(rows)
+		.iter()
+		.map(|template| {
+			let colour = if template.category == "ERROR" { Color::Red } else { Color::Yellow };
+			let message = text::truncate(&template.template, message_width);
+			ListItem::new(vec![Spans::from(format!("{:>6} {}", template.count, message))])
+				.style(Style::default().fg(colour))
+		})
+		.collect();
+
+	let heading = format!("Node {:>2} Top Errors", monitor.index + 1);
+	let errors_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(errors_widget, area);
+}
+
+fn draw_node_duplicates<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor, view: &NodeView) {
+	let rows = area.height.saturating_sub(2) as usize;
+	let peer_width = area.width.saturating_sub(2 + 7) as usize;
+	let duplicates = &view.metrics.duplicates;
+	let items: Vec<ListItem> = duplicates
+		.top(rows)
+		.iter()
+		.map(|peer| {
+			// Repeat offenders stand out from peers which have sent the odd duplicate
+			let style = match peer.is_repeat_offender() {
+				true => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+				false => Style::default().fg(Color::Yellow),
+			};
+			ListItem::new(Spans::from(format!("{:>6} {}", peer.count, text::truncate(&peer.peer, peer_width)))).style(style)
+		})
+		.collect();
+
+	let heading = match duplicates.repeat_offenders() {
+		0 => format!("Node {:>2} Duplicates {}", monitor.index + 1, duplicates.count),
+		offenders => format!("Node {:>2} Duplicates {} ({} repeat)", monitor.index + 1, duplicates.count, offenders),
+	};
+	let duplicates_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(duplicates_widget, area);
+}
+
+fn draw_node_data_types<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor, view: &NodeView) {
+	// A bar for each data type, PUTs then GETs, scaled to the busiest type
+	let label_width = 8;
+	let count_width = 13;
+	let bar_width = (area.width as usize).saturating_sub(2 + label_width + count_width);
+	let rows = view.metrics.data_types.rows();
+	let max = rows.iter().map(|(_, counts)| counts.total()).max().unwrap_or(0);
+	let scaled = |count: u64| (count as usize * bar_width).checked_div(max as usize).unwrap_or(0);
+	let items: Vec<ListItem> = rows
+		.iter()
+		.map(|(data_type, counts)| {
+			let puts = scaled(counts.puts);
+			let gets = scaled(counts.total()) - puts;
+			ListItem::new(Spans::from(vec![
+				Span::raw(format!("{:<w$}{:>6}/{:<6} ", data_type, counts.puts, counts.gets, w = label_width)),
+				Span::styled("█".repeat(puts), Style::default().fg(Color::Green)),
+				Span::styled("█".repeat(gets), Style::default().fg(Color::Blue)),
+			]))
+		})
+		.collect();
+
+	let heading = format!("Node {:>2} PUT/GET by Data", monitor.index + 1);
+	let data_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(data_widget, area);
+}
+
+///! Shades for a heatmap cell, from no requests to the most in any cell
+static HEATMAP_SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+fn heatmap_shade(count: u64, max: u64) -> char {
+	match count {
+		0 => HEATMAP_SHADES[0],
+		_ => HEATMAP_SHADES[(count * 4).div_ceil(max).clamp(1, 4) as usize],
+	}
+}
+
+fn draw_node_latency<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, monitor: &LogMonitor, view: &NodeView) {
+	// The slowest latencies at the top, leaving out the fastest if there isn't room
+	let label_width = 6;
+	let rows = area.height.saturating_sub(2) as usize;
+	let columns = (area.width as usize).saturating_sub(2 + label_width + 1);
+	let heatmap = view.metrics.latency.recent_columns(Utc::now(), columns);
+	let max = heatmap.iter().flat_map(|column| column.iter()).copied().max().unwrap_or(0);
+	let items: Vec<ListItem> = (0..LATENCY_BUCKETS)
+		.rev()
+		.take(rows)
+		.map(|bucket| {
+			let cells: String = heatmap.iter().map(|column| heatmap_shade(column[bucket], max)).collect();
+			ListItem::new(Spans::from(vec![
+				Span::raw(format!("{:>width$} ", latency_bucket_label(bucket), width = label_width)),
+				Span::styled(cells, Style::default().fg(Color::Yellow)),
+			]))
+		})
+		.collect();
+
+	let mut heading = vec![Span::raw(match view.metrics.latency.column_time {
+		Some(_) => format!("Node {:>2} Latency (1 minute columns)", monitor.index + 1),
+		None => format!("Node {:>2} Latency (see --latency-pattern)", monitor.index + 1),
+	})];
+	// The p95 is only worked out when it has a threshold
+	if let Some(p95) = view.metric_levels.iter().find(|level| level.metric == Metric::LatencyP95) {
+		let style = level_colour(p95.level).map_or(Style::default(), |colour| Style::default().fg(colour));
+		heading.push(Span::styled(format!(" {}", p95), style));
+	}
+	if let Some(score) = view.metrics.latency.apdex(Utc::now(), LATENCY_MINUTES, dash_state.apdex_ms) {
+		heading.push(Span::styled(format!(" apdex {:.2}", score), Style::default().fg(apdex_colour(score))));
+	}
+	let heading = Spans::from(heading);
+	let latency_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(latency_widget, area);
+}
+
+fn draw_node_peers<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor, view: &NodeView) {
+	let mut items = Vec::<ListItem>::new();
+	push_subheading(&mut items, &"Section".to_string());
+	push_metric(&mut items, "Elders", &view.metrics.elders.to_string());
+	push_metric(&mut items, "Adults", &view.metrics.adults.to_string());
+
+	let heading = format!("Node {:>2} Peers", monitor.index + 1);
+	let peers_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(peers_widget, area);
+}
+
+///! The reward key in full, a line at a time, unlike the abbreviation on the Config line
+fn draw_node_rewards<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor, view: &NodeView) {
+	let mut items = Vec::<ListItem>::new();
+	push_subheading(&mut items, &"Reward key".to_string());
+	match &view.metrics.reward_key {
+		Some(key) => {
+			let width = (area.width.saturating_sub(2) as usize).max(1);
+			let chars: Vec<char> = key.chars().collect();
+			for line in chars.chunks(width) {
+				items.push(ListItem::new(Spans::from(line.iter().collect::<String>())).style(Style::default().fg(Color::Blue)));
+			}
+			items.push(ListItem::new(Spans::from("'y' copies")).style(Style::default().fg(Color::DarkGray)));
+		}
+		None => items.push(ListItem::new(Spans::from("Not logged yet")).style(Style::default().fg(Color::DarkGray))),
+	}
+
+	let heading = format!("Node {:>2} Rewards", monitor.index + 1);
+	let rewards_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(rewards_widget, area);
+}
+
+fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor, view: &NodeView) {
+	let mut items = Vec::<ListItem>::new();
+	items.push(format_address_item(&view.metrics.address));
+	push_metric(
+		&mut items,
+		"Role",
+		&view.metrics.agebracket_string(),
+	);
+	push_metric(
+		&mut items,
+		"Age",
+		&view.metrics.node_age.to_string()
+	);
 	push_metric(
 		&mut items,
-		&"Role".to_string(),
-		&monitor.metrics.agebracket_string(),
+		"Name",
+		&view.metrics.node_name,
 	);
 	push_metric(
 		&mut items,
-		&"Age".to_string(),
-		&monitor.metrics.node_age.to_string()
+		"Restarts",
+		&restarts_text(view.metrics),
 	);
 	push_metric(
 		&mut items,
-		&"Name".to_string(),
-		&monitor.metrics.node_name,
+		"Section",
+		&view.metrics.section_prefix,
 	);
+
+	// ERROR and WARN rates, in the space between node and activity
+	let rates: Vec<String> = ["ERROR", "WARN"]
+		.iter()
+		.map(|category| {
+			let (per_minute, per_hour) = view.metrics.category_rates(category).unwrap_or((0, 0));
+			format!("{} {}/m {}/h", category_abbreviation(category), per_minute, per_hour)
+		})
+		.collect();
+	let rates_colour = level_colour(level_of(&view.metric_levels, Metric::ErrorRate)).unwrap_or(Color::Yellow);
+	items.push(ListItem::new(vec![Spans::from(rates.join("  "))]).style(Style::default().fg(rates_colour)));
 	push_metric(
 		&mut items,
-		&"Section".to_string(),
-		&monitor.metrics.section_prefix,
+		"GETS",
+		&view.metrics.activity_gets.to_string(),
 	);
 
-	push_subheading(&mut items, &"".to_string());
 	push_metric(
 		&mut items,
-		&"GETS".to_string(),
-		&monitor.metrics.activity_gets.to_string(),
+		"PUTS",
+		&view.metrics.activity_puts.to_string(),
 	);
 
 	push_metric(
 		&mut items,
-		&"PUTS".to_string(),
-		&monitor.metrics.activity_puts.to_string(),
+		"ERRORS",
+		&view.metrics.activity_errors.to_string(),
 	);
 
 	push_metric(
 		&mut items,
-		&"ERRORS".to_string(),
-		&monitor.metrics.activity_errors.to_string(),
+		"NET ERRS",
+		&network_errors_text(view.metrics),
 	);
 
-	push_subheading(&mut items, &"".to_string());
+	push_metric(
+		&mut items,
+		"Clients",
+		&view.metrics.clients_connected.to_string(),
+	);
+
+	push_metric(
+		&mut items,
+		"Served",
+		&view.metrics.clients_served.to_string(),
+	);
+
+	// Red when the logfile's title gives a problem with its watch
+	let watch = format!("{}: {}", text::pad("Watch", 12, Align::Left), text::pad(&monitor.watch.describe(), 12, Align::Right));
+	let watch_colour = if monitor.watch_status().is_some() { Color::Red } else { Color::Blue };
+	items.push(ListItem::new(vec![Spans::from(watch)]).style(Style::default().fg(watch_colour)));
+
 	// TODO re-instate when available
 	// push_subheading(&mut items, &"Network".to_string());
 	// push_metric(
 	// 	&mut items,
 	// 	&"Elders".to_string(),
-	// 	&monitor.metrics.elders.to_string(),
+	// 	&view.metrics.elders.to_string(),
 	// );
 	// push_metric(
 	// 	&mut items,
 	// 	&"Adults".to_string(),
-	// 	&monitor.metrics.elders.to_string(),
+	// 	&view.metrics.elders.to_string(),
 	// );
 
-	let heading = format!("Node {:>2} Status", monitor.index + 1);
+	let status = view.metrics.status();
+	let node_tags: String = monitor.tags.iter().map(|tag| format!(" #{}", tag)).collect();
+	let heading = Spans::from(vec![
+		Span::styled(
+			match &monitor.label {
+				Some(label) => format!("Node {:>2} {}{}: {} ", monitor.index + 1, label, node_tags, status),
+				None => format!("Node {:>2}{} Status: {} ", monitor.index + 1, node_tags, status),
+			},
+			Style::default().fg(status_colour(status)),
+		),
+		Span::styled(
+			format!("Health {}", view.health),
+			Style::default().fg(health_colour(view.health)).add_modifier(Modifier::BOLD),
+		),
+	]);
 	let monitor_widget = List::new(items).block(
 		Block::default()
 			.borders(Borders::ALL)
-			.title(heading.to_string()),
+			.title(heading),
 	);
-	f.render_stateful_widget(monitor_widget, area, &mut monitor.metrics_status.state);
+	f.render_widget(monitor_widget, area);
+}
+
+///! The node subheading with its address, and a badge if the address has changed
+fn format_address_item<'a>(address: &NodeAddress) -> ListItem<'a> {
+	let mut spans = vec![Span::styled(
+		format!("Node {}", address.current.as_deref().unwrap_or("")),
+		Style::default().fg(Color::Yellow),
+	)];
+	if address.changed() {
+		spans.push(Span::styled(
+			format!(" ⚠ changed x{}", address.changes.len()),
+			Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+		));
+	}
+	ListItem::new(vec![Spans::from(spans)])
 }
 
 fn push_subheading(items: &mut Vec<ListItem>, subheading: &String) {
@@ -150,16 +1018,54 @@ fn push_subheading(items: &mut Vec<ListItem>, subheading: &String) {
 	);
 }
 
-fn push_metric(items: &mut Vec<ListItem>, metric: &String, value: &String) {
-	let s = format!("{:<12}: {:>12}", metric, value);
+///! Colour of a metric beyond its threshold
+pub fn level_colour(level: Level) -> Option<Color> {
+	match level {
+		Level::Ok => None,
+		Level::Warn => Some(Color::Yellow),
+		Level::Crit => Some(Color::Red),
+	}
+}
+
+///! A metric coloured by the level of its threshold
+///! The count of network errors, with the most common kind
+fn network_errors_text(metrics: &NodeMetrics) -> String {
+	let kinds = metrics.network_errors.kinds();
+	match kinds.iter().max_by_key(|(_, count)| *count) {
+		Some((kind, _)) => text::truncate(&format!("{} {}", metrics.network_errors.count, kind), 12).into_owned(),
+		None => metrics.network_errors.count.to_string(),
+	}
+}
+
+fn push_metric_level(items: &mut Vec<ListItem>, metric: &str, value: &str, level: Level) {
+	let s = format!("{}: {}", text::pad(metric, 12, Align::Left), text::pad(value, 12, Align::Right));
+	let colour = level_colour(level).unwrap_or(Color::Blue);
+	items.push(ListItem::new(vec![Spans::from(s)]).style(Style::default().fg(colour)));
+}
+
+///! Restarts seen, and how many rejoined as a new node, e.g. '3 (1 new)'
+fn restarts_text(metrics: &NodeMetrics) -> String {
+	let restarts = metrics.runs.iter().filter(|run| run.identity != Identity::First).count();
+	match metrics.runs.iter().filter(|run| run.identity == Identity::New).count() {
+		0 => restarts.to_string(),
+		new => format!("{} ({} new)", restarts, new),
+	}
+}
+
+fn push_metric(items: &mut Vec<ListItem>, metric: &str, value: &str) {
+	let s = format!("{}: {}", text::pad(metric, 12, Align::Left), text::pad(value, 12, Align::Right));
 	items.push(
 		ListItem::new(vec![Spans::from(s.clone())])
 			.style(Style::default().fg(Color::Blue)),
 	);
 }
 
-fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut DashState, monitor: &mut LogMonitor) {
-	let total_string = format_size(monitor.chunk_store.total_used, 1);
+///! Rows of the chunk store's Device section above its 'Disk Used' gauge: a gap,
+///! the Device heading, Total Chunks and Space Free
+const STORAGE_DEVICE_ROWS: usize = 4;
+
+fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut DashState, monitor: &LogMonitor, view: &NodeView) {
+	let total_string = monitor.chunk_store.total_used.describe(|used| format_size(used, 1));
 	let limit_string = match &monitor.chunk_store_fsstats {
 		Some(fsstats) => {
 			let chunk_store_limit = fsstats.free_space();
@@ -182,9 +1088,9 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 				.bg(Color::LightGreen)
 				.add_modifier(Modifier::BOLD),
 		);
-	f.render_stateful_widget(monitor_widget, area, &mut monitor.content.state);
+	f.render_widget(monitor_widget, area);
 
-	if monitor.chunk_store.chunk_store_stats.len() < 1 {
+	if monitor.chunk_store.chunk_store_stats.len() < 1 && monitor.data_dir_size.is_none() {
 		return;
 	}
 
@@ -205,11 +1111,13 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 		let mut gauges_column = columns[1];
 		gauges_column.height = 1;
 
-		// One gauge gap for heading, and an extra gauge so the last one drawn doesn't expand to the bottom
-		let constraints = vec![Constraint::Length(1); monitor.chunk_store.chunk_store_stats.len() + 2];
+		// The disk gauge follows the Chunks heading, a row per chunk type and the device's rows,
+		// and an extra gauge after it stops the last one drawn expanding to the bottom
+		let disk_gauge = 1 + monitor.chunk_store.chunk_store_stats.len() + STORAGE_DEVICE_ROWS;
+		let constraints = vec![Constraint::Length(1); disk_gauge + 2];
 		let gauges = Layout::default()
 			.direction(Direction::Vertical)
-			.constraints(constraints.as_ref())
+			.constraints(constraints.as_slice())
 			.split(columns[1]);
 
 		// Metrics with label + gauge
@@ -226,7 +1134,7 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 			let gauge = Gauge2::default()
 				.block(Block::default())
 				.gauge_style(Style::default().fg(Color::Yellow))
-				.ratio(ratio(stat.space_used, monitor.chunk_store.total_used));
+				.ratio(ratio(stat.space_used, monitor.chunk_store.total_used.value));
 			f.render_widget(gauge, gauges[next_gauge]);
 			next_gauge += 1;
 		}
@@ -236,16 +1144,39 @@ fn draw_node_storage<B: Backend>(f: &mut Frame<B>, area: Rect, _dash_state: &mut
 
 		push_storage_metric(
 			&mut label_items,
-			&"Total Chunks".to_string(),
+			"Total Chunks",
 			&total_string
 		);
 
 		push_storage_metric(
 			&mut label_items,
-			&"Space Free".to_string(),
+			"Space Free",
 			&limit_string
 		);
 
+		// Coloured by its threshold, if it has one
+		if let Some(disk_used) = monitor.disk_used() {
+			let level = level_of(&view.metric_levels, Metric::StoragePercent);
+			let colour = level_colour(level).unwrap_or(Color::Green);
+			push_storage_metric(&mut label_items, "Disk Used", &format!("{:.0}%", disk_used * 100.0));
+			let gauge = Gauge2::default()
+				.block(Block::default())
+				.gauge_style(Style::default().fg(colour))
+				.ratio(disk_used.clamp(0.0, 1.0));
+			f.render_widget(gauge, gauges[disk_gauge]);
+		}
+
+		// Measured from --data-dir, flagged if it differs from the chunk store's records
+		if let Some(data_dir_size) = monitor.data_dir_size {
+			push_storage_metric(&mut label_items, "On Disk", &format_size(data_dir_size, 1));
+			if data_dir::diverges(data_dir_size, monitor.chunk_store.total_used.value) {
+				label_items.push(
+					ListItem::new(vec![Spans::from("Differs from records")])
+						.style(Style::default().fg(Color::Red)),
+				);
+			}
+		}
+
 
 		// Render labels
 		let labels_widget = List::new(label_items).block(
@@ -282,8 +1213,8 @@ fn push_storage_subheading(items: &mut Vec<ListItem>, subheading: &String) {
 	);
 }
 
-fn push_storage_metric(items: &mut Vec<ListItem>, metric: &String, value: &String) {
-	let s = format!("{:<13}:{:>9}", metric, value);
+fn push_storage_metric(items: &mut Vec<ListItem>, metric: &str, value: &str) {
+	let s = format!("{}:{}", text::pad(metric, 13, Align::Left), text::pad(value, 9, Align::Right));
 	items.push(
 		ListItem::new(vec![Spans::from(s.clone())])
 			.style(Style::default().fg(Color::Blue)),
@@ -294,7 +1225,7 @@ fn draw_timeline<B: Backend>(
 	f: &mut Frame<B>,
 	area: Rect,
 	dash_state: &mut DashState,
-	monitor: &mut LogMonitor,
+	metrics: &NodeMetrics,
 ) {
 	let active_timeline_name = match TIMELINES.get(dash_state.active_timeline) {
 		None => {
@@ -304,9 +1235,14 @@ fn draw_timeline<B: Backend>(
 		Some((name, _)) => name,
 	};
 
+	let mut title = format!("Timeline - {}", active_timeline_name);
+	if metrics.logfile_switches.len() > 0 {
+		title.push_str(" (│ switched to newer logfile)");
+	}
 	let window_widget = Block::default()
 		.borders(Borders::ALL)
-		.title(format!("Timeline - {}", active_timeline_name).to_string());
+		.border_style(focus::border_style(dash_state.focus == Focus::Timeline))
+		.title(title);
 	f.render_widget(window_widget, area);
 
 	// For debugging the bucket state
@@ -339,36 +1275,45 @@ fn draw_timeline<B: Backend>(
 		.margin(1)
 		.constraints(
 			[
-				Constraint::Percentage(33),
-				Constraint::Percentage(33),
-				Constraint::Percentage(33),
+				Constraint::Percentage(25),
+				Constraint::Percentage(25),
+				Constraint::Percentage(25),
+				Constraint::Percentage(25),
 			]
 			.as_ref(),
 		)
 		.split(area);
 
-	if let Some(bucket_set) = monitor
-		.metrics
+	if let Some(bucket_set) = metrics
 		.puts_timeline
 		.get_bucket_set(active_timeline_name)
 	{
 		draw_sparkline(f, chunks[0], &bucket_set.buckets(), &"PUTS", Color::Yellow);
+		draw_logfile_switches(f, chunks[0], bucket_set, &metrics.logfile_switches);
 	};
 
-	if let Some(bucket_set) = monitor
-		.metrics
+	if let Some(bucket_set) = metrics
 		.gets_timeline
 		.get_bucket_set(active_timeline_name)
 	{
 		draw_sparkline(f, chunks[1], &bucket_set.buckets(), &"GETS", Color::Green);
+		draw_logfile_switches(f, chunks[1], bucket_set, &metrics.logfile_switches);
 	};
 
-	if let Some(bucket_set) = monitor
-		.metrics
+	if let Some(bucket_set) = metrics
 		.errors_timeline
 		.get_bucket_set(active_timeline_name)
 	{
 		draw_sparkline(f, chunks[2], &bucket_set.buckets(), &"ERRORS", Color::Red);
+		draw_logfile_switches(f, chunks[2], bucket_set, &metrics.logfile_switches);
+	};
+
+	if let Some(bucket_set) = metrics
+		.network_errors_timeline
+		.get_bucket_set(active_timeline_name)
+	{
+		draw_sparkline(f, chunks[3], &bucket_set.buckets(), &"NET ERRS", Color::LightRed);
+		draw_logfile_switches(f, chunks[3], bucket_set, &metrics.logfile_switches);
 	};
 }
 
@@ -390,6 +1335,29 @@ fn draw_sparkline<B: Backend>(
 	f.render_widget(sparkline, area);
 }
 
+// Mark each switch to a newer logfile (--latest-in) with a line across a sparkline
+fn draw_logfile_switches<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	bucket_set: &BucketSet,
+	switches: &[DateTime<Utc>],
+) {
+	if area.height < 2 {
+		return;
+	}
+	let hidden = bucket_set.buckets().len().saturating_sub(area.width as usize);
+	for switch in switches.iter() {
+		if let Some(index) = bucket_set.index_of(*switch) {
+			if index >= hidden {
+				let mark = Rect::new(area.x + (index - hidden) as u16, area.y + 1, 1, area.height - 1);
+				let line = Paragraph::new(vec![Spans::from("│"); mark.height as usize])
+					.style(Style::default().fg(Color::Magenta));
+				f.render_widget(line, mark);
+			}
+		}
+	}
+}
+
 // Right justify and truncate (left) a set of buckets to width
 fn buckets_right_justify(buckets: &Vec<u64>, width: u16) -> &[u64] {
 	let width = width as usize;
@@ -400,6 +1368,29 @@ fn buckets_right_justify(buckets: &Vec<u64>, width: u16) -> &[u64] {
 	buckets
 }
 
+///! The node's config, if it has echoed one, its reward key and its runs sit
+///! collapsed above the bottom panel, which gets the rest of area
+fn draw_node_settings<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, metrics: &NodeMetrics) -> Rect {
+	let settings = metrics.node_config.len() + metrics.reward_key.iter().count();
+	let runs = metrics.runs.len();
+	match settings + runs {
+		0 => area,
+		_ => {
+			let runs_heading = if runs > 0 { 1 } else { 0 };
+			let height = match dash_state.show_node_config {
+				true => ((settings + runs_heading + runs) as u16 + 2).min(area.height / 2),
+				false => 1,
+			};
+			let chunks = Layout::default()
+				.direction(Direction::Vertical)
+				.constraints([Constraint::Length(height), Constraint::Min(0)].as_ref())
+				.split(area);
+			draw_node_config(f, chunks[0], dash_state, metrics);
+			chunks[1]
+		}
+	}
+}
+
 fn draw_bottom_panel<B: Backend>(
 	f: &mut Frame<B>,
 	area: Rect,
@@ -408,30 +1399,66 @@ fn draw_bottom_panel<B: Backend>(
 	monitor: &mut LogMonitor,
 ) {
 	if dash_state.debug_window {
-		// Vertical split:
-		let constraints = [
-			Constraint::Percentage(50), // Logfile
-			Constraint::Percentage(50), // Debug window
-		];
+		// Vertical split: logfile and debug window
+		let constraints = dash_state.splits.side_constraints();
 
 		let chunks = Layout::default()
 			.direction(Direction::Horizontal)
 			.constraints(constraints.as_ref())
 			.split(area);
 
-		draw_logfile(f, chunks[0], &logfile, monitor);
+		draw_logfile_or_parser_view(f, chunks[0], dash_state, logfile, monitor);
+		dash_state.splits.drawn(Split::Side, area, chunks[1].x);
 		draw_debug_window(f, chunks[1], dash_state);
 	} else {
-		draw_logfile(f, area, &logfile, monitor);
+		draw_logfile_or_parser_view(f, area, dash_state, logfile, monitor);
 	}
 }
 
+///! The logfile, split into its raw lines and the parser's view of them when that is toggled on
+fn draw_logfile_or_parser_view<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &mut DashState,
+	logfile: &String,
+	monitor: &mut LogMonitor,
+) {
+	let logfile_focused = dash_state.focus == Focus::Logfile;
+	if !dash_state.parser_view {
+		draw_logfile(f, area, logfile, monitor, dash_state.aligned, logfile_focused);
+		return;
+	}
+	let chunks = Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints(dash_state.splits.side_constraints().as_ref())
+		.split(area);
+	dash_state.splits.drawn(Split::Side, area, chunks[1].x);
+	// Raw lines, as it is the parser's reading of them which is shown alongside
+	draw_logfile(f, chunks[0], logfile, monitor, false, logfile_focused);
+	draw_parser_view(f, chunks[1], monitor, dash_state.focus == Focus::ParserView);
+}
+
 pub fn draw_logfile<B: Backend>(
 	f: &mut Frame<B>,
 	area: Rect,
 	logfile: &String,
 	monitor: &mut LogMonitor,
+	aligned: bool,
+	focused: bool,
 ) {
+	// Pinned lines stay in view above the logfile as it scrolls
+	let area = match monitor.pins.len() {
+		0 => area,
+		pins => {
+			let chunks = Layout::default()
+				.direction(Direction::Vertical)
+				.constraints([Constraint::Length(pins as u16 + 2), Constraint::Min(0)].as_ref())
+				.split(area);
+			draw_pins(f, chunks[0], &monitor.pins);
+			chunks[1]
+		}
+	};
+
 	let highlight_style = match monitor.has_focus {
 		true => Style::default()
 			.bg(Color::LightGreen)
@@ -439,31 +1466,163 @@ pub fn draw_logfile<B: Backend>(
 		false => Style::default().add_modifier(Modifier::BOLD),
 	};
 
-	let items: Vec<ListItem> = monitor
-		.content
-		.items
+	// Fields changed since the line's previous occurrence
+	let changed_style = Style::default()
+		.fg(Color::Blue)
+		.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+	let content = &monitor.content.items;
+	// A safety net for content replaced without its changed fields
+	if monitor.changed_fields.changed.len() != content.len() {
+		monitor.changed_fields.rebuild(content);
+	}
+	let changed_fields = &monitor.changed_fields.changed;
+	let assumed_offset = monitor.metrics.assumed_offset;
+	monitor.line_times.fit(content, &assumed_offset);
+	let line_times = &monitor.line_times;
+	// Only lines in view are drawn, as the buffer may retain hundreds of thousands
+	let selected = monitor.content.state.selected();
+	let window = monitor.viewport.window(content.len(), selected, area.height.saturating_sub(2) as usize);
+	let now = Utc::now();
+	let fade = monitor.fade;
+	// The last line loaded at startup is followed by a divider, below which lines arrived live
+	let historical = content.len() - monitor.live_lines;
+	let divider = format!("── live {}", "─".repeat((area.width as usize).saturating_sub(10)));
+	let divider_style = Style::default().fg(Color::Blue);
+	let items: Vec<ListItem> = content[window.clone()]
 		.iter()
-		.map(|s| {
-			ListItem::new(vec![Spans::from(s.clone())])
-				.style(Style::default().fg(Color::Black).bg(Color::White))
+		.zip(changed_fields.range(window.clone()))
+		.enumerate()
+		.map(|(index, (s, changed))| {
+			let index = window.start + index;
+			let entry = LogEntry::decode(s, &assumed_offset, line_times.before(index));
+			let mut colour = Color::Black;
+			// Lines without a timestamp are as old as the entry they continue
+			if let (Some(fade), Some(time)) = (fade, line_times.get(index)) {
+				colour = faded_colour(now - time, fade);
+			}
+
+			// An aligned line is its fields in columns then its message, which ends the line
+			let (prefix, start) = match (&entry, aligned) {
+				(Some(entry), true) => (aligned_prefix(entry), s.len() - entry.message.len()),
+				_ => (String::new(), 0),
+			};
+			let mut spans = vec![Span::raw(prefix)];
+			let mut end = start;
+			for field in changed.iter().filter(|field| field.start >= start) {
+				spans.push(Span::raw(&s[end..field.start]));
+				spans.push(Span::styled(&s[field.clone()], changed_style));
+				end = field.end;
+			}
+			spans.push(Span::raw(&s[end..]));
+			// Part of the line's item, so that items stay one to one with content
+			let mut lines = vec![Spans::from(spans)];
+			if index + 1 == historical {
+				lines.push(Spans::from(Span::styled(divider.as_str(), divider_style)));
+			}
+			ListItem::new(lines)
+				.style(Style::default().fg(colour).bg(Color::White))
 		})
 		.collect();
 
-	let node_log_title = format!("Node Log ({})", logfile);
+	let mut node_log_title = format!("Node Log ({})", logfile);
+	let statuses = [
+		monitor.pause_status(),
+		monitor.metrics_only_status(),
+		monitor.filter_status(),
+		monitor.flapping.status(),
+		monitor.watch_status(),
+	];
+	for status in statuses.iter().flatten() {
+		node_log_title.push_str(&format!(" [{}]", status));
+	}
 
 	let logfile_widget = List::new(items)
 		.block(
 			Block::default()
 				.borders(Borders::ALL)
+				.border_style(focus::border_style(focused))
 				.title(node_log_title.clone()),
 		)
 		.highlight_style(highlight_style);
 
-	f.render_stateful_widget(logfile_widget, area, &mut monitor.content.state);
+	// The selection within the window, leaving content.state's in terms of the whole buffer
+	let mut state = ListState::default();
+	state.select(selected.map(|selected| selected.saturating_sub(window.start)));
+	f.render_stateful_widget(logfile_widget, area, &mut state);
+	monitor.faded_at = Some(now);
+}
+
+fn draw_node_config<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, metrics: &NodeMetrics) {
+	let config = &metrics.node_config;
+	let runs = &metrics.runs;
+	// Abbreviated, so that it fits the collapsed line
+	let reward_key = metrics.reward_key.as_deref().map(node_config::abbreviate_key);
+	if !dash_state.show_node_config {
+		// A Block needs two lines for its title, so this is a Paragraph
+		let mut title = format!(" Config ({} settings, {} runs, 'n' to show)", config.len(), runs.len());
+		if let Some(reward_key) = &reward_key {
+			title.push_str(&format!("  Reward key {} ('y' copies)", reward_key));
+		}
+		f.render_widget(Paragraph::new(Spans::from(title)), area);
+		return;
+	}
+
+	let name_width = config.keys().map(|name| text::width(name)).max().unwrap_or(0);
+	let mut items = Vec::<ListItem>::new();
+	if let Some(reward_key) = reward_key {
+		items.push(ListItem::new(Spans::from(vec![
+			Span::styled("Reward key", Style::default().fg(Color::Yellow)),
+			Span::raw(format!("  {}  ('y' copies)", reward_key)),
+		])));
+	}
+	items.extend(config.iter().map(|(name, value)| {
+		ListItem::new(Spans::from(vec![
+			Span::styled(text::fit(name, name_width, Align::Left), Style::default().fg(Color::Yellow)),
+			Span::raw(format!("  {}", value)),
+		]))
+	}));
+	// Each run from a start, most recent first, and whether the node kept its identity
+	if !runs.is_empty() {
+		items.push(ListItem::new(Spans::from(Span::styled("Runs", Style::default().fg(Color::Yellow)))));
+	}
+	for run in runs.iter().rev() {
+		let started = run
+			.started
+			.map_or(String::from("-"), |started| started.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string());
+		let name = if run.node_name.is_empty() { "-" } else { run.node_name.as_str() };
+		let colour = match run.identity {
+			Identity::New => Color::Red,
+			Identity::Same => Color::Green,
+			_ => Color::Reset,
+		};
+		items.push(ListItem::new(Spans::from(vec![
+			Span::raw(format!("  {}  {}  {}  ", started, run.version, name)),
+			Span::styled(run.identity.describe(), Style::default().fg(colour)),
+		])));
+	}
+	let config_widget = List::new(items).block(Block::default().borders(Borders::ALL).title("Config ('n' to hide)"));
+	f.render_widget(config_widget, area);
+}
+
+fn draw_pins<B: Backend>(f: &mut Frame<B>, area: Rect, pins: &[String]) {
+	let items: Vec<ListItem> = pins
+		.iter()
+		.map(|pin| ListItem::new(pin.as_str()).style(Style::default().fg(Color::Black).bg(Color::White)))
+		.collect();
+	let pins_widget = List::new(items).block(Block::default().borders(Borders::ALL).title("Pinned ('b' pins or unpins the selected line, 'B' unpins all)"));
+	f.render_widget(pins_widget, area);
+}
+
+///! A shade from the 256 colour greyscale ramp, from near black for a new
+///! line to mid grey once age reaches fade
+fn faded_colour(age: Duration, fade: Duration) -> Color {
+	let step = (age.num_seconds().max(0) * FADE_STEPS as i64 / fade.num_seconds().max(1)).min(FADE_STEPS as i64);
+	Color::Indexed(232 + step as u8)
 }
 
 fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState) {
-	let highlight_style = match dash_state.debug_window_has_focus {
+	let focused = dash_state.focus == Focus::DebugWindow;
+	let highlight_style = match focused {
 		true => Style::default()
 			.bg(Color::LightGreen)
 			.add_modifier(Modifier::BOLD),
@@ -484,7 +1643,11 @@ fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut
 		.block(
 			Block::default()
 				.borders(Borders::ALL)
-				.title(String::from(DEBUG_WINDOW_NAME)),
+				.border_style(focus::border_style(focused))
+				.title(match dash_state.debug_capture {
+					true => String::from(DEBUG_WINDOW_NAME),
+					false => format!("{} (paused, 'd' to resume)", DEBUG_WINDOW_NAME),
+				}),
 		)
 		.highlight_style(highlight_style);
 
@@ -494,3 +1657,37 @@ fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut
 		&mut dash_state.debug_window_list.state,
 	);
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::opt::{Opt, StructOpt};
+
+	#[test]
+	fn groups_the_summary_by_section() {
+		let opt = Opt::from_iter(&["vdash", "a.log", "b.log", "c.log"]);
+		let monitor = |logfile: &str, prefix: Option<&str>| {
+			let mut monitor = LogMonitor::new(&opt, logfile.to_string(), opt.lines_max);
+			// As if loaded, so that the line is timed by its timestamp
+			monitor.metrics.loading = true;
+			if let Some(prefix) = prefix {
+				let line = format!("[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] We are Adult, section prefix: {}, age: 6, node name: f67c2e", prefix);
+				monitor.append_to_content(&line).unwrap();
+			}
+			monitor
+		};
+		let (a, b, c) = (monitor("a.log", Some("10")), monitor("b.log", None), monitor("c.log", Some("01")));
+		let groups = SummaryGroups::default();
+		assert!(group_monitors(&groups, &[&b]).is_empty());
+
+		// In order of section, with nodes whose section isn't known last
+		let grouped = group_monitors(&groups, &[&a, &b, &c]);
+		let names: Vec<Option<&str>> = grouped.iter().map(|(name, _)| name.as_deref()).collect();
+		assert_eq!(names, [Some("01"), Some("10"), None]);
+
+		// Nothing logged lately, so both are stalled
+		assert_eq!(group_heading(Grouping::Section, &None, &[&b, &c], false), "▾ Section unknown (2 nodes): 2 ■ STALLED");
+		let collapsed = group_heading(Grouping::Section, &Some(String::from("10")), &[&a], true);
+		assert_eq!(collapsed, "▸ Section 10 (1 node): 1 ■ STALLED; GETS 0, PUTS 0, ERRORS 0, lowest health 100");
+	}
+}
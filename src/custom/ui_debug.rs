@@ -1,10 +1,14 @@
 ///! Terminal based interface and dashboard
 ///!
 use super::app::{DashState, DashViewMain, LogMonitor};
+use super::self_metrics::{draw_self_metrics, SELF_METRICS_ROWS};
 use std::collections::HashMap;
 
 use tui::{
 	backend::Backend,
+	layout::{Constraint, Direction, Layout, Rect},
+	text::Spans,
+	widgets::{Block, Borders, List, ListItem, ListState},
 	Frame
 };
 
@@ -16,6 +20,10 @@ pub fn draw_dashboard<B: Backend>(
 	match dash_state.main_view {
 		DashViewMain::DashSummary => {}
 		DashViewMain::DashNode => {}
+		DashViewMain::DashTop => {}
+		DashViewMain::DashRegex => {}
+		DashViewMain::DashSearch => {}
+		DashViewMain::DashTable => {}
 		DashViewMain::DashDebug => draw_debug_dashboard(f, dash_state, monitors),
 	}
 }
@@ -24,12 +32,98 @@ use super::ui::draw_logfile;
 
 fn draw_debug_dashboard<B: Backend>(
 	f: &mut Frame<B>,
-	_dash_state: &DashState,
+	dash_state: &DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
-	for (logfile, monitor) in monitors.iter_mut() {
-		if monitor.is_debug_dashboard_log {
-			draw_logfile(f, f.size(), logfile, monitor);
+	let has_debug_log = monitors.values().any(|monitor| monitor.is_debug_dashboard_log);
+	let history_area = if has_debug_log {
+		let chunks = Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+			.split(f.size());
+
+		for (logfile, monitor) in monitors.iter_mut() {
+			if monitor.is_debug_dashboard_log {
+				// Raw, as parsed by the debug dashboard
+				draw_logfile(f, chunks[0], logfile, monitor, false, false);
+			}
 		}
+		chunks[1]
+	} else {
+		f.size()
+	};
+
+	let stats_rows = (monitors.len() as u16 + 4).max(SELF_METRICS_ROWS);
+	let rows = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Min(0), Constraint::Length(stats_rows)].as_ref())
+		.split(history_area);
+	let columns = Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints([Constraint::Min(0), Constraint::Length(56)].as_ref())
+		.split(rows[1]);
+
+	if let Some(monitor) = monitors.get(&dash_state.dash_node_focus) {
+		draw_parser_history(f, rows[0], monitor);
+	}
+	draw_memory_stats(f, columns[0], dash_state, monitors);
+	draw_self_metrics(f, columns[1], &dash_state.self_metrics);
+}
+
+///! Sizes of the structures which grow with input, to check they stay bounded
+fn draw_memory_stats<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &DashState,
+	monitors: &HashMap<String, LogMonitor>,
+) {
+	let mut lines = Vec::<String>::new();
+	lines.push(format!(
+		"Debug window: {} lines",
+		dash_state.debug_window_list.items.len()
+	));
+	lines.push(format!(
+		"{:>4} {:>8} {:>8} {:>8} {:>8} {:>10}",
+		"Node", "Content", "Entries", "Activity", "Parser", "Categories"
+	));
+
+	let mut monitors: Vec<&LogMonitor> = monitors.values().collect();
+	monitors.sort_by_key(|monitor| monitor.index);
+	for monitor in monitors {
+		let metrics = &monitor.metrics;
+		lines.push(format!(
+			"{:>4} {:>8} {:>8} {:>8} {:>8} {:>10}",
+			monitor.index + 1,
+			monitor.content.items.len(),
+			metrics.log_history.len(),
+			metrics.activity_history.len(),
+			metrics.parser_history.len(),
+			metrics.category_count.len(),
+		));
+	}
+
+	let items: Vec<ListItem> = lines
+		.into_iter()
+		.map(|line| ListItem::new(vec![Spans::from(line)]))
+		.collect();
+	let stats_widget = List::new(items).block(Block::default().borders(Borders::ALL).title("Memory"));
+	f.render_widget(stats_widget, area);
+}
+
+///! Most recent parser results for the focused node, newest at the bottom
+fn draw_parser_history<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor) {
+	let history = &monitor.metrics.parser_history;
+	let items: Vec<ListItem> = history
+		.iter()
+		.map(|s| ListItem::new(vec![Spans::from(s.clone())]))
+		.collect();
+
+	let title = format!("Parser History ({})", monitor.logfile);
+	let history_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+
+	let mut state = ListState::default();
+	if history.len() > 0 {
+		state.select(Some(history.len() - 1));
 	}
+	f.render_stateful_widget(history_widget, area, &mut state);
 }
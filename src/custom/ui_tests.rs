@@ -0,0 +1,252 @@
+///! End to end tests of the dashboard, drawn on tui's TestBackend
+///!
+///! Each test scripts an App as the event loop would drive it, giving lines to
+///! its monitors and keys to its keymap, and checks what is drawn in each view
+///! so that regressions in layout, focus and filters show up before a release.
+///! A Screen is the drawn buffer, whose rows are searched for the text a view
+///! should (or shouldn't) draw and whose cells are looked up by the text drawn
+///! in them to check their style, rather than compared whole with a stored
+///! buffer, so that a test breaks only on the part of a view it checks.
+
+use crate::custom::app::App;
+use crate::custom::keymap::{Action, InputKey};
+use crate::custom::ui::draw_dashboard;
+
+use tui::{backend::TestBackend, buffer::Buffer, style::Color, Terminal};
+
+const WIDTH: u16 = 160;
+const HEIGHT: u16 = 50;
+
+static NODE1: &str = "node1.log";
+static NODE2: &str = "node2.log";
+
+///! The last row of the banner celebrating a promotion to Elder
+static ELDER_BANNER_ROW: &str = "█████ █████ ████  █████ █   █ █";
+
+static ELDER_LINES: [&str; 3] = [
+	"[sn_node] INFO 2021-02-20T09:02:28.960474+00:00 [src/node/mod.rs:97] Node promoted to Elder",
+	"[sn_node] INFO 2021-02-20T09:02:29.112084+00:00 [src/routing.rs:210] No. of Elders: 7",
+	"[sn_node] WARN 2021-02-20T09:02:31.500218+00:00 [src/routing.rs:52] Dead peer detected",
+];
+
+struct Harness {
+	app: App,
+	terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+	fn new(logfiles: &[&str]) -> Harness {
+		let mut args = vec!["vdash"];
+		args.extend_from_slice(logfiles);
+		Harness {
+			app: App::for_test(&args),
+			terminal: Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap(),
+		}
+	}
+
+	///! Lines as if read from logfile
+	fn lines(&mut self, logfile: &str, lines: &[&str]) -> &mut Harness {
+		let monitor = self.app.get_monitor_for_file_path(&logfile.to_string()).unwrap();
+		for line in lines {
+			monitor.append_to_content(line).unwrap();
+		}
+		self
+	}
+
+	///! Keys typed, one per character
+	fn keys(&mut self, keys: &str) -> &mut Harness {
+		for c in keys.chars() {
+			self.key(match c {
+				'\t' => InputKey::Tab,
+				'\n' => InputKey::Enter,
+				c => InputKey::Char(c),
+			});
+		}
+		self
+	}
+
+	fn key(&mut self, key: InputKey) -> &mut Harness {
+		match self.app.keymap.handle(key) {
+			Some((Action::Quit, _)) | None => {}
+			Some((action, count)) => self.app.handle_action(action, count),
+		}
+		self
+	}
+
+	fn draw(&mut self) -> Screen {
+		let app = &mut self.app;
+		self.terminal.draw(|f| draw_dashboard(f, app)).unwrap();
+		Screen::new(self.terminal.backend().buffer().clone())
+	}
+}
+
+struct Screen {
+	rows: Vec<String>,
+	buffer: Buffer,
+}
+
+impl Screen {
+	fn new(buffer: Buffer) -> Screen {
+		let area = buffer.area;
+		let rows = (area.top()..area.bottom())
+			.map(|y| (area.left()..area.right()).map(|x| buffer.get(x, y).symbol.as_str()).collect())
+			.collect();
+		Screen { rows, buffer }
+	}
+
+	fn contains(&self, text: &str) -> bool {
+		self.rows.iter().any(|row| row.contains(text))
+	}
+
+	///! The cell where text is first drawn
+	fn cell_at(&self, text: &str) -> &tui::buffer::Cell {
+		for (y, row) in self.rows.iter().enumerate() {
+			if let Some(start) = row.find(text) {
+				let x = row[..start].chars().count() as u16;
+				return self.buffer.get(self.buffer.area.left() + x, self.buffer.area.top() + y as u16);
+			}
+		}
+		panic!("'{}' is not drawn in:\n{}", text, self);
+	}
+
+	fn assert_contains(&self, text: &str) {
+		assert!(self.contains(text), "'{}' is not drawn in:\n{}", text, self);
+	}
+
+	fn assert_lacks(&self, text: &str) {
+		assert!(!self.contains(text), "'{}' is drawn in:\n{}", text, self);
+	}
+}
+
+impl std::fmt::Display for Screen {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		for row in self.rows.iter() {
+			writeln!(f, "{}", row.trim_end())?;
+		}
+		Ok(())
+	}
+}
+
+fn two_nodes() -> Harness {
+	let mut harness = Harness::new(&[NODE1, NODE2]);
+	harness.lines(NODE1, &ELDER_LINES).lines(NODE2, &ELDER_LINES[..1]);
+	harness
+}
+
+#[test]
+fn node_view_follows_focus() {
+	let mut harness = two_nodes();
+	let screen = harness.draw();
+	screen.assert_contains("Node Log (node1.log)");
+	screen.assert_contains("Node  1 Status:");
+	assert_eq!(screen.cell_at("Dead peer detected").bg, Color::LightGreen);
+
+	let screen = harness.key(InputKey::Right).draw();
+	screen.assert_contains("Node Log (node2.log)");
+	screen.assert_contains("Node  2 Status:");
+	screen.assert_lacks("Dead peer detected");
+}
+
+#[test]
+fn tab_moves_focus_between_widgets() {
+	let mut harness = two_nodes();
+	let screen = harness.draw();
+	assert_eq!(screen.cell_at("┌Node Log").fg, Color::LightGreen);
+	assert_eq!(screen.cell_at("┌Timeline").fg, Color::Reset);
+
+	// The logfile is last, so Tab wraps to the timeline, and down zooms it out in place of scrolling
+	let screen = harness.keys("\t").draw();
+	assert_eq!(screen.cell_at("┌Timeline").fg, Color::LightGreen);
+	assert_eq!(screen.cell_at("┌Node Log").fg, Color::Reset);
+	screen.assert_contains("Node Log (node1.log)");
+	harness.key(InputKey::Down);
+	assert_eq!(harness.app.dash_state.active_timeline, 1);
+
+	let screen = harness.key(InputKey::BackTab).draw();
+	assert_eq!(screen.cell_at("┌Node Log").fg, Color::LightGreen);
+}
+
+#[test]
+fn summary_lists_every_node() {
+	let screen = two_nodes().keys("s").draw();
+	screen.assert_contains("Status");
+	screen.assert_contains("Health");
+	let elders = screen.rows.iter().filter(|row| row.contains("Elder")).count();
+	assert!(elders >= 2, "Both nodes aren't shown as Elders in:\n{}", screen);
+}
+
+#[test]
+fn filter_hides_lines() {
+	let mut harness = two_nodes();
+	harness.draw().assert_contains("Node promoted to Elder");
+
+	harness.keys("f").draw().assert_contains("Filter (enter adds a term");
+	let screen = harness.keys("dead\n\n").draw();
+	screen.assert_contains("[FILTER: +dead]");
+	screen.assert_contains("Dead peer detected");
+	screen.assert_lacks("Node promoted to Elder");
+}
+
+#[test]
+fn views_have_their_titles() {
+	let mut harness = two_nodes();
+	harness.keys("t").draw().assert_contains("Top, sorted by");
+	harness.keys("c").draw().assert_contains("Table (node1.log)");
+	harness.keys("/").draw().assert_contains("Regex Playground (node1.log)");
+	harness.key(InputKey::Esc).draw().assert_contains("Node Log (node1.log)");
+	harness.keys("?").draw().assert_contains("Search All Nodes");
+	harness.key(InputKey::Esc).draw().assert_contains("Node Log (node1.log)");
+	let screen = harness.keys("D").draw();
+	screen.assert_contains("Parser History (node1.log)");
+	screen.assert_contains("vdash (ingest and drawing)");
+	harness.keys(":").draw().assert_contains("Command Palette");
+}
+
+#[test]
+fn low_power_shows_only_the_summary() {
+	let mut harness = two_nodes();
+	harness.keys("z").draw().assert_contains("Summary (low power, 'z' for all views)");
+	let screen = harness.keys("t").draw();
+	screen.assert_contains("Summary (low power, 'z' for all views)");
+	screen.assert_lacks("Top, sorted by");
+	harness.keys("z").draw().assert_contains("Node Log (node1.log)");
+}
+
+#[test]
+fn line_detail_shows_the_selected_line() {
+	let screen = two_nodes().keys("\n").draw();
+	screen.assert_contains("Line (up/down or j/k scroll, esc closes)");
+	screen.assert_contains("src/routing.rs:52");
+}
+
+#[test]
+fn alerts_bar_shows_until_acknowledged() {
+	let mut harness = two_nodes();
+	harness.app.run_hooks();
+	harness.draw().assert_lacks("('!' to review)");
+
+	harness.lines(NODE2, &["[sn_node] ERROR 2021-02-20T09:03:00.000000+00:00 [src/node/mod.rs:120] Failed to store chunk"]);
+	harness.app.run_hooks();
+	let screen = harness.draw();
+	screen.assert_contains(" 1 alert ");
+	screen.assert_contains("node 2 error:");
+
+	harness.keys("!").draw().assert_contains("Alerts, 1 pending");
+	harness.keys("\n").draw().assert_contains("Alerts, 0 pending");
+	harness.key(InputKey::Esc).draw().assert_lacks("('!' to review)");
+}
+
+#[test]
+fn celebrates_promotion_to_elder_when_asked() {
+	// A promotion is only an event from a known age
+	let adult = "[sn_node] INFO 2021-02-20T08:40:12.301958+00:00 [src/node/mod.rs:97] Node promoted to Adult";
+	let mut harness = Harness::new(&[NODE1]);
+	harness.lines(NODE1, &[adult, ELDER_LINES[0]]).app.run_hooks();
+	harness.draw().assert_lacks("has been promoted to Elder");
+
+	let mut harness = Harness::new(&["--celebrate", NODE1]);
+	harness.lines(NODE1, &[adult, ELDER_LINES[0]]).app.run_hooks();
+	let screen = harness.draw();
+	screen.assert_contains("Node 1 (node1.log) has been promoted to Elder");
+	screen.assert_contains(ELDER_BANNER_ROW);
+}
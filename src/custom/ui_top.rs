@@ -0,0 +1,210 @@
+///! Top view: nodes ordered by a live metric, like htop
+///!
+///! Rows are sorted again on every redraw so that the busiest or most troubled
+///! nodes stay at the top. '<' and '>' change the column sorted by, which is
+///! highlighted in the header. Rates are counts in the last complete minute,
+///! except lines which are per second. The p95 column is the 95th percentile
+///! request latency of the last LATENCY_MINUTES, to the resolution of the
+///! latency heatmap's buckets, and is blank without --latency-pattern as
+///! sn_node doesn't log latency itself.
+use super::app::{DashState, LogMonitor, NodeStatus, OUTLIER_TIMELINE};
+use super::tags;
+use super::text::{self, Align};
+use super::ui::status_colour;
+use chrono::Utc;
+use std::collections::HashMap;
+use vault_metrics::{latency_bucket_label, LATENCY_BUCKETS, LATENCY_MINUTES};
+
+use tui::{
+	backend::Backend,
+	style::{Color, Modifier, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, List, ListItem},
+	Frame,
+};
+
+///! Column headings and widths, in display order
+pub static TOP_COLUMNS: [(&str, usize); 9] = [
+	("Node", 4),
+	("Status", 9),
+	("Role", 8),
+	("Age", 4),
+	("Lines/s", 8),
+	("GETS/m", 8),
+	("PUTS/m", 8),
+	("ERRORS/m", 9),
+	("p95 ms", 7),
+];
+
+///! Sorted by errors until another column is chosen
+pub const TOP_SORT_DEFAULT: usize = 7;
+
+///! A node's values for each column, from which the row is sorted and shown
+struct TopRow {
+	index: usize,
+	status: NodeStatus,
+	role: String,
+	agebracket: u64,
+	age: u64,
+	lines_per_minute: Option<u64>,
+	gets: Option<u64>,
+	puts: Option<u64>,
+	errors: Option<u64>,
+	///! None without --latency-pattern, and Some(None) without recent requests
+	latency_p95: Option<Option<f64>>,
+}
+
+impl TopRow {
+	fn new(monitor: &LogMonitor) -> TopRow {
+		let metrics = &monitor.metrics;
+		TopRow {
+			index: monitor.index,
+			status: metrics.status(),
+			role: metrics.agebracket_string(),
+			agebracket: metrics.agebracket as u64,
+			age: metrics.node_age as u64,
+			lines_per_minute: metrics.lines_timeline.last_complete_bucket(OUTLIER_TIMELINE),
+			gets: metrics.gets_timeline.last_complete_bucket(OUTLIER_TIMELINE),
+			puts: metrics.puts_timeline.last_complete_bucket(OUTLIER_TIMELINE),
+			errors: metrics.errors_timeline.last_complete_bucket(OUTLIER_TIMELINE),
+			latency_p95: match monitor.measures_latency() {
+				true => Some(metrics.latency.percentile(Utc::now(), LATENCY_MINUTES, 0.95)),
+				false => None,
+			},
+		}
+	}
+
+	///! Value sorted on for a column, largest first
+	fn sort_value(&self, column: usize) -> u64 {
+		match column {
+			1 => self.status as u64,
+			2 => self.agebracket,
+			3 => self.age,
+			4 => self.lines_per_minute.unwrap_or(0),
+			5 => self.gets.unwrap_or(0),
+			6 => self.puts.unwrap_or(0),
+			7 => self.errors.unwrap_or(0),
+			// Casting saturates, so the unbounded top bucket sorts above any other
+			8 => self.latency_p95.flatten().map_or(0, |millis| millis as u64),
+			_ => 0,
+		}
+	}
+
+	fn columns(&self) -> Vec<String> {
+		let rate = |count: Option<u64>| count.map_or(String::from("-"), |count| count.to_string());
+		vec![
+			(self.index + 1).to_string(),
+			self.status.to_string(),
+			self.role.clone(),
+			self.age.to_string(),
+			self.lines_per_minute
+				.map_or(String::from("-"), |count| format!("{:.1}", count as f64 / 60.0)),
+			rate(self.gets),
+			rate(self.puts),
+			rate(self.errors),
+			match self.latency_p95 {
+				None => String::new(),
+				Some(None) => String::from("-"),
+				Some(Some(millis)) if millis.is_infinite() => latency_bucket_label(LATENCY_BUCKETS - 1),
+				Some(Some(millis)) => format!("{:.0}", millis),
+			},
+		]
+	}
+}
+
+///! Order rows by column, largest first except for the node number, with ties in node order
+fn sort_rows(rows: &mut [TopRow], column: usize) {
+	rows.sort_by(|a, b| b.sort_value(column).cmp(&a.sort_value(column)).then(a.index.cmp(&b.index)));
+}
+
+fn format_cell(value: &str, column: usize) -> String {
+	// Text columns are left aligned as in the summary view
+	let align = if column == 1 || column == 2 { Align::Left } else { Align::Right };
+	format!("{} ", text::fit(value, TOP_COLUMNS[column].1, align))
+}
+
+pub fn draw_top_dash<B: Backend>(
+	f: &mut Frame<B>,
+	dash_state: &DashState,
+	logfile_names: &[String],
+	monitors: &HashMap<String, LogMonitor>,
+) {
+	let sort_column = dash_state.top_sort;
+	let header: Vec<Span> = TOP_COLUMNS
+		.iter()
+		.enumerate()
+		.map(|(column, (heading, _))| {
+			let style = if column == sort_column {
+				Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD)
+			} else {
+				Style::default().fg(Color::Yellow)
+			};
+			Span::styled(format_cell(heading, column), style)
+		})
+		.collect();
+
+	let mut rows: Vec<TopRow> = logfile_names
+		.iter()
+		.filter_map(|logfile| monitors.get(logfile))
+		.filter(|monitor| !monitor.is_debug_dashboard_log)
+		.map(TopRow::new)
+		.collect();
+	sort_rows(&mut rows, sort_column);
+
+	let mut items = vec![ListItem::new(vec![Spans::from(header)])];
+	for row in rows.iter() {
+		let text: String = row
+			.columns()
+			.iter()
+			.enumerate()
+			.map(|(column, value)| format_cell(value, column))
+			.collect();
+		items.push(ListItem::new(vec![Spans::from(text)]).style(Style::default().fg(status_colour(row.status))));
+	}
+
+	let title = tags::filtered_title(
+		&format!("Top, sorted by {} ('<' '>' to change)", TOP_COLUMNS[sort_column].0),
+		&dash_state.tag_filter,
+	);
+	let top_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(top_widget, f.size());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn row(index: usize, errors: Option<u64>) -> TopRow {
+		TopRow {
+			index,
+			status: NodeStatus::Ok,
+			role: String::from("Adult"),
+			agebracket: 0,
+			age: 0,
+			lines_per_minute: None,
+			gets: None,
+			puts: None,
+			errors,
+			latency_p95: None,
+		}
+	}
+
+	#[test]
+	fn sorts_largest_first_with_ties_in_node_order() {
+		let mut rows = vec![row(0, Some(1)), row(1, None), row(2, Some(5)), row(3, Some(1))];
+		sort_rows(&mut rows, TOP_SORT_DEFAULT);
+		let order: Vec<usize> = rows.iter().map(|row| row.index).collect();
+		assert_eq!(order, vec![2, 0, 3, 1]);
+
+		sort_rows(&mut rows, 0);
+		let order: Vec<usize> = rows.iter().map(|row| row.index).collect();
+		assert_eq!(order, vec![0, 1, 2, 3]);
+
+		rows[0].latency_p95 = Some(Some(256.0));
+		rows[1].latency_p95 = Some(Some(f64::INFINITY));
+		rows[2].latency_p95 = Some(None);
+		sort_rows(&mut rows, 8);
+		let p95: Vec<String> = rows.iter().map(|row| row.columns()[8].clone()).collect();
+		assert_eq!(p95, ["1s+", "256", "-", ""]);
+	}
+}
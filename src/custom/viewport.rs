@@ -0,0 +1,49 @@
+///! The slice of a large buffer of lines in view
+///!
+///! Building a ListItem for every line retained each frame is too slow when
+///! retention runs to hundreds of thousands of lines, so the logfile view
+///! renders only the lines which fit its area. The selection stays an index
+///! into the full buffer, and the viewport scrolls as little as needed to
+///! keep it in view, as a List would over all of the items.
+use std::ops::Range;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+	///! Index in the buffer of the first line in view
+	pub offset: usize,
+}
+
+impl Viewport {
+	///! The lines in view of a buffer of len lines, height lines high, scrolled to show selected
+	pub fn window(&mut self, len: usize, selected: Option<usize>, height: usize) -> Range<usize> {
+		let height = height.max(1);
+		// Stay within the buffer, as lines expire from its start
+		self.offset = self.offset.min(len.saturating_sub(height));
+		if let Some(selected) = selected.map(|selected| selected.min(len.saturating_sub(1))) {
+			if selected < self.offset {
+				self.offset = selected;
+			} else if selected >= self.offset + height {
+				self.offset = selected + 1 - height;
+			}
+		}
+		self.offset..len.min(self.offset + height)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scrolls_to_keep_selection_in_view() {
+		let mut viewport = Viewport::default();
+		assert_eq!(viewport.window(0, None, 10), 0..0);
+		assert_eq!(viewport.window(500_000, Some(499_999), 10), 499_990..500_000);
+		// Moving within the view doesn't scroll
+		assert_eq!(viewport.window(500_000, Some(499_995), 10), 499_990..500_000);
+		assert_eq!(viewport.window(500_000, Some(100), 10), 100..110);
+		assert_eq!(viewport.window(500_000, Some(115), 10), 106..116);
+		// Lines expiring from the start leave the view within the buffer
+		assert_eq!(viewport.window(50, None, 10), 40..50);
+	}
+}
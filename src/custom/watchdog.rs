@@ -0,0 +1,176 @@
+///! Watchdog integration, so that a supervisor can restart a wedged vdash
+///!
+///! Under systemd with Type=notify, vdash sends READY=1 once its logfiles are
+///! loaded and, if the service sets WatchdogSec, WATCHDOG=1 from its main loop
+///! at half that interval. With --liveness-file FILE, the time is written to
+///! FILE from the main loop every second or so, for supervisors and health
+///! checks which watch the age of a file instead. Pings come from the main loop
+///! (on each tick) rather than a thread of their own so that they stop if the
+///! main loop stops.
+use chrono::Utc;
+use std::time::{Duration, Instant};
+
+///! Least time between writes of the --liveness-file
+const LIVENESS_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct Watchdog {
+	///! Connection to systemd, when started by systemd with Type=notify
+	notifier: Option<Notifier>,
+	///! Time between WATCHDOG=1 pings, when systemd expects them
+	ping_interval: Option<Duration>,
+	last_ping: Option<Instant>,
+	liveness_file: Option<String>,
+	last_liveness: Option<Instant>,
+}
+
+impl Watchdog {
+	pub fn new(liveness_file: Option<String>) -> Watchdog {
+		// WATCHDOG_PID names the process systemd is watching, if it isn't us
+		let watched = match std::env::var("WATCHDOG_PID") {
+			Ok(pid) => pid.parse() == Ok(std::process::id()),
+			Err(_) => true,
+		};
+		let ping_interval = match std::env::var("WATCHDOG_USEC").map(|usec| usec.parse::<u64>()) {
+			Ok(Ok(usec)) if watched && usec > 0 => Some(Duration::from_micros(usec / 2)),
+			_ => None,
+		};
+
+		Watchdog {
+			notifier: Notifier::from_env(),
+			ping_interval,
+			last_ping: None,
+			liveness_file,
+			last_liveness: None,
+		}
+	}
+
+	///! Tell systemd that vdash has started, and ping at once
+	pub fn ready(&mut self) {
+		self.notify("READY=1");
+		self.tick();
+	}
+
+	///! Ping systemd and write the liveness file, when due
+	pub fn tick(&mut self) {
+		let now = Instant::now();
+		if let Some(ping_interval) = self.ping_interval {
+			if !matches!(self.last_ping, Some(last_ping) if now - last_ping < ping_interval) {
+				self.notify("WATCHDOG=1");
+				self.last_ping = Some(now);
+			}
+		}
+
+		if let Some(liveness_file) = &self.liveness_file {
+			if !matches!(self.last_liveness, Some(last_liveness) if now - last_liveness < LIVENESS_INTERVAL) {
+				if let Err(e) = std::fs::write(liveness_file, format!("{}\n", Utc::now().to_rfc3339())) {
+					warn!("failed to write liveness file {}: {}", liveness_file, e);
+				}
+				self.last_liveness = Some(now);
+			}
+		}
+	}
+
+	///! Tell systemd that vdash is shutting down, so a slow shutdown isn't taken for a hang
+	pub fn stopping(&self) {
+		self.notify("STOPPING=1");
+	}
+
+	fn notify(&self, state: &str) {
+		if let Some(notifier) = &self.notifier {
+			if let Err(e) = notifier.send(state) {
+				warn!("failed to notify systemd of {}: {}", state, e);
+			}
+		}
+	}
+}
+
+#[cfg(unix)]
+use self::unix::Notifier;
+
+#[cfg(not(unix))]
+struct Notifier;
+
+#[cfg(not(unix))]
+impl Notifier {
+	fn from_env() -> Option<Notifier> {
+		None
+	}
+
+	fn send(&self, _state: &str) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(unix)]
+mod unix {
+	use std::os::unix::net::UnixDatagram;
+
+	///! Sends state to the socket given by systemd in NOTIFY_SOCKET (see sd_notify(3))
+	pub struct Notifier {
+		pub socket: UnixDatagram,
+		pub path: String,
+	}
+
+	impl Notifier {
+		pub fn from_env() -> Option<Notifier> {
+			let path = std::env::var("NOTIFY_SOCKET").ok().filter(|path| !path.is_empty())?;
+			match UnixDatagram::unbound() {
+				Ok(socket) => Some(Notifier { socket, path }),
+				Err(e) => {
+					warn!("failed to create socket for NOTIFY_SOCKET: {}", e);
+					None
+				}
+			}
+		}
+
+		pub fn send(&self, state: &str) -> std::io::Result<()> {
+			// A leading '@' is a socket in the abstract namespace, which only Linux has
+			#[cfg(target_os = "linux")]
+			{
+				if let Some(name) = self.path.strip_prefix('@') {
+					use std::os::linux::net::SocketAddrExt;
+					let address = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+					return self.socket.send_to_addr(state.as_bytes(), &address).map(|_| ());
+				}
+			}
+			self.socket.send_to(state.as_bytes(), &self.path).map(|_| ())
+		}
+	}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use std::os::unix::net::UnixDatagram;
+
+	#[test]
+	fn notifies_and_writes_liveness_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let socket_path = dir.path().join("notify");
+		let systemd = UnixDatagram::bind(&socket_path).unwrap();
+		let liveness_file = dir.path().join("alive");
+
+		let mut watchdog = Watchdog {
+			notifier: Some(Notifier {
+				socket: UnixDatagram::unbound().unwrap(),
+				path: socket_path.to_str().unwrap().to_string(),
+			}),
+			ping_interval: Some(Duration::from_secs(60)),
+			last_ping: None,
+			liveness_file: Some(liveness_file.to_str().unwrap().to_string()),
+			last_liveness: None,
+		};
+		watchdog.ready();
+		watchdog.tick(); // Not yet due
+
+		let mut buffer = [0; 64];
+		let mut received = Vec::<String>::new();
+		systemd.set_nonblocking(true).unwrap();
+		while let Ok(length) = systemd.recv(&mut buffer) {
+			received.push(String::from_utf8_lossy(&buffer[..length]).to_string());
+		}
+		assert_eq!(received, vec!["READY=1", "WATCHDOG=1"]);
+		let alive = std::fs::read_to_string(&liveness_file).unwrap();
+		assert!(chrono::DateTime::parse_from_rfc3339(alive.trim()).is_ok());
+	}
+}
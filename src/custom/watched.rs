@@ -0,0 +1,179 @@
+///! Watched values: numbers a node logs, recorded over time and charted
+///!
+///! Each --watch NAME=REGEX is matched against every line of every node, and
+///! the number captured by the group named `value` is recorded at the line's
+///! time, for example:
+///!
+///!     --watch 'used=used space: (?P<value>\d+)'
+///!
+///! Any numeric output in a logfile so becomes a graph without changes to
+///! the parser. The `watched` node widget charts each series, and is added to
+///! the node view whenever a --watch is given.
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use super::app::LogMonitor;
+
+use tui::{
+	backend::Backend,
+	layout::Rect,
+	style::{Color, Style},
+	text::{Span, Spans},
+	widgets::{Block, Borders, List, ListItem},
+	Frame,
+};
+
+///! Most recent values kept in each series
+const SERIES_VALUES_MAX: usize = 1000;
+
+///! Heights of a value in a chart, lowest first
+static CHART_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+///! A --watch option: a name and a regex capturing a value
+#[derive(Debug, Clone)]
+pub struct WatchPattern {
+	pub name: String,
+	pub regex: Regex,
+}
+
+impl FromStr for WatchPattern {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<WatchPattern, String> {
+		let (name, pattern) = match s.split_once('=') {
+			Some((name, pattern)) if !name.is_empty() && !pattern.is_empty() => (name, pattern),
+			_ => return Err(format!("'{}' is not of the form NAME=REGEX", s)),
+		};
+		let regex = Regex::new(pattern).map_err(|e| format!("{}: {}", name, e))?;
+		if !regex.capture_names().any(|capture| capture == Some("value")) {
+			return Err(format!("{}: the regex has no capture named 'value', as in (?P<value>\\d+)", name));
+		}
+		Ok(WatchPattern {
+			name: name.to_string(),
+			regex,
+		})
+	}
+}
+
+impl WatchPattern {
+	///! The value captured from line, if it matches with a number
+	pub fn value(&self, line: &str) -> Option<f64> {
+		let captures = self.regex.captures(line)?;
+		captures.name("value")?.as_str().trim().parse().ok()
+	}
+}
+
+///! Values of a watched pattern, oldest first
+#[derive(Debug, Default)]
+pub struct ValueSeries {
+	pub values: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl ValueSeries {
+	pub fn record(&mut self, time: DateTime<Utc>, value: f64) {
+		self.values.push_back((time, value));
+		while self.values.len() > SERIES_VALUES_MAX {
+			self.values.pop_front();
+		}
+	}
+
+	pub fn latest(&self) -> Option<f64> {
+		self.values.back().map(|(_, value)| *value)
+	}
+
+	///! The least and greatest of the most recent count values
+	pub fn range(&self, count: usize) -> Option<(f64, f64)> {
+		self.recent(count).fold(None, |range, value| match range {
+			None => Some((value, value)),
+			Some((least, greatest)) => Some((least.min(value), greatest.max(value))),
+		})
+	}
+
+	fn recent(&self, count: usize) -> impl Iterator<Item = f64> + '_ {
+		self.values.iter().skip(self.values.len().saturating_sub(count)).map(|(_, value)| *value)
+	}
+
+	///! The most recent count values as a chart of one row, each scaled between the least and greatest
+	pub fn chart(&self, count: usize) -> String {
+		let (least, greatest) = match self.range(count) {
+			Some(range) => range,
+			None => return String::new(),
+		};
+		let top = CHART_LEVELS.len() - 1;
+		self.recent(count)
+			.map(|value| match greatest > least {
+				true => CHART_LEVELS[(((value - least) / (greatest - least)) * top as f64).round() as usize],
+				false => CHART_LEVELS[top / 2],
+			})
+			.collect()
+	}
+}
+
+///! A value, without a fraction if it is whole
+fn format_value(value: f64) -> String {
+	match value.fract() == 0.0 && value.abs() < 1e15 {
+		true => format!("{}", value as i64),
+		false => format!("{:.2}", value),
+	}
+}
+
+pub fn draw_node_watched<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor) {
+	// Each value is a column of the chart, leaving room for the border
+	let columns = (area.width as usize).saturating_sub(2);
+	let mut items = Vec::<ListItem>::new();
+	for (pattern, series) in monitor.watch_patterns.iter().zip(monitor.watched.iter()) {
+		let summary = match (series.latest(), series.range(columns)) {
+			(Some(latest), Some((least, greatest))) => format!(
+				"{} (min {}, max {})",
+				format_value(latest),
+				format_value(least),
+				format_value(greatest)
+			),
+			_ => String::from("-"),
+		};
+		items.push(ListItem::new(Spans::from(vec![
+			Span::styled(format!("{}: ", pattern.name), Style::default().fg(Color::Yellow)),
+			Span::raw(summary),
+		])));
+		items.push(ListItem::new(Spans::from(Span::styled(series.chart(columns), Style::default().fg(Color::Cyan)))));
+	}
+	if items.is_empty() {
+		items.push(ListItem::new(Spans::from("Nothing watched (see --watch)")));
+	}
+
+	let heading = format!("Node {:>2} Watched", monitor.index + 1);
+	let watched_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(watched_widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn captures_values_from_lines() {
+		let pattern: WatchPattern = "used=used space: (?P<value>[0-9.]+)".parse().unwrap();
+		assert_eq!(pattern.name, "used");
+		assert_eq!(pattern.value("Checking used space: 1024 bytes"), Some(1024.0));
+		assert_eq!(pattern.value("Checking used space: none"), None);
+		assert!("used=used space: (\\d+)".parse::<WatchPattern>().is_err());
+		assert!("used space".parse::<WatchPattern>().is_err());
+	}
+
+	#[test]
+	fn charts_recent_values() {
+		let time = Utc::now();
+		let mut series = ValueSeries::default();
+		assert_eq!(series.chart(4), "");
+		for value in [5.0, 10.0, 0.0, 7.0, 14.0].iter() {
+			series.record(time, *value);
+		}
+		assert_eq!(series.latest(), Some(14.0));
+		assert_eq!(series.range(4), Some((0.0, 14.0)));
+		assert_eq!(series.chart(4), "▆▁▅█");
+		assert_eq!(format_value(14.0), "14");
+		assert_eq!(format_value(2.5), "2.50");
+	}
+}
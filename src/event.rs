@@ -8,15 +8,16 @@ use std::thread;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use termion::event::Key;
+use termion::event::{self as term_event, Key, MouseEvent};
 use termion::input::TermRead;
 
 pub enum Event<I> {
 	Input(I),
+	Mouse(MouseEvent),
 	Tick,
 }
 
-/// A small event handler that wrap termion input and tick events. Each event
+/// A small event handler that wrap termion input (keys and mouse) and tick events. Each event
 /// type is handled in its own thread and returned to a common `Receiver`
 pub struct Events {
 	pub rx: mpsc::UnboundedReceiver<Event<Key>>,
@@ -53,15 +54,19 @@ impl Events {
 			let ignore_exit_key = ignore_exit_key.clone();
 			thread::spawn(move || {
 				let stdin = io::stdin();
-				for evt in stdin.keys() {
-					if let Ok(key) = evt {
-						if let Err(err) = tx.send(Event::Input(key)) {
-							eprintln!("{}", err);
-							return;
-						}
-						if !ignore_exit_key.load(Ordering::Relaxed) && key == config.exit_key {
-							return;
-						}
+				for evt in stdin.events() {
+					let event = match evt {
+						Ok(term_event::Event::Key(key)) => Event::Input(key),
+						Ok(term_event::Event::Mouse(mouse)) => Event::Mouse(mouse),
+						_ => continue,
+					};
+					let exit = matches!(event, Event::Input(key) if key == config.exit_key);
+					if let Err(err) = tx.send(event) {
+						eprintln!("{}", err);
+						return;
+					}
+					if !ignore_exit_key.load(Ordering::Relaxed) && exit {
+						return;
 					}
 				}
 			})
@@ -1,57 +1,5 @@
-use rand::distributions::{Distribution, Uniform};
-use rand::rngs::ThreadRng;
 use tui::widgets::ListState;
 
-#[derive(Clone)]
-pub struct RandomSignal {
-	distribution: Uniform<u64>,
-	rng: ThreadRng,
-}
-
-impl RandomSignal {
-	pub fn new(lower: u64, upper: u64) -> RandomSignal {
-		RandomSignal {
-			distribution: Uniform::new(lower, upper),
-			rng: rand::thread_rng(),
-		}
-	}
-}
-
-impl Iterator for RandomSignal {
-	type Item = u64;
-	fn next(&mut self) -> Option<u64> {
-		Some(self.distribution.sample(&mut self.rng))
-	}
-}
-
-#[derive(Clone)]
-pub struct SinSignal {
-	x: f64,
-	interval: f64,
-	period: f64,
-	scale: f64,
-}
-
-impl SinSignal {
-	pub fn new(interval: f64, period: f64, scale: f64) -> SinSignal {
-		SinSignal {
-			x: 0.0,
-			interval,
-			period,
-			scale,
-		}
-	}
-}
-
-impl Iterator for SinSignal {
-	type Item = (f64, f64);
-	fn next(&mut self) -> Option<Self::Item> {
-		let point = (self.x, (self.x * 1.0 / self.period).sin() * self.scale);
-		self.x += self.interval;
-		Some(point)
-	}
-}
-
 pub struct TabsState<'a> {
 	pub titles: Vec<&'a str>,
 	pub index: usize,
@@ -125,4 +73,44 @@ impl<T> StatefulList<T> {
 	pub fn unselect(&mut self) {
 		self.state.select(None);
 	}
+
+	///! Add item at the end, dropping the oldest beyond max, and keep the selection
+	///! on the same item, or on the new item if the last was selected
+	pub fn push_capped(&mut self, item: T, max: usize) {
+		let following = match self.state.selected() {
+			Some(i) => i + 1 >= self.items.len(),
+			None => true,
+		};
+		self.items.push(item);
+		let excess = self.items.len().saturating_sub(max);
+		self.items.drain(..excess);
+		let selected = match (following, self.state.selected()) {
+			(false, Some(i)) => i.saturating_sub(excess),
+			_ => self.items.len().saturating_sub(1),
+		};
+		self.state.select(Some(selected));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn capped_list_keeps_its_selection() {
+		let mut list = StatefulList::new();
+		for item in 0..5 {
+			list.push_capped(item, 3);
+		}
+		assert_eq!(list.items, [2, 3, 4]);
+		assert_eq!(list.state.selected(), Some(2));
+
+		// Off the end, the selected item stays selected until it is dropped
+		list.state.select(Some(1));
+		list.push_capped(5, 3);
+		assert_eq!(list.state.selected(), Some(0));
+		assert_eq!(list.items[0], 3);
+		list.push_capped(6, 3);
+		assert_eq!(list.state.selected(), Some(0));
+	}
 }
@@ -1,2 +1,3 @@
 pub mod gauge;
+pub mod monochrome;
 pub mod sparkline;
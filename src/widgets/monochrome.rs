@@ -0,0 +1,39 @@
+use tui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+/// A widget which removes colour from everything already drawn in its area
+///
+/// Render it last, over the whole frame, for a display which relies on
+/// text and modifiers (e.g. bold) alone.
+#[derive(Debug, Clone, Default)]
+pub struct Monochrome;
+
+impl Widget for Monochrome {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::{Modifier, Style};
+
+    #[test]
+    fn removes_colour_but_not_modifiers() {
+        let area = Rect::new(0, 0, 10, 2);
+        let mut buf = Buffer::empty(area);
+        let style = Style::default().fg(Color::Red).bg(Color::Blue).add_modifier(Modifier::BOLD);
+        buf.set_string(0, 0, "✖ ERROR", style);
+        Monochrome.render(Rect::new(0, 0, 10, 1), &mut buf);
+
+        let cell = buf.get(0, 0);
+        assert_eq!((cell.symbol.as_str(), cell.fg, cell.bg), ("✖", Color::Reset, Color::Reset));
+        assert!(cell.modifier.contains(Modifier::BOLD));
+    }
+}
@@ -0,0 +1,13 @@
+//! Decode any line, which must not panic and must keep the line it was given
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vault_metrics::{AssumedOffset, LogEntry};
+
+fuzz_target!(|data: &[u8]| {
+	let line = String::from_utf8_lossy(data);
+	for offset in &[AssumedOffset::Local, AssumedOffset::Fixed(0)] {
+		if let Some(entry) = LogEntry::decode(&line, offset, None) {
+			assert_eq!(entry.logstring, line);
+		}
+	}
+});
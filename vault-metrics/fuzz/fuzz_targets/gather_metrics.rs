@@ -0,0 +1,18 @@
+//! Gather metrics from any logfile, one line per line of input, which must not
+//! panic or lower a count other than at a restart
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vault_metrics::{AssumedOffset, NodeMetrics};
+
+fuzz_target!(|data: &[u8]| {
+	let logfile = String::from_utf8_lossy(data);
+	let mut metrics = NodeMetrics::new(1, AssumedOffset::Fixed(0));
+	metrics.loading = true;
+	for line in logfile.lines() {
+		let before = (metrics.activity_gets, metrics.activity_puts, metrics.activity_errors, metrics.clients_served);
+		let _ = metrics.gather_metrics(line);
+		let after = (metrics.activity_gets, metrics.activity_puts, metrics.activity_errors, metrics.clients_served);
+		let restarted = metrics.log_history.last().map_or(false, |entry| entry.category == "START");
+		assert!(restarted || (after.0 >= before.0 && after.1 >= before.1 && after.2 >= before.2 && after.3 >= before.3));
+	}
+});
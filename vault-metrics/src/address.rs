@@ -0,0 +1,123 @@
+///! The address a node advertises to the network, from its logs
+///!
+///! A node logs the address it listens on and, once it knows it, the public
+///! address it advertises to other nodes, such as:
+///!
+///!     Listening for incoming connections on 0.0.0.0:12000
+///!     Our public address is 203.0.113.7:12000
+///!     Our contact info: {"peer": "203.0.113.7:12000"}
+///!
+///! The public address is preferred, as it is how other nodes reach this one.
+///! An address which changes while the node runs suggests a NAT mapping or
+///! configuration problem, so each change is recorded. A node's address is
+///! forgotten when it restarts, as it may rightly come back on another.
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static::lazy_static! {
+	static ref ADDRESS_MESSAGE: Regex =
+		Regex::new(r"(?i)\b(?P<kind>public|external|advertised|advertising|contact|listening|listens|bound)\b").expect("The regex failed to compile. This is a bug.");
+	static ref SOCKET_ADDRESS: Regex =
+		Regex::new(r"\[[0-9a-fA-F:.]+\]:\d+|\b\d{1,3}(?:\.\d{1,3}){3}:\d+\b").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Limit on changes remembered, oldest first to go
+static CHANGES_MAX: usize = 20;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct NodeAddress {
+	///! The address the node advertises, or failing that the one it listens on
+	pub current: Option<String>,
+	///! Whether current is the advertised (public) address
+	pub public: bool,
+	///! Changes of address since the node started, most recent last
+	pub changes: Vec<AddressChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AddressChange {
+	pub from: String,
+	pub to: String,
+	pub time: Option<DateTime<Utc>>,
+}
+
+impl NodeAddress {
+	///! Note any address message gives, returning true if it gives one
+	pub fn record(&mut self, message: &str, time: Option<DateTime<Utc>>) -> bool {
+		let kind = match ADDRESS_MESSAGE.captures(message).and_then(|captures| captures.name("kind")) {
+			Some(kind) => kind,
+			None => return false,
+		};
+		// The first address after the word saying what it is, other than one which listens on every interface
+		let address = match SOCKET_ADDRESS.find(&message[kind.end()..]) {
+			Some(address) if !is_unspecified(address.as_str()) => address.as_str(),
+			_ => return false,
+		};
+		let public = !matches!(kind.as_str().to_lowercase().as_str(), "listening" | "listens" | "bound");
+
+		match &self.current {
+			None => {}
+			Some(current) if current == address => {}
+			// A public address is better than a listening one, but isn't a change of address
+			Some(_) if public && !self.public => {}
+			Some(_) if !public && self.public => return true,
+			Some(current) => {
+				self.changes.push(AddressChange {
+					from: current.clone(),
+					to: address.to_string(),
+					time,
+				});
+				if self.changes.len() > CHANGES_MAX {
+					self.changes.remove(0);
+				}
+			}
+		}
+		self.current = Some(address.to_string());
+		self.public = public;
+		true
+	}
+
+	///! Whether the address has changed since the node started
+	pub fn changed(&self) -> bool {
+		!self.changes.is_empty()
+	}
+}
+
+fn is_unspecified(address: &str) -> bool {
+	address.starts_with("0.0.0.0:") || address.starts_with("[::]:")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn notes_address_and_changes() {
+		let mut address = NodeAddress::default();
+		assert!(!address.record("Listening for incoming connections on 0.0.0.0:12000", None));
+		assert!(address.record("Listening for incoming connections on 192.168.1.5:12000", None));
+		assert_eq!(address.current.as_deref(), Some("192.168.1.5:12000"));
+		assert!(!address.public);
+
+		assert!(address.record("Our public address is 203.0.113.7:12000", None));
+		assert_eq!(address.current.as_deref(), Some("203.0.113.7:12000"));
+		assert!(address.public && !address.changed());
+
+		// A listening address doesn't replace the public one
+		assert!(address.record("Listening on 192.168.1.5:12001", None));
+		assert_eq!(address.current.as_deref(), Some("203.0.113.7:12000"));
+		assert!(!address.record("Received message from 198.51.100.2:12000", None));
+
+		assert!(address.record(r#"Our contact info: {"peer": "203.0.113.7:40122"}"#, None));
+		assert_eq!(address.current.as_deref(), Some("203.0.113.7:40122"));
+		assert_eq!(
+			address.changes,
+			[AddressChange {
+				from: String::from("203.0.113.7:12000"),
+				to: String::from("203.0.113.7:40122"),
+				time: None,
+			}]
+		);
+	}
+}
@@ -0,0 +1,99 @@
+///! Counting of PUTs and GETs by the type of data stored or read
+///!
+///! Handler messages name the data they handle, such as 'ReadChunk' or
+///! 'MapStorage: Writing chunk PASSED', which is taken as one of DATA_TYPES
+///! (an immutable chunk counting as a Blob). Which of these a node stores
+///! depends on its version, so only those seen are listed, along with
+///! DATA_TYPE_OTHER for PUTs and GETs whose message names no data type.
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+///! Data types in the order listed, each with the pattern naming it, the first to match being taken
+pub static DATA_TYPES: [&str; 4] = ["Blob", "Map", "Sequence", "Register"];
+pub static DATA_TYPE_OTHER: &str = "Other";
+
+lazy_static::lazy_static! {
+	static ref DATA_TYPE_PATTERNS: Vec<(&'static str, Regex)> = vec![
+		("Register", Regex::new(r"(?i)register").expect("The regex failed to compile. This is a bug.")),
+		("Sequence", Regex::new(r"(?i)sequence").expect("The regex failed to compile. This is a bug.")),
+		// 'Map' in a name such as ReadMap or MapStorage, but 'map' only as a word
+		("Map", Regex::new(r"Map|\bmap\b").expect("The regex failed to compile. This is a bug.")),
+		("Blob", Regex::new(r"(?i)blob|chunk").expect("The regex failed to compile. This is a bug.")),
+	];
+}
+
+///! The data type named by a handler message, or DATA_TYPE_OTHER
+pub fn data_type_of(message: &str) -> &'static str {
+	DATA_TYPE_PATTERNS
+		.iter()
+		.find(|(_, pattern)| pattern.is_match(message))
+		.map_or(DATA_TYPE_OTHER, |(data_type, _)| data_type)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct OperationCounts {
+	pub puts: u64,
+	pub gets: u64,
+}
+
+impl OperationCounts {
+	pub fn total(&self) -> u64 {
+		self.puts + self.gets
+	}
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct DataTypeCounts {
+	counts: BTreeMap<String, OperationCounts>,
+}
+
+impl DataTypeCounts {
+	///! Count a PUT handled by message
+	pub fn count_put(&mut self, message: &str) {
+		self.counts.entry(data_type_of(message).to_string()).or_default().puts += 1;
+	}
+
+	///! Count a GET handled by message
+	pub fn count_get(&mut self, message: &str) {
+		self.counts.entry(data_type_of(message).to_string()).or_default().gets += 1;
+	}
+
+	///! The counts of each data type seen, in the order of DATA_TYPES with DATA_TYPE_OTHER last
+	pub fn rows(&self) -> Vec<(&str, OperationCounts)> {
+		DATA_TYPES
+			.iter()
+			.chain([DATA_TYPE_OTHER].iter())
+			.filter_map(|data_type| self.counts.get(*data_type).map(|counts| (*data_type, *counts)))
+			.collect()
+	}
+
+	pub fn clear(&mut self) {
+		self.counts.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn counts_operations_by_data_type() {
+		assert_eq!(data_type_of("Handling NodeDuty: ReadChunk"), "Blob");
+		assert_eq!(data_type_of("MapStorage: Writing chunk PASSED"), "Map");
+		assert_eq!(data_type_of("Handling NodeDuty: WriteSequence"), "Sequence");
+		assert_eq!(data_type_of("Wrote data from message for register b4f1.."), "Register");
+		assert_eq!(data_type_of("Wrote data from message: the bitmap"), "Other");
+
+		let mut counts = DataTypeCounts::default();
+		counts.count_get("Handling NodeDuty: ReadChunk");
+		counts.count_get("Handling NodeDuty: ReadChunk");
+		counts.count_put("Writing chunk succeeded");
+		counts.count_put("Wrote data from message");
+		counts.count_put("Handling NodeDuty: WriteRegister");
+		let rows = counts.rows();
+		assert_eq!(rows.iter().map(|(data_type, _)| *data_type).collect::<Vec<_>>(), ["Blob", "Register", "Other"]);
+		assert_eq!(rows[0].1, OperationCounts { puts: 1, gets: 2 });
+		assert_eq!(rows[2].1.total(), 1);
+	}
+}
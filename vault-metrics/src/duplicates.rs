@@ -0,0 +1,123 @@
+///! Counting of duplicate and replayed messages, by the peer which sent them
+///!
+///! A node logs a warning when it drops a message it has already handled,
+///! such as:
+///!
+///!     Ignoring duplicate message 5e3f..a1 from 10.0.0.7:12000
+///!     Replay detected: msg 91c2..0f from peer b4f1c2d9 already processed
+///!
+///! Each such message is counted, in all and by the peer named in it (a
+///! socket address, or the word after 'from', 'peer' or 'sender'), so that a
+///! misbehaving peer hammering the node stands out as a repeat offender.
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+lazy_static::lazy_static! {
+	static ref DUPLICATE_MESSAGE: Regex =
+		Regex::new(r"(?i)\b(?:duplicate|replay(?:ed)?)\b.*\b(?:msg|message)s?\b|\b(?:msg|message)s?\b.*\b(?:duplicate|replay(?:ed)?)\b").expect("The regex failed to compile. This is a bug.");
+	static ref PEER_ADDRESS: Regex =
+		Regex::new(r"\[[0-9a-fA-F:.]+\]:\d+|\b\d{1,3}(?:\.\d{1,3}){3}(?::\d+)?\b").expect("The regex failed to compile. This is a bug.");
+	static ref PEER_NAMED: Regex =
+		Regex::new(r"(?i)\b(?:from|peer|sender)\b[:=]?\s+(?:peer\s+)?(?P<peer>[^\s,;]+)").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Limit on distinct peers, beyond which duplicates count as PEER_OTHER
+static PEERS_MAX: usize = 100;
+static PEER_OTHER: &str = "(other)";
+///! Duplicates from messages which don't name a peer
+static PEER_UNKNOWN: &str = "(unknown)";
+///! Duplicates from a peer at which it counts as a repeat offender
+pub static REPEAT_OFFENDER_COUNT: u64 = 10;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct DuplicateMessages {
+	///! Duplicate and replayed messages from all peers
+	pub count: u64,
+	peers: HashMap<String, PeerDuplicates>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PeerDuplicates {
+	pub peer: String,
+	pub count: u64,
+	pub last: Option<DateTime<Utc>>,
+}
+
+impl PeerDuplicates {
+	pub fn is_repeat_offender(&self) -> bool {
+		self.count >= REPEAT_OFFENDER_COUNT && self.peer != PEER_OTHER && self.peer != PEER_UNKNOWN
+	}
+}
+
+impl DuplicateMessages {
+	///! Count message if it reports a duplicate or replayed message, returning true if it does
+	pub fn record(&mut self, message: &str, time: Option<DateTime<Utc>>) -> bool {
+		if !DUPLICATE_MESSAGE.is_match(message) {
+			return false;
+		}
+		self.count += 1;
+		let mut peer = peer_of(message).unwrap_or_else(|| String::from(PEER_UNKNOWN));
+		if !self.peers.contains_key(&peer) && self.peers.len() >= PEERS_MAX {
+			peer = String::from(PEER_OTHER);
+		}
+		let duplicates = self.peers.entry(peer.clone()).or_insert_with(|| PeerDuplicates {
+			peer,
+			count: 0,
+			last: None,
+		});
+		duplicates.count += 1;
+		duplicates.last = time.or(duplicates.last);
+		true
+	}
+
+	///! The peers sending most duplicates, most first
+	pub fn top(&self, n: usize) -> Vec<PeerDuplicates> {
+		let mut peers: Vec<&PeerDuplicates> = self.peers.values().collect();
+		peers.sort_by(|a, b| b.count.cmp(&a.count).then(a.peer.cmp(&b.peer)));
+		peers.into_iter().take(n).cloned().collect()
+	}
+
+	pub fn repeat_offenders(&self) -> usize {
+		self.peers.values().filter(|peer| peer.is_repeat_offender()).count()
+	}
+}
+
+///! The peer a message names, preferring a socket address
+fn peer_of(message: &str) -> Option<String> {
+	if let Some(address) = PEER_ADDRESS.find(message) {
+		return Some(address.as_str().to_string());
+	}
+	let peer = PEER_NAMED.captures(message)?.name("peer")?.as_str();
+	let peer = peer.trim_end_matches(&['.', ')', ':'][..]);
+	match peer.is_empty() {
+		true => None,
+		false => Some(peer.to_string()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn counts_duplicates_by_peer() {
+		let mut duplicates = DuplicateMessages::default();
+		for _ in 0..REPEAT_OFFENDER_COUNT {
+			assert!(duplicates.record("Ignoring duplicate message 5e3fa1 from 10.0.0.7:12000", None));
+		}
+		assert!(duplicates.record("Replay detected: msg 91c20f from peer b4f1c2d9 already processed", None));
+		assert!(duplicates.record("Dropping replayed message", None));
+		assert!(!duplicates.record("Wrote data from message", None));
+		assert!(!duplicates.record("Found duplicate chunk on disk", None));
+
+		assert_eq!(duplicates.count, REPEAT_OFFENDER_COUNT + 2);
+		let top = duplicates.top(3);
+		assert_eq!(top[0].peer, "10.0.0.7:12000");
+		assert!(top[0].is_repeat_offender());
+		assert_eq!(top[1].peer, PEER_UNKNOWN);
+		assert_eq!(top[2].peer, "b4f1c2d9");
+		assert_eq!(duplicates.repeat_offenders(), 1);
+	}
+}
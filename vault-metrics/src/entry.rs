@@ -0,0 +1,233 @@
+///! Decoding of node logfile lines into LogEntry
+use chrono::{DateTime, FixedOffset, Local, LocalResult, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+lazy_static::lazy_static! {
+	pub static ref LOG_LINE_PATTERN: Regex =
+		Regex::new(r"(?P<module>^\[[A-Z,a-z,_,-]*\]) (?P<category>[A-Z]{4,6}) (?P<time_string>[^ ]{19,35}) (?P<source>\[.*\]) (?P<message>.*)").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Offset assumed for logfile timestamps without one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AssumedOffset {
+	///! The local timezone, including any daylight saving changes
+	Local,
+	///! A fixed offset, in seconds east of UTC
+	Fixed(i32),
+}
+
+impl AssumedOffset {
+	///! Convert a timestamp without an offset to UTC
+	///!
+	///! A local time repeated when daylight saving ends is taken as the
+	///! earlier of the two unless that is before previous, the time of the
+	///! entry before it. None is returned for a local time skipped when
+	///! daylight saving starts.
+	pub fn to_utc(&self, naive: &NaiveDateTime, previous: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+		match self {
+			AssumedOffset::Local => match Local.from_local_datetime(naive) {
+				LocalResult::Single(time) => Some(time.with_timezone(&Utc)),
+				LocalResult::Ambiguous(earlier, later) => {
+					let earlier = earlier.with_timezone(&Utc);
+					match previous {
+						Some(previous) if earlier < previous => Some(later.with_timezone(&Utc)),
+						_ => Some(earlier),
+					}
+				}
+				LocalResult::None => None,
+			},
+			AssumedOffset::Fixed(seconds) => FixedOffset::east_opt(*seconds)?
+				.from_local_datetime(naive)
+				.single()
+				.map(|time| time.with_timezone(&Utc)),
+		}
+	}
+
+	///! Whether a timestamp without an offset is a local time repeated or
+	///! skipped by a daylight saving change, so that to_utc() may be wrong
+	pub fn is_ambiguous(&self, naive: &NaiveDateTime) -> bool {
+		match self {
+			AssumedOffset::Local => !matches!(Local.from_local_datetime(naive), LocalResult::Single(_)),
+			AssumedOffset::Fixed(_) => false,
+		}
+	}
+}
+
+impl FromStr for AssumedOffset {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<AssumedOffset, String> {
+		let invalid = || format!("invalid offset '{}', expected 'local', 'utc' or e.g. '+01:00'", s);
+		match s.to_lowercase().as_str() {
+			"local" => return Ok(AssumedOffset::Local),
+			"utc" | "z" => return Ok(AssumedOffset::Fixed(0)),
+			_ => {}
+		}
+
+		let sign = match s.chars().next() {
+			Some('+') => 1,
+			Some('-') => -1,
+			_ => return Err(invalid()),
+		};
+		let digits: String = s[1..].chars().filter(|c| *c != ':').collect();
+		if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+			return Err(invalid());
+		}
+		let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+		let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+		if hours > 23 || minutes > 59 {
+			return Err(invalid());
+		}
+		Ok(AssumedOffset::Fixed(sign * (hours * 3600 + minutes * 60)))
+	}
+}
+
+///! Node activity for node activity_history
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ActivityEntry {
+	pub message: String,
+	pub activity: String,
+	pub logstring: String,
+	pub category: String, // First word, "Running", "INFO", "WARN" etc
+	pub time: Option<DateTime<Utc>>,
+	pub source: String,
+
+	pub parser_output: String,
+}
+
+impl ActivityEntry {
+	pub fn new(entry: &LogEntry, activity: &str) -> ActivityEntry {
+		ActivityEntry {
+			message: entry.message.clone(),
+			activity: activity.to_string(),
+			logstring: entry.logstring.clone(),
+			category: entry.category.clone(),
+			time: entry.time,
+			source: entry.source.clone(),
+
+			parser_output: String::from(""),
+		}
+	}
+}
+
+///! Decoded logfile entries for a node log history
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LogEntry {
+	pub logstring: String,
+	pub category: String, // First word, "Running", "INFO", "WARN" etc
+	pub time: Option<DateTime<Utc>>,
+	///! Whether time is a local time made ambiguous by daylight saving, see AssumedOffset::is_ambiguous()
+	#[serde(default)]
+	pub time_ambiguous: bool,
+	pub source: String,
+	pub message: String,
+
+	pub parser_output: String,
+}
+
+impl LogEntry {
+	///! Decode node logfile lines of the form:
+	///! 	[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5
+	///!	[sn_node] ERROR 2020-12-18T16:33:54.237345352+00:00 [src/utils.rs:52] Failed to load auto dump db at /home/mrh/.safe/node/baby-fleming-nodes/sn-node-genesis/transfers/f67c2e75cbce0a6097187cdf95be1c0963ad34105d643cbb00aa1f0e8b113761.db: No such file or directory (os error 2)
+	///!
+	///!
+	///! Timestamps without an offset are converted using assumed_offset, with
+	///! previous being the time of the entry before.
+	pub fn decode(line: &str, assumed_offset: &AssumedOffset, previous: Option<DateTime<Utc>>) -> Option<LogEntry> {
+		let mut _test_entry = LogEntry {
+			logstring: String::from(line),
+			category: String::from("test"),
+			time: None,
+			time_ambiguous: false,
+			source: String::from(""),
+			message: String::from(""),
+			parser_output: String::from("decode()..."),
+		};
+
+		if line.is_empty() {
+			return None;
+		}
+
+		LogEntry::parse_logfile_line(line, assumed_offset, previous)
+	}
+
+	///! Parse a line of the form:
+	///! 	[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5
+	///!	[sn_node] ERROR 2020-12-18T16:33:54.237345352+00:00 [src/utils.rs:52] Failed to load auto dump db at /home/mrh/.safe/node/baby-fleming-nodes/sn-node-genesis/transfers/f67c2e75cbce0a6097187cdf95be1c0963ad34105d643cbb00aa1f0e8b113761.db: No such file or directory (os error 2)
+	fn parse_logfile_line(line: &str, assumed_offset: &AssumedOffset, previous: Option<DateTime<Utc>>) -> Option<LogEntry> {
+		if let Some(captures) = LOG_LINE_PATTERN.captures(line) {
+			let module = captures.name("module").map_or("", |m| m.as_str());
+			let category = captures.name("category").map_or("", |m| m.as_str());
+			let time_string = captures.name("time_string").map_or("", |m| m.as_str());
+			let source = captures.name("source").map_or("", |m| m.as_str());
+			let message = captures.name("message").map_or("", |m| m.as_str());
+			let mut time_str = String::from("None");
+
+			let mut time_utc: Option<DateTime<Utc>> = None;
+			let mut time_ambiguous = false;
+
+			match DateTime::parse_from_str(time_string, "%+") {
+				Ok(time) => {
+					time_utc = Some(time.with_timezone(&Utc));
+					time_str = format!("{}", time);
+					Some(time)
+				}
+				Err(e) => match NaiveDateTime::parse_from_str(time_string, "%Y-%m-%dT%H:%M:%S%.f") {
+					Ok(naive) => {
+						time_utc = assumed_offset.to_utc(&naive, previous);
+						time_ambiguous = assumed_offset.is_ambiguous(&naive);
+						time_str = format!("{} (assumed {:?})", naive, assumed_offset);
+						None
+					}
+					Err(_) => {
+						debug!("ERROR parsing logfile time: {}", e);
+						None
+					}
+				},
+			};
+			let parser_output = format!(
+				"m: {}, c: {}, t: {}, s: {}, m: {}",
+				module, category, time_str, source, message
+			);
+
+			return Some(LogEntry {
+				logstring: String::from(line),
+				category: String::from(category),
+				time: time_utc,
+				time_ambiguous,
+				source: String::from(source),
+				message: String::from(message),
+				parser_output,
+			});
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_timestamps_without_offset() {
+		let line = "[sn_node] INFO 2020-12-18T14:33:49.799447454 [src/node/mod.rs:97] Our Age: 5";
+		let entry = LogEntry::decode(line, &AssumedOffset::Fixed(3600), None).unwrap();
+		assert_eq!(entry.time.unwrap().to_rfc3339(), "2020-12-18T13:33:49.799447454+00:00");
+
+		let line = "[sn_node] INFO 2020-12-18T14:33:49.799447454+02:00 [src/node/mod.rs:97] Our Age: 5";
+		let entry = LogEntry::decode(line, &AssumedOffset::Fixed(3600), None).unwrap();
+		assert_eq!(entry.time.unwrap().to_rfc3339(), "2020-12-18T12:33:49.799447454+00:00");
+	}
+
+#[test]
+	fn parses_assumed_offsets() {
+		assert_eq!("local".parse(), Ok(AssumedOffset::Local));
+		assert_eq!("UTC".parse(), Ok(AssumedOffset::Fixed(0)));
+		assert_eq!("+01:00".parse(), Ok(AssumedOffset::Fixed(3600)));
+		assert_eq!("-0530".parse(), Ok(AssumedOffset::Fixed(-19800)));
+		assert!("+1".parse::<AssumedOffset>().is_err());
+		assert!("01:00".parse::<AssumedOffset>().is_err());
+	}
+}
@@ -0,0 +1,172 @@
+///! Health score: a node's state summed up as a number from 0 to 100
+///!
+///! Each input gives a penalty from 0 (healthy) to 1, and the score is 100
+///! less their weighted average as a percentage:
+///!
+///! - errors: the fraction of entries in the last ERROR_MINUTES which are
+///!   ERRORs, in full at ERROR_FRACTION_FULL
+///! - stalls: time since the last entry, from a minute up to when the node
+///!   counts as STALLED
+///! - restarts: starts in the last RESTART_WINDOW_MINUTES which followed an
+///!   earlier start, in full at RESTARTS_FULL
+///! - disk: how full the chunk store's filesystem is, beyond DISK_USED_FREE
+///! - thresholds: the worst of the metrics checked against [thresholds], half
+///!   at warn and in full at crit (see thresholds.rs)
+///!
+///! An input which isn't available, such as the disk of a remote node, is
+///! left out rather than counted as healthy.
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::metrics::{NodeMetrics, STATUS_STALLED_MINUTES};
+use super::thresholds::MetricLevel;
+use super::timeline::OUTLIER_TIMELINE;
+
+///! Minutes of entries in which ERRORs are counted
+const ERROR_MINUTES: usize = 10;
+///! Fraction of entries which are ERRORs for the full penalty
+const ERROR_FRACTION_FULL: f64 = 0.05;
+///! Minutes in which restarts are counted
+const RESTART_WINDOW_MINUTES: i64 = 60;
+///! Restarts for the full penalty
+const RESTARTS_FULL: f64 = 3.0;
+///! Fraction of the disk which can be used without penalty
+const DISK_USED_FREE: f64 = 0.8;
+
+///! Weight of each input to the score, set in the [health] section of the config file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthWeights {
+	pub errors: u32,
+	pub stalls: u32,
+	pub restarts: u32,
+	pub disk: u32,
+	pub thresholds: u32,
+}
+
+impl Default for HealthWeights {
+	fn default() -> HealthWeights {
+		HealthWeights {
+			errors: 40,
+			stalls: 30,
+			restarts: 20,
+			disk: 10,
+			thresholds: 20,
+		}
+	}
+}
+
+///! Health score of a node from 0 to 100, where disk_used is the fraction of its disk used, if known,
+///! and levels are its metrics checked against thresholds
+pub fn health_score(
+	metrics: &NodeMetrics,
+	weights: &HealthWeights,
+	disk_used: Option<f64>,
+	levels: &[MetricLevel],
+	now: DateTime<Utc>,
+) -> u8 {
+	let thresholds = levels.iter().map(|level| level.level.penalty()).fold(None, |worst: Option<f64>, penalty| {
+		Some(worst.map_or(penalty, |worst| worst.max(penalty)))
+	});
+	let inputs = [
+		(weights.errors, Some(error_penalty(metrics))),
+		(weights.stalls, Some(stall_penalty(metrics))),
+		(weights.restarts, Some(restart_penalty(metrics, now))),
+		(weights.disk, disk_used.map(|used| ((used - DISK_USED_FREE) / (1.0 - DISK_USED_FREE)).clamp(0.0, 1.0))),
+		(weights.thresholds, thresholds),
+	];
+	let mut total_weight = 0.0;
+	let mut penalty = 0.0;
+	for (weight, input) in inputs.iter() {
+		if let Some(input) = input {
+			total_weight += *weight as f64;
+			penalty += *weight as f64 * input;
+		}
+	}
+	if total_weight == 0.0 {
+		return 100;
+	}
+	(100.0 * (1.0 - penalty / total_weight)).round() as u8
+}
+
+fn error_penalty(metrics: &NodeMetrics) -> f64 {
+	let recent = |timeline: &super::timeline::TimelineSet| -> u64 {
+		timeline
+			.bucket_sets
+			.get(OUTLIER_TIMELINE)
+			.map_or(0, |bucket_set| bucket_set.buckets().iter().rev().take(ERROR_MINUTES).sum())
+	};
+	let errors = recent(&metrics.errors_timeline);
+	let lines = recent(&metrics.lines_timeline).max(errors);
+	if lines == 0 {
+		return 0.0;
+	}
+	(errors as f64 / lines as f64 / ERROR_FRACTION_FULL).min(1.0)
+}
+
+fn stall_penalty(metrics: &NodeMetrics) -> f64 {
+	let idle_time = match metrics.idle_time() {
+		Some(idle_time) => idle_time,
+		None => return 1.0,
+	};
+	let grace = Duration::minutes(1);
+	let stalled = Duration::minutes(STATUS_STALLED_MINUTES);
+	let idle = (idle_time - grace).num_seconds().max(0) as f64;
+	(idle / (stalled - grace).num_seconds() as f64).min(1.0)
+}
+
+fn restart_penalty(metrics: &NodeMetrics, now: DateTime<Utc>) -> f64 {
+	let window_start = now - Duration::minutes(RESTART_WINDOW_MINUTES);
+	let restarts = metrics.starts.iter().skip(1).filter(|start| **start >= window_start).count();
+	(restarts as f64 / RESTARTS_FULL).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::entry::AssumedOffset;
+	use crate::thresholds::{Level, Metric};
+
+	#[test]
+	fn weighs_penalties() {
+		let now = Utc::now();
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		let weights = HealthWeights::default();
+		// No entries yet, so the node is as good as stalled
+		assert_eq!(health_score(&metrics, &weights, None, &[], now), 67);
+
+		metrics.most_recent = Some(now);
+		assert_eq!(health_score(&metrics, &weights, None, &[], now), 100);
+		assert_eq!(health_score(&metrics, &weights, Some(0.9), &[], now), 95);
+		let warn = MetricLevel {
+			metric: Metric::ErrorRate,
+			value: 6.0,
+			level: Level::Warn,
+		};
+		let crit = MetricLevel {
+			metric: Metric::StoragePercent,
+			value: 96.0,
+			level: Level::Crit,
+		};
+		assert_eq!(health_score(&metrics, &weights, None, &[warn], now), 91);
+		assert_eq!(health_score(&metrics, &weights, None, &[warn, crit], now), 82);
+
+		// Two restarts within the hour, and one earlier start
+		metrics.starts = vec![now - Duration::hours(2), now - Duration::minutes(30), now - Duration::minutes(5)];
+		assert_eq!(health_score(&metrics, &weights, None, &[], now), 85);
+
+		let errors_only = HealthWeights {
+			errors: 1,
+			stalls: 0,
+			restarts: 0,
+			disk: 0,
+			thresholds: 0,
+		};
+		assert_eq!(health_score(&metrics, &errors_only, None, &[], now), 100);
+		let none = HealthWeights {
+			errors: 0,
+			..errors_only
+		};
+		assert_eq!(health_score(&metrics, &none, None, &[], now), 100);
+	}
+}
@@ -0,0 +1,244 @@
+///! Distribution of request latency over time, for a heatmap
+///!
+///! Latencies are counted in buckets which double in width (under 1ms, 1-2ms,
+///! 2-4ms and so on up to 1s or more), in a column for each minute. A column
+///! shows the shape of the distribution, including a slow tail which a single
+///! percentile would hide. sn_node doesn't log request latency, so the
+///! latencies come from lines matched by a pattern the user provides.
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+///! Latency buckets, the last holding everything from 2^(LATENCY_BUCKETS - 2) ms
+pub const LATENCY_BUCKETS: usize = 12;
+
+///! Minutes of history kept
+const LATENCY_COLUMNS_MAX: usize = 240;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct LatencyHeatmap {
+	///! Start of the most recent column
+	pub column_time: Option<DateTime<Utc>>,
+	///! Count in each latency bucket, oldest column first
+	pub columns: VecDeque<[u64; LATENCY_BUCKETS]>,
+}
+
+impl LatencyHeatmap {
+	pub fn new() -> LatencyHeatmap {
+		LatencyHeatmap::default()
+	}
+
+	///! Count a latency in milliseconds, of a request at time
+	pub fn record(&mut self, time: DateTime<Utc>, millis: f64) {
+		let start = column_start(time);
+		let column_time = match self.column_time {
+			Some(column_time) => column_time,
+			None => {
+				self.column_time = Some(start);
+				self.columns.push_back([0; LATENCY_BUCKETS]);
+				start
+			}
+		};
+
+		if start > column_time {
+			let new_columns = ((start - column_time).num_minutes() as usize).min(LATENCY_COLUMNS_MAX);
+			for _ in 0..new_columns {
+				self.columns.push_back([0; LATENCY_BUCKETS]);
+			}
+			while self.columns.len() > LATENCY_COLUMNS_MAX {
+				self.columns.pop_front();
+			}
+			self.column_time = Some(start);
+		}
+
+		// Earlier requests are counted in their column if it is still kept
+		let age = (self.column_time.unwrap_or(start) - start).num_minutes() as usize;
+		if age < self.columns.len() {
+			let column = self.columns.len() - 1 - age;
+			self.columns[column][latency_bucket(millis)] += 1;
+		}
+	}
+
+	///! The count columns minutes ending at the minute of now, oldest first
+	pub fn recent_columns(&self, now: DateTime<Utc>, count: usize) -> Vec<[u64; LATENCY_BUCKETS]> {
+		let mut recent = vec![[0; LATENCY_BUCKETS]; count];
+		if let Some(column_time) = self.column_time {
+			// Minutes since the most recent column, which have no requests
+			let empty = (column_start(now) - column_time).num_minutes().max(0) as usize;
+			for (i, column) in self.columns.iter().rev().enumerate() {
+				match count.checked_sub(1 + empty + i) {
+					Some(index) => recent[index] = *column,
+					None => break,
+				}
+			}
+		}
+		recent
+	}
+
+	///! The count in each bucket over the minutes ending at the minute of now
+	pub fn recent_counts(&self, now: DateTime<Utc>, minutes: usize) -> [u64; LATENCY_BUCKETS] {
+		let mut counts = [0u64; LATENCY_BUCKETS];
+		for column in self.recent_columns(now, minutes).iter() {
+			add_counts(&mut counts, column);
+		}
+		counts
+	}
+
+	///! The latency below which fraction of requests in the minutes ending at now fell, to
+	///! the resolution of the buckets (the upper bound of the bucket it falls in), if any
+	///!
+	///! The upper bound is reported so that a threshold above the last bucket's
+	///! least latency can still be reached, the last bucket being unbounded.
+	pub fn percentile(&self, now: DateTime<Utc>, minutes: usize, fraction: f64) -> Option<f64> {
+		let counts = self.recent_counts(now, minutes);
+		let total: u64 = counts.iter().sum();
+		if total == 0 {
+			return None;
+		}
+		let rank = (fraction.clamp(0.0, 1.0) * total as f64).ceil().max(1.0) as u64;
+		let mut counted = 0;
+		for (bucket, count) in counts.iter().enumerate() {
+			counted += count;
+			if counted >= rank {
+				return Some(bucket_most_millis(bucket));
+			}
+		}
+		None
+	}
+
+	///! The Apdex score of requests in the minutes ending at now, if there were any
+	pub fn apdex(&self, now: DateTime<Utc>, minutes: usize, threshold_millis: f64) -> Option<f64> {
+		apdex_score(&self.recent_counts(now, minutes), threshold_millis)
+	}
+}
+
+///! Add the counts of a column (or of another node's recent_counts) to counts
+pub fn add_counts(counts: &mut [u64; LATENCY_BUCKETS], more: &[u64; LATENCY_BUCKETS]) {
+	for (count, more) in counts.iter_mut().zip(more.iter()) {
+		*count += more;
+	}
+}
+
+///! The Apdex score of the requests counted, from 0 (all frustrated) to 1 (all satisfied)
+///!
+///! A request is satisfied by a latency up to the threshold, tolerated up to four
+///! times it and frustrating beyond that, and the score is the fraction satisfied
+///! plus half the fraction tolerated. Those in a bucket which spans a limit are
+///! divided in proportion to the part of the bucket either side of it, as if
+///! spread evenly across it, except in the last bucket which has no upper bound.
+pub fn apdex_score(counts: &[u64; LATENCY_BUCKETS], threshold_millis: f64) -> Option<f64> {
+	let total: u64 = counts.iter().sum();
+	if total == 0 {
+		return None;
+	}
+	let within = |limit: f64| -> f64 {
+		counts
+			.iter()
+			.enumerate()
+			.map(|(bucket, count)| *count as f64 * bucket_fraction_below(bucket, limit))
+			.sum()
+	};
+	let satisfied = within(threshold_millis);
+	let tolerated = within(threshold_millis * 4.0) - satisfied;
+	Some((satisfied + tolerated / 2.0) / total as f64)
+}
+
+///! The fraction of a bucket's latencies below limit, if spread evenly across it
+fn bucket_fraction_below(bucket: usize, limit: f64) -> f64 {
+	let least = bucket_least_millis(bucket);
+	if bucket >= LATENCY_BUCKETS - 1 {
+		return if least < limit { 1.0 } else { 0.0 };
+	}
+	let most = bucket_least_millis(bucket + 1);
+	((limit - least) / (most - least)).clamp(0.0, 1.0)
+}
+
+///! The latency in milliseconds above those counted by a bucket, infinite for the last
+fn bucket_most_millis(bucket: usize) -> f64 {
+	match bucket {
+		_ if bucket >= LATENCY_BUCKETS - 1 => f64::INFINITY,
+		_ => bucket_least_millis(bucket + 1),
+	}
+}
+
+///! The least latency in milliseconds counted by a bucket
+pub(crate) fn bucket_least_millis(bucket: usize) -> f64 {
+	match bucket {
+		0 => 0.0,
+		_ => (1u64 << (bucket - 1)) as f64,
+	}
+}
+
+fn column_start(time: DateTime<Utc>) -> DateTime<Utc> {
+	time - Duration::seconds(time.timestamp().rem_euclid(60)) - Duration::nanoseconds(time.timestamp_subsec_nanos() as i64)
+}
+
+///! The bucket counting a latency in milliseconds
+pub fn latency_bucket(millis: f64) -> usize {
+	if millis.is_nan() || millis < 1.0 {
+		return 0;
+	}
+	((millis.log2().floor() as usize) + 1).min(LATENCY_BUCKETS - 1)
+}
+
+///! The least latency counted by a bucket, for display
+pub fn latency_bucket_label(bucket: usize) -> String {
+	match bucket {
+		0 => String::from("<1ms"),
+		_ if bucket >= LATENCY_BUCKETS - 1 => format!("{}s+", (1u64 << (bucket - 1)) / 1000),
+		_ => format!("{}ms", 1u64 << (bucket - 1)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn counts_latency_by_minute_and_bucket() {
+		let start = DateTime::parse_from_rfc3339("2021-01-20T18:00:30Z").unwrap().with_timezone(&Utc);
+		let mut heatmap = LatencyHeatmap::new();
+		heatmap.record(start, 0.5);
+		heatmap.record(start + Duration::minutes(2), 3.0);
+		heatmap.record(start + Duration::minutes(2), 5000.0);
+		heatmap.record(start + Duration::minutes(1), 1.0);
+
+		let columns = heatmap.recent_columns(start + Duration::minutes(3), 5);
+		assert_eq!(columns[0], [0; LATENCY_BUCKETS]);
+		assert_eq!(columns[1][0], 1);
+		assert_eq!(columns[2][1], 1);
+		assert_eq!((columns[3][2], columns[3][LATENCY_BUCKETS - 1]), (1, 1));
+		assert_eq!(columns[4], [0; LATENCY_BUCKETS]);
+		assert_eq!(latency_bucket_label(LATENCY_BUCKETS - 1), "1s+");
+
+		let now = start + Duration::minutes(2);
+		assert_eq!(heatmap.percentile(now, 5, 0.5), Some(2.0));
+		assert_eq!(heatmap.percentile(now, 5, 0.95), Some(f64::INFINITY));
+		assert_eq!(heatmap.percentile(now, 1, 0.5), Some(4.0));
+		assert_eq!(heatmap.percentile(now + Duration::minutes(10), 5, 0.95), None);
+	}
+
+	#[test]
+	fn scores_apdex_from_buckets() {
+		let mut counts = [0; LATENCY_BUCKETS];
+		assert_eq!(apdex_score(&counts, 500.0), None);
+
+		counts[latency_bucket(20.0)] = 6; // Satisfied
+		counts[latency_bucket(1500.0)] = 2; // Tolerated, in the last bucket
+		assert_eq!(apdex_score(&counts, 500.0), Some(0.875));
+		// Frustrated, as 4 times the threshold is below the whole bucket
+		assert_eq!(apdex_score(&counts, 200.0), Some(0.75));
+
+		// Half of the 256-512ms bucket is below 384ms
+		let mut counts = [0; LATENCY_BUCKETS];
+		counts[latency_bucket(300.0)] = 4;
+		assert_eq!(apdex_score(&counts, 384.0), Some(0.75));
+
+		let start = DateTime::parse_from_rfc3339("2021-01-20T18:00:30Z").unwrap().with_timezone(&Utc);
+		let mut heatmap = LatencyHeatmap::new();
+		heatmap.record(start, 3.0);
+		heatmap.record(start + Duration::minutes(1), 5000.0);
+		assert_eq!(heatmap.apdex(start + Duration::minutes(1), 10, 500.0), Some(0.75));
+		assert_eq!(heatmap.apdex(start + Duration::minutes(1), 1, 500.0), Some(0.5));
+	}
+}
@@ -0,0 +1,64 @@
+//! Parsing of SAFE Network node logfiles into metrics
+//!
+//! This is the parsing logic of vdash, the node dashboard, for use by other
+//! dashboards and bots. Pass each line of a node's logfile to
+//! NodeMetrics::gather_metrics() and read the metrics from its fields:
+//!
+//! ```
+//! use vault_metrics::{AssumedOffset, NodeAgebracket, NodeMetrics, NodeStatus};
+//!
+//! let mut metrics = NodeMetrics::new(210, AssumedOffset::Local);
+//! metrics.gather_metrics("[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Node promoted to Elder")?;
+//! assert_eq!(metrics.agebracket, NodeAgebracket::Elder);
+//! assert_eq!(metrics.status(), NodeStatus::Ok);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! Lines are taken to be arriving live, so a local time made ambiguous by
+//! daylight saving is replaced by the time of arrival. Set NodeMetrics::loading
+//! while passing the existing content of a logfile to keep its timestamps.
+//!
+//! Public types derive Serialize and Deserialize so that metrics can be
+//! saved or sent elsewhere.
+#[macro_use]
+extern crate log;
+
+pub mod address;
+pub mod data_types;
+pub mod duplicates;
+pub mod entry;
+pub mod health;
+pub mod latency;
+pub mod metrics;
+pub mod network;
+pub mod network_errors;
+pub mod node_config;
+pub mod runs;
+pub mod scrub;
+pub mod stat;
+pub mod thresholds;
+pub mod timeline;
+
+pub use address::{AddressChange, NodeAddress};
+pub use data_types::{data_type_of, DataTypeCounts, OperationCounts, DATA_TYPES, DATA_TYPE_OTHER};
+pub use duplicates::{DuplicateMessages, PeerDuplicates, REPEAT_OFFENDER_COUNT};
+pub use entry::{ActivityEntry, AssumedOffset, LogEntry, LOG_LINE_PATTERN};
+pub use health::{health_score, HealthWeights};
+pub use metrics::{
+	ErrorTemplate, ErrorTemplates, LogFormat, NodeAgebracket, NodeEvent, NodeMetrics, NodeStatus, RecentError, EVENTS,
+	EVENT_CONTROL, EVENT_ERROR, EVENT_ESCALATION, EVENT_FLAPPING, EVENT_IDENTITY, EVENT_PROMOTION, EVENT_START, EVENT_STATUS,
+	RECENT_ERRORS_MAX,
+};
+pub use latency::{add_counts, apdex_score, latency_bucket_label, LatencyHeatmap, LATENCY_BUCKETS};
+pub use network::{NetworkEstimate, PeerReport, SectionEstimate};
+pub use network_errors::{network_error_kind, NetworkErrors, NETWORK_ERROR_KINDS};
+pub use runs::{Identity, NodeRun, IDENTITY_NEW, IDENTITY_SAME};
+pub use scrub::Scrubber;
+pub use stat::Stat;
+pub use thresholds::{level_of, Level, Metric, MetricLevel, Threshold, Thresholds, LATENCY_MINUTES};
+pub use timeline::{BucketSet, Outlier, TimelineSet, OUTLIER_STDDEVS, OUTLIER_TIMELINE, TIMELINES};
+
+///! Parser profiles: 'sn_node' gathers node metrics, 'plain' only shows the logfile
+pub static PARSER_SN_NODE: &str = "sn_node";
+pub static PARSER_PLAIN: &str = "plain";
+pub static PARSERS: [&str; 2] = ["sn_node", "plain"];
@@ -0,0 +1,1222 @@
+///! Metrics gathered from a node's logfile
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::time::Instant;
+
+use crate::address::NodeAddress;
+use crate::data_types::DataTypeCounts;
+use crate::duplicates::DuplicateMessages;
+use crate::network_errors::NetworkErrors;
+use crate::stat::Stat;
+use crate::entry::{ActivityEntry, AssumedOffset, LogEntry};
+use crate::latency::LatencyHeatmap;
+use crate::node_config::{parse_config_echo, parse_reward_key, reward_key_setting};
+use crate::runs::{NodeRun, RUNS_MAX};
+use crate::timeline::{TimelineSet, OUTLIER_STDDEVS, OUTLIER_TIMELINE, TIMELINES};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum NodeAgebracket {
+	Unknown,
+	Infant,
+	Adult,
+	Elder,
+}
+
+///! Events queued in NodeMetrics::events, such as to trigger hooks
+pub static EVENTS: [&str; 8] = [
+	EVENT_START,
+	EVENT_IDENTITY,
+	EVENT_PROMOTION,
+	EVENT_ERROR,
+	EVENT_STATUS,
+	EVENT_FLAPPING,
+	EVENT_CONTROL,
+	EVENT_ESCALATION,
+];
+///! Node started, with its version as detail
+pub static EVENT_START: &str = "start";
+///! A restarted node logged its name, with detail saying whether it is the same node or a new one (see runs.rs)
+pub static EVENT_IDENTITY: &str = "identity";
+///! Node promoted to Adult or Elder
+pub static EVENT_PROMOTION: &str = "promotion";
+///! An ERROR entry, with its message as detail
+pub static EVENT_ERROR: &str = "error";
+///! Node status changed (OK, WARN, ERROR or STALLED)
+pub static EVENT_STATUS: &str = "status";
+///! Events of one kind repeated rapidly, so are held until they settle
+pub static EVENT_FLAPPING: &str = "flapping";
+///! A control action run on the node from the dashboard, with its result and output as detail
+pub static EVENT_CONTROL: &str = "control";
+///! Lines matching an alert rule's pattern clustered within its time, with the rule as detail
+pub static EVENT_ESCALATION: &str = "escalation";
+
+///! An event waiting to be taken from NodeMetrics::events
+#[derive(Debug, Serialize, Clone)]
+pub struct NodeEvent {
+	pub event: &'static str,
+	pub detail: String,
+}
+
+pub static PARSER_HISTORY_MAX: usize = 100;
+
+///! The body of a recent ERROR entry, kept in NodeMetrics::recent_errors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentError {
+	pub time: Option<DateTime<Utc>>,
+	pub message: String,
+}
+
+lazy_static::lazy_static! {
+	// Hex IDs are words of hex digits including both a digit and a letter
+	static ref TEMPLATE_HEX: Regex =
+		Regex::new(r"\b(0x)?[0-9a-fA-F]*([0-9][0-9a-fA-F]*[a-fA-F]|[a-fA-F][0-9a-fA-F]*[0-9])[0-9a-fA-F]*\b").expect("The regex failed to compile. This is a bug.");
+	static ref TEMPLATE_NUMBER: Regex =
+		Regex::new(r"\d+").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Limit on distinct templates, beyond which messages count as CATEGORY_OTHER
+static ERROR_TEMPLATES_MAX: usize = 100;
+
+///! ERROR and WARN messages grouped by template, which replaces the parts
+///! that vary (hex IDs, numbers) so that repeats of an error count together
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ErrorTemplates {
+	counts: HashMap<String, ErrorTemplate>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ErrorTemplate {
+	pub category: String,
+	pub template: String,
+	pub count: u64,
+}
+
+impl ErrorTemplates {
+	///! Reduce a message to its template
+	pub fn template(message: &str) -> String {
+		let template = TEMPLATE_HEX.replace_all(message, "<id>");
+		TEMPLATE_NUMBER.replace_all(&template, "#").to_string()
+	}
+
+	pub fn record(&mut self, category: &str, message: &str) {
+		let mut template = ErrorTemplates::template(message);
+		let mut key = format!("{} {}", category, template);
+		if !self.counts.contains_key(&key) && self.counts.len() >= ERROR_TEMPLATES_MAX {
+			template = String::from(CATEGORY_OTHER);
+			key = format!("{} {}", category, template);
+		}
+		let entry = self.counts.entry(key).or_insert_with(|| ErrorTemplate {
+			category: category.to_string(),
+			template,
+			count: 0,
+		});
+		entry.count += 1;
+	}
+
+	///! The most frequent templates, most frequent first
+	pub fn top(&self, n: usize) -> Vec<ErrorTemplate> {
+		let mut templates: Vec<&ErrorTemplate> = self.counts.values().collect();
+		templates.sort_by(|a, b| b.count.cmp(&a.count).then(a.template.cmp(&b.template)));
+		templates.into_iter().take(n).cloned().collect()
+	}
+}
+
+///! Limits which stop NodeMetrics growing during long runs
+pub static LOG_HISTORY_MAX: usize = 1000;
+pub static ACTIVITY_HISTORY_MAX: usize = 1000;
+static CATEGORY_COUNT_MAX: usize = 32;
+static CATEGORY_OTHER: &str = "(other)";
+///! Minutes of entries counted in a category's rate per hour
+static CATEGORY_RATE_MINUTES: usize = 60;
+static EVENTS_MAX: usize = 100;
+pub static RECENT_ERRORS_MAX: usize = 10;
+
+///! Keep the most recent max entries, trimming only when there are twice as
+///! many so the cost of removing from the front is spread out
+fn trim_history<T>(history: &mut Vec<T>, max: usize) {
+	if history.len() >= 2 * max {
+		history.drain(..history.len() - max);
+	}
+}
+
+///! Limit on logfile switches remembered for marking on the timelines
+static LOGFILE_SWITCHES_MAX: usize = 20;
+
+///! Limit on node starts remembered, for counting restarts
+static STARTS_MAX: usize = 20;
+
+///! Lines to examine before deciding a logfile's format is unknown
+static LOG_FORMAT_SNIFF_LINES: usize = 50;
+
+///! Logfile format, inferred from the first lines parsed
+///!
+///! sn_node has logged its lines in the one format across its versions, so
+///! the format can't tell which version logged them. The version is known
+///! only once the node logs its start ('Running sn_node VERSION'), as
+///! running_version, and not at all for a logfile which begins part way
+///! through a run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LogFormat {
+	Sniffing,
+	SnNode,
+	Plain,
+	Unknown,
+}
+
+///! Errors in the current or previous minute make a node's status ERROR
+static STATUS_ERROR_MINUTES: usize = 2;
+///! A node with no log entries for this long is STALLED
+pub static STATUS_STALLED_MINUTES: i64 = 10;
+
+///! New lines whose timestamps are compared with their arrival, see clock_skew()
+static CLOCK_SKEW_LINES: usize = 20;
+///! New lines needed before clock skew is estimated
+static CLOCK_SKEW_LINES_MIN: usize = 5;
+
+///! Node status, which has a glyph and label so it doesn't rely on colour
+///!
+///! Ordered by severity, so the worst of several is their maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum NodeStatus {
+	Ok,
+	Warn,
+	Stalled,
+	Error,
+}
+
+impl NodeStatus {
+	pub fn glyph(&self) -> &'static str {
+		match self {
+			NodeStatus::Ok => "✔",
+			NodeStatus::Warn => "▲",
+			NodeStatus::Error => "✖",
+			NodeStatus::Stalled => "■",
+		}
+	}
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			NodeStatus::Ok => "OK",
+			NodeStatus::Warn => "WARN",
+			NodeStatus::Error => "ERROR",
+			NodeStatus::Stalled => "STALLED",
+		}
+	}
+}
+
+impl fmt::Display for NodeStatus {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} {}", self.glyph(), self.label())
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NodeMetrics {
+	pub node_started: Option<DateTime<Utc>>,
+	pub running_message: Option<String>,
+	pub running_version: Option<String>,
+	pub category_count: HashMap<String, usize>,
+	///! Entries of each category by the minute, for category_rates()
+	pub category_timelines: HashMap<String, TimelineSet>,
+	pub activity_history: Vec<ActivityEntry>,
+	pub log_history: Vec<LogEntry>,
+
+	pub puts_timeline: TimelineSet,
+	pub gets_timeline: TimelineSet,
+	pub errors_timeline: TimelineSet, // TODO add code to collect and display
+	///! Failed network sends and connections, see network_errors
+	pub network_errors_timeline: TimelineSet,
+	///! Every logfile entry, for the rate of lines logged
+	pub lines_timeline: TimelineSet,
+	///! Request latencies given to count_latency()
+	pub latency: LatencyHeatmap,
+
+	pub most_recent: Option<DateTime<Utc>>,
+	pub agebracket: NodeAgebracket,
+	pub section_prefix: String,
+	pub node_age: usize,
+	pub node_name: String,
+	///! Settings the node echoed when it started, by name
+	pub node_config: BTreeMap<String, String>,
+	///! The key rewards are paid to, from the config echo or a line of its own
+	pub reward_key: Option<String>,
+	pub adults: Stat<usize>,
+	pub elders: Stat<usize>,
+	///! When elders or adults were last reported
+	pub peers_reported: Option<DateTime<Utc>>,
+	pub activity_gets: u64,
+	pub activity_puts: u64,
+	pub activity_errors: u64,
+	///! PUTs and GETs by the type of data
+	pub data_types: DataTypeCounts,
+	pub clients_connected: u64,
+	pub clients_served: u64,
+	pub error_templates: ErrorTemplates,
+	///! The most recent ERROR bodies, newest last, kept across restarts as the last may say why
+	pub recent_errors: VecDeque<RecentError>,
+	///! Duplicate and replayed messages, by peer
+	pub duplicates: DuplicateMessages,
+	///! Failed network sends and connections, by kind
+	pub network_errors: NetworkErrors,
+	///! The address the node advertises, and any changes to it
+	pub address: NodeAddress,
+	pub logfile_switches: Vec<DateTime<Utc>>,
+	///! When the node started, most recent last
+	pub starts: Vec<DateTime<Utc>>,
+	///! Each run of the node from a start, most recent last, with whether it kept its identity
+	pub runs: Vec<NodeRun>,
+
+	pub log_format: LogFormat,
+	lines_sniffed: usize,
+	pub assumed_offset: AssumedOffset,
+	///! Lines given to gather_metrics(), and those which didn't decode as an entry or a start
+	pub lines_gathered: u64,
+	pub lines_undecoded: u64,
+
+	///! True while existing logfile content is loaded, rather than new lines arriving
+	#[serde(skip)]
+	pub loading: bool,
+	///! When the most recent new line arrived, by the monotonic clock
+	#[serde(skip)]
+	pub last_arrival: Option<Instant>,
+	///! Arrival time less logged time of recent new lines, in milliseconds
+	#[serde(skip)]
+	clock_skews: VecDeque<i64>,
+	///! The time metrics are as of, when not now, such as for metrics replayed up to a time
+	#[serde(skip)]
+	pub as_of: Option<DateTime<Utc>>,
+
+	#[serde(skip)]
+	pub events: Vec<NodeEvent>,
+
+	pub parser_history: VecDeque<String>,
+}
+
+impl NodeMetrics {
+	///! New metrics, with timelines of timeline_steps buckets
+	pub fn new(timeline_steps: usize, assumed_offset: AssumedOffset) -> NodeMetrics {
+		NodeMetrics::new_at(timeline_steps, assumed_offset, Utc::now())
+	}
+
+	///! NodeMetrics whose timelines start at time, for lines logged since then
+	pub fn new_at(timeline_steps: usize, assumed_offset: AssumedOffset, time: DateTime<Utc>) -> NodeMetrics {
+		let mut puts_timeline = TimelineSet::new("PUTS".to_string());
+		let mut gets_timeline = TimelineSet::new("GETS".to_string());
+		let mut errors_timeline = TimelineSet::new("ERRORS".to_string());
+		let mut network_errors_timeline = TimelineSet::new("NET ERRS".to_string());
+		let mut lines_timeline = TimelineSet::new("LINES".to_string());
+		for timeline in [
+			&mut puts_timeline,
+			&mut gets_timeline,
+			&mut errors_timeline,
+			&mut network_errors_timeline,
+			&mut lines_timeline,
+		]
+		.iter_mut()
+		{
+			for i in 0..TIMELINES.len() {
+				if let Some(spec) = TIMELINES.get(i) {
+					timeline.add_bucket_set(spec.0, spec.1, timeline_steps);
+				}
+			}
+		}
+
+		let mut metrics = NodeMetrics {
+			// Start
+			node_started: None,
+			running_message: None,
+			running_version: None,
+
+			// Logfile entries
+			activity_history: Vec::<ActivityEntry>::new(),
+			log_history: Vec::<LogEntry>::new(),
+			most_recent: None,
+
+			// Timelines / Sparklines
+			puts_timeline,
+			gets_timeline,
+			errors_timeline,
+			network_errors_timeline,
+			lines_timeline,
+			latency: LatencyHeatmap::new(),
+
+			// Counts
+			category_count: HashMap::new(),
+			category_timelines: HashMap::new(),
+			activity_gets: 0,
+			activity_puts: 0,
+			activity_errors: 0,
+			data_types: DataTypeCounts::default(),
+			clients_connected: 0,
+			clients_served: 0,
+			error_templates: ErrorTemplates::default(),
+			recent_errors: VecDeque::new(),
+			duplicates: DuplicateMessages::default(),
+			network_errors: NetworkErrors::default(),
+			address: NodeAddress::default(),
+			logfile_switches: Vec::new(),
+			starts: Vec::new(),
+			runs: Vec::new(),
+
+			// State (node)
+			agebracket: NodeAgebracket::Unknown,
+			section_prefix: String::from(""),
+			node_age: 0,
+			node_name: String::from(""),
+			node_config: BTreeMap::new(),
+			reward_key: None,
+
+			// State (network)
+			adults: Stat::default(),
+			elders: Stat::default(),
+			peers_reported: None,
+
+			// Format
+			log_format: LogFormat::Sniffing,
+			assumed_offset,
+			loading: false,
+			last_arrival: None,
+			clock_skews: VecDeque::new(),
+			as_of: None,
+			lines_sniffed: 0,
+			lines_gathered: 0,
+			lines_undecoded: 0,
+
+			events: Vec::<NodeEvent>::new(),
+
+			// Debug
+			parser_history: VecDeque::<String>::new(),
+		};
+		metrics.update_timelines(Some(time));
+		metrics
+	}
+
+	pub fn agebracket_string(&self) -> String {
+		match self.agebracket {
+			NodeAgebracket::Infant => "Infant".to_string(),
+			NodeAgebracket::Adult => "Adult".to_string(),
+			NodeAgebracket::Elder => "Elder".to_string(),
+			NodeAgebracket::Unknown => "Unknown".to_string(),
+		}
+	}
+
+	///! Record a switch to a new logfile (--latest-in), to be marked on the timelines
+	pub fn logfile_switch(&mut self, time: DateTime<Utc>) {
+		self.logfile_switches.push(time);
+		trim_history(&mut self.logfile_switches, LOGFILE_SWITCHES_MAX);
+	}
+
+	fn reset_metrics(&mut self) {
+		self.agebracket = NodeAgebracket::Infant;
+		self.section_prefix = String::from("");
+		self.node_age = 0;
+		self.node_name = String::from("");
+		self.node_config.clear();
+		self.reward_key = None;
+		self.adults.set(0);
+		self.elders.set(0);
+		self.peers_reported = None;
+		self.activity_gets = 0;
+		self.activity_puts = 0;
+		self.activity_errors = 0;
+		self.data_types.clear();
+		self.clients_connected = 0;
+		self.clients_served = 0;
+		self.error_templates = ErrorTemplates::default();
+		self.address = NodeAddress::default();
+	}
+
+	///! Process a line from a SAFE Node logfile.
+	///! May add a LogEntry to the NodeMetrics::log_history vector.
+	///! Use a created LogEntry to update metrics.
+	pub fn gather_metrics(&mut self, line: &str) -> Result<(), std::io::Error> {
+		let decoded = LogEntry::decode(line, &self.assumed_offset, self.most_recent);
+		self.sniff_log_format(decoded.is_some());
+		self.lines_gathered += 1;
+		if let Some(mut entry) = decoded.or_else(|| self.parse_start(line)) {
+			if !self.loading {
+				self.last_arrival = Some(Instant::now());
+				// A local time repeated or skipped by daylight saving is timed by its
+				// arrival, while other times are kept as logged, however far off
+				if entry.time_ambiguous {
+					entry.time = Some(Utc::now());
+				} else if let Some(time) = entry.time {
+					self.clock_skews.push_back((Utc::now() - time).num_milliseconds());
+					if self.clock_skews.len() > CLOCK_SKEW_LINES {
+						self.clock_skews.pop_front();
+					}
+				}
+			}
+			if entry.time.is_none() {
+				entry.time = self.most_recent;
+			} else {
+				self.most_recent = entry.time;
+			}
+
+			self.update_timelines(self.most_recent);
+			self.lines_timeline.increment_value(entry.time);
+			self.parse_logentry_counts(&entry);
+			if entry.category == "ERROR" || entry.category == "WARN" {
+				self.error_templates.record(&entry.category, &entry.message);
+			}
+			if self.duplicates.record(&entry.message, entry.time) {
+				self.parser_output(format!("duplicate messages: {}", self.duplicates.count));
+			}
+			if self.network_errors.record(&entry.message, entry.time) {
+				self.network_errors_timeline.increment_value(entry.time);
+				self.parser_output(format!("network errors: {}", self.network_errors.count));
+			}
+			let last_change = self.address.changes.last().cloned();
+			if self.address.record(&entry.message, entry.time) {
+				if let Some(change) = self.address.changes.last().filter(|change| Some(*change) != last_change.as_ref()) {
+					let output = format!("address changed: {} -> {}", change.from, change.to);
+					self.parser_output(output);
+				}
+			}
+			if entry.category == "ERROR" {
+				self.recent_error(entry.time, &entry.message);
+				self.event(EVENT_ERROR, entry.message.clone());
+			}
+			self.parser_output(entry.parser_output.clone());
+			let agebracket = self.agebracket;
+			self.process_logfile_entry(&entry); // May add to self.parser_history
+			if agebracket != NodeAgebracket::Unknown && self.agebracket > agebracket {
+				self.event(EVENT_PROMOTION, self.agebracket_string());
+			}
+			self.log_history.push(entry);
+			trim_history(&mut self.log_history, LOG_HISTORY_MAX);
+		} else {
+			self.lines_undecoded += 1;
+			// For debugging LogEntry::decode()
+			self.parser_output(format!("LogEntry::decode() failed on: {}", line));
+		}
+
+		Ok(())
+	}
+
+	///! Infer the logfile format from whether lines decode as sn_node entries
+	fn sniff_log_format(&mut self, decoded: bool) {
+		match self.log_format {
+			LogFormat::Sniffing | LogFormat::Unknown if decoded => self.log_format = LogFormat::SnNode,
+			LogFormat::Sniffing => {
+				self.lines_sniffed += 1;
+				if self.lines_sniffed >= LOG_FORMAT_SNIFF_LINES {
+					self.log_format = LogFormat::Unknown;
+					self.parser_output(format!("unknown log format after {} lines", self.lines_sniffed));
+				}
+			}
+			_ => {}
+		}
+	}
+
+	///! Warning to show when the logfile format isn't recognised
+	pub fn log_format_warning(&self) -> Option<String> {
+		if self.log_format != LogFormat::Unknown {
+			return None;
+		}
+		let software = match &self.running_version {
+			Some(version) => format!("sn_node {}", version),
+			None => String::from("this node"),
+		};
+		Some(format!(
+			"Logfile format of {} not recognised, so no metrics. Check the logging level, or try --parser plain or a config update.",
+			software
+		))
+	}
+
+	///! How far the node's clock is behind local time (negative if ahead), from new lines
+	///!
+	///! A line's arrival time less the time it was logged is the skew plus any
+	///! delay in writing and reading the line, so the least of recent lines is
+	///! taken as the skew.
+	pub fn clock_skew(&self) -> Option<Duration> {
+		if self.clock_skews.len() < CLOCK_SKEW_LINES_MIN {
+			return None;
+		}
+		self.clock_skews.iter().min().map(|&skew| Duration::milliseconds(skew))
+	}
+
+	///! Warning to show when clock_skew() exceeds threshold
+	pub fn clock_skew_warning(&self, threshold: Duration) -> Option<String> {
+		let skew = self.clock_skew()?;
+		if skew.num_milliseconds().abs() <= threshold.num_milliseconds() {
+			return None;
+		}
+		let seconds = skew.num_seconds().abs();
+		let amount = match seconds {
+			seconds if seconds < 60 => format!("{}s", seconds),
+			seconds if seconds < 3600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+			seconds => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+		};
+		let direction = if skew > Duration::zero() { "behind" } else { "ahead of" };
+		Some(format!(
+			"Clock skew: log times are {} {} local time, misaligning this node with others. Check its clock (NTP), timezone or --assume-offset.",
+			amount, direction
+		))
+	}
+
+	fn recent_error(&mut self, time: Option<DateTime<Utc>>, message: &str) {
+		if self.recent_errors.len() >= RECENT_ERRORS_MAX {
+			self.recent_errors.pop_front();
+		}
+		self.recent_errors.push_back(RecentError {
+			time,
+			message: message.trim().to_string(),
+		});
+	}
+
+	///! The most recent ERROR entry, if any
+	pub fn last_error(&self) -> Option<&RecentError> {
+		self.recent_errors.back()
+	}
+
+	///! Queue an event for any hooks, dropping the oldest if they aren't being run
+	pub fn event(&mut self, event: &'static str, detail: String) {
+		if self.events.len() >= EVENTS_MAX {
+			self.events.remove(0);
+		}
+		self.events.push(NodeEvent { event, detail });
+	}
+
+	///! Note the name of the node in its current run, with an event saying whether a restarted node kept it
+	fn identify_run(&mut self) {
+		let node_name = self.node_name.clone();
+		let detail = match self.runs.last_mut() {
+			Some(run) => run.name(&node_name).and_then(|_| run.identity_detail()),
+			None => None,
+		};
+		if let Some(detail) = detail {
+			self.event(EVENT_IDENTITY, detail);
+		}
+	}
+
+	///! Record a parser result, keeping only the most recent PARSER_HISTORY_MAX
+	fn parser_output(&mut self, output: String) {
+		self.parser_history.push_back(output);
+		while self.parser_history.len() > PARSER_HISTORY_MAX {
+			self.parser_history.pop_front();
+		}
+	}
+
+	pub fn update_timelines(&mut self, now: Option<DateTime<Utc>>) {
+		for timeline in &mut [
+			&mut self.puts_timeline,
+			&mut self.gets_timeline,
+			&mut self.errors_timeline,
+			&mut self.network_errors_timeline,
+			&mut self.lines_timeline,
+		]
+		.iter_mut()
+		{
+			timeline.update_current_time(now);
+		}
+		for timeline in self.category_timelines.values_mut() {
+			timeline.update_current_time(now);
+		}
+	}
+
+	///! Entries of a category in the last complete minute and in the last hour
+	pub fn category_rates(&self, category: &str) -> Option<(u64, u64)> {
+		let timeline = self.category_timelines.get(category)?;
+		let per_minute = timeline.last_complete_bucket(OUTLIER_TIMELINE).unwrap_or(0);
+		let per_hour = timeline
+			.bucket_sets
+			.get(OUTLIER_TIMELINE)
+			.map_or(0, |bucket_set| bucket_set.buckets().iter().rev().take(CATEGORY_RATE_MINUTES).sum());
+		Some((per_minute, per_hour))
+	}
+
+	///! Returm a LogEntry and capture metadata for logfile node start:
+	///!	'Running safe-node v0.24.0'
+	pub fn parse_start(&mut self, line: &str) -> Option<LogEntry> {
+		let running_prefix = String::from("Running sn_node ");
+
+		if line.starts_with(&running_prefix) {
+			self.running_message = Some(line.to_string());
+			self.running_version = Some(line[running_prefix.len()..].to_string());
+			self.node_started = self.most_recent;
+			if let Some(started) = self.most_recent {
+				self.starts.push(started);
+				trim_history(&mut self.starts, STARTS_MAX);
+			}
+			let parser_output = format!(
+				"START at {}",
+				self.most_recent
+					.map_or(String::from("None"), |m| format!("{}", m))
+			);
+
+			let version = self.running_version.clone().unwrap_or_default();
+			let first = self.runs.is_empty() && self.node_name.is_empty();
+			self.runs.push(NodeRun::new(self.most_recent, &version, &self.node_name, first));
+			trim_history(&mut self.runs, RUNS_MAX);
+
+			self.reset_metrics();
+			self.event(EVENT_START, version);
+			return Some(LogEntry {
+				logstring: String::from(line),
+				category: String::from("START"),
+				time: self.most_recent,
+				time_ambiguous: false,
+				source: String::from(""),
+				message: line.to_string(),
+				parser_output,
+			});
+		}
+
+		None
+	}
+
+	///! Process a logfile entry
+	///! Returns true if the line has been processed and can be discarded
+	pub fn process_logfile_entry(&mut self, entry: &LogEntry) -> bool {
+		return self.parse_data_response(
+			&entry,
+			"Running as Node: SendToSection [ msg: MsgEnvelope { message: QueryResponse { response: QueryResponse::",
+		) || self.parse_gets_and_puts(&entry) || self.parse_clients(entry) || self.parse_node_config(entry) || self.parse_reward_key(entry) || self.parse_states(&entry);
+	}
+
+	///! Capture the configuration echoed by a node when it starts
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_node_config(&mut self, entry: &LogEntry) -> bool {
+		match parse_config_echo(&entry.message) {
+			Some(settings) => {
+				if let Some(key) = reward_key_setting(&settings) {
+					self.reward_key = Some(key);
+				}
+				self.node_config = settings.into_iter().collect();
+				self.parser_output(format!("node config: {} settings", self.node_config.len()));
+				true
+			}
+			None => false,
+		}
+	}
+
+	///! Capture the reward key of a node which logs it on a line of its own
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_reward_key(&mut self, entry: &LogEntry) -> bool {
+		match parse_reward_key(&entry.message) {
+			Some(key) => {
+				self.parser_output(format!("reward key: {}", key));
+				self.reward_key = Some(key);
+				true
+			}
+			None => false,
+		}
+	}
+
+	///! Update the client gauge (connected) and counter (requests served)
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_clients(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Client connected") || entry.message.contains("New client connection") {
+			self.clients_connected += 1;
+			self.parser_output(format!("clients connected: {}", self.clients_connected));
+			return true;
+		} else if entry.message.contains("Client disconnected") || entry.message.contains("Removing client") {
+			// Saturate as the connect may predate the logfile
+			self.clients_connected = self.clients_connected.saturating_sub(1);
+			self.parser_output(format!("clients connected: {}", self.clients_connected));
+			return true;
+		} else if entry.message.contains("Responded to client") || entry.message.contains("Sending response to client") {
+			self.clients_served += 1;
+			self.parser_output(format!("client requests served: {}", self.clients_served));
+			return true;
+		}
+		false
+	}
+
+	///! TODO: Review and update these tests
+	///! TODO: see forum conversation https://safenetforum.org/t/vdash-safe-node-dashboard-safe-vault-run-baby-fleming-t/32630/38
+	fn parse_gets_and_puts(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Handling NodeDuty: ReadChunk") {
+			self.count_get(entry);
+			return true;
+		} else if entry.message.contains("Wrote data from message") {
+			self.count_put(entry);
+			return true;
+			// TODO: delete the following checks once the new test network is out
+		} else if entry.message.contains("Writing chunk succeeded") {
+			self.count_put(entry);
+			return true;
+		} else if entry.message.starts_with("MapStorage: Writing chunk PASSED") {
+			self.count_put(entry);
+			return true;
+		}
+		return false;
+	}
+
+	///! Update data metrics from a handler response logfile entry
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_data_response(&mut self, entry: &LogEntry, pattern: &str) -> bool {
+		if let Some(mut response_start) = entry.logstring.find(pattern) {
+			response_start += pattern.len();
+			let mut response = "";
+
+			if let Some(response_end) = entry.logstring[response_start..].find(",") {
+				response = entry.logstring.as_str()[response_start..response_start + response_end]
+					.as_ref();
+				if !response.is_empty() {
+					let activity_entry = ActivityEntry::new(entry, response);
+					self.activity_history.push(activity_entry);
+					trim_history(&mut self.activity_history, ACTIVITY_HISTORY_MAX);
+					self.parser_output(format!("node activity: {}", response));
+				}
+			}
+			if response.is_empty() {
+				self.parser_output(format!("failed to parse_data_response: {}", entry.logstring));
+			};
+
+			return true;
+		};
+		return false;
+	}
+
+	///! Capture state updates from a logfile entry
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_states(&mut self, entry: &LogEntry) -> bool {
+		if entry.category.eq("ERROR") {
+			self.count_error(entry.time);
+		}
+
+		let &content = &entry.logstring.as_str();
+		if let Some(elders) = self.parse_usize("No. of Elders:", content) {
+			self.elders.set(elders);
+			self.peers_reported = entry.time;
+			self.parser_output(format!("ELDERS: {}", elders));
+			return true;
+		};
+
+		if let Some(adults) = self.parse_usize("No. of Adults:", &entry.logstring) {
+			self.adults.set(adults);
+			self.peers_reported = entry.time;
+			self.parser_output(format!("ADULTS: {}", adults));
+			return true;
+		};
+
+		// TODO: review as things stabilise during Fleming testnets
+		// Pre-Fleming testnets code with additions for Fleming T4.1
+		if let Some(agebracket) = self
+			.parse_word("Node promoted to ", &entry.logstring)
+			.or(self.parse_word("We are ", &entry.logstring))
+			.or(self.parse_word("New RoutingEvent received. Current role:", &entry.logstring))
+		{
+			self.agebracket = match agebracket.as_str() {
+				"Infant" => NodeAgebracket::Infant,
+				"Adult" => NodeAgebracket::Adult,
+				"Elder" => NodeAgebracket::Elder,
+				_ => NodeAgebracket::Unknown,
+			};
+			if self.agebracket != NodeAgebracket::Unknown {
+				self.parser_output(format!("Node agebracket: {}", agebracket));
+			} else {
+				self.parser_output(format!("FAILED to parse agebracket in: {}", &entry.logstring));
+			}
+
+			if let Some(section_prefix) = self.parse_word("section prefix:", &entry.logstring) {
+				self.parser_output(format!("section prefix: {}", &section_prefix));
+				self.section_prefix = section_prefix;
+			} else {
+				self.parser_output(format!("FAILED to parse section prefix in: {}", &entry.logstring));
+			}
+
+			if let Some(node_age) = self.parse_usize("age:", &entry.logstring) {
+				self.parser_output(format!("age: {}", node_age));
+				self.node_age = node_age;
+			} else {
+				self.parser_output(format!("FAILED to parse node age in: {}", &entry.logstring));
+			}
+
+			if let Some(node_name) = self.parse_word("node name:", &entry.logstring) {
+				self.parser_output(format!("node name: {}", &node_name));
+				self.node_name = node_name;
+				self.identify_run();
+			} else {
+				self.parser_output(format!("FAILED to parse node name in: {}", &entry.logstring));
+			}
+
+			return true;
+		};
+
+		// TODO: probably needs deprecating as of T4.1 except perhaps for this agebracket check which needs review
+		// Fleming Testnet 3 based
+		if entry.logstring.contains("The network is not accepting nodes right now")
+		{
+			self.agebracket = NodeAgebracket::Infant;
+			self.parser_output(format!("Age updated to: Infant"));
+			return true;
+		}
+
+		if entry.logstring.contains("Handling NodeDuty: WriteChunk") {
+			self.agebracket = NodeAgebracket::Adult;
+			self.parser_output(format!("Age updated to: Adult"));
+			return true;
+		}
+
+		if entry.logstring.contains("as an Elder") {
+			self.agebracket = NodeAgebracket::Elder;
+			self.parser_output(format!("Age updated to: Elder"));
+			return true;
+		}
+
+		false
+	}
+
+	fn parse_usize(&mut self, prefix: &str, content: &str) -> Option<usize> {
+		if let Some(position) = content.find(prefix) {
+			let word: Vec<&str> = content[position + prefix.len()..]
+				.trim()
+				.splitn(2, |c| c == ' ' || c == ',')
+				.collect();
+			if word.len() > 0 {
+				match word[0].parse::<usize>() {
+					Ok(value) => return Some(value),
+					Err(_e) => self.parser_output(format!("failed to parse '{}' as usize from: '{}'", word[0], &content[position + prefix.len()..])),
+				}
+			}
+		}
+		None
+	}
+
+	fn parse_word(&mut self, prefix: &str, content: &str) -> Option<String> {
+		if let Some(start) = content.find(prefix) {
+			let word: Vec<&str> = content[start + prefix.len()..]
+				.trim_start()
+				.splitn(2, |c| c == ' ' || c == ',')
+				.collect();
+			if word.len() > 0 {
+				return Some(word[0].to_string());
+			} else {
+				self.parser_output(format!("failed to parse word at: '{}'", &content[start..]));
+			}
+		}
+		None
+	}
+
+	fn count_get(&mut self, entry: &LogEntry) {
+		self.activity_gets += 1;
+		self.gets_timeline.increment_value(entry.time);
+		self.data_types.count_get(&entry.message);
+	}
+
+	fn count_put(&mut self, entry: &LogEntry) {
+		self.activity_puts += 1;
+		self.puts_timeline.increment_value(entry.time);
+		self.data_types.count_put(&entry.message);
+	}
+
+	///! Count the latency of a request, in milliseconds, logged by the line just gathered
+	///!
+	///! sn_node doesn't log latency, so this is left to callers which know how to find it.
+	pub fn count_latency(&mut self, millis: f64) {
+		if let Some(time) = self.most_recent {
+			self.latency.record(time, millis);
+		}
+	}
+
+	fn count_error(&mut self, time: Option<DateTime<Utc>>) {
+		self.activity_errors += 1;
+		self.errors_timeline.increment_value(time);
+	}
+
+	///! Describe any timelines whose latest per-minute count is an outlier
+	pub fn outliers(&self) -> Vec<String> {
+		let mut outliers = Vec::<String>::new();
+		for timeline in [&self.puts_timeline, &self.gets_timeline, &self.errors_timeline].iter() {
+			if let Some(bucket_set) = timeline.bucket_sets.get(OUTLIER_TIMELINE) {
+				if let Some(outlier) = bucket_set.outlier(OUTLIER_STDDEVS) {
+					let direction = if outlier.value as f64 > outlier.mean { "high" } else { "low" };
+					outliers.push(format!(
+						"{} {} {}/min (mean {:.1} ± {:.1})",
+						timeline.get_name(),
+						direction,
+						outlier.value,
+						outlier.mean,
+						outlier.stddev
+					));
+				}
+			}
+		}
+		outliers
+	}
+
+	///! Time since the most recent entry
+	///!
+	///! Uses the monotonic clock once new lines have arrived, so that a jump
+	///! in the system clock doesn't make a node appear idle.
+	pub fn idle_time(&self) -> Option<Duration> {
+		let now = self.as_of.unwrap_or_else(Utc::now);
+		let logged = self.most_recent.map(|most_recent| now - most_recent);
+		let arrived = self.last_arrival.and_then(|arrival| Duration::from_std(arrival.elapsed()).ok());
+		match (logged, arrived) {
+			(Some(logged), Some(arrived)) if logged >= Duration::zero() => Some(logged.min(arrived)),
+			(_, Some(arrived)) => Some(arrived),
+			(logged, None) => logged,
+		}
+	}
+
+	///! Overall status, from recent errors, log activity and outliers
+	pub fn status(&self) -> NodeStatus {
+		if let Some(bucket_set) = self.errors_timeline.bucket_sets.get(OUTLIER_TIMELINE) {
+			if bucket_set.buckets().iter().rev().take(STATUS_ERROR_MINUTES).any(|&count| count > 0) {
+				return NodeStatus::Error;
+			}
+		}
+
+		match self.idle_time() {
+			Some(idle_time) if idle_time <= Duration::minutes(STATUS_STALLED_MINUTES) => {}
+			_ => return NodeStatus::Stalled,
+		}
+
+		if self.outliers().is_empty() {
+			NodeStatus::Ok
+		} else {
+			NodeStatus::Warn
+		}
+	}
+
+	///! Count the entry by its category, see category_rates()
+	pub fn parse_logentry_counts(&mut self, entry: &LogEntry) {
+		// Categories ('INFO', 'WARN' etc)
+		if !entry.category.is_empty() {
+			// Malformed lines can produce any number of categories
+			let category = if self.category_count.contains_key(&entry.category)
+				|| self.category_count.len() < CATEGORY_COUNT_MAX
+			{
+				entry.category.clone()
+			} else {
+				String::from(CATEGORY_OTHER)
+			};
+			let time = entry.time;
+			self.category_timelines
+				.entry(category.clone())
+				.or_insert_with(|| {
+					let mut timeline = TimelineSet::new(category.clone());
+					// One more than the hour counted, as the current minute is incomplete
+					timeline.add_bucket_set(OUTLIER_TIMELINE, Duration::minutes(1), CATEGORY_RATE_MINUTES + 1);
+					timeline.update_current_time(time);
+					timeline
+				})
+				.increment_value(time);
+			*self.category_count.entry(category).or_insert(0) += 1;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runs::Identity;
+
+	#[test]
+	fn error_templates_group_variable_parts() {
+		assert_eq!(
+			ErrorTemplates::template("Failed to load chunk 3fa9e1c0 after 12 attempts"),
+			"Failed to load chunk <id> after # attempts"
+		);
+
+		let mut templates = ErrorTemplates::default();
+		templates.record("ERROR", "Peer 10.0.0.1:1200 timed out");
+		templates.record("ERROR", "Peer 10.0.0.2:1201 timed out");
+		templates.record("WARN", "Dead peer");
+		let top = templates.top(1);
+		assert_eq!(top[0].template, "Peer #.#.#.#:# timed out");
+		assert_eq!(top[0].count, 2);
+	}
+
+	#[test]
+	fn estimates_clock_skew() {
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		let line = |behind: i64| {
+			let time = Utc::now() - Duration::seconds(behind);
+			format!("[sn_node] INFO {} [src/a.rs:1] entry", time.format("%Y-%m-%dT%H:%M:%S%.6f+00:00"))
+		};
+		for behind in [95, 90, 120, 91].iter() {
+			metrics.gather_metrics(&line(*behind)).unwrap();
+		}
+		assert_eq!(metrics.clock_skew(), None);
+		metrics.gather_metrics(&line(100)).unwrap();
+		let skew = metrics.clock_skew().unwrap();
+		assert!(skew >= Duration::seconds(90) && skew < Duration::seconds(91));
+		let warning = metrics.clock_skew_warning(Duration::seconds(30)).unwrap();
+		assert!(warning.contains("1m30s behind"));
+		assert_eq!(metrics.clock_skew_warning(Duration::minutes(2)), None);
+
+		// Lines loaded from the logfile arrived earlier, so don't count
+		let mut loaded = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		loaded.loading = true;
+		for _ in 0..CLOCK_SKEW_LINES {
+			loaded.gather_metrics(&line(3600)).unwrap();
+		}
+		assert_eq!(loaded.clock_skew(), None);
+	}
+
+	#[test]
+	fn keeps_skewed_times_as_logged() {
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		let behind = Utc::now() - Duration::hours(2);
+		let line = format!("[sn_node] INFO {} [src/a.rs:1] entry", behind.format("%Y-%m-%dT%H:%M:%S%.6f+00:00"));
+		metrics.gather_metrics(&line).unwrap();
+		assert_eq!(metrics.most_recent.map(|time| time.timestamp()), Some(behind.timestamp()));
+		assert!(!AssumedOffset::Fixed(3600).is_ambiguous(&behind.naive_utc()));
+	}
+
+	#[test]
+	fn category_rates_roll() {
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		metrics.loading = true;
+		let line = |minute: u32, category: &str| {
+			format!("[sn_node] {} 2021-01-20T18:{:02}:30.000000+00:00 [src/a.rs:1] entry", category, minute)
+		};
+		for minute in 0..3 {
+			for _ in 0..minute + 1 {
+				metrics.gather_metrics(&line(minute, "ERROR")).unwrap();
+			}
+		}
+		metrics.gather_metrics(&line(3, "WARN")).unwrap();
+		// Last complete minute is 18:02 with 3 ERRORs, and 6 in all
+		assert_eq!(metrics.category_rates("ERROR"), Some((3, 6)));
+		assert_eq!(metrics.category_rates("WARN"), Some((0, 1)));
+		assert_eq!(metrics.category_rates("INFO"), None);
+
+		// Over an hour on, earlier entries have rolled out of the hour
+		metrics.gather_metrics(&line(4, "WARN").replace("T18:", "T19:")).unwrap();
+		assert_eq!(metrics.category_rates("ERROR"), Some((0, 0)));
+		assert_eq!(metrics.category_rates("WARN"), Some((0, 1)));
+	}
+
+	#[test]
+	fn keeps_recent_errors() {
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		metrics.loading = true;
+		let line = |second: usize, category: &str| {
+			format!("[sn_node] {} 2021-01-20T18:00:{:02}.000000+00:00 [src/a.rs:1] failure {}", category, second, second)
+		};
+		assert!(metrics.last_error().is_none());
+		for second in 0..RECENT_ERRORS_MAX + 2 {
+			metrics.gather_metrics(&line(second, "ERROR")).unwrap();
+		}
+		metrics.gather_metrics(&line(50, "WARN")).unwrap();
+		assert_eq!(metrics.recent_errors.len(), RECENT_ERRORS_MAX);
+		assert_eq!(metrics.recent_errors[0].message, "failure 2");
+		let last = metrics.last_error().unwrap();
+		assert_eq!(last.message, format!("failure {}", RECENT_ERRORS_MAX + 1));
+		assert_eq!(last.time.unwrap().format("%S").to_string(), format!("{:02}", RECENT_ERRORS_MAX + 1));
+	}
+
+	#[test]
+	fn tells_restarts_as_the_same_node_from_new_ones() {
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		let named = |name: &str| {
+			format!("[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] We are Adult, section prefix: 10, age: 6, node name: {}", name)
+		};
+		let identities = |metrics: &mut NodeMetrics| -> Vec<String> {
+			metrics.events.drain(..).filter(|event| event.event == EVENT_IDENTITY).map(|event| event.detail).collect()
+		};
+		for line in ["Running sn_node 0.25.0", &named("f67c2e"), "Running sn_node 0.25.1", &named("f67c2e")].iter() {
+			metrics.gather_metrics(line).unwrap();
+		}
+		assert_eq!(identities(&mut metrics), vec!["same node f67c2e"]);
+
+		for line in ["Running sn_node 0.25.1", &named("0b51d9"), &named("0b51d9")].iter() {
+			metrics.gather_metrics(line).unwrap();
+		}
+		assert_eq!(identities(&mut metrics), vec!["new node 0b51d9, was f67c2e"]);
+		let identities: Vec<Identity> = metrics.runs.iter().map(|run| run.identity).collect();
+		assert_eq!(identities, vec![Identity::First, Identity::Same, Identity::New]);
+	}
+
+	#[test]
+	fn status_is_shown_without_colour() {
+		let at = |time: &str| Some(time.parse::<DateTime<Utc>>().unwrap());
+		let mut metrics = NodeMetrics::new_at(20, AssumedOffset::Fixed(0), at("2021-01-20T18:00:00Z").unwrap());
+		metrics.loading = true;
+		let line = |second: u32, category: &str| {
+			format!("[sn_node] {} 2021-01-20T18:00:{:02}.000000+00:00 [src/a.rs:1] entry", category, second)
+		};
+		assert_eq!(metrics.status(), NodeStatus::Stalled);
+		metrics.gather_metrics(&line(0, "INFO")).unwrap();
+		metrics.as_of = at("2021-01-20T18:05:00Z");
+		assert_eq!(metrics.status(), NodeStatus::Ok);
+		metrics.as_of = at("2021-01-20T18:20:00Z");
+		assert_eq!(metrics.status(), NodeStatus::Stalled);
+		metrics.gather_metrics(&line(10, "ERROR")).unwrap();
+		assert_eq!(metrics.status(), NodeStatus::Error);
+
+		let statuses = [NodeStatus::Ok, NodeStatus::Warn, NodeStatus::Stalled, NodeStatus::Error];
+		let shown: Vec<String> = statuses.iter().map(NodeStatus::to_string).collect();
+		assert_eq!(shown, ["✔ OK", "▲ WARN", "■ STALLED", "✖ ERROR"]);
+		assert_eq!(statuses.iter().max(), Some(&NodeStatus::Error));
+	}
+
+	#[test]
+	fn counts_clients_and_requests_served() {
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		let line = |message: &str| format!("[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] {}", message);
+		// The first connection may predate the logfile
+		for message in ["Client disconnected: 0b51d9", "Client connected: f67c2e", "New client connection from 10.0.0.2"].iter() {
+			metrics.gather_metrics(&line(message)).unwrap();
+		}
+		assert_eq!((metrics.clients_connected, metrics.clients_served), (2, 0));
+		for message in ["Responded to client f67c2e", "Sending response to client 3fa9e1", "Removing client f67c2e"].iter() {
+			metrics.gather_metrics(&line(message)).unwrap();
+		}
+		assert_eq!((metrics.clients_connected, metrics.clients_served), (1, 2));
+
+		// A restart starts the counts again
+		metrics.gather_metrics("Running sn_node v0.25.3").unwrap();
+		assert_eq!((metrics.clients_connected, metrics.clients_served), (0, 0));
+	}
+
+	#[test]
+	fn history_stays_bounded() {
+		let mut history = Vec::<usize>::new();
+		for i in 0..25 {
+			history.push(i);
+			trim_history(&mut history, 5);
+			assert!(history.len() < 10);
+		}
+		assert_eq!(history.last(), Some(&24));
+	}
+
+	#[test]
+	fn sniffs_the_format_of_each_logfile() {
+		let entry = "[sn_node] INFO 2021-01-20T18:00:00.000000+00:00 [src/a.rs:1] entry";
+		let sniff = |lines: &[&str]| {
+			let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+			for line in lines {
+				metrics.gather_metrics(line).unwrap();
+			}
+			metrics
+		};
+
+		// Part way through a run, the version isn't known
+		let metrics = sniff(&[entry]);
+		assert_eq!((metrics.log_format, metrics.running_version), (LogFormat::SnNode, None));
+		let metrics = sniff(&["Running sn_node v0.25.3", entry]);
+		assert_eq!(metrics.log_format, LogFormat::SnNode);
+		assert_eq!(metrics.running_version.as_deref(), Some("v0.25.3"));
+		assert_eq!(metrics.log_format_warning(), None);
+
+		let text = vec!["just some text"; LOG_FORMAT_SNIFF_LINES];
+		assert_eq!(sniff(&text[1..]).log_format, LogFormat::Sniffing);
+		let metrics = sniff(&text);
+		assert_eq!(metrics.log_format, LogFormat::Unknown);
+		assert!(metrics.log_format_warning().unwrap().starts_with("Logfile format of this node not recognised"));
+		let mut started = vec!["Running sn_node v0.25.3"];
+		started.extend_from_slice(&text);
+		let warning = sniff(&started).log_format_warning().unwrap();
+		assert!(warning.starts_with("Logfile format of sn_node v0.25.3 not recognised"));
+
+		// Such as when the logging level is raised, entries found later are recognised
+		let mut raised = text.clone();
+		raised.push(entry);
+		assert_eq!(sniff(&raised).log_format, LogFormat::SnNode);
+
+		let mut plain = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		plain.log_format = LogFormat::Plain;
+		plain.gather_metrics(entry).unwrap();
+		assert_eq!(plain.log_format, LogFormat::Plain);
+	}
+}
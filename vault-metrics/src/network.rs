@@ -0,0 +1,121 @@
+///! Network-wide estimate of elders and adults, reconciled from several nodes
+///!
+///! Each node reports the number of elders and adults in its section. Nodes
+///! are grouped by section prefix and the most recent report in each section
+///! wins, the estimate for the network being the total over its sections.
+///! Other recent reports from the same section which differ are kept as
+///! disagreements, since nodes with different views of their section may
+///! have been partitioned from each other.
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::metrics::NodeMetrics;
+
+///! Reports this much older than the most recent in a section are out of date rather than disagreeing
+pub static DISAGREEMENT_MINUTES: i64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerReport {
+	///! Index of the reporting node, in the order given to NetworkEstimate::new()
+	pub node: usize,
+	pub time: DateTime<Utc>,
+	pub elders: usize,
+	pub adults: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionEstimate {
+	///! Section prefix, empty where not yet known
+	pub prefix: String,
+	///! The most recent report in the section
+	pub report: PeerReport,
+	///! Other recent reports which differ from it
+	pub disagreements: Vec<PeerReport>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkEstimate {
+	pub sections: Vec<SectionEstimate>,
+	pub elders: usize,
+	pub adults: usize,
+}
+
+impl NetworkEstimate {
+	///! Reconcile the elders and adults reported by nodes
+	pub fn new<'a>(nodes: impl IntoIterator<Item = &'a NodeMetrics>) -> NetworkEstimate {
+		let mut sections = BTreeMap::<String, Vec<PeerReport>>::new();
+		for (node, metrics) in nodes.into_iter().enumerate() {
+			if let Some(time) = metrics.peers_reported {
+				sections.entry(metrics.section_prefix.clone()).or_default().push(PeerReport {
+					node,
+					time,
+					elders: metrics.elders.value,
+					adults: metrics.adults.value,
+				});
+			}
+		}
+
+		let mut estimate = NetworkEstimate::default();
+		for (prefix, mut reports) in sections {
+			reports.sort_by(|a, b| b.time.cmp(&a.time).then(a.node.cmp(&b.node)));
+			let report = reports.remove(0);
+			let recent = report.time - Duration::minutes(DISAGREEMENT_MINUTES);
+			let disagreements = reports
+				.into_iter()
+				.filter(|other| other.time >= recent && (other.elders, other.adults) != (report.elders, report.adults))
+				.collect();
+
+			estimate.elders += report.elders;
+			estimate.adults += report.adults;
+			estimate.sections.push(SectionEstimate {
+				prefix,
+				report,
+				disagreements,
+			});
+		}
+		estimate
+	}
+
+	///! True if nodes in any section disagree
+	pub fn has_disagreements(&self) -> bool {
+		self.sections.iter().any(|section| !section.disagreements.is_empty())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::entry::AssumedOffset;
+
+	fn node(prefix: &str, minutes_ago: i64, elders: usize, adults: usize) -> NodeMetrics {
+		let mut metrics = NodeMetrics::new(10, AssumedOffset::Local);
+		metrics.section_prefix = prefix.to_string();
+		metrics.peers_reported = Some(Utc::now() - Duration::minutes(minutes_ago));
+		metrics.elders.set(elders);
+		metrics.adults.set(adults);
+		metrics
+	}
+
+	#[test]
+	fn most_recent_wins_and_recent_differences_disagree() {
+		let nodes = [
+			node("0", 1, 7, 10),
+			node("0", 0, 7, 11),
+			node("0", 30, 5, 2), // Out of date
+			node("1", 2, 6, 9),
+			node("1", 3, 4, 9),
+			NodeMetrics::new(10, AssumedOffset::Local), // Nothing reported
+		];
+		let estimate = NetworkEstimate::new(nodes.iter());
+		assert_eq!((estimate.elders, estimate.adults), (13, 20));
+		assert_eq!(estimate.sections[0].report.node, 1);
+		let disagreeing: Vec<Vec<usize>> = estimate
+			.sections
+			.iter()
+			.map(|section| section.disagreements.iter().map(|report| report.node).collect())
+			.collect();
+		assert_eq!(disagreeing, vec![vec![0], vec![4]]);
+		assert!(estimate.has_disagreements());
+	}
+}
@@ -0,0 +1,92 @@
+///! Counting of failed network sends and connections, by kind
+///!
+///! A flaky network is the most common trouble for a node, but its failures
+///! are lost among other errors, and are often logged as warnings rather than
+///! errors. Any line reporting a failed send or connection, such as:
+///!
+///!     Failed to send message to 10.0.0.7:12000: connection refused
+///!     Connection to 10.0.0.9:12000 timed out
+///!
+///! is counted as a network error, apart from the ERROR count, and by the
+///! first of NETWORK_ERROR_KINDS it reports.
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+///! Kinds of network error in the order listed, each with the pattern reporting it, the first to match being taken
+pub static NETWORK_ERROR_KINDS: [&str; 5] = ["refused", "timeout", "reset", "unreachable", "send failed"];
+
+lazy_static::lazy_static! {
+	static ref NETWORK_ERROR_PATTERNS: Vec<(&'static str, Regex)> = vec![
+		("refused", Regex::new(r"(?i)connection refused|\bECONNREFUSED\b").expect("The regex failed to compile. This is a bug.")),
+		("timeout", Regex::new(r"(?i)\btimed? ?out\b|\btimeout\b|\bETIMEDOUT\b").expect("The regex failed to compile. This is a bug.")),
+		("reset", Regex::new(r"(?i)connection (?:was )?(?:reset|closed|aborted|lost)|\bECONNRESET\b|broken pipe").expect("The regex failed to compile. This is a bug.")),
+		("unreachable", Regex::new(r"(?i)(?:host|network) (?:is )?unreachable|no route to host").expect("The regex failed to compile. This is a bug.")),
+		("send failed", Regex::new(r"(?i)\b(?:fail(?:ed|ure)?|unable|could ?n[o']t|error) (?:to )?(?:send|sending|connect|connecting|deliver)").expect("The regex failed to compile. This is a bug.")),
+	];
+}
+
+///! The kind of network error message reports, if any
+pub fn network_error_kind(message: &str) -> Option<&'static str> {
+	NETWORK_ERROR_PATTERNS
+		.iter()
+		.find(|(_, pattern)| pattern.is_match(message))
+		.map(|(kind, _)| *kind)
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct NetworkErrors {
+	///! Network errors of every kind
+	pub count: u64,
+	kinds: BTreeMap<String, u64>,
+	///! When the most recent was logged
+	pub last: Option<DateTime<Utc>>,
+}
+
+impl NetworkErrors {
+	///! Count message if it reports a network error, returning true if it does
+	pub fn record(&mut self, message: &str, time: Option<DateTime<Utc>>) -> bool {
+		let kind = match network_error_kind(message) {
+			Some(kind) => kind,
+			None => return false,
+		};
+		self.count += 1;
+		*self.kinds.entry(kind.to_string()).or_default() += 1;
+		self.last = time.or(self.last);
+		true
+	}
+
+	///! The count of each kind seen, in the order of NETWORK_ERROR_KINDS
+	pub fn kinds(&self) -> Vec<(&str, u64)> {
+		NETWORK_ERROR_KINDS
+			.iter()
+			.filter_map(|kind| self.kinds.get(*kind).map(|count| (*kind, *count)))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn counts_network_errors_by_kind() {
+		assert_eq!(network_error_kind("Failed to send message to 10.0.0.7:12000: connection refused"), Some("refused"));
+		assert_eq!(network_error_kind("Connection to 10.0.0.9:12000 timed out"), Some("timeout"));
+		assert_eq!(network_error_kind("Error sending to peer: Connection reset by peer"), Some("reset"));
+		assert_eq!(network_error_kind("send error: No route to host (os error 113)"), Some("unreachable"));
+		assert_eq!(network_error_kind("Failed to send ack to b4f1c2.."), Some("send failed"));
+		assert_eq!(network_error_kind("Could not connect to bootstrap node"), Some("send failed"));
+		assert_eq!(network_error_kind("Failed to store chunk: not enough space"), None);
+		assert_eq!(network_error_kind("Sent message to 10.0.0.7:12000"), None);
+
+		let mut errors = NetworkErrors::default();
+		assert!(errors.record("Connection to 10.0.0.9:12000 timed out", None));
+		assert!(errors.record("ECONNREFUSED", None));
+		assert!(errors.record("Request timeout after 30s", None));
+		assert!(!errors.record("Node promoted to Elder", None));
+		assert_eq!(errors.count, 3);
+		assert_eq!(errors.kinds(), [("refused", 1), ("timeout", 2)]);
+	}
+}
@@ -0,0 +1,180 @@
+///! Parsing of the configuration a node echoes when it starts
+///!
+///! A node logs its effective configuration (ports, capacity, wallet and so
+///! on) as Rust debug output, such as:
+///!
+///!     Node config: Config { wallet_id: Some("ab12"), max_capacity: Some(2147483648), network_config: Config { local_port: Some(12000) } }
+///!
+///! which is flattened into settings named by their fields, so that this
+///! example gives wallet_id = ab12, max_capacity = 2147483648 and
+///! network_config.local_port = 12000.
+///!
+///! The reward key (or wallet ID) rewards are paid to is picked out of the
+///! config echo, or of a line of its own such as 'Our reward key: 8f3a..', so
+///! operators can check each node pays out to the address they intended.
+use regex::Regex;
+
+lazy_static::lazy_static! {
+	static ref CONFIG_ECHO: Regex =
+		Regex::new(r"^(?:Node |Vault )?[Cc]onfig(?:uration)?:\s*(?P<config>\S.*)$").expect("The regex failed to compile. This is a bug.");
+	static ref REWARD_KEY_ECHO: Regex =
+		Regex::new(r#"(?i)^(?:our |using |node )?(?:reward|wallet)[\w ]{0,12}?[:=]\s*(?:Some\()?"?(?P<key>(?:0x)?[0-9a-f]{8,})"?\)?\s*$"#).expect("The regex failed to compile. This is a bug.");
+	static ref REWARD_KEY: Regex =
+		Regex::new(r"^(?:0x)?[0-9a-fA-F]{8,}$").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Hex digits kept at either end of an abbreviated reward key
+const KEY_ABBREVIATED_DIGITS: usize = 6;
+
+///! The settings echoed by message, if it is a configuration echo
+pub fn parse_config_echo(message: &str) -> Option<Vec<(String, String)>> {
+	let config = CONFIG_ECHO.captures(message)?.name("config")?.as_str();
+	let mut settings = Vec::<(String, String)>::new();
+	flatten(struct_fields(config)?, "", &mut settings);
+	match settings.is_empty() {
+		true => None,
+		false => Some(settings),
+	}
+}
+
+///! The reward key in a node's settings, the first wallet or reward setting which holds one
+pub fn reward_key_setting(settings: &[(String, String)]) -> Option<String> {
+	settings
+		.iter()
+		.find(|(name, value)| {
+			let field = name.rsplit('.').next().unwrap_or(name).to_lowercase();
+			(field.contains("wallet") || field.contains("reward")) && REWARD_KEY.is_match(value)
+		})
+		.map(|(_, value)| value.clone())
+}
+
+///! The reward key echoed by message, if it gives one on its own
+pub fn parse_reward_key(message: &str) -> Option<String> {
+	Some(REWARD_KEY_ECHO.captures(message.trim())?.name("key")?.as_str().to_string())
+}
+
+///! A reward key short enough for a column, e.g. '8f3a61…9c02d4'
+pub fn abbreviate_key(key: &str) -> String {
+	let digits = key.trim_start_matches("0x");
+	match digits.len() > 2 * KEY_ABBREVIATED_DIGITS + 1 {
+		true => format!(
+			"{}…{}",
+			&digits[..KEY_ABBREVIATED_DIGITS],
+			&digits[digits.len() - KEY_ABBREVIATED_DIGITS..]
+		),
+		false => digits.to_string(),
+	}
+}
+
+///! The fields between the braces of debug output 'Name { fields }'
+fn struct_fields(value: &str) -> Option<&str> {
+	let value = value.trim();
+	let open = value.find('{')?;
+	// A set such as {a, b} has no name
+	let name = value[..open].trim();
+	if !value.ends_with('}') || name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':') {
+		return None;
+	}
+	Some(&value[open + 1..value.len() - 1])
+}
+
+fn flatten(fields: &str, prefix: &str, settings: &mut Vec<(String, String)>) {
+	for field in split_top_level(fields) {
+		let (name, value) = match field.split_once(':') {
+			Some((name, value)) => (name.trim(), value.trim()),
+			None => continue, // Such as '..' for fields left out
+		};
+		let name = format!("{}{}", prefix, name);
+		match struct_fields(value) {
+			Some(fields) => flatten(fields, &format!("{}.", name), settings),
+			None => settings.push((name, display_value(value))),
+		}
+	}
+}
+
+///! Split at commas which aren't within brackets or quotes
+fn split_top_level(text: &str) -> Vec<&str> {
+	let mut parts = Vec::<&str>::new();
+	let mut depth = 0;
+	let mut in_quotes = false;
+	let mut escaped = false;
+	let mut start = 0;
+	for (i, c) in text.char_indices() {
+		if in_quotes {
+			match c {
+				_ if escaped => escaped = false,
+				'\\' => escaped = true,
+				'"' => in_quotes = false,
+				_ => {}
+			}
+			continue;
+		}
+		match c {
+			'"' => in_quotes = true,
+			'(' | '[' | '{' => depth += 1,
+			')' | ']' | '}' => depth -= 1,
+			',' if depth == 0 => {
+				parts.push(&text[start..i]);
+				start = i + 1;
+			}
+			_ => {}
+		}
+	}
+	parts.push(&text[start..]);
+	parts.into_iter().map(str::trim).filter(|part| !part.is_empty()).collect()
+}
+
+///! A value without the Some(..) and quotes of debug output
+fn display_value(value: &str) -> String {
+	let mut value = value;
+	while let Some(inner) = value.strip_prefix("Some(").and_then(|inner| inner.strip_suffix(')')) {
+		value = inner.trim();
+	}
+	match value.strip_prefix('"').and_then(|inner| inner.strip_suffix('"')) {
+		Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+		None => value.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn flattens_config_echo() {
+		let settings = parse_config_echo(
+			r#"Node config: Config { wallet_id: Some("ab, \"12\""), max_capacity: Some(2147483648), local_ip: None, network_config: Config { hard_coded_contacts: {127.0.0.1:12000, 127.0.0.1:12001}, local_port: Some(12000) }, .. }"#,
+		)
+		.unwrap();
+		assert_eq!(
+			settings,
+			vec![
+				("wallet_id".to_string(), "ab, \"12\"".to_string()),
+				("max_capacity".to_string(), "2147483648".to_string()),
+				("local_ip".to_string(), "None".to_string()),
+				(
+					"network_config.hard_coded_contacts".to_string(),
+					"{127.0.0.1:12000, 127.0.0.1:12001}".to_string()
+				),
+				("network_config.local_port".to_string(), "12000".to_string()),
+			]
+		);
+		assert!(parse_config_echo("Connecting to the network").is_none());
+	}
+
+	#[test]
+	fn finds_reward_key() {
+		let key = "8f3a61b2c4d5e6f708192a3b4c5d6e7f9c02d4";
+		let settings = parse_config_echo(&format!(
+			r#"Node config: Config {{ wallet: None, network_config: Config {{ reward_key: Some("{}") }} }}"#,
+			key
+		))
+		.unwrap();
+		assert_eq!(reward_key_setting(&settings).as_deref(), Some(key));
+		assert_eq!(parse_reward_key(&format!("Our reward key: {}", key)).as_deref(), Some(key));
+		assert_eq!(parse_reward_key(&format!("Using wallet ID = 0x{}", key)).as_deref(), Some(&*format!("0x{}", key)));
+		assert!(parse_reward_key(&format!("Sending rewards to wallet: {} failed", key)).is_none());
+		assert_eq!(abbreviate_key(&format!("0x{}", key)), "8f3a61…9c02d4");
+		assert_eq!(abbreviate_key("ab12cd34"), "ab12cd34");
+	}
+}
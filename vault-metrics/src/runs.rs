@@ -0,0 +1,114 @@
+///! Runs of a node, one from each start, and whether it kept its identity
+///!
+///! A node which restarts may rejoin with the same keys, keeping its name, age
+///! and rewards, or as a new node which starts again as an infant. Each start
+///! begins a NodeRun, whose identity is known once the node logs its name, by
+///! comparing it with the node's name before the start.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+///! Limit on runs remembered
+pub static RUNS_MAX: usize = 20;
+
+///! How the detail of an identity event starts, for a node which kept its name
+pub static IDENTITY_SAME: &str = "same node";
+///! How the detail of an identity event starts, for a node which rejoined under a new name
+pub static IDENTITY_NEW: &str = "new node";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Identity {
+	///! The first run seen, with nothing to compare
+	First,
+	///! A restart, before the node has logged its name
+	Pending,
+	///! A restart after a run whose name wasn't logged
+	Unknown,
+	Same,
+	New,
+}
+
+impl Identity {
+	pub fn describe(&self) -> &'static str {
+		match self {
+			Identity::First => "first seen",
+			Identity::Pending => "name not yet logged",
+			Identity::Unknown => "previous name unknown",
+			Identity::Same => IDENTITY_SAME,
+			Identity::New => IDENTITY_NEW,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeRun {
+	pub started: Option<DateTime<Utc>>,
+	pub version: String,
+	///! The node's name in this run, once logged
+	pub node_name: String,
+	///! The node's name in the run before, if it was logged
+	pub previous_name: String,
+	pub identity: Identity,
+}
+
+impl NodeRun {
+	///! A run starting at started, after a run named previous_name, if this isn't the first run seen
+	pub fn new(started: Option<DateTime<Utc>>, version: &str, previous_name: &str, first: bool) -> NodeRun {
+		NodeRun {
+			started,
+			version: version.to_string(),
+			node_name: String::new(),
+			previous_name: previous_name.to_string(),
+			identity: if first { Identity::First } else { Identity::Pending },
+		}
+	}
+
+	///! Note the name the node logged, returning its identity if this is the first time in the run
+	pub fn name(&mut self, node_name: &str) -> Option<Identity> {
+		if !self.node_name.is_empty() || node_name.is_empty() {
+			return None;
+		}
+		self.node_name = node_name.to_string();
+		self.identity = match self.identity {
+			Identity::First => Identity::First,
+			_ if self.previous_name.is_empty() => Identity::Unknown,
+			_ if self.previous_name == node_name => Identity::Same,
+			_ => Identity::New,
+		};
+		Some(self.identity)
+	}
+
+	///! The detail of the event for a restart whose identity is known
+	pub fn identity_detail(&self) -> Option<String> {
+		match self.identity {
+			Identity::Same => Some(format!("{} {}", IDENTITY_SAME, self.node_name)),
+			Identity::New => Some(format!("{} {}, was {}", IDENTITY_NEW, self.node_name, self.previous_name)),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn compares_names_across_restarts() {
+		let mut first = NodeRun::new(None, "0.25.0", "", true);
+		assert_eq!(first.name("f67c2e"), Some(Identity::First));
+		assert_eq!(first.identity_detail(), None);
+
+		let mut same = NodeRun::new(None, "0.25.1", "f67c2e", false);
+		assert_eq!(same.identity, Identity::Pending);
+		assert_eq!(same.name("f67c2e"), Some(Identity::Same));
+		assert_eq!(same.name("f67c2e"), None);
+		assert_eq!(same.identity_detail().as_deref(), Some("same node f67c2e"));
+
+		let mut new = NodeRun::new(None, "0.25.1", "f67c2e", false);
+		assert_eq!(new.name("0b51d9"), Some(Identity::New));
+		assert_eq!(new.identity_detail().as_deref(), Some("new node 0b51d9, was f67c2e"));
+
+		let mut unknown = NodeRun::new(None, "0.25.1", "", false);
+		assert_eq!(unknown.name("0b51d9"), Some(Identity::Unknown));
+		assert_eq!(unknown.identity_detail(), None);
+	}
+}
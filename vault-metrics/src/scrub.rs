@@ -0,0 +1,171 @@
+///! Anonymising of node logfiles, so that they can be shared publicly
+///!
+///! Scrubber replaces XorNames, IP addresses and wallet IDs with pseudonyms
+///! which are consistent throughout a logfile, so that a problem can still be
+///! followed from line to line. Only the message of a line is scrubbed, using
+///! the fields of LOG_LINE_PATTERN, so the module, level, timestamp and source
+///! remain as they were and the logfile still parses. Lines which aren't in
+///! the node's format, such as continuations, are scrubbed whole.
+///!
+///! Wallet IDs are the values the node gives for its wallet or reward key,
+///! such as 'wallet_id: Some("ab12..")' in its config echo. XorNames are any
+///! other hex IDs of six or more digits, taken to be the same name when their
+///! first six digits are the same, so that the abbreviated name in debug
+///! output has the same pseudonym as the name in full. Loopback and
+///! unspecified IP addresses (127.0.0.1, ::1, 0.0.0.0) are kept.
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+use super::entry::LOG_LINE_PATTERN;
+
+lazy_static::lazy_static! {
+	static ref WALLET_ID: Regex =
+		Regex::new(r#"(?i)(?P<key>\b(?:wallet|reward)[\w ]{0,12}?[:=]\s*(?:Some\()?"?)(?P<id>(?:0x)?[0-9a-f]{4,})"#).expect("The regex failed to compile. This is a bug.");
+	// As for error templates, hex IDs include both a digit and a letter
+	static ref XOR_NAME: Regex =
+		Regex::new(r"\b(0x)?[0-9a-fA-F]*([0-9][0-9a-fA-F]*[a-fA-F]|[a-fA-F][0-9a-fA-F]*[0-9])[0-9a-fA-F]*\b").expect("The regex failed to compile. This is a bug.");
+	static ref IPV6_CANDIDATE: Regex =
+		Regex::new(r"[0-9a-fA-F:.]*:[0-9a-fA-F:.]*:[0-9a-fA-F:.]*").expect("The regex failed to compile. This is a bug.");
+	static ref IPV4: Regex =
+		Regex::new(r"\b\d{1,3}\.\d{1,3}\.\d{1,3}\.\d{1,3}\b").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Digits of a XorName which identify it
+const XOR_NAME_PREFIX: usize = 6;
+
+#[derive(Default)]
+pub struct Scrubber {
+	wallet_ids: HashMap<String, String>,
+	xor_names: HashMap<String, String>,
+	ip_addresses: HashMap<String, String>,
+}
+
+impl Scrubber {
+	pub fn new() -> Scrubber {
+		Scrubber::default()
+	}
+
+	///! The line with its message scrubbed
+	pub fn scrub_line(&mut self, line: &str) -> String {
+		let message = LOG_LINE_PATTERN.captures(line).and_then(|captures| captures.name("message"));
+		match message {
+			Some(message) => format!("{}{}", &line[..message.start()], self.scrub(message.as_str())),
+			None => self.scrub(line),
+		}
+	}
+
+	///! Replace the identifying parts of text
+	pub fn scrub(&mut self, text: &str) -> String {
+		let wallet_ids = &mut self.wallet_ids;
+		let text = WALLET_ID.replace_all(text, |captures: &Captures| {
+			let id = captures["id"].to_lowercase();
+			let count = wallet_ids.len();
+			let pseudonym = wallet_ids.entry(id).or_insert_with(|| format!("wallet-{}", count + 1));
+			format!("{}{}", &captures["key"], pseudonym)
+		});
+
+		let xor_names = &mut self.xor_names;
+		let text = XOR_NAME.replace_all(&text, |captures: &Captures| {
+			let name = captures[0].trim_start_matches("0x").to_lowercase();
+			if name.len() < XOR_NAME_PREFIX {
+				return captures[0].to_string();
+			}
+			let count = xor_names.len();
+			let prefix = name[..XOR_NAME_PREFIX].to_string();
+			xor_names.entry(prefix).or_insert_with(|| format!("xorname-{}", count + 1)).clone()
+		});
+
+		let text = self.scrub_ipv6(&text);
+		let ip_addresses = &mut self.ip_addresses;
+		IPV4.replace_all(&text, |captures: &Captures| {
+			let address = &captures[0];
+			match address.parse::<Ipv4Addr>() {
+				Ok(ip) if !ip.is_loopback() && !ip.is_unspecified() => {
+					let count = ip_addresses.len() + 1;
+					let pseudonym = Ipv4Addr::new(10, (count >> 16) as u8, (count >> 8) as u8, count as u8);
+					ip_addresses.entry(address.to_string()).or_insert_with(|| pseudonym.to_string()).clone()
+				}
+				_ => address.to_string(),
+			}
+		})
+		.to_string()
+	}
+
+	// The regex crate can't look around a match, so the boundaries of IPv6 addresses are checked here
+	fn scrub_ipv6(&mut self, text: &str) -> String {
+		let is_word = |c: char| c.is_alphanumeric() || c == '_';
+		let mut scrubbed = String::new();
+		let mut end = 0;
+		for candidate in IPV6_CANDIDATE.find_iter(text) {
+			let bounded = !text[..candidate.start()].ends_with(is_word) && !text[candidate.end()..].starts_with(is_word);
+			let address = candidate.as_str();
+			match address.parse::<Ipv6Addr>() {
+				Ok(ip) if bounded && !ip.is_loopback() && !ip.is_unspecified() => {
+					let count = self.ip_addresses.len() + 1;
+					let pseudonym = format!("fd00::{:x}", count);
+					scrubbed.push_str(&text[end..candidate.start()]);
+					scrubbed.push_str(self.ip_addresses.entry(address.to_string()).or_insert(pseudonym));
+					end = candidate.end();
+				}
+				_ => {}
+			}
+		}
+		scrubbed.push_str(&text[end..]);
+		scrubbed
+	}
+
+	///! What has been replaced, such as "2 XorNames, 1 IP address and 1 wallet ID"
+	pub fn summary(&self) -> String {
+		let counted = |count: usize, one: &str, many: &str| format!("{} {}", count, if count == 1 { one } else { many });
+		format!(
+			"{}, {} and {}",
+			counted(self.xor_names.len(), "XorName", "XorNames"),
+			counted(self.ip_addresses.len(), "IP address", "IP addresses"),
+			counted(self.wallet_ids.len(), "wallet ID", "wallet IDs")
+		)
+	}
+}
+
+///! Write a scrubbed copy of the logfile at input to output, returning the Scrubber used
+pub fn scrub_logfile(input: &Path, output: &Path) -> Result<Scrubber, Error> {
+	if input.canonicalize().ok() == output.canonicalize().ok() && output.exists() {
+		return Err(Error::new(ErrorKind::Other, "can't scrub a logfile in place, give a new file for the output"));
+	}
+	let reader = BufReader::new(File::open(input)?);
+	let mut writer = BufWriter::new(File::create(output)?);
+	let mut scrubber = Scrubber::new();
+	for line in reader.lines() {
+		writeln!(writer, "{}", scrubber.scrub_line(&line?))?;
+	}
+	writer.flush()?;
+	Ok(scrubber)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scrubs_consistently() {
+		let mut scrubber = Scrubber::new();
+		let lines = [
+			"[sn_node] INFO 2021-01-20T18:00:00.123456789+00:00 [src/node/mod.rs:97] Node config: Config { wallet_id: Some(\"ab12\"), local_ip: Some(192.168.1.20) }",
+			"[sn_node] INFO 2021-01-20T18:00:01.000000000+00:00 [src/routing/mod.rs:12] We are Adult, node name: f67c2e.., connected to [2001:db8:85a3::8a2e:370:7334]:12000 and 127.0.0.1:12001",
+			"  while storing f67c2e75cbce0a6097187cdf95be1c0963ad34105d643cbb00aa1f0e8b113761 from 192.168.1.20:12000 via QueryResponse::GetChunk",
+		];
+		let scrubbed: Vec<String> = lines.iter().map(|line| scrubber.scrub_line(line)).collect();
+		assert_eq!(
+			scrubbed,
+			vec![
+				"[sn_node] INFO 2021-01-20T18:00:00.123456789+00:00 [src/node/mod.rs:97] Node config: Config { wallet_id: Some(\"wallet-1\"), local_ip: Some(10.0.0.1) }",
+				"[sn_node] INFO 2021-01-20T18:00:01.000000000+00:00 [src/routing/mod.rs:12] We are Adult, node name: xorname-1.., connected to [fd00::2]:12000 and 127.0.0.1:12001",
+				"  while storing xorname-1 from 10.0.0.1:12000 via QueryResponse::GetChunk",
+			]
+		);
+		assert_eq!(scrubber.summary(), "1 XorName, 2 IP addresses and 1 wallet ID");
+	}
+}
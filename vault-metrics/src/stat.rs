@@ -0,0 +1,61 @@
+///! A value which latches the most it has been, such as a node's peers
+///!
+///! Gauges which go up and down hide how far they went, so each Stat keeps
+///! the session's maximum alongside the current value, shown once the value
+///! falls from it, as for example '34 (max 51)'. The maximum survives a node
+///! restarting, which resets only the value, as it is the most seen while
+///! watching the node.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct Stat<T> {
+	pub value: T,
+	pub max: T,
+}
+
+impl<T: Copy + PartialOrd> Stat<T> {
+	pub fn set(&mut self, value: T) {
+		self.value = value;
+		if value > self.max {
+			self.max = value;
+		}
+	}
+
+	///! Whether the value has been higher than it is now
+	pub fn below_max(&self) -> bool {
+		self.value < self.max
+	}
+
+	///! The value, and the maximum if it is higher, each formatted by format
+	pub fn describe(&self, format: impl Fn(T) -> String) -> String {
+		match self.below_max() {
+			true => format!("{} (max {})", format(self.value), format(self.max)),
+			false => format(self.value),
+		}
+	}
+}
+
+impl<T: Copy + PartialOrd + fmt::Display> fmt::Display for Stat<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.describe(|value| value.to_string()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn latches_the_maximum() {
+		let mut peers = Stat::<usize>::default();
+		peers.set(34);
+		assert_eq!(peers.to_string(), "34");
+		peers.set(51);
+		peers.set(34);
+		assert_eq!(peers.to_string(), "34 (max 51)");
+		assert_eq!(peers.describe(|value| format!("{}%", value)), "34% (max 51%)");
+		peers.set(0);
+		assert_eq!((peers.value, peers.max), (0, 51));
+	}
+}
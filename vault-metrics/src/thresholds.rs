@@ -0,0 +1,197 @@
+///! Thresholds: warn and crit levels for numeric metrics
+///!
+///! A metric at or above its warn level is a warning, and at or above its crit
+///! level is critical. Thresholds are set in the [thresholds] section of the
+///! config file, and any left out are not checked:
+///!
+///!     [thresholds]
+///!     error_rate = { warn = 5, crit = 20 }        # ERRORs per minute
+///!     storage_percent = { warn = 80, crit = 95 }  # of the chunk store's disk used
+///!     latency_p95_ms = { warn = 500, crit = 2000 } # over the last LATENCY_MINUTES
+///!
+///! The same levels colour every widget showing the metric, and the summary,
+///! and metrics beyond their thresholds lower the health score.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::latency::{bucket_least_millis, LATENCY_BUCKETS};
+use super::metrics::NodeMetrics;
+
+///! Minutes of requests over which latency_p95_ms is measured
+pub const LATENCY_MINUTES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Level {
+	Ok,
+	Warn,
+	Crit,
+}
+
+impl Level {
+	///! Penalty to the health score, from 0 to 1
+	pub fn penalty(&self) -> f64 {
+		match self {
+			Level::Ok => 0.0,
+			Level::Warn => 0.5,
+			Level::Crit => 1.0,
+		}
+	}
+}
+
+impl fmt::Display for Level {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Level::Ok => write!(f, "ok"),
+			Level::Warn => write!(f, "warn"),
+			Level::Crit => write!(f, "crit"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Threshold {
+	pub warn: f64,
+	pub crit: f64,
+}
+
+impl Threshold {
+	pub fn level(&self, value: f64) -> Level {
+		if value >= self.crit {
+			Level::Crit
+		} else if value >= self.warn {
+			Level::Warn
+		} else {
+			Level::Ok
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+	///! ERRORs in the last complete minute
+	pub error_rate: Option<Threshold>,
+	///! Percentage of the chunk store's disk which is used
+	pub storage_percent: Option<Threshold>,
+	///! 95th percentile of request latency in milliseconds (see --latency-pattern)
+	pub latency_p95_ms: Option<Threshold>,
+}
+
+///! A metric checked against its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricLevel {
+	pub metric: Metric,
+	pub value: f64,
+	pub level: Level,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Metric {
+	ErrorRate,
+	StoragePercent,
+	LatencyP95,
+}
+
+impl fmt::Display for MetricLevel {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.metric {
+			Metric::ErrorRate => write!(f, "errors {}/m", self.value),
+			Metric::StoragePercent => write!(f, "disk {:.0}%", self.value),
+			// Latencies in the last bucket have no upper bound
+			Metric::LatencyP95 if self.value.is_infinite() => write!(f, "p95 >{}ms", bucket_least_millis(LATENCY_BUCKETS - 1)),
+			Metric::LatencyP95 => write!(f, "p95 {}ms", self.value),
+		}
+	}
+}
+
+impl Thresholds {
+	pub fn validate(&self) -> Result<(), String> {
+		let thresholds = [
+			("error_rate", self.error_rate),
+			("storage_percent", self.storage_percent),
+			("latency_p95_ms", self.latency_p95_ms),
+		];
+		for (name, threshold) in thresholds.iter() {
+			if let Some(threshold) = threshold {
+				if threshold.warn > threshold.crit {
+					return Err(format!("[thresholds] {}: warn ({}) is above crit ({})", name, threshold.warn, threshold.crit));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	///! Each metric with a threshold and a value, where disk_used is the fraction of the disk used, if known
+	pub fn check(&self, metrics: &NodeMetrics, disk_used: Option<f64>, now: DateTime<Utc>) -> Vec<MetricLevel> {
+		let error_rate = metrics.category_rates("ERROR").map_or(0.0, |(per_minute, _)| per_minute as f64);
+		let values = [
+			(Metric::ErrorRate, self.error_rate, Some(error_rate)),
+			(Metric::StoragePercent, self.storage_percent, disk_used.map(|used| used * 100.0)),
+			(Metric::LatencyP95, self.latency_p95_ms, metrics.latency.percentile(now, LATENCY_MINUTES, 0.95)),
+		];
+		values
+			.iter()
+			.filter_map(|(metric, threshold, value)| match (threshold, value) {
+				(Some(threshold), Some(value)) => Some(MetricLevel {
+					metric: *metric,
+					value: *value,
+					level: threshold.level(*value),
+				}),
+				_ => None,
+			})
+			.collect()
+	}
+}
+
+///! The level of a metric among those checked, Ok if it wasn't
+pub fn level_of(levels: &[MetricLevel], metric: Metric) -> Level {
+	levels.iter().find(|level| level.metric == metric).map_or(Level::Ok, |level| level.level)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::entry::AssumedOffset;
+
+	#[test]
+	fn checks_metrics_against_thresholds() {
+		let thresholds = Thresholds {
+			error_rate: Some(Threshold { warn: 5.0, crit: 20.0 }),
+			storage_percent: Some(Threshold { warn: 80.0, crit: 95.0 }),
+			latency_p95_ms: None,
+		};
+		assert_eq!(thresholds.validate(), Ok(()));
+		let threshold = thresholds.storage_percent.unwrap();
+		assert_eq!((threshold.level(79.9), threshold.level(80.0), threshold.level(99.0)), (Level::Ok, Level::Warn, Level::Crit));
+
+		let now = Utc::now();
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		metrics.most_recent = Some(now);
+		metrics.count_latency(5000.0);
+		let levels = thresholds.check(&metrics, Some(0.9), now);
+		assert_eq!(levels.len(), 2, "latency has no threshold");
+		assert_eq!(level_of(&levels, Metric::ErrorRate), Level::Ok);
+		assert_eq!(level_of(&levels, Metric::StoragePercent), Level::Warn);
+		assert_eq!(levels[1].to_string(), "disk 90%");
+		assert_eq!(thresholds.check(&metrics, None, now).len(), 1);
+
+		let latency = Thresholds {
+			latency_p95_ms: Some(Threshold { warn: 500.0, crit: 100.0 }),
+			..Thresholds::default()
+		};
+		assert!(latency.validate().unwrap_err().contains("latency_p95_ms"));
+		let levels = Thresholds { latency_p95_ms: Some(Threshold { warn: 500.0, crit: 2000.0 }), ..latency }.check(&metrics, None, now);
+		// 5s is counted in the top bucket, from 1024ms up
+		assert_eq!(levels, [MetricLevel { metric: Metric::LatencyP95, value: f64::INFINITY, level: Level::Crit }]);
+		assert_eq!(levels[0].to_string(), "p95 >1024ms");
+
+		// A p95 in a bounded bucket is reported as the bucket's upper bound
+		let mut metrics = NodeMetrics::new(20, AssumedOffset::Fixed(0));
+		metrics.most_recent = Some(now);
+		metrics.count_latency(300.0);
+		let levels = Thresholds { latency_p95_ms: Some(Threshold { warn: 500.0, crit: 2000.0 }), ..latency }.check(&metrics, None, now);
+		assert_eq!(levels, [MetricLevel { metric: Metric::LatencyP95, value: 512.0, level: Level::Warn }]);
+	}
+}
@@ -0,0 +1,311 @@
+///! Timelines of metrics, held as sets of time buckets
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+lazy_static::lazy_static! {
+	pub static ref TIMELINES: std::vec::Vec<(&'static str, Duration)> = vec!(
+		("1 second columns", Duration::seconds(1)),
+		("1 minute columns", Duration::minutes(1)),
+		("1 hour columns", Duration::hours(1)),
+		("1 day columns", Duration::days(1)),
+		("1 week columns", Duration::days(7)),
+		("1 year columns", Duration::days(365)),
+	);
+}
+
+///! Maintains one or more 'marching bucket' histories for
+///! a given metric, each with its own duration and granularity.
+///!
+///! A BucketSet is used to hold the history of values with
+///! a given bucket_duration and maximum number of buckets.
+///!
+///! A BucketSet begins with a single bucket of fixed
+///! duration holding the initial metric value. New buckets
+///! are added as time progresses until the number of buckets
+///! covers the total duration of the BucketSet. At this
+///! point the oldest bucket is removed when a new bucket is
+///! added, so that the total duration remains constant and
+///! the specified maximum number of buckets is never
+///! exceeded.
+///!
+///! By adding more than one BucketSet, a given metric can be
+///! recorded for different durations and with different
+///! granularities. E.g. 60 * 1s buckets covers a minute
+///! and 60 * 1m buckets covers an hour, and so on.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TimelineSet {
+	name: String,
+	pub(crate) bucket_sets: HashMap<String, BucketSet>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BucketSet {
+	pub bucket_time: Option<DateTime<Utc>>,
+	#[serde(with = "duration_millis")]
+	pub total_duration: Duration,
+	#[serde(with = "duration_millis")]
+	pub bucket_duration: Duration,
+	pub max_buckets: usize,
+	pub buckets: Vec<u64>,
+}
+
+impl TimelineSet {
+	pub fn new(name: String) -> TimelineSet {
+		TimelineSet {
+			name,
+			bucket_sets: HashMap::<String, BucketSet>::new(),
+		}
+	}
+
+	pub fn get_name(&self) -> &String {
+		&self.name
+	}
+
+	///! Count in the most recently completed bucket of the named BucketSet
+	pub fn last_complete_bucket(&self, bucket_set_name: &str) -> Option<u64> {
+		let buckets = self.bucket_sets.get(bucket_set_name)?.buckets();
+		if buckets.len() < 2 {
+			return None;
+		}
+		Some(buckets[buckets.len() - 2])
+	}
+
+	pub fn add_bucket_set(&mut self, name: &'static str, duration: Duration, max_buckets: usize) {
+		self.bucket_sets
+			.insert(name.to_string(), BucketSet::new(duration, max_buckets));
+	}
+
+	pub fn get_bucket_set(&mut self, bucket_set_name: &str) -> Option<&BucketSet> {
+		self.bucket_sets.get(bucket_set_name)
+	}
+
+	///! Update all bucket_sets with new current time
+	///!
+	///! Call significantly more frequently than the smallest BucketSet duration
+	pub(crate) fn update_current_time(&mut self, new_time: Option<DateTime<Utc>>) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			bs.update_current_time(new_time);
+		}
+	}
+
+	pub(crate) fn increment_value(&mut self, time: Option<DateTime<Utc>>) {
+		// debug_log!("increment_value()");
+		if let Some(time) = time {
+			for (_name, bs) in self.bucket_sets.iter_mut() {
+				// debug_log!(format!("name       : {}", _name).as_str());
+				bs.increment_at(time);
+			}
+		} else {
+			debug!("increment FAIL");
+		}
+	}
+}
+
+// chrono::Duration has no serde support so is (de)serialised as milliseconds
+mod duration_millis {
+	use chrono::Duration;
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_i64(duration.num_milliseconds())
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+		Ok(Duration::milliseconds(i64::deserialize(deserializer)?))
+	}
+}
+
+impl BucketSet {
+	pub fn new(bucket_duration: Duration, max_buckets: usize) -> BucketSet {
+		BucketSet {
+			bucket_duration,
+			max_buckets,
+			total_duration: bucket_duration * max_buckets as i32,
+
+			bucket_time: None,
+			buckets: vec![0; max_buckets],
+		}
+	}
+
+	pub fn set_bucket_value(&mut self, value: u64) {
+		let index = self.buckets.len() - 1;
+		self.buckets[index] = value;
+	}
+
+	pub fn increment_value(&mut self) {
+		let index = self.buckets.len() - 1;
+		self.buckets[index] += 1;
+	}
+
+	///! Start new buckets up to new_time, dropping the oldest beyond max_buckets
+	pub fn update_current_time(&mut self, new_time: Option<DateTime<Utc>>) {
+		if let Some(mut bucket_time) = self.bucket_time {
+			if let Some(new_time) = new_time {
+				let mut end_time = bucket_time + self.bucket_duration;
+
+				while end_time.lt(&new_time) {
+					// Start new bucket
+					self.bucket_time = Some(end_time);
+					bucket_time = end_time;
+					end_time = bucket_time + self.bucket_duration;
+
+					self.buckets.push(0);
+					if self.buckets.len() > self.max_buckets {
+						self.buckets.remove(0);
+					}
+				}
+			}
+		} else {
+			self.bucket_time = new_time;
+		}
+	}
+
+	///! Count one in the bucket holding time, or the current bucket if time is later
+	pub fn increment_at(&mut self, time: DateTime<Utc>) {
+		let mut index = Some(self.buckets.len() - 1);
+		// debug_log!(format!("time       : {}", time).as_str());
+		if let Some(bucket_time) = self.bucket_time {
+			// debug_log!(format!("bucket_time: {}", bucket_time).as_str());
+			if time.lt(&bucket_time) {
+				// Use the closest bucket to this time
+				// debug_log!("increment (closest bucket)");
+				let time_difference = (bucket_time - time).num_nanoseconds();
+				let bucket_duration = self.bucket_duration.num_nanoseconds();
+				if time_difference.and(bucket_duration).is_some() {
+					let buckets_behind = time_difference.unwrap() / bucket_duration.unwrap();
+					if buckets_behind as usize >= self.buckets.len() {
+						// debug_log!(format!("increment DISCARDED buckets_behind: {}", buckets_behind).as_str());
+						index = None;
+					} else {
+						// debug_log!(format!("increment INCLUDED buckets_behind: {}", buckets_behind).as_str());
+						index = Some(self.buckets.len() - 1 - buckets_behind as usize);
+					}
+				}
+			}
+		}
+		if let Some(index) = index {
+			// debug_log!(format!("increment index: {}", index).as_str());
+			self.buckets[index] += 1;
+		}
+	}
+
+	///! Index of the bucket containing time, if still within the BucketSet
+	pub fn index_of(&self, time: DateTime<Utc>) -> Option<usize> {
+		let bucket_time = self.bucket_time?;
+		let buckets_behind = if time < bucket_time {
+			((bucket_time - time).num_milliseconds() - 1) / self.bucket_duration.num_milliseconds() + 1
+		} else {
+			0
+		} as usize;
+		if buckets_behind < self.buckets.len() {
+			Some(self.buckets.len() - 1 - buckets_behind)
+		} else {
+			None
+		}
+	}
+
+	pub fn buckets(&self) -> &Vec<u64> {
+		&self.buckets
+	}
+
+	pub fn buckets_mut(&mut self) -> &mut Vec<u64> {
+		&mut self.buckets
+	}
+
+	///! Test whether the most recently completed bucket is an outlier
+	///!
+	///! The bucket is compared with the mean and standard deviation of the
+	///! buckets before it, ignoring any before the metric was first non-zero.
+	///! Returns None if it is within mean ± k·stddev, or there is too little history.
+	pub fn outlier(&self, k: f64) -> Option<Outlier> {
+		if self.buckets.len() < 2 {
+			return None;
+		}
+		let value = self.buckets[self.buckets.len() - 2];
+		let history = &self.buckets[..self.buckets.len() - 2];
+		let history = match history.iter().position(|&count| count > 0) {
+			Some(first_active) => &history[first_active..],
+			None => return None,
+		};
+		if history.len() < OUTLIER_MIN_HISTORY {
+			return None;
+		}
+
+		let n = history.len() as f64;
+		let mean = history.iter().sum::<u64>() as f64 / n;
+		let variance = history
+			.iter()
+			.map(|&count| (count as f64 - mean).powi(2))
+			.sum::<f64>()
+			/ n;
+		let stddev = variance.sqrt();
+
+		// A floor on stddev stops a steady metric flagging every small change
+		let tolerance = k * stddev.max(1.0);
+		if (value as f64 - mean).abs() > tolerance {
+			Some(Outlier {
+				value,
+				mean,
+				stddev,
+			})
+		} else {
+			None
+		}
+	}
+}
+
+///! Outlier detection uses the per-minute timelines
+pub static OUTLIER_TIMELINE: &str = "1 minute columns";
+pub static OUTLIER_STDDEVS: f64 = 3.0;
+static OUTLIER_MIN_HISTORY: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Outlier {
+	pub value: u64,
+	pub mean: f64,
+	pub stddev: f64,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn bucket_set_with(buckets: Vec<u64>) -> BucketSet {
+		let mut bucket_set = BucketSet::new(Duration::minutes(1), buckets.len());
+		bucket_set.buckets = buckets;
+		bucket_set
+	}
+
+	#[test]
+	fn outlier_ignores_steady_and_short_history() {
+		let mut buckets = vec![0; 5];
+		buckets.extend(vec![5, 6, 5, 4, 5, 6, 5, 4, 5, 6, 5, 0]);
+		assert!(bucket_set_with(buckets).outlier(OUTLIER_STDDEVS).is_none());
+
+		assert!(bucket_set_with(vec![0, 0, 5, 6, 40, 0]).outlier(OUTLIER_STDDEVS).is_none());
+	}
+
+	#[test]
+	fn outlier_detects_spike() {
+		let mut buckets = vec![5, 6, 5, 4, 5, 6, 5, 4, 5, 6];
+		buckets.extend(vec![40, 0]);
+		let outlier = bucket_set_with(buckets).outlier(OUTLIER_STDDEVS).unwrap();
+		assert_eq!(outlier.value, 40);
+		assert!((outlier.mean - 5.1).abs() < 0.01);
+	}
+
+	#[test]
+	fn bucket_index_of_time() {
+		let mut bucket_set = BucketSet::new(Duration::seconds(60), 10);
+		let bucket_time = Utc::now();
+		assert_eq!(bucket_set.index_of(bucket_time), None);
+
+		bucket_set.bucket_time = Some(bucket_time);
+		assert_eq!(bucket_set.index_of(bucket_time + Duration::seconds(30)), Some(9));
+		assert_eq!(bucket_set.index_of(bucket_time - Duration::seconds(1)), Some(8));
+		assert_eq!(bucket_set.index_of(bucket_time - Duration::seconds(60)), Some(8));
+		assert_eq!(bucket_set.index_of(bucket_time - Duration::seconds(61)), Some(7));
+		assert_eq!(bucket_set.index_of(bucket_time - Duration::minutes(10)), None);
+	}
+}
@@ -0,0 +1,245 @@
+///! Property tests of LogEntry::decode() and NodeMetrics::gather_metrics()
+///!
+///! Node logfiles may be corrupted (truncated writes, binary junk, mixed
+///! encodings) or written by something hostile, so the parser is fed lines
+///! damaged from real ones and checked for invariants which must hold
+///! whatever it is given. The timestamps and the damage come from proptest
+///! strategies, so a failure is shrunk to a small case and saved so that it
+///! repeats. See fuzz/ for open ended fuzzing of the same functions.
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+use proptest::sample::{select, Index};
+use vault_metrics::{AssumedOffset, LogEntry, NodeMetrics};
+
+///! Lines as nodes log them, to be damaged, with {} standing for the timestamp
+static LINES: [&str; 12] = [
+	"[sn_node] INFO {} [src/node/mod.rs:97] Our Age: 5",
+	"[sn_node] INFO {} [src/node/mod.rs:97] Node promoted to Elder",
+	"[sn_node] INFO {} [src/routing/mod.rs:12] We are Adult, node name: f67c2e.., connected to [2001:db8::7334]:12000",
+	"[sn_node] ERROR {} [src/utils.rs:52] Failed to load auto dump db at /tmp/f67c2e75.db: No such file or directory (os error 2)",
+	"[sn_node] WARN {} [src/node/handle.rs:41] Dropping duplicate message from 203.0.113.7:12000",
+	"[sn_node] INFO {} [src/node/mod.rs:30] Our public address is 203.0.113.7:12000",
+	"[sn_node] INFO {} [src/chunk_store/mod.rs:88] Used space: 1048576 of 2147483648",
+	"[sn_node] DEBUG {} [src/node/client.rs:20] Client connected: 198.51.100.2:5483",
+	"[sn_node] INFO {} [src/node/mod.rs:97] Node config: Config { wallet_id: Some(\"ab12\"), max_capacity: Some(2147483648) }",
+	"[sn_node] TRACE {} [src/node/msg.rs:7] Handling GetChunk for 6c5d4e..",
+	"Running sn_node v0.25.0",
+	"    at src/node/mod.rs:97 continued",
+];
+
+///! The ways nodes format the timestamp
+static FORMATS: [&str; 4] = [
+	"%Y-%m-%dT%H:%M:%S%.9f+00:00",
+	"%Y-%m-%dT%H:%M:%S%.6f",
+	"%Y-%m-%dT%H:%M:%S%.3fZ",
+	"%Y-%m-%dT%H:%M:%S%.6f+00:00",
+];
+
+///! Characters to insert, including multi-byte and control characters
+static JUNK: [&str; 14] = ["[", "]", " ", ":", "+", "-", ".", "T", "Z", "\t", "\u{0}", "é", "ノ", "🚀"];
+
+fn start() -> DateTime<Utc> {
+	Utc.ymd(2021, 1, 20).and_hms(18, 0, 0)
+}
+
+///! Damage done to text at a position, which is taken modulo its length plus one
+#[derive(Debug, Clone)]
+enum Damage {
+	Remove(Index),
+	Insert(Index, &'static str),
+	Truncate(Index),
+	Replace(Index, u8),
+}
+
+fn damage() -> impl Strategy<Value = Damage> {
+	prop_oneof![
+		any::<Index>().prop_map(Damage::Remove),
+		(any::<Index>(), select(&JUNK[..])).prop_map(|(at, junk)| Damage::Insert(at, junk)),
+		any::<Index>().prop_map(Damage::Truncate),
+		(any::<Index>(), 0u8..128).prop_map(|(at, byte)| Damage::Replace(at, byte)),
+	]
+}
+
+fn damaged(text: &str, damage: &[Damage]) -> String {
+	let mut chars: Vec<char> = text.chars().collect();
+	for damage in damage {
+		match damage {
+			Damage::Remove(at) => {
+				let at = at.index(chars.len() + 1);
+				if at < chars.len() {
+					chars.remove(at);
+				}
+			}
+			Damage::Insert(at, junk) => {
+				let at = at.index(chars.len() + 1);
+				chars.splice(at..at, junk.chars()).for_each(drop);
+			}
+			Damage::Truncate(at) => chars.truncate(at.index(chars.len() + 1)),
+			Damage::Replace(at, byte) => {
+				let at = at.index(chars.len() + 1);
+				if at < chars.len() {
+					chars[at] = *byte as char;
+				}
+			}
+		}
+	}
+	// Lines are split on newlines, so never hold one
+	chars.into_iter().filter(|c| *c != '\n').collect()
+}
+
+///! A line to give the parser, once given the time it is logged at
+#[derive(Debug, Clone)]
+enum TestLine {
+	///! A line as logged, with its timestamp left alone and damage to either side of it
+	Logged {
+		template: &'static str,
+		format: &'static str,
+		before: Vec<Damage>,
+		after: Vec<Damage>,
+	},
+	///! Any line at all, from random bytes made valid UTF-8
+	Arbitrary(String),
+}
+
+impl TestLine {
+	fn at(&self, time: DateTime<Utc>) -> String {
+		match self {
+			TestLine::Logged {
+				template,
+				format,
+				before,
+				after,
+			} => {
+				let (text_before, text_after) = template.split_at(template.find("{}").unwrap_or(template.len()));
+				// The spaces either side of the timestamp are kept, as damage next to it (such as a '-' before it) changes the time
+				let text_after = text_after.strip_prefix("{} ").map_or(String::new(), |text_after| {
+					format!(" {} {}", time.format(format), damaged(text_after, after))
+				});
+				format!("{}{}", damaged(text_before.trim_end(), before), text_after)
+			}
+			TestLine::Arbitrary(line) => line.clone(),
+		}
+	}
+}
+
+///! Lines of which one in arbitrary_in is arbitrary and the rest damaged from LINES
+fn test_line(arbitrary_in: u32) -> impl Strategy<Value = TestLine> {
+	let logged = (select(&LINES[..]), select(&FORMATS[..]), vec(damage(), 0..4), vec(damage(), 0..4)).prop_map(
+		|(template, format, before, after)| TestLine::Logged {
+			template,
+			format,
+			before,
+			after,
+		},
+	);
+	let arbitrary = vec(any::<u8>(), 0..200).prop_map(|bytes| {
+		let bytes: Vec<u8> = bytes.into_iter().filter(|byte| *byte != b'\n').collect();
+		TestLine::Arbitrary(String::from_utf8_lossy(&bytes).into_owned())
+	});
+	prop_oneof![arbitrary_in - 1 => logged, 1 => arbitrary]
+}
+
+///! A time within a year of start()
+fn timestamp() -> impl Strategy<Value = DateTime<Utc>> {
+	(0i64..365 * 24 * 3600 * 1000).prop_map(|millis| start() + Duration::milliseconds(millis))
+}
+
+fn assumed_offset() -> impl Strategy<Value = AssumedOffset> {
+	select(vec![AssumedOffset::Local, AssumedOffset::Fixed(0), AssumedOffset::Fixed(-12 * 3600)])
+}
+
+proptest! {
+	#![proptest_config(ProptestConfig::with_cases(10_000))]
+
+	#[test]
+	fn decode_never_panics_and_keeps_the_line(
+		line in test_line(4),
+		time in timestamp(),
+		offset in assumed_offset(),
+		previous in option::of(Just(start())),
+	) {
+		let line = line.at(time);
+		if let Some(entry) = LogEntry::decode(&line, &offset, previous) {
+			prop_assert_eq!(&entry.logstring, &line);
+			prop_assert!(line.ends_with(&entry.message), "{:?} doesn't end with its message", line);
+			prop_assert!(line.contains(&entry.category) && line.contains(&entry.source));
+		}
+	}
+}
+
+proptest! {
+	#![proptest_config(ProptestConfig::with_cases(64))]
+
+	#[test]
+	fn gather_metrics_keeps_times_in_order_and_counts_rising(
+		lines in vec((0i64..5_000, test_line(10)), 0..500),
+	) {
+		let mut metrics = NodeMetrics::new(1, AssumedOffset::Fixed(0));
+		metrics.loading = true;
+		let mut time = start();
+		let mut previous_time: Option<DateTime<Utc>> = None;
+		for (step, line) in lines.iter() {
+			time = time + Duration::milliseconds(*step);
+			let line = line.at(time);
+			let before = Counts::of(&metrics);
+			let history_len = metrics.log_history.len();
+			metrics.gather_metrics(&line).unwrap();
+
+			if metrics.log_history.len() == history_len {
+				prop_assert_eq!(Counts::of(&metrics), before, "{:?} changed counts without being logged", line);
+				continue;
+			}
+			let entry = metrics.log_history.last().unwrap();
+			// A line without a time takes that of the line before
+			match (previous_time, entry.time) {
+				(Some(previous), Some(time)) => prop_assert!(time >= previous, "{:?} went back in time", line),
+				(Some(_), None) => prop_assert!(false, "{:?} lost the time of the line before", line),
+				_ => {}
+			}
+			previous_time = entry.time.or(previous_time);
+
+			let after = Counts::of(&metrics);
+			prop_assert!(after.categories >= before.categories);
+			// A restart resets a node's counts
+			if entry.category != "START" {
+				prop_assert!(after.rises_from(&before), "{:?} lowered counts {:?} to {:?}", line, before, after);
+			}
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+struct Counts {
+	categories: usize,
+	gets: u64,
+	puts: u64,
+	errors: u64,
+	served: u64,
+	duplicates: u64,
+	address_changes: usize,
+}
+
+impl Counts {
+	fn of(metrics: &NodeMetrics) -> Counts {
+		Counts {
+			categories: metrics.category_count.values().sum(),
+			gets: metrics.activity_gets,
+			puts: metrics.activity_puts,
+			errors: metrics.activity_errors,
+			served: metrics.clients_served,
+			duplicates: metrics.duplicates.count,
+			address_changes: metrics.address.changes.len(),
+		}
+	}
+
+	fn rises_from(&self, before: &Counts) -> bool {
+		self.gets >= before.gets
+			&& self.puts >= before.puts
+			&& self.errors >= before.errors
+			&& self.served >= before.served
+			&& self.duplicates >= before.duplicates
+			&& self.address_changes >= before.address_changes
+	}
+}